@@ -0,0 +1,73 @@
+-- Core form and response tables. Databases created before the migrations directory
+-- already have them, hence IF NOT EXISTS; migrations_postgres/ mirrors this schema.
+CREATE TABLE IF NOT EXISTS forms (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT,
+    instructions TEXT,
+    status TEXT NOT NULL DEFAULT 'draft',
+    welcome_message TEXT,
+    closing_message TEXT,
+    settings JSON,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS sections (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    position INTEGER NOT NULL,
+    FOREIGN KEY (form_id) REFERENCES forms(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS questions (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL,
+    section_id TEXT,
+    position INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    features JSON,
+    FOREIGN KEY (form_id) REFERENCES forms(id) ON DELETE CASCADE,
+    FOREIGN KEY (section_id) REFERENCES sections(id) ON DELETE CASCADE
+);
+
+-- PII lives here only, so it can be deleted without touching responses
+CREATE TABLE IF NOT EXISTS respondents (
+    id TEXT PRIMARY KEY,
+    name TEXT,
+    email TEXT,
+    email_hash TEXT,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS responses (
+    id TEXT PRIMARY KEY,
+    respondent_id TEXT,
+    form_id TEXT NOT NULL,
+    role TEXT,
+    ip_address TEXT,
+    metadata JSON,
+    submitted_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (respondent_id) REFERENCES respondents(id) ON DELETE SET NULL,
+    FOREIGN KEY (form_id) REFERENCES forms(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS answers (
+    id TEXT PRIMARY KEY,
+    response_id TEXT NOT NULL,
+    question_id TEXT NOT NULL,
+    value JSON,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_sections_form_id ON sections(form_id);
+CREATE INDEX IF NOT EXISTS idx_questions_form_id ON questions(form_id);
+CREATE INDEX IF NOT EXISTS idx_respondents_email_hash ON respondents(email_hash);
+CREATE INDEX IF NOT EXISTS idx_responses_form_id ON responses(form_id);
+CREATE INDEX IF NOT EXISTS idx_answers_response_id ON answers(response_id);
+CREATE INDEX IF NOT EXISTS idx_answers_question_id ON answers(question_id);
@@ -0,0 +1,18 @@
+-- Invitation lists: one single-use token per invitee
+CREATE TABLE IF NOT EXISTS invitations (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    role TEXT,
+    token TEXT NOT NULL UNIQUE,
+    response_id TEXT,
+    used_at DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (form_id) REFERENCES forms(id) ON DELETE CASCADE,
+    FOREIGN KEY (response_id) REFERENCES responses(id) ON DELETE SET NULL,
+    UNIQUE(form_id, email)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invitations_form_id ON invitations(form_id);
+CREATE INDEX IF NOT EXISTS idx_invitations_role ON invitations(role);
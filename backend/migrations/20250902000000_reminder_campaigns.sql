@@ -0,0 +1,28 @@
+-- Scheduled reminder emails to invitees who have not responded yet
+CREATE TABLE IF NOT EXISTS reminder_campaigns (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    body_template TEXT NOT NULL,
+    closes_at DATETIME NOT NULL,
+    days_before JSON NOT NULL DEFAULT '[]',
+    active BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (form_id) REFERENCES forms(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminder_campaigns_form_id ON reminder_campaigns(form_id);
+
+-- One row per reminder actually delivered, so nobody is mailed twice for the same step
+CREATE TABLE IF NOT EXISTS reminder_sends (
+    id TEXT PRIMARY KEY,
+    campaign_id TEXT NOT NULL,
+    invitation_id TEXT NOT NULL,
+    days_before INTEGER NOT NULL,
+    sent_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (campaign_id) REFERENCES reminder_campaigns(id) ON DELETE CASCADE,
+    FOREIGN KEY (invitation_id) REFERENCES invitations(id) ON DELETE CASCADE,
+    UNIQUE(campaign_id, invitation_id, days_before)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminder_sends_campaign_id ON reminder_sends(campaign_id);
@@ -0,0 +1,10 @@
+-- Per-form template for new-response notification emails
+CREATE TABLE IF NOT EXISTS notification_templates (
+    form_id TEXT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    subject TEXT NOT NULL,
+    html_template TEXT NOT NULL,
+    include_pii BOOLEAN NOT NULL DEFAULT 0,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (form_id) REFERENCES forms(id) ON DELETE CASCADE
+);
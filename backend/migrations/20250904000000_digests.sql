@@ -0,0 +1,27 @@
+-- Daily/weekly digest emails per admin and form
+CREATE TABLE IF NOT EXISTS digest_subscriptions (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL,
+    email TEXT NOT NULL,
+    frequency TEXT NOT NULL CHECK (frequency IN ('daily', 'weekly')),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (form_id) REFERENCES forms(id) ON DELETE CASCADE,
+    UNIQUE(form_id, email)
+);
+
+CREATE INDEX IF NOT EXISTS idx_digest_subscriptions_form_id ON digest_subscriptions(form_id);
+
+-- One row per digest sent; period_end of the latest run is where the next digest starts
+CREATE TABLE IF NOT EXISTS digest_runs (
+    id TEXT PRIMARY KEY,
+    subscription_id TEXT NOT NULL,
+    period_start DATETIME NOT NULL,
+    period_end DATETIME NOT NULL,
+    new_responses INTEGER NOT NULL,
+    total_responses INTEGER NOT NULL,
+    average_score REAL,
+    sent_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (subscription_id) REFERENCES digest_subscriptions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_digest_runs_subscription_id ON digest_runs(subscription_id);
@@ -0,0 +1,14 @@
+-- Outgoing webhook endpoints; form_id NULL means the endpoint receives events for every form
+CREATE TABLE IF NOT EXISTS webhooks (
+    id TEXT PRIMARY KEY,
+    form_id TEXT,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    events JSON NOT NULL DEFAULT '[]',
+    include_pii BOOLEAN NOT NULL DEFAULT 0,
+    active BOOLEAN NOT NULL DEFAULT 1,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (form_id) REFERENCES forms(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhooks_form_id ON webhooks(form_id);
@@ -0,0 +1,36 @@
+-- Full-text index over free-text answers and answer comments.
+-- Rows are written on submit and removed with their form; see src/search.rs.
+CREATE VIRTUAL TABLE IF NOT EXISTS answer_search USING fts5(
+    body,
+    form_id UNINDEXED,
+    response_id UNINDEXED,
+    question_id UNINDEXED,
+    field UNINDEXED,
+    tokenize = 'porter unicode61'
+);
+
+-- Index answers submitted before the search table existed
+INSERT INTO answer_search (body, form_id, response_id, question_id, field)
+SELECT body, form_id, response_id, question_id, field
+FROM (
+    SELECT
+        CASE
+            WHEN json_valid(a.value) = 0 THEN NULL
+            WHEN json_type(a.value) = 'text' AND q.type IN ('text', 'textarea')
+                THEN json_extract(a.value, '$')
+            WHEN json_type(a.value) = 'object' AND json_type(a.value, '$.comment') = 'text'
+                THEN json_extract(a.value, '$.comment')
+        END AS body,
+        r.form_id,
+        a.response_id,
+        a.question_id,
+        CASE
+            WHEN json_valid(a.value) = 0 THEN 'text'
+            WHEN json_type(a.value) = 'object' THEN 'comment'
+            ELSE 'text'
+        END AS field
+    FROM answers a
+    JOIN responses r ON r.id = a.response_id
+    LEFT JOIN questions q ON q.id = a.question_id
+)
+WHERE body IS NOT NULL AND trim(body) != '';
@@ -0,0 +1,11 @@
+-- Questions shared across forms. Forms hold copies that point back here through the
+-- `bankId` feature, which is how analytics match a question from one cycle to the next.
+CREATE TABLE IF NOT EXISTS question_bank (
+    id TEXT PRIMARY KEY,
+    question_type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    features JSON NOT NULL DEFAULT '{}',
+    tags JSON NOT NULL DEFAULT '[]',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,67 @@
+-- Core form and response tables for the PostgreSQL backend.
+-- Mirrors the SQLite schema, with JSONB for settings, features and answer values.
+CREATE TABLE IF NOT EXISTS forms (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT,
+    instructions TEXT,
+    status TEXT NOT NULL DEFAULT 'draft',
+    welcome_message TEXT,
+    closing_message TEXT,
+    settings JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS sections (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL REFERENCES forms(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    description TEXT,
+    position INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS questions (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL REFERENCES forms(id) ON DELETE CASCADE,
+    section_id TEXT REFERENCES sections(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    description TEXT,
+    features JSONB NOT NULL DEFAULT '{}'
+);
+
+-- PII lives here only, so it can be deleted without touching responses
+CREATE TABLE IF NOT EXISTS respondents (
+    id TEXT PRIMARY KEY,
+    name TEXT,
+    email TEXT,
+    email_hash TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS responses (
+    id TEXT PRIMARY KEY,
+    respondent_id TEXT REFERENCES respondents(id) ON DELETE SET NULL,
+    form_id TEXT NOT NULL REFERENCES forms(id) ON DELETE CASCADE,
+    role TEXT,
+    ip_address TEXT,
+    metadata JSONB NOT NULL DEFAULT '{}',
+    submitted_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS answers (
+    id TEXT PRIMARY KEY,
+    response_id TEXT NOT NULL REFERENCES responses(id) ON DELETE CASCADE,
+    question_id TEXT NOT NULL,
+    value JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_sections_form_id ON sections(form_id);
+CREATE INDEX IF NOT EXISTS idx_questions_form_id ON questions(form_id);
+CREATE INDEX IF NOT EXISTS idx_respondents_email_hash ON respondents(email_hash);
+CREATE INDEX IF NOT EXISTS idx_responses_form_id ON responses(form_id);
+CREATE INDEX IF NOT EXISTS idx_answers_response_id ON answers(response_id);
+CREATE INDEX IF NOT EXISTS idx_answers_question_id ON answers(question_id);
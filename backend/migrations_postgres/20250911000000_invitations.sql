@@ -0,0 +1,16 @@
+-- Invitation lists: one single-use token per invitee
+CREATE TABLE IF NOT EXISTS invitations (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL REFERENCES forms(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    role TEXT,
+    token TEXT NOT NULL UNIQUE,
+    response_id TEXT REFERENCES responses(id) ON DELETE SET NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (form_id, email)
+);
+
+CREATE INDEX IF NOT EXISTS idx_invitations_form_id ON invitations(form_id);
+CREATE INDEX IF NOT EXISTS idx_invitations_role ON invitations(role);
@@ -0,0 +1,9 @@
+-- Per-form template for new-response notification emails
+CREATE TABLE IF NOT EXISTS notification_templates (
+    form_id TEXT PRIMARY KEY REFERENCES forms(id) ON DELETE CASCADE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    subject TEXT NOT NULL,
+    html_template TEXT NOT NULL,
+    include_pii BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
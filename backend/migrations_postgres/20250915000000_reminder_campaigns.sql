@@ -0,0 +1,25 @@
+-- Scheduled reminder emails to invitees who have not responded yet
+CREATE TABLE IF NOT EXISTS reminder_campaigns (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL REFERENCES forms(id) ON DELETE CASCADE,
+    subject TEXT NOT NULL,
+    body_template TEXT NOT NULL,
+    closes_at TIMESTAMPTZ NOT NULL,
+    days_before JSONB NOT NULL DEFAULT '[]',
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminder_campaigns_form_id ON reminder_campaigns(form_id);
+
+-- One row per reminder queued, so nobody is mailed twice for the same step
+CREATE TABLE IF NOT EXISTS reminder_sends (
+    id TEXT PRIMARY KEY,
+    campaign_id TEXT NOT NULL REFERENCES reminder_campaigns(id) ON DELETE CASCADE,
+    invitation_id TEXT NOT NULL REFERENCES invitations(id) ON DELETE CASCADE,
+    days_before BIGINT NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (campaign_id, invitation_id, days_before)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminder_sends_campaign_id ON reminder_sends(campaign_id);
@@ -0,0 +1,25 @@
+-- Daily/weekly digest emails per admin and form
+CREATE TABLE IF NOT EXISTS digest_subscriptions (
+    id TEXT PRIMARY KEY,
+    form_id TEXT NOT NULL REFERENCES forms(id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    frequency TEXT NOT NULL CHECK (frequency IN ('daily', 'weekly')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (form_id, email)
+);
+
+CREATE INDEX IF NOT EXISTS idx_digest_subscriptions_form_id ON digest_subscriptions(form_id);
+
+-- One row per digest sent; period_end of the latest run is where the next digest starts
+CREATE TABLE IF NOT EXISTS digest_runs (
+    id TEXT PRIMARY KEY,
+    subscription_id TEXT NOT NULL REFERENCES digest_subscriptions(id) ON DELETE CASCADE,
+    period_start TIMESTAMPTZ NOT NULL,
+    period_end TIMESTAMPTZ NOT NULL,
+    new_responses BIGINT NOT NULL,
+    total_responses BIGINT NOT NULL,
+    average_score DOUBLE PRECISION,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_digest_runs_subscription_id ON digest_runs(subscription_id);
@@ -0,0 +1,13 @@
+-- Full-text index over free-text answers and answer comments, kept in step with the
+-- answers by the repository: rows are written on submit and go with their response.
+CREATE TABLE IF NOT EXISTS answer_search (
+    id BIGSERIAL PRIMARY KEY,
+    response_id TEXT NOT NULL REFERENCES responses(id) ON DELETE CASCADE,
+    question_id TEXT NOT NULL,
+    field TEXT NOT NULL,
+    body TEXT NOT NULL,
+    document TSVECTOR GENERATED ALWAYS AS (to_tsvector('english', body)) STORED
+);
+
+CREATE INDEX IF NOT EXISTS idx_answer_search_response_id ON answer_search(response_id);
+CREATE INDEX IF NOT EXISTS idx_answer_search_document ON answer_search USING GIN (document);
@@ -0,0 +1,12 @@
+-- Form definitions saved for reuse; the built-in templates ship in the binary instead
+CREATE TABLE IF NOT EXISTS form_templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    category TEXT,
+    source_form_id TEXT,
+    definition JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_form_templates_category ON form_templates(category);
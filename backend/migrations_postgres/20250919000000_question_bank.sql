@@ -0,0 +1,13 @@
+-- Questions shared across forms. Forms hold copies that point back here through the
+-- `bankId` feature, which is how analytics match a question from one cycle to the next.
+CREATE TABLE IF NOT EXISTS question_bank (
+    id TEXT PRIMARY KEY,
+    question_type TEXT NOT NULL,
+    title TEXT NOT NULL,
+    features JSONB NOT NULL DEFAULT '{}',
+    tags JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_questions_bank_id ON questions((features ->> 'bankId'));
@@ -0,0 +1,965 @@
+//! Statistics on rating samples: summaries, significance tests, effect sizes and bootstrap
+//! confidence intervals, and the admin endpoints that run them on a form's likert ratings.
+//!
+//! There is no statistics crate in the tree, so the distribution functions the tests need
+//! are implemented here from the usual series and continued fractions. p-values of the rank
+//! tests use the normal and chi-squared approximations with a correction for ties, which
+//! suits likert data, where ties are the rule.
+
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    error::AppError,
+    handlers::{check_admin_auth, AuthQuery},
+    repository::FormDefinition,
+    AppState,
+};
+
+pub fn mean(sample: &[f64]) -> Option<f64> {
+    (!sample.is_empty()).then(|| sample.iter().sum::<f64>() / sample.len() as f64)
+}
+
+/// Sample variance, with `n - 1` in the denominator
+pub fn variance(sample: &[f64]) -> Option<f64> {
+    let mean = mean(sample)?;
+    (sample.len() > 1)
+        .then(|| sample.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (sample.len() - 1) as f64)
+}
+
+/// Result of Welch's unequal-variances t-test
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TTest {
+    pub t: f64,
+    /// Welch–Satterthwaite degrees of freedom
+    pub df: f64,
+    /// Two-sided
+    pub p_value: f64,
+}
+
+/// Welch's t-test of `a` against `b`. `None` when either sample has fewer than two values
+/// or neither varies, since the statistic is undefined then.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TTest> {
+    let (mean_a, mean_b) = (mean(a)?, mean(b)?);
+    let se_a = variance(a)? / a.len() as f64;
+    let se_b = variance(b)? / b.len() as f64;
+    let se = se_a + se_b;
+    if se <= 0.0 {
+        return None;
+    }
+
+    let t = (mean_a - mean_b) / se.sqrt();
+    let df =
+        se.powi(2) / (se_a.powi(2) / (a.len() - 1) as f64 + se_b.powi(2) / (b.len() - 1) as f64);
+    Some(TTest {
+        t,
+        df,
+        p_value: student_t_two_sided(t, df),
+    })
+}
+
+/// Mean, standard deviation and a bootstrap confidence interval for the mean
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Summary {
+    pub n: usize,
+    pub mean: Option<f64>,
+    pub sd: Option<f64>,
+    pub ci: Option<[f64; 2]>,
+}
+
+pub fn summarize(sample: &[f64], bootstrap: &Bootstrap) -> Summary {
+    Summary {
+        n: sample.len(),
+        mean: mean(sample),
+        sd: variance(sample).map(f64::sqrt),
+        ci: bootstrap.mean_interval(sample),
+    }
+}
+
+/// Result of the Mann–Whitney U test
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MannWhitney {
+    /// U of the first sample: the pairs in which it is higher, ties counting half
+    pub u: f64,
+    /// Normal approximation, with continuity and tie corrections
+    pub z: f64,
+    /// Two-sided
+    pub p_value: f64,
+    /// 2U / (n₁n₂) − 1: from −1 (every value of the first sample lower) to 1 (every higher)
+    pub rank_biserial: f64,
+}
+
+/// The Mann–Whitney U test of `a` against `b`. `None` when a sample is empty or every
+/// value is the same.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<MannWhitney> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let n = n_a + n_b;
+    let pooled: Vec<f64> = a.iter().chain(b).copied().collect();
+    let (ranks, ties) = rank(&pooled);
+
+    let rank_sum_a: f64 = ranks[..a.len()].iter().sum();
+    let u = rank_sum_a - n_a * (n_a + 1.0) / 2.0;
+    let expected = n_a * n_b / 2.0;
+    let variance = n_a * n_b / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return None;
+    }
+
+    let distance = ((u - expected).abs() - 0.5).max(0.0);
+    let z = distance.copysign(u - expected) / variance.sqrt();
+    Some(MannWhitney {
+        u,
+        z,
+        p_value: normal_two_sided(z),
+        rank_biserial: 2.0 * u / (n_a * n_b) - 1.0,
+    })
+}
+
+/// Result of the Kruskal–Wallis H test
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct KruskalWallis {
+    /// Corrected for ties
+    pub h: f64,
+    pub df: usize,
+    /// From the chi-squared distribution with `df` degrees of freedom
+    pub p_value: f64,
+    /// H / (N − 1), the share of rank variance explained by the groups
+    pub epsilon_squared: f64,
+}
+
+/// The Kruskal–Wallis H test across `groups`. Empty groups are left out; `None` unless two
+/// or more remain and the values are not all the same.
+pub fn kruskal_wallis(groups: &[&[f64]]) -> Option<KruskalWallis> {
+    let groups: Vec<&[f64]> = groups
+        .iter()
+        .copied()
+        .filter(|group| !group.is_empty())
+        .collect();
+    if groups.len() < 2 {
+        return None;
+    }
+    let pooled: Vec<f64> = groups
+        .iter()
+        .flat_map(|group| group.iter())
+        .copied()
+        .collect();
+    let n = pooled.len() as f64;
+    let (ranks, ties) = rank(&pooled);
+
+    let mut offset = 0;
+    let mut between = 0.0;
+    for group in &groups {
+        let rank_sum: f64 = ranks[offset..offset + group.len()].iter().sum();
+        between += rank_sum.powi(2) / group.len() as f64;
+        offset += group.len();
+    }
+    let correction = 1.0 - ties / (n.powi(3) - n);
+    if correction <= 0.0 {
+        return None;
+    }
+
+    let h = (12.0 / (n * (n + 1.0)) * between - 3.0 * (n + 1.0)) / correction;
+    let df = groups.len() - 1;
+    Some(KruskalWallis {
+        h,
+        df,
+        p_value: chi_squared_upper_tail(h, df as f64),
+        epsilon_squared: h / (n - 1.0),
+    })
+}
+
+/// Standardized differences in means between two samples
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EffectSizes {
+    /// Difference in means over the pooled standard deviation
+    pub cohens_d: f64,
+    /// Cohen's d corrected for small samples
+    pub hedges_g: f64,
+}
+
+/// Effect sizes of `a` against `b`. `None` when either sample has fewer than two values or
+/// neither varies.
+pub fn effect_sizes(a: &[f64], b: &[f64]) -> Option<EffectSizes> {
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let pooled = ((n_a - 1.0) * variance(a)? + (n_b - 1.0) * variance(b)?) / (n_a + n_b - 2.0);
+    if pooled <= 0.0 {
+        return None;
+    }
+
+    let cohens_d = (mean(a)? - mean(b)?) / pooled.sqrt();
+    Some(EffectSizes {
+        cohens_d,
+        hedges_g: cohens_d * (1.0 - 3.0 / (4.0 * (n_a + n_b) - 9.0)),
+    })
+}
+
+/// Average ranks (1-based) of `values`, with Σ(t³ − t) over groups of t tied values
+fn rank(values: &[f64]) -> (Vec<f64>, f64) {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&i, &j| values[i].total_cmp(&values[j]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut ties = 0.0;
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let average = (start + end + 1) as f64 / 2.0;
+        for &index in &order[start..end] {
+            ranks[index] = average;
+        }
+        let tied = (end - start) as f64;
+        ties += tied.powi(3) - tied;
+        start = end;
+    }
+    (ranks, ties)
+}
+
+/// Percentile bootstrap settings. The generator is seeded, so the same request gives the
+/// same interval every time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Bootstrap {
+    pub confidence: f64,
+    pub resamples: usize,
+    pub seed: u64,
+}
+
+impl Default for Bootstrap {
+    fn default() -> Self {
+        Self {
+            confidence: 0.95,
+            resamples: 2000,
+            seed: 1,
+        }
+    }
+}
+
+impl Bootstrap {
+    const MAX_RESAMPLES: usize = 20_000;
+
+    /// Settings from optional request values, checked
+    pub fn from_options(
+        confidence: Option<f64>,
+        resamples: Option<usize>,
+        seed: Option<u64>,
+    ) -> Result<Self, AppError> {
+        let default = Self::default();
+        let bootstrap = Self {
+            confidence: confidence.unwrap_or(default.confidence),
+            resamples: resamples.unwrap_or(default.resamples),
+            seed: seed.unwrap_or(default.seed),
+        };
+        if !(0.5..1.0).contains(&bootstrap.confidence) {
+            return Err(AppError::BadRequest(
+                "confidence must be at least 0.5 and below 1".to_string(),
+            ));
+        }
+        if !(100..=Self::MAX_RESAMPLES).contains(&bootstrap.resamples) {
+            return Err(AppError::BadRequest(format!(
+                "resamples must be between 100 and {}",
+                Self::MAX_RESAMPLES
+            )));
+        }
+        Ok(bootstrap)
+    }
+
+    /// Confidence interval for the mean of `sample`
+    pub fn mean_interval(&self, sample: &[f64]) -> Option<[f64; 2]> {
+        if sample.is_empty() {
+            return None;
+        }
+        let mut rng = SplitMix64(self.seed);
+        let means = (0..self.resamples)
+            .map(|_| resample_mean(sample, &mut rng))
+            .collect();
+        Some(self.interval(means))
+    }
+
+    /// Confidence interval for mean(a) − mean(b), resampling each independently
+    pub fn difference_interval(&self, a: &[f64], b: &[f64]) -> Option<[f64; 2]> {
+        if a.is_empty() || b.is_empty() {
+            return None;
+        }
+        let mut rng = SplitMix64(self.seed);
+        let differences = (0..self.resamples)
+            .map(|_| resample_mean(a, &mut rng) - resample_mean(b, &mut rng))
+            .collect();
+        Some(self.interval(differences))
+    }
+
+    fn interval(&self, mut estimates: Vec<f64>) -> [f64; 2] {
+        estimates.sort_by(f64::total_cmp);
+        let tail = (1.0 - self.confidence) / 2.0;
+        [quantile(&estimates, tail), quantile(&estimates, 1.0 - tail)]
+    }
+}
+
+/// The mean of a same-size sample drawn with replacement
+fn resample_mean(sample: &[f64], rng: &mut SplitMix64) -> f64 {
+    (0..sample.len())
+        .map(|_| sample[rng.below(sample.len())])
+        .sum::<f64>()
+        / sample.len() as f64
+}
+
+/// Quantile of sorted values, interpolating linearly between neighbours
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+/// A small seedable generator; the bootstrap needs reproducibility, not cryptography
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`
+    fn below(&mut self, bound: usize) -> usize {
+        ((self.next() as u128 * bound as u128) >> 64) as usize
+    }
+}
+
+/// P(|Z| >= |z|) for the standard normal
+fn normal_two_sided(z: f64) -> f64 {
+    regularized_gamma_q(0.5, z * z / 2.0)
+}
+
+/// P(X >= x) for chi-squared with `df` degrees of freedom
+fn chi_squared_upper_tail(x: f64, df: f64) -> f64 {
+    regularized_gamma_q(df / 2.0, x / 2.0)
+}
+
+/// P(|T| >= |t|) for Student's t with `df` degrees of freedom
+fn student_t_two_sided(t: f64, df: f64) -> f64 {
+    regularized_incomplete_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+/// ln Γ(x) for x > 0 (Lanczos approximation, g = 7)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection keeps the approximation in its accurate range
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, coefficient) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += coefficient / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// I_x(a, b), the regularized incomplete beta function
+fn regularized_incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly only below this point; use the symmetry
+    // I_x(a, b) = 1 - I_(1-x)(b, a) above it
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// The continued fraction for I_x(a, b), evaluated with the modified Lentz method
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    const EPSILON: f64 = 1e-15;
+    let nonzero = |value: f64| if value.abs() < TINY { TINY } else { value };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / nonzero(1.0 - (a + b) * x / (a + 1.0));
+    let mut fraction = d;
+    for m in 1..=500 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / nonzero(1.0 + even * d);
+        c = nonzero(1.0 + even / c);
+        fraction *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / nonzero(1.0 + odd * d);
+        c = nonzero(1.0 + odd / c);
+        let step = d * c;
+        fraction *= step;
+        if (step - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    fraction
+}
+
+/// Q(a, x) = Γ(a, x) / Γ(a), the regularized upper incomplete gamma function
+fn regularized_gamma_q(a: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    const EPSILON: f64 = 1e-15;
+    if x <= 0.0 {
+        return 1.0;
+    }
+
+    let front = (a * x.ln() - x - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // The series for P(a, x) converges quickly here
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..=1000 {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        1.0 - front * sum
+    } else {
+        // And the continued fraction for Q(a, x) here (modified Lentz)
+        let nonzero = |value: f64| if value.abs() < TINY { TINY } else { value };
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / nonzero(b);
+        let mut fraction = d;
+        for i in 1..=1000 {
+            let i = i as f64;
+            let numerator = -i * (i - a);
+            b += 2.0;
+            d = 1.0 / nonzero(numerator * d + b);
+            c = nonzero(b + numerator / c);
+            let step = d * c;
+            fraction *= step;
+            if (step - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        front * fraction
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleAnalysisQuery {
+    pub token: Option<String>,
+    /// Only this question; every likert question of the form unless given
+    pub question_id: Option<String>,
+    pub confidence: Option<f64>,
+    pub resamples: Option<usize>,
+    pub seed: Option<u64>,
+}
+
+/// A summary under the name of the role it describes
+#[derive(Debug, Serialize)]
+struct RoleGroup<'a> {
+    role: &'a str,
+    #[serde(flatten)]
+    summary: Summary,
+}
+
+async fn find_definition(state: &AppState, form_id: &str) -> Result<FormDefinition, AppError> {
+    state
+        .repos
+        .forms
+        .find_form(form_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest(format!("Form '{}' not found", form_id)))
+}
+
+/// How each role rated a form's likert questions (admin only): the overall and per-role
+/// summaries with bootstrap intervals, and a Kruskal–Wallis test across the roles.
+/// Responses without a role count toward the overall summary only.
+pub async fn analyze_roles(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(params): Query<RoleAnalysisQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+    let bootstrap = Bootstrap::from_options(params.confidence, params.resamples, params.seed)?;
+
+    let definition = find_definition(&state, &form_id).await?;
+    let ratings = state.repos.responses.likert_ratings(&form_id).await?;
+    let questions: Vec<_> = definition
+        .questions
+        .iter()
+        .filter(|question| question.kind.type_name() == "likert")
+        .filter(|question| {
+            params.question_id.is_none() || params.question_id.as_ref() == Some(&question.id)
+        })
+        .collect();
+    if params.question_id.is_some() && questions.is_empty() {
+        return Err(AppError::BadRequest(
+            "Question not found or not a likert question".to_string(),
+        ));
+    }
+
+    let results: Vec<_> = questions
+        .into_iter()
+        .map(|question| {
+            let mut overall = Vec::new();
+            let mut by_role: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+            let mut without_role = 0;
+            for rating in ratings.iter().filter(|r| r.question_id == question.id) {
+                overall.push(rating.rating as f64);
+                match &rating.role {
+                    Some(role) => by_role.entry(role).or_default().push(rating.rating as f64),
+                    None => without_role += 1,
+                }
+            }
+            let groups: Vec<&[f64]> = by_role.values().map(Vec::as_slice).collect();
+            let kruskal_wallis = kruskal_wallis(&groups);
+            let roles: Vec<RoleGroup> = by_role
+                .iter()
+                .map(|(role, sample)| RoleGroup {
+                    role,
+                    summary: summarize(sample, &bootstrap),
+                })
+                .collect();
+
+            json!({
+                "question_id": question.id,
+                "title": question.title,
+                "bank_question_id": question.kind.common().bank_id,
+                "overall": summarize(&overall, &bootstrap),
+                "without_role": without_role,
+                "roles": roles,
+                "kruskal_wallis": kruskal_wallis
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "form_id": form_id,
+        "bootstrap": bootstrap,
+        "questions": results
+    })))
+}
+
+/// One side of a comparison: a likert question's ratings on a form, optionally from one role
+#[derive(Debug, Deserialize)]
+pub struct SampleSelector {
+    pub form_id: String,
+    /// The question's ID on the form...
+    pub question_id: Option<String>,
+    /// ...or its question-bank ID
+    pub bank_question_id: Option<String>,
+    /// Only responses with this role
+    pub role: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    pub a: SampleSelector,
+    pub b: SampleSelector,
+    pub confidence: Option<f64>,
+    pub resamples: Option<usize>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct SelectedSample<'a> {
+    form_id: &'a str,
+    question_id: String,
+    role: Option<&'a str>,
+    #[serde(flatten)]
+    summary: Summary,
+}
+
+impl<'a> SelectedSample<'a> {
+    fn new(
+        selector: &'a SampleSelector,
+        question_id: String,
+        sample: &[f64],
+        bootstrap: &Bootstrap,
+    ) -> Self {
+        Self {
+            form_id: &selector.form_id,
+            question_id,
+            role: selector.role.as_deref(),
+            summary: summarize(sample, bootstrap),
+        }
+    }
+}
+
+/// The ratings a selector picks out, with the form-local ID of its question
+async fn select_sample(
+    state: &AppState,
+    selector: &SampleSelector,
+) -> Result<(String, Vec<f64>), AppError> {
+    let definition = find_definition(state, &selector.form_id).await?;
+    let question = match (&selector.question_id, &selector.bank_question_id) {
+        (Some(question_id), None) => definition
+            .questions
+            .iter()
+            .find(|question| &question.id == question_id),
+        (None, Some(bank_id)) => definition
+            .questions
+            .iter()
+            .find(|question| question.kind.common().bank_id.as_ref() == Some(bank_id)),
+        _ => {
+            return Err(AppError::BadRequest(
+                "Give either question_id or bank_question_id for each sample".to_string(),
+            ))
+        }
+    };
+    let question = question
+        .filter(|question| question.kind.type_name() == "likert")
+        .ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "No such likert question on form '{}'",
+                selector.form_id
+            ))
+        })?;
+
+    let sample = state
+        .repos
+        .responses
+        .likert_ratings(&selector.form_id)
+        .await?
+        .into_iter()
+        .filter(|rating| rating.question_id == question.id)
+        .filter(|rating| selector.role.is_none() || rating.role == selector.role)
+        .map(|rating| rating.rating as f64)
+        .collect();
+    Ok((question.id.clone(), sample))
+}
+
+/// Compare two samples of likert ratings, such as two roles on one form or one question
+/// in two review cycles (admin only): summaries with bootstrap intervals, the difference
+/// in means, Welch's t-test, the Mann–Whitney U test and effect sizes
+pub async fn compare_samples(
+    headers: HeaderMap,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<CompareRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+    let bootstrap = Bootstrap::from_options(req.confidence, req.resamples, req.seed)?;
+
+    let (a_question, a) = select_sample(&state, &req.a).await?;
+    let (b_question, b) = select_sample(&state, &req.b).await?;
+    Ok(Json(json!({
+        "a": SelectedSample::new(&req.a, a_question, &a, &bootstrap),
+        "b": SelectedSample::new(&req.b, b_question, &b, &bootstrap),
+        "difference": {
+            "mean": mean(&a).zip(mean(&b)).map(|(a, b)| a - b),
+            "ci": bootstrap.difference_interval(&a, &b)
+        },
+        "welch_t_test": welch_t_test(&a, &b),
+        "mann_whitney_u": mann_whitney_u(&a, &b),
+        "effect_sizes": effect_sizes(&a, &b),
+        "bootstrap": bootstrap
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64, tolerance: f64) -> bool {
+        (actual - expected).abs() < tolerance
+    }
+
+    #[test]
+    fn distribution_functions_match_known_values() {
+        // Γ(5) = 24 and Γ(1/2) = √π
+        assert!(close(ln_gamma(5.0), 24f64.ln(), 1e-12));
+        assert!(close(
+            ln_gamma(0.5),
+            std::f64::consts::PI.sqrt().ln(),
+            1e-12
+        ));
+        // With one degree of freedom t is Cauchy: P(|T| >= 1) = 1/2
+        assert!(close(student_t_two_sided(1.0, 1.0), 0.5, 1e-12));
+        // Two-sided 5% critical values from t tables
+        assert!(close(student_t_two_sided(2.228_138_85, 10.0), 0.05, 1e-6));
+        assert!(close(student_t_two_sided(1.959_963_98, 1e7), 0.05, 1e-6));
+        assert_eq!(student_t_two_sided(0.0, 4.0), 1.0);
+        assert!(close(normal_two_sided(1.959_963_985), 0.05, 1e-9));
+        assert!(close(chi_squared_upper_tail(3.841_458_82, 1.0), 0.05, 1e-9));
+        // With two degrees of freedom the upper tail is exp(-x/2)
+        assert!(close(
+            chi_squared_upper_tail(7.2, 2.0),
+            (-3.6f64).exp(),
+            1e-12
+        ));
+    }
+
+    #[test]
+    fn welch_t_test_of_shifted_samples() {
+        let test = welch_t_test(&[1.0, 2.0, 3.0, 4.0, 5.0], &[3.0, 4.0, 5.0, 6.0, 7.0]).unwrap();
+        assert!(close(test.t, -2.0, 1e-12));
+        assert!(close(test.df, 8.0, 1e-12));
+        assert!(close(test.p_value, 0.080_516, 1e-5));
+
+        assert_eq!(welch_t_test(&[4.0], &[1.0, 2.0]), None);
+        assert_eq!(welch_t_test(&[3.0, 3.0], &[3.0, 3.0, 3.0]), None);
+    }
+
+    #[test]
+    fn rank_tests_correct_for_ties() {
+        let separated = mann_whitney_u(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]).unwrap();
+        assert_eq!(separated.u, 0.0);
+        assert_eq!(separated.rank_biserial, -1.0);
+        assert!(close(separated.z, -1.745_743_12, 1e-8));
+        assert!(close(separated.p_value, 0.080_855_598, 1e-8));
+
+        let tied = mann_whitney_u(&[1.0, 2.0, 2.0, 3.0, 4.0], &[3.0, 3.0, 4.0, 5.0, 5.0]).unwrap();
+        assert_eq!(tied.u, 3.5);
+        assert!(close(tied.rank_biserial, -0.72, 1e-12));
+        assert!(close(tied.p_value, 0.069_601_003, 1e-8));
+        assert_eq!(mann_whitney_u(&[3.0, 3.0], &[3.0]), None);
+        assert_eq!(mann_whitney_u(&[], &[3.0]), None);
+
+        let distinct =
+            kruskal_wallis(&[&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0], &[7.0, 8.0, 9.0]]).unwrap();
+        assert!(close(distinct.h, 7.2, 1e-12));
+        assert_eq!(distinct.df, 2);
+        assert!(close(distinct.epsilon_squared, 0.9, 1e-12));
+
+        let tied = kruskal_wallis(&[
+            &[1.0, 2.0, 2.0, 3.0],
+            &[3.0, 3.0, 4.0],
+            &[],
+            &[4.0, 5.0, 5.0, 5.0],
+        ])
+        .unwrap();
+        assert!(close(tied.h, 8.371_031_746, 1e-8));
+        assert_eq!(tied.df, 2);
+        assert!(close(tied.p_value, 0.015_214_355, 1e-8));
+        assert_eq!(kruskal_wallis(&[&[1.0, 2.0], &[]]), None);
+        assert_eq!(kruskal_wallis(&[&[4.0], &[4.0, 4.0]]), None);
+    }
+
+    #[test]
+    fn effect_sizes_of_shifted_samples() {
+        let sizes = effect_sizes(&[1.0, 2.0, 3.0, 4.0, 5.0], &[3.0, 4.0, 5.0, 6.0, 7.0]).unwrap();
+        assert!(close(sizes.cohens_d, -2.0 / 2.5f64.sqrt(), 1e-12));
+        assert!(close(sizes.hedges_g, sizes.cohens_d * 28.0 / 31.0, 1e-12));
+        assert_eq!(effect_sizes(&[2.0, 2.0], &[2.0, 2.0]), None);
+    }
+
+    #[test]
+    fn bootstrap_intervals_are_reproducible() {
+        let bootstrap = Bootstrap::default();
+        let sample = [1.0, 2.0, 2.0, 3.0, 4.0, 4.0, 5.0, 5.0];
+
+        let [low, high] = bootstrap.mean_interval(&sample).unwrap();
+        assert!(1.0 < low && low < 3.25 && 3.25 < high && high < 5.0);
+        assert_eq!(bootstrap.mean_interval(&sample), Some([low, high]));
+        let narrower = Bootstrap {
+            confidence: 0.5,
+            ..bootstrap
+        };
+        let [narrow_low, narrow_high] = narrower.mean_interval(&sample).unwrap();
+        assert!(low < narrow_low && narrow_high < high);
+
+        assert_eq!(bootstrap.mean_interval(&[4.0, 4.0]), Some([4.0, 4.0]));
+        assert_eq!(bootstrap.mean_interval(&[]), None);
+        let [low, high] = bootstrap.difference_interval(&sample, &[1.0, 1.0]).unwrap();
+        assert!(low < 2.25 && 2.25 < high);
+
+        assert!(Bootstrap::from_options(Some(1.0), None, None).is_err());
+        assert!(Bootstrap::from_options(None, Some(10), None).is_err());
+        assert_eq!(
+            Bootstrap::from_options(None, None, Some(7)).unwrap().seed,
+            7
+        );
+    }
+
+    #[tokio::test]
+    async fn compares_roles_and_cycles() {
+        use std::sync::Arc;
+
+        use serde_json::Value as JsonValue;
+
+        use crate::{
+            questions::QuestionKind,
+            repository::{
+                memory::{MemoryStore, StoredResponse},
+                Form, Question,
+            },
+        };
+
+        let store = Arc::new(MemoryStore::default());
+        let state = store.app_state();
+        for (form_id, question_id) in [("ed-2024", "q1"), ("ed-2025", "q9")] {
+            state
+                .repos
+                .forms
+                .create_form(&FormDefinition {
+                    form: Form {
+                        id: form_id.to_string(),
+                        title: "ED Review".to_string(),
+                        description: None,
+                        instructions: None,
+                        status: "published".to_string(),
+                        welcome_message: None,
+                        closing_message: None,
+                        settings: None,
+                    },
+                    sections: Vec::new(),
+                    questions: vec![Question {
+                        id: question_id.to_string(),
+                        form_id: form_id.to_string(),
+                        section_id: None,
+                        position: 1,
+                        title: "Sets a clear vision".to_string(),
+                        description: None,
+                        kind: QuestionKind::from_parts("likert", &json!({ "bankId": "vision" }))
+                            .unwrap(),
+                    }],
+                })
+                .await
+                .unwrap();
+        }
+        let ratings_2024 = [
+            ("Staff", 2),
+            ("Staff", 3),
+            ("Staff", 2),
+            ("Board", 4),
+            ("Board", 5),
+        ];
+        let ratings_2025 = [("Staff", 4), ("Staff", 5), ("Staff", 4), ("Board", 5)];
+        for (form_id, question_id, ratings) in [
+            ("ed-2024", "q1", &ratings_2024[..]),
+            ("ed-2025", "q9", &ratings_2025[..]),
+        ] {
+            for (role, rating) in ratings {
+                store.add_response(StoredResponse {
+                    form_id: form_id.to_string(),
+                    role: Some(role.to_string()),
+                    email_hash: None,
+                    answers: vec![(question_id.to_string(), json!(rating))],
+                    ..Default::default()
+                });
+            }
+        }
+        store.add_response(StoredResponse {
+            form_id: "ed-2024".to_string(),
+            role: None,
+            email_hash: None,
+            answers: vec![("q1".to_string(), json!(3))],
+            ..Default::default()
+        });
+        let body = |response: axum::response::Response| async {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<JsonValue>(&bytes).unwrap()
+        };
+
+        let roles = body(
+            analyze_roles(
+                HeaderMap::new(),
+                Path("ed-2024".to_string()),
+                Query(RoleAnalysisQuery {
+                    token: Some("admin".to_string()),
+                    question_id: None,
+                    confidence: None,
+                    resamples: None,
+                    seed: None,
+                }),
+                State(state.clone()),
+            )
+            .await
+            .unwrap()
+            .into_response(),
+        )
+        .await;
+        let question = &roles["questions"][0];
+        assert_eq!(question["bank_question_id"], "vision");
+        assert_eq!(question["overall"]["n"], 6);
+        assert_eq!(question["without_role"], 1);
+        assert_eq!(question["roles"][0]["role"], "Board");
+        assert_eq!(question["roles"][0]["n"], 2);
+        assert!(close(
+            question["roles"][1]["mean"].as_f64().unwrap(),
+            7.0 / 3.0,
+            1e-12
+        ));
+        assert_eq!(question["kruskal_wallis"]["df"], 1);
+        assert_eq!(roles["bootstrap"]["resamples"], 2000);
+
+        // The same bank question in two cycles, under different local IDs
+        let compare = |request: JsonValue| {
+            compare_samples(
+                HeaderMap::new(),
+                Query(AuthQuery {
+                    token: Some("admin".to_string()),
+                }),
+                State(state.clone()),
+                Json(serde_json::from_value(request).unwrap()),
+            )
+        };
+        let cycles = body(
+            compare(json!({
+                "a": { "form_id": "ed-2025", "bank_question_id": "vision", "role": "Staff" },
+                "b": { "form_id": "ed-2024", "question_id": "q1", "role": "Staff" },
+                "seed": 42
+            }))
+            .await
+            .unwrap()
+            .into_response(),
+        )
+        .await;
+        assert_eq!(cycles["a"]["question_id"], "q9");
+        assert_eq!(cycles["a"]["n"], 3);
+        assert_eq!(cycles["b"]["n"], 3);
+        assert!(close(
+            cycles["difference"]["mean"].as_f64().unwrap(),
+            2.0,
+            1e-12
+        ));
+        assert_eq!(cycles["mann_whitney_u"]["u"], 9.0);
+        assert_eq!(cycles["mann_whitney_u"]["rank_biserial"], 1.0);
+        assert!(cycles["welch_t_test"]["p_value"].as_f64().unwrap() < 0.05);
+        assert!(cycles["effect_sizes"]["hedges_g"].as_f64().unwrap() > 0.0);
+        assert_eq!(cycles["bootstrap"]["seed"], 42);
+
+        let ambiguous = compare(json!({
+            "a": { "form_id": "ed-2025" },
+            "b": { "form_id": "ed-2024", "question_id": "q1" }
+        }))
+        .await;
+        assert!(matches!(ambiguous, Err(AppError::BadRequest(_))));
+    }
+}
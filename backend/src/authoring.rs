@@ -0,0 +1,550 @@
+//! Form definitions in the formats people write them in: JSON, YAML and a small Markdown
+//! dialect (see `markdown`).
+//!
+//! Every format is read into the same JSON document, which `import_form` then checks
+//! against the schema, so the rules and error pointers do not depend on the format.
+//! Stored forms render back into any of them through [`export_definition`].
+
+mod markdown;
+
+use axum::{
+    body::Body,
+    extract::{FromRequest, Path, Query, Request, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::{
+    error::AppError,
+    handlers::{check_admin_auth, ImportFormRequest, ImportQuestion, ImportSection},
+    repository::FormDefinition,
+    AppState,
+};
+
+/// Keys of `features` that the import format carries as question fields instead
+const LIFTED_FEATURES: [&str; 3] = ["required", "allowComment", "helpText"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DefinitionFormat {
+    Json,
+    Yaml,
+    Markdown,
+}
+
+impl DefinitionFormat {
+    /// The format named by `?format=`, JSON when absent
+    fn parse(format: Option<&str>) -> Result<Self, AppError> {
+        match format.unwrap_or("json") {
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            "markdown" => Ok(Self::Markdown),
+            other => Err(AppError::BadRequest(format!(
+                "Unknown definition format '{}'. Must be json, yaml or markdown",
+                other
+            ))),
+        }
+    }
+
+    /// The format of a request body; JSON unless the `Content-Type` says otherwise
+    fn from_headers(headers: &HeaderMap) -> Result<Self, AppError> {
+        let Some(content_type) = headers.get(header::CONTENT_TYPE) else {
+            return Ok(Self::Json);
+        };
+        let content_type = content_type.to_str().unwrap_or_default();
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+
+        match essence.to_ascii_lowercase().as_str() {
+            "application/json" => Ok(Self::Json),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Ok(Self::Yaml)
+            }
+            "text/markdown" | "text/x-markdown" => Ok(Self::Markdown),
+            _ => Err(AppError::BadRequest(format!(
+                "Unsupported Content-Type '{}'. Send application/json, application/yaml or text/markdown",
+                content_type
+            ))),
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Yaml => "application/yaml; charset=utf-8",
+            Self::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Markdown => "md",
+        }
+    }
+
+    /// Read a definition into the JSON document the import schema describes
+    pub fn read(self, text: &str) -> Result<JsonValue, AppError> {
+        match self {
+            Self::Json => serde_json::from_str(text)
+                .map_err(|e| AppError::BadRequest(format!("Invalid JSON: {}", e))),
+            Self::Yaml => serde_yaml::from_str(text)
+                .map_err(|e| AppError::BadRequest(format!("Invalid YAML: {}", e))),
+            Self::Markdown => markdown::parse(text).map_err(AppError::InvalidDefinition),
+        }
+    }
+
+    pub fn render(self, definition: &ImportFormRequest) -> Result<String, AppError> {
+        let rendered = match self {
+            Self::Json => serde_json::to_string_pretty(definition).map_err(|e| e.to_string()),
+            Self::Yaml => serde_yaml::to_string(definition).map_err(|e| e.to_string()),
+            Self::Markdown => serde_json::to_value(definition)
+                .map_err(|e| e.to_string())
+                .and_then(|document| markdown::render(&document)),
+        };
+        rendered.map_err(AppError::InternalError)
+    }
+}
+
+/// An import body in whichever format its `Content-Type` names, read into JSON
+pub struct Definition(pub JsonValue);
+
+impl<S: Send + Sync> FromRequest<S> for Definition {
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let format = DefinitionFormat::from_headers(req.headers())?;
+        let text = String::from_request(req, state)
+            .await
+            .map_err(|e| AppError::BadRequest(e.body_text()))?;
+        format.read(&text).map(Definition)
+    }
+}
+
+impl From<&FormDefinition> for ImportFormRequest {
+    /// The import document for a stored form. `required`, `allowComment` and `helpText`
+    /// move from `features` back to the question fields; questions outside any section
+    /// are left out, as the format has no place for them.
+    fn from(definition: &FormDefinition) -> Self {
+        let form = &definition.form;
+        let mut sections: Vec<_> = definition.sections.iter().collect();
+        sections.sort_by_key(|section| section.position);
+
+        let sections = sections
+            .into_iter()
+            .map(|section| {
+                let mut questions: Vec<_> = definition
+                    .questions
+                    .iter()
+                    .filter(|question| question.section_id.as_deref() == Some(&section.id))
+                    .collect();
+                questions.sort_by_key(|question| question.position);
+
+                let questions = questions
+                    .into_iter()
+                    .zip(1..)
+                    .map(|(question, position)| {
+                        let mut features = match question.kind.features() {
+                            JsonValue::Object(features) => features,
+                            _ => Map::new(),
+                        };
+                        for key in LIFTED_FEATURES {
+                            features.remove(key);
+                        }
+                        let common = question.kind.common();
+
+                        ImportQuestion {
+                            id: question.id.clone(),
+                            title: question.title.clone(),
+                            question_type: question.kind.type_name().to_string(),
+                            is_required: common.required,
+                            allow_comment: common.allow_comment.unwrap_or(false),
+                            help_text: common.help_text.clone(),
+                            position,
+                            features: (!features.is_empty()).then_some(JsonValue::Object(features)),
+                        }
+                    })
+                    .collect();
+
+                ImportSection {
+                    id: section.id.clone(),
+                    title: section.title.clone(),
+                    description: section.description.clone(),
+                    position: section.position,
+                    questions,
+                }
+            })
+            .collect();
+
+        ImportFormRequest {
+            id: form.id.clone(),
+            title: form.title.clone(),
+            description: form.description.clone(),
+            instructions: form.instructions.clone(),
+            status: form.status.clone(),
+            welcome_message: form.welcome_message.clone(),
+            closing_message: form.closing_message.clone(),
+            settings: form.settings.clone(),
+            sections,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DefinitionQuery {
+    pub token: Option<String>,
+    pub format: Option<String>,
+}
+
+/// Download a form's definition as JSON, YAML or Markdown, ready for `import_form` (admin only)
+pub async fn export_definition(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(params): Query<DefinitionQuery>,
+    State(state): State<AppState>,
+) -> Result<Response, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+    let format = DefinitionFormat::parse(params.format.as_deref())?;
+
+    let definition = state
+        .repos
+        .forms
+        .find_form(&form_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+    let body = format.render(&ImportFormRequest::from(&definition))?;
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{}.{}\"",
+                form_id,
+                format.extension()
+            ),
+        )
+        .body(Body::from(body))
+        .map_err(|e| AppError::InternalError(e.to_string()))
+        .map(IntoResponse::into_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        handlers::{import_form, AuthQuery, DryRunQuery},
+        repository::memory::MemoryStore,
+        schema,
+    };
+
+    /// Import `definition` into an empty store and read back what was saved
+    async fn import_fresh(definition: JsonValue) -> FormDefinition {
+        let state = Arc::new(MemoryStore::default()).app_state();
+        let id = definition["id"].as_str().unwrap().to_string();
+        import_form(
+            HeaderMap::new(),
+            Query(AuthQuery {
+                token: Some("admin".to_string()),
+            }),
+            Query(DryRunQuery::default()),
+            State(state.clone()),
+            Definition(definition),
+        )
+        .await
+        .unwrap();
+        state.repos.forms.find_form(&id).await.unwrap().unwrap()
+    }
+
+    /// Printable text, including the characters YAML and JSON have to quote
+    fn text(max: usize) -> impl Strategy<Value = String> {
+        proptest::string::string_regex(&format!("[ -~À-ÿ✓]{{1,{}}}", max)).unwrap()
+    }
+
+    /// A `question_type` with `features` that type accepts
+    fn question_kind() -> impl Strategy<Value = (&'static str, JsonValue)> {
+        let labels = || proptest::option::of(text(12));
+        let options = || proptest::collection::vec(text(20), 0..4);
+        prop_oneof![
+            (
+                prop::sample::select(vec!["text", "textarea"]),
+                proptest::option::of(text(20)),
+                proptest::option::of(1u32..2000),
+            )
+                .prop_map(|(question_type, placeholder, limit)| {
+                    (
+                        question_type,
+                        json!({ "placeholder": placeholder, "charLimit": limit }),
+                    )
+                }),
+            (
+                prop::sample::select(vec!["multiple_choice", "checkbox", "dropdown"]),
+                options(),
+            )
+                .prop_map(|(question_type, options)| (
+                    question_type,
+                    json!({ "options": options })
+                )),
+            (0i64..2, 3i64..11, labels(), labels()).prop_map(|(min, max, low, high)| {
+                let scale = json!({ "min": min, "max": max, "minLabel": low, "maxLabel": high });
+                ("likert", json!({ "scale": scale }))
+            }),
+            (
+                0i64..10,
+                10i64..100,
+                prop::sample::select(vec![0.5, 1.0, 2.5])
+            )
+                .prop_map(|(min, max, step)| (
+                    "number",
+                    json!({ "min": min, "max": max, "step": step })
+                )),
+            (prop::sample::select(vec!["stars", "numbers"])).prop_map(|style| (
+                "rating",
+                json!({ "min": 1, "max": 5, "ratingStyle": style })
+            )),
+            prop::sample::select(vec!["yes_no", "date", "section_header"])
+                .prop_map(|question_type| (question_type, json!({}))),
+        ]
+        .prop_flat_map(|(question_type, features)| {
+            // Keys no type knows about are kept as they are
+            (
+                Just(question_type),
+                Just(features),
+                proptest::option::of(text(10)),
+            )
+        })
+        .prop_map(|(question_type, mut features, extra)| {
+            let features = features.as_object_mut().unwrap();
+            features.retain(|_, value| !value.is_null());
+            if let Some(extra) = extra {
+                features.insert("legacyColour".to_string(), json!(extra));
+            }
+            (question_type, JsonValue::Object(features.clone()))
+        })
+    }
+
+    fn question() -> impl Strategy<Value = JsonValue> {
+        (
+            text(40),
+            question_kind(),
+            any::<bool>(),
+            any::<bool>(),
+            proptest::option::of(text(30)),
+        )
+            .prop_map(
+                |(title, (question_type, features), required, comment, help_text)| {
+                    json!({
+                        "id": "",
+                        "title": title,
+                        "question_type": question_type,
+                        "is_required": required,
+                        "allow_comment": comment,
+                        "help_text": help_text,
+                        "position": 0,
+                        "features": (!features.as_object().unwrap().is_empty()).then_some(features)
+                    })
+                },
+            )
+    }
+
+    /// Definitions as people write them: sections listed in any order, optional fields left out
+    fn definition() -> impl Strategy<Value = JsonValue> {
+        let section = (
+            text(30),
+            proptest::option::of(text(40)),
+            proptest::collection::vec(question(), 0..4),
+        );
+        (
+            text(30),
+            proptest::option::of(text(40)),
+            proptest::option::of(text(40)),
+            prop::sample::select(vec!["draft", "published", "finished", "archived"]),
+            proptest::option::of(any::<bool>()),
+            proptest::collection::vec(section, 1..4),
+        )
+            .prop_flat_map(|form| {
+                let positions: Vec<i32> = (1..=form.5.len() as i32).collect();
+                (Just(form), Just(positions).prop_shuffle())
+            })
+            .prop_map(
+                |((title, description, closing, status, anonymous, sections), positions)| {
+                    let sections: Vec<JsonValue> = sections
+                        .into_iter()
+                        .zip(positions)
+                        .map(|((title, description, questions), position)| {
+                            let questions: Vec<JsonValue> = questions
+                                .into_iter()
+                                .zip(1..)
+                                .map(|(mut question, index)| {
+                                    question["id"] = json!(format!("s{}q{}", position, index));
+                                    question["position"] = json!(index);
+                                    question
+                                })
+                                .collect();
+                            json!({
+                                "id": format!("s{}", position),
+                                "title": title,
+                                "description": description,
+                                "position": position,
+                                "questions": questions
+                            })
+                        })
+                        .collect();
+                    json!({
+                        "id": "review",
+                        "title": title,
+                        "description": description,
+                        "closing_message": closing,
+                        "status": status,
+                        "settings": anonymous.map(|anonymous| json!({ "allowAnonymous": anonymous })),
+                        "sections": sections
+                    })
+                },
+            )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn import_export_import_is_lossless(definition in definition()) {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            let stored = runtime.block_on(import_fresh(definition));
+            let exported = ImportFormRequest::from(&stored);
+            let document = serde_json::to_value(&exported).unwrap();
+            prop_assert_eq!(schema::validate(&document), Vec::new());
+
+            // Markdown can't carry every string, so it is checked by the example test only
+            for format in [DefinitionFormat::Json, DefinitionFormat::Yaml] {
+                let text = format.render(&exported).unwrap();
+                let reimported = runtime.block_on(import_fresh(format.read(&text).unwrap()));
+                prop_assert_eq!(&reimported, &stored, "{:?}:\n{}", format, text);
+                prop_assert_eq!(
+                    serde_json::to_value(ImportFormRequest::from(&reimported)).unwrap(),
+                    document.clone()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn picks_the_format_from_the_content_type() {
+        let format = |content_type: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            if let Some(content_type) = content_type {
+                headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+            }
+            DefinitionFormat::from_headers(&headers)
+        };
+
+        assert_eq!(format(None).unwrap(), DefinitionFormat::Json);
+        assert_eq!(
+            format(Some("application/json; charset=utf-8")).unwrap(),
+            DefinitionFormat::Json
+        );
+        assert_eq!(
+            format(Some("application/x-yaml")).unwrap(),
+            DefinitionFormat::Yaml
+        );
+        assert_eq!(
+            format(Some("Text/Markdown")).unwrap(),
+            DefinitionFormat::Markdown
+        );
+        assert!(matches!(
+            format(Some("text/csv")),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn every_format_reads_back_what_it_exported() {
+        let state = Arc::new(MemoryStore::default()).app_state();
+        let yaml = r#"
+id: review
+title: Annual Review
+status: draft
+settings:
+  allowAnonymous: false
+sections:
+  - id: leadership
+    title: Leadership
+    position: 1
+    questions:
+      - id: q1
+        title: The director communicates a clear vision
+        question_type: likert
+        is_required: true
+        allow_comment: true
+        position: 1
+        features:
+          scale: { min: 1, max: 7, minLabel: Never, maxLabel: Always }
+      - id: q2
+        title: Which areas need attention?
+        question_type: checkbox
+        is_required: false
+        help_text: Pick any that apply
+        position: 2
+        features:
+          options: [Fundraising, "Board relations"]
+"#;
+
+        let imported = import_form(
+            HeaderMap::new(),
+            Query(AuthQuery {
+                token: Some("admin".to_string()),
+            }),
+            Query(DryRunQuery::default()),
+            State(state.clone()),
+            Definition(DefinitionFormat::Yaml.read(yaml).unwrap()),
+        )
+        .await;
+        assert!(imported.is_ok());
+
+        let stored = state
+            .repos
+            .forms
+            .find_form("review")
+            .await
+            .unwrap()
+            .unwrap();
+        let exported = ImportFormRequest::from(&stored);
+        let expected = serde_json::to_value(&exported).unwrap();
+        assert_eq!(
+            expected["sections"][0]["questions"][1],
+            json!({
+                "id": "q2",
+                "title": "Which areas need attention?",
+                "question_type": "checkbox",
+                "is_required": false,
+                "allow_comment": false,
+                "help_text": "Pick any that apply",
+                "position": 2,
+                "features": { "options": ["Fundraising", "Board relations"] }
+            })
+        );
+
+        for format in [
+            DefinitionFormat::Json,
+            DefinitionFormat::Yaml,
+            DefinitionFormat::Markdown,
+        ] {
+            let text = format.render(&exported).unwrap();
+            assert_eq!(
+                format.read(&text).unwrap(),
+                expected,
+                "{:?}:\n{}",
+                format,
+                text
+            );
+        }
+    }
+}
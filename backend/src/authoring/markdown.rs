@@ -0,0 +1,693 @@
+//! A Markdown dialect for writing forms by hand:
+//!
+//! ```text
+//! ---
+//! id: annual-review
+//! title: Annual Review
+//! status: draft
+//! ---
+//!
+//! # Leadership
+//! How the director sets direction.
+//!
+//! - The director communicates a clear vision {likert required scale.max=7}
+//!   Think about the last twelve months.
+//! - Which areas need attention? {checkbox comment}
+//!   - Fundraising
+//!   - Board relations
+//! ```
+//!
+//! - The YAML front matter holds the form's own fields: `id`, `title`, `status`,
+//!   `settings` and so on.
+//! - `#` starts a section. Text between the heading and the first question is its
+//!   description. The braces take `id=` and `position=`.
+//! - `-` starts a question, `text` unless the braces name another type. They also take the
+//!   `required` and `comment` flags, `id=`, and any feature as `key=value`, with dots for
+//!   nesting (`scale.minLabel="Strongly disagree"`). Values are JSON (`7`, `true`,
+//!   `"two words"`, `["a","b"]`) or a bare word.
+//! - Indented `-` lines under a question are its `options`; other indented text is its
+//!   help text.
+//!
+//! Sections get their ID from the title and questions are numbered `q1`, `q2`, ... across
+//! the form when the braces do not give one.
+
+use std::fmt::Write;
+
+use serde_json::{json, Map, Value as JsonValue};
+
+use crate::schema::FieldError;
+
+const FRONT_MATTER_ORDER: [&str; 8] = [
+    "id",
+    "title",
+    "description",
+    "instructions",
+    "status",
+    "welcome_message",
+    "closing_message",
+    "settings",
+];
+
+/// The import document for a Markdown definition, or every line that could not be read
+pub fn parse(text: &str) -> Result<JsonValue, Vec<FieldError>> {
+    let mut parser = Parser::default();
+    let mut lines = text
+        .lines()
+        .zip(1..)
+        .skip_while(|(line, _)| line.trim().is_empty());
+
+    let mut body = lines.clone();
+    if let Some((_, number)) = body.next().filter(|(line, _)| line.trim_end() == "---") {
+        let mut front = Vec::new();
+        let mut closed = false;
+        for (line, _) in body.by_ref() {
+            if line.trim_end() == "---" {
+                closed = true;
+                break;
+            }
+            front.push(line);
+        }
+
+        match serde_yaml::from_str::<JsonValue>(&front.join("\n")) {
+            _ if !closed => parser.error("", number, "the front matter is not closed with `---`"),
+            Ok(JsonValue::Object(fields)) => parser.form = fields,
+            Ok(JsonValue::Null) => {}
+            Ok(_) => parser.error(
+                "",
+                number,
+                "the front matter must be a mapping of form fields",
+            ),
+            Err(e) => parser.error("", number, &format!("front matter: {}", e)),
+        }
+        lines = body;
+    }
+
+    for (line, number) in lines {
+        parser.line(line, number);
+    }
+    parser.finish()
+}
+
+#[derive(Default)]
+struct Parser {
+    form: Map<String, JsonValue>,
+    sections: Vec<Map<String, JsonValue>>,
+    /// Description lines of the last section, kept until its first question
+    description: Vec<String>,
+    question_count: usize,
+    errors: Vec<FieldError>,
+}
+
+impl Parser {
+    fn error(&mut self, pointer: &str, number: usize, message: &str) {
+        self.errors.push(FieldError {
+            pointer: pointer.to_string(),
+            message: format!("line {}: {}", number, message),
+        });
+    }
+
+    fn section_pointer(&self) -> String {
+        format!("/sections/{}", self.sections.len().saturating_sub(1))
+    }
+
+    fn questions(&mut self) -> Option<&mut Vec<JsonValue>> {
+        self.sections
+            .last_mut()
+            .and_then(|section| section.get_mut("questions"))
+            .and_then(JsonValue::as_array_mut)
+    }
+
+    fn line(&mut self, line: &str, number: usize) {
+        let indented = line.starts_with([' ', '\t']);
+        let has_questions = self
+            .questions()
+            .is_some_and(|questions| !questions.is_empty());
+
+        if line.trim().is_empty() {
+            if !self.sections.is_empty() && !has_questions {
+                self.description.push(String::new());
+            }
+        } else if let Some(heading) = line.strip_prefix('#') {
+            if heading.starts_with('#') {
+                self.error(
+                    &self.section_pointer(),
+                    number,
+                    "only `#` headings start sections",
+                );
+                return;
+            }
+            self.section(heading, number);
+        } else if let Some(question) = line.strip_prefix("- ").filter(|_| !indented) {
+            self.question(question, number);
+        } else if indented && has_questions {
+            self.question_detail(line.trim(), number);
+        } else if !self.sections.is_empty() && !has_questions {
+            self.description.push(line.trim().to_string());
+        } else {
+            self.error(
+                "",
+                number,
+                "expected front matter, a `#` section, a `-` question or indented text under a question",
+            );
+        }
+    }
+
+    /// Store the pending description on the last section
+    fn close_section(&mut self) {
+        let description = std::mem::take(&mut self.description).join("\n");
+        let description = description.trim();
+        if let Some(section) = self.sections.last_mut() {
+            if !description.is_empty() {
+                section.insert("description".to_string(), json!(description));
+            }
+        }
+    }
+
+    fn section(&mut self, heading: &str, number: usize) {
+        self.close_section();
+        let (title, attributes) = split_attributes(heading);
+        let position = self.sections.len() + 1;
+        let mut section = Map::new();
+        section.insert("id".to_string(), json!(slug(title, position)));
+        section.insert("title".to_string(), json!(title));
+        section.insert("position".to_string(), json!(position));
+        section.insert("questions".to_string(), json!([]));
+        self.sections.push(section);
+
+        let pointer = self.section_pointer();
+        for token in self.tokens(attributes, &pointer, number) {
+            match token.split_once('=') {
+                Some(("id", value)) => {
+                    let id = word(value);
+                    self.sections
+                        .last_mut()
+                        .unwrap()
+                        .insert("id".to_string(), json!(id));
+                }
+                Some(("position", value)) => {
+                    let position = value_of(value);
+                    self.sections
+                        .last_mut()
+                        .unwrap()
+                        .insert("position".to_string(), position);
+                }
+                _ => self.error(
+                    &pointer,
+                    number,
+                    &format!(
+                        "unknown section attribute `{}`; sections take `id=` and `position=`",
+                        token
+                    ),
+                ),
+            }
+        }
+    }
+
+    fn question(&mut self, text: &str, number: usize) {
+        if self.sections.is_empty() {
+            self.error(
+                "",
+                number,
+                "questions must come after a `#` section heading",
+            );
+            return;
+        }
+        self.close_section();
+
+        let (title, attributes) = split_attributes(text);
+        self.question_count += 1;
+        let mut question_type = None;
+        let mut question = Map::new();
+        question.insert("id".to_string(), json!(format!("q{}", self.question_count)));
+        question.insert("title".to_string(), json!(title));
+        question.insert("is_required".to_string(), json!(false));
+        question.insert("allow_comment".to_string(), json!(false));
+        let mut features = Map::new();
+
+        let questions = self.questions().map_or(0, |questions| questions.len());
+        let pointer = format!("{}/questions/{}", self.section_pointer(), questions);
+        for token in self.tokens(attributes, &pointer, number) {
+            let result = match token.split_once('=') {
+                Some(("id", value)) => {
+                    question.insert("id".to_string(), json!(word(value)));
+                    Ok(())
+                }
+                Some((key, value)) => insert_path(&mut features, key, value_of(value)),
+                None if token == "required" => {
+                    question.insert("is_required".to_string(), json!(true));
+                    Ok(())
+                }
+                None if token == "comment" => {
+                    question.insert("allow_comment".to_string(), json!(true));
+                    Ok(())
+                }
+                None => match question_type.replace(token.clone()) {
+                    None => Ok(()),
+                    Some(earlier) => Err(format!(
+                        "`{}` is not a flag, and the type is already `{}`",
+                        token, earlier
+                    )),
+                },
+            };
+            if let Err(message) = result {
+                self.error(&pointer, number, &message);
+            }
+        }
+
+        question.insert(
+            "question_type".to_string(),
+            json!(question_type.as_deref().unwrap_or("text")),
+        );
+        question.insert("position".to_string(), json!(questions + 1));
+        if !features.is_empty() {
+            question.insert("features".to_string(), JsonValue::Object(features));
+        }
+        self.questions().unwrap().push(JsonValue::Object(question));
+    }
+
+    /// An indented line: an option when it is a list item, help text otherwise
+    fn question_detail(&mut self, text: &str, number: usize) {
+        let pointer = format!(
+            "{}/questions/{}",
+            self.section_pointer(),
+            self.questions()
+                .map_or(0, |questions| questions.len().saturating_sub(1))
+        );
+        let question = self
+            .questions()
+            .and_then(|questions| questions.last_mut())
+            .and_then(JsonValue::as_object_mut)
+            .unwrap();
+
+        if let Some(option) = text.strip_prefix("- ") {
+            let options = question
+                .entry("features")
+                .or_insert_with(|| json!({}))
+                .as_object_mut()
+                .and_then(|features| {
+                    features
+                        .entry("options")
+                        .or_insert_with(|| json!([]))
+                        .as_array_mut()
+                });
+            match options {
+                Some(options) => options.push(json!(option.trim())),
+                None => self.error(&pointer, number, "`options` is already set in the braces"),
+            }
+        } else {
+            match question.get_mut("help_text") {
+                Some(JsonValue::String(help_text)) => {
+                    help_text.push('\n');
+                    help_text.push_str(text);
+                }
+                _ => {
+                    question.insert("help_text".to_string(), json!(text));
+                }
+            }
+        }
+    }
+
+    /// The whitespace-separated attributes inside the braces
+    fn tokens(&mut self, attributes: Option<&str>, pointer: &str, number: usize) -> Vec<String> {
+        let Some(attributes) = attributes else {
+            return Vec::new();
+        };
+
+        let mut tokens = Vec::new();
+        let mut current = String::new();
+        let (mut quoted, mut escaped, mut depth) = (false, false, 0usize);
+        for c in attributes.chars() {
+            if quoted {
+                quoted = escaped || c != '"';
+                escaped = !escaped && c == '\\';
+                current.push(c);
+                continue;
+            }
+            match c {
+                '"' => quoted = true,
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth = depth.saturating_sub(1),
+                c if c.is_whitespace() && depth == 0 => {
+                    if !current.is_empty() {
+                        tokens.push(std::mem::take(&mut current));
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            current.push(c);
+        }
+        if quoted {
+            self.error(pointer, number, "unclosed quote in the braces");
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+        tokens
+    }
+
+    fn finish(mut self) -> Result<JsonValue, Vec<FieldError>> {
+        self.close_section();
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+
+        let sections = self.sections.into_iter().map(JsonValue::Object).collect();
+        self.form
+            .insert("sections".to_string(), JsonValue::Array(sections));
+        Ok(JsonValue::Object(self.form))
+    }
+}
+
+/// Split `Title {attributes}` at the braces that close the line
+fn split_attributes(text: &str) -> (&str, Option<&str>) {
+    let text = text.trim();
+    if !text.ends_with('}') {
+        return (text, None);
+    }
+
+    // Walk back to the matching `{`, skipping quoted values
+    let bytes = text.as_bytes();
+    let (mut depth, mut quoted) = (0usize, false);
+    for (index, &byte) in bytes.iter().enumerate().rev() {
+        match byte {
+            b'"' if index == 0 || bytes[index - 1] != b'\\' => quoted = !quoted,
+            b'}' if !quoted => depth += 1,
+            b'{' if !quoted => {
+                depth -= 1;
+                if depth == 0 {
+                    return (
+                        text[..index].trim_end(),
+                        Some(&text[index + 1..text.len() - 1]),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Unbalanced quotes; the braces still run from the last `{`, and reading them reports it
+    match text.rfind('{') {
+        Some(index) => (
+            text[..index].trim_end(),
+            Some(&text[index + 1..text.len() - 1]),
+        ),
+        None => (text, None),
+    }
+}
+
+/// A JSON literal, or the text itself when it is not one
+fn value_of(text: &str) -> JsonValue {
+    serde_json::from_str(text).unwrap_or_else(|_| json!(text))
+}
+
+/// An ID written bare or quoted
+fn word(text: &str) -> String {
+    match value_of(text) {
+        JsonValue::String(word) => word,
+        _ => text.to_string(),
+    }
+}
+
+/// Set `scale.max` and the like, creating the objects on the way
+fn insert_path(
+    features: &mut Map<String, JsonValue>,
+    path: &str,
+    value: JsonValue,
+) -> Result<(), String> {
+    let mut keys: Vec<&str> = path.split('.').collect();
+    let last = keys.pop().unwrap_or_default();
+    let mut object = features;
+    for key in keys {
+        object = object
+            .entry(key)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .ok_or_else(|| format!("`{}` is set both as a value and as an object", key))?;
+    }
+    object.insert(last.to_string(), value);
+    Ok(())
+}
+
+fn slug(title: &str, position: usize) -> String {
+    let slug = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        format!("section-{}", position)
+    } else {
+        slug
+    }
+}
+
+/// Render an import document in the dialect; `parse` reads it back unchanged
+pub fn render(document: &JsonValue) -> Result<String, String> {
+    let mut front = document.as_object().cloned().unwrap_or_default();
+    let sections = front.remove("sections").unwrap_or_else(|| json!([]));
+    front.retain(|_, value| !value.is_null());
+
+    // The form's fields in the order an author reads them, anything else after
+    let mut ordered = serde_yaml::Mapping::new();
+    for key in FRONT_MATTER_ORDER {
+        if let Some(value) = front.remove(key) {
+            ordered.insert(key.into(), to_yaml(&value)?);
+        }
+    }
+    for (key, value) in &front {
+        ordered.insert(key.as_str().into(), to_yaml(value)?);
+    }
+
+    let mut out = String::from("---\n");
+    out.push_str(&serde_yaml::to_string(&ordered).map_err(|e| e.to_string())?);
+    out.push_str("---\n");
+
+    for (index, section) in sections.as_array().into_iter().flatten().enumerate() {
+        let mut attributes = vec![format!("id={}", inline(&section["id"]))];
+        if section["position"] != json!(index + 1) {
+            attributes.push(format!("position={}", inline(&section["position"])));
+        }
+        let _ = writeln!(
+            out,
+            "\n# {} {{{}}}",
+            text_of(&section["title"]),
+            attributes.join(" ")
+        );
+        if let Some(description) = section["description"].as_str() {
+            let _ = writeln!(out, "{}", description);
+        }
+        out.push('\n');
+
+        for question in section["questions"].as_array().into_iter().flatten() {
+            let mut features = question["features"]
+                .as_object()
+                .cloned()
+                .unwrap_or_default();
+            let options = match features.remove("options") {
+                Some(JsonValue::Array(options)) if options.iter().all(JsonValue::is_string) => {
+                    options
+                }
+                Some(other) => {
+                    features.insert("options".to_string(), other);
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+
+            let mut attributes = vec![text_of(&question["question_type"]).to_string()];
+            if question["is_required"] == json!(true) {
+                attributes.push("required".to_string());
+            }
+            if question["allow_comment"] == json!(true) {
+                attributes.push("comment".to_string());
+            }
+            attributes.push(format!("id={}", inline(&question["id"])));
+            flatten("", &features, &mut attributes);
+
+            let _ = writeln!(
+                out,
+                "- {} {{{}}}",
+                text_of(&question["title"]),
+                attributes.join(" ")
+            );
+            if let Some(help_text) = question["help_text"].as_str() {
+                for line in help_text.lines() {
+                    let _ = writeln!(out, "  {}", line);
+                }
+            }
+            for option in options {
+                let _ = writeln!(out, "  - {}", text_of(&option));
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn to_yaml(value: &JsonValue) -> Result<serde_yaml::Value, String> {
+    serde_yaml::to_value(value).map_err(|e| e.to_string())
+}
+
+fn text_of(value: &JsonValue) -> &str {
+    value.as_str().unwrap_or_default()
+}
+
+/// `key=value` pairs for every feature, nested objects as dotted keys
+fn flatten(prefix: &str, features: &Map<String, JsonValue>, attributes: &mut Vec<String>) {
+    for (key, value) in features {
+        let path = format!("{}{}", prefix, key);
+        match value {
+            JsonValue::Null => {}
+            JsonValue::Object(nested) if !nested.is_empty() => {
+                flatten(&format!("{}.", path), nested, attributes)
+            }
+            _ => attributes.push(format!("{}={}", path, inline(value))),
+        }
+    }
+}
+
+/// A value as it is written in the braces: bare when that reads back the same
+fn inline(value: &JsonValue) -> String {
+    let bare = |text: &str| {
+        !text.is_empty()
+            && text
+                .chars()
+                .all(|c| c.is_alphanumeric() || "_-.:/@".contains(c))
+            && serde_json::from_str::<JsonValue>(text).is_err()
+    };
+    match value {
+        JsonValue::String(text) if bare(text) => text.clone(),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_sections_questions_and_attributes() {
+        let text = r#"
+---
+id: review
+title: Annual Review
+status: draft
+---
+
+# Leadership & Vision
+How the director sets direction.
+
+- The director communicates a clear vision {likert required scale.max=7 scale.minLabel="Strongly disagree"}
+  Think about the last twelve months.
+- Which areas need attention? {checkbox comment id=areas}
+  - Fundraising
+  - Board relations
+- Anything else?
+
+# Wrap-up {id=end position=9}
+- Would you serve again? {yes_no}
+"#;
+
+        assert_eq!(
+            parse(text).unwrap(),
+            json!({
+                "id": "review",
+                "title": "Annual Review",
+                "status": "draft",
+                "sections": [
+                    {
+                        "id": "leadership-vision",
+                        "title": "Leadership & Vision",
+                        "description": "How the director sets direction.",
+                        "position": 1,
+                        "questions": [
+                            {
+                                "id": "q1",
+                                "title": "The director communicates a clear vision",
+                                "question_type": "likert",
+                                "is_required": true,
+                                "allow_comment": false,
+                                "help_text": "Think about the last twelve months.",
+                                "position": 1,
+                                "features": { "scale": { "max": 7, "minLabel": "Strongly disagree" } }
+                            },
+                            {
+                                "id": "areas",
+                                "title": "Which areas need attention?",
+                                "question_type": "checkbox",
+                                "is_required": false,
+                                "allow_comment": true,
+                                "position": 2,
+                                "features": { "options": ["Fundraising", "Board relations"] }
+                            },
+                            {
+                                "id": "q3",
+                                "title": "Anything else?",
+                                "question_type": "text",
+                                "is_required": false,
+                                "allow_comment": false,
+                                "position": 3
+                            }
+                        ]
+                    },
+                    {
+                        "id": "end",
+                        "title": "Wrap-up",
+                        "position": 9,
+                        "questions": [{
+                            "id": "q4",
+                            "title": "Would you serve again?",
+                            "question_type": "yes_no",
+                            "is_required": false,
+                            "allow_comment": false,
+                            "position": 1
+                        }]
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn reports_each_bad_line() {
+        let text = "Stray text\n# One\n- Rate it {likert rating}\n## Two\n- Name {text placeholder=\"unclosed}\n";
+
+        let errors = parse(text).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                FieldError {
+                    pointer: String::new(),
+                    message: "line 1: expected front matter, a `#` section, a `-` question or indented text under a question".to_string(),
+                },
+                FieldError {
+                    pointer: "/sections/0/questions/0".to_string(),
+                    message: "line 3: `rating` is not a flag, and the type is already `likert`".to_string(),
+                },
+                FieldError {
+                    pointer: "/sections/0".to_string(),
+                    message: "line 4: only `#` headings start sections".to_string(),
+                },
+                FieldError {
+                    pointer: "/sections/0/questions/1".to_string(),
+                    message: "line 5: unclosed quote in the braces".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_values_only_when_needed() {
+        assert_eq!(inline(&json!("Never")), "Never");
+        assert_eq!(inline(&json!("two words")), "\"two words\"");
+        assert_eq!(inline(&json!("5")), "\"5\"");
+        assert_eq!(inline(&json!("true")), "\"true\"");
+        assert_eq!(inline(&json!(2.5)), "2.5");
+        assert_eq!(inline(&json!(["a b", "c"])), "[\"a b\",\"c\"]");
+        assert_eq!(
+            split_attributes(r#"Pick {one} {dropdown placeholder="a } b"}"#),
+            ("Pick {one}", Some(r#"dropdown placeholder="a } b""#))
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Bulk question entry: paste a block of text into a section and get one question per line.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    handlers::{check_admin_auth, AuthQuery, DryRunQuery},
+    questions::QuestionKind,
+    repository::Question,
+    schema::FieldError,
+    AppState,
+};
+
+/// More lines than this in one paste is almost certainly the wrong text
+const MAX_QUESTIONS: usize = 200;
+
+fn default_question_type() -> String {
+    "likert".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkQuestionsRequest {
+    /// One question title per line; blank lines are skipped
+    pub text: String,
+    /// Type of every created question, `likert` unless given
+    #[serde(default = "default_question_type")]
+    pub question_type: String,
+    #[serde(default)]
+    pub is_required: bool,
+    #[serde(default)]
+    pub allow_comment: bool,
+    /// `features` for every created question, such as a likert `scale` or choice `options`
+    pub features: Option<JsonValue>,
+}
+
+/// The question titles in pasted text, without the list markers people paste along
+fn question_titles(text: &str) -> Vec<String> {
+    text.lines()
+        .map(strip_list_marker)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `- `, `* `, `• `, `1. ` and `1) ` in front of a line
+fn strip_list_marker(line: &str) -> &str {
+    let line = line.trim();
+    for bullet in ["- ", "* ", "• "] {
+        if let Some(rest) = line.strip_prefix(bullet) {
+            return rest.trim_start();
+        }
+    }
+
+    let number = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if number.len() < line.len() {
+        if let Some(rest) = number
+            .strip_prefix(". ")
+            .or_else(|| number.strip_prefix(") "))
+        {
+            return rest.trim_start();
+        }
+    }
+    line
+}
+
+/// Add one question per line of `text` to a section (admin only).
+/// With `dry_run=true` nothing is saved and the response shows the questions that would be.
+pub async fn bulk_add_questions(
+    headers: HeaderMap,
+    Path((form_id, section_id)): Path<(String, String)>,
+    Query(auth): Query<AuthQuery>,
+    Query(options): Query<DryRunQuery>,
+    State(state): State<AppState>,
+    Json(request): Json<BulkQuestionsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let definition = state
+        .repos
+        .forms
+        .find_form(&form_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+    if !definition
+        .sections
+        .iter()
+        .any(|section| section.id == section_id)
+    {
+        return Err(AppError::BadRequest("Section not found".to_string()));
+    }
+
+    let features = request.features.unwrap_or_else(|| json!({}));
+    let mut kind = QuestionKind::from_parts(&request.question_type, &features).map_err(|e| {
+        AppError::InvalidDefinition(vec![FieldError {
+            pointer: e.pointer(),
+            message: e.message,
+        }])
+    })?;
+    let common = kind.common_mut();
+    common.required = request.is_required;
+    common.allow_comment.get_or_insert(request.allow_comment);
+
+    let titles = question_titles(&request.text);
+    if titles.is_empty() {
+        return Err(AppError::BadRequest(
+            "No questions found; put one question on each line".to_string(),
+        ));
+    }
+    if titles.len() > MAX_QUESTIONS {
+        return Err(AppError::BadRequest(format!(
+            "Too many questions ({}); add at most {} at a time",
+            titles.len(),
+            MAX_QUESTIONS
+        )));
+    }
+
+    // Positions are unique across the form, so new questions go after every existing one
+    let last_position = definition
+        .questions
+        .iter()
+        .map(|question| question.position)
+        .max()
+        .unwrap_or(0);
+    let questions: Vec<Question> = titles
+        .into_iter()
+        .zip(1..)
+        .map(|(title, offset)| Question {
+            id: Uuid::new_v4().to_string(),
+            form_id: form_id.clone(),
+            section_id: Some(section_id.clone()),
+            position: last_position + offset,
+            title,
+            description: kind.common().help_text.clone(),
+            kind: kind.clone(),
+        })
+        .collect();
+
+    if !options.dry_run {
+        state
+            .repos
+            .forms
+            .add_questions(&form_id, &questions)
+            .await?;
+    }
+
+    let message = if options.dry_run {
+        "Preview only; nothing was saved"
+    } else {
+        "Questions added successfully"
+    };
+
+    Ok(Json(json!({
+        "message": message,
+        "dry_run": options.dry_run,
+        "form_id": form_id,
+        "section_id": section_id,
+        "count": questions.len(),
+        "questions": questions
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::repository::{memory::MemoryStore, Form, FormDefinition, Section};
+
+    #[test]
+    fn strips_list_markers_and_blank_lines() {
+        let text = "Communicates a clear vision\n\n  - Builds a strong team\n* Manages the budget\n• Works with the board\n1. Raises funds\n12) Plans ahead\n2024 goals were met\n   \n";
+
+        assert_eq!(
+            question_titles(text),
+            vec![
+                "Communicates a clear vision",
+                "Builds a strong team",
+                "Manages the budget",
+                "Works with the board",
+                "Raises funds",
+                "Plans ahead",
+                "2024 goals were met",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn previews_then_appends_to_the_section() {
+        let store = Arc::new(MemoryStore::default());
+        let state = store.app_state();
+        let section = |id: &str, position: i32| Section {
+            id: id.to_string(),
+            form_id: "review".to_string(),
+            title: id.to_string(),
+            description: None,
+            position,
+        };
+        state
+            .repos
+            .forms
+            .create_form(&FormDefinition {
+                form: Form {
+                    id: "review".to_string(),
+                    title: "Annual Review".to_string(),
+                    description: None,
+                    instructions: None,
+                    status: "draft".to_string(),
+                    welcome_message: None,
+                    closing_message: None,
+                    settings: None,
+                },
+                sections: vec![section("s1", 1), section("s2", 2)],
+                questions: vec![Question {
+                    id: "q1".to_string(),
+                    form_id: "review".to_string(),
+                    section_id: Some("s2".to_string()),
+                    position: 7,
+                    title: "Vision".to_string(),
+                    description: None,
+                    kind: QuestionKind::from_parts("likert", &json!({})).unwrap(),
+                }],
+            })
+            .await
+            .unwrap();
+
+        let add = |section_id: &str, dry_run: bool, request: JsonValue| {
+            bulk_add_questions(
+                HeaderMap::new(),
+                Path(("review".to_string(), section_id.to_string())),
+                Query(AuthQuery {
+                    token: Some("admin".to_string()),
+                }),
+                Query(DryRunQuery { dry_run }),
+                State(state.clone()),
+                Json(serde_json::from_value(request).unwrap()),
+            )
+        };
+        let request = json!({
+            "text": "Team\n- Budget\n",
+            "is_required": true,
+            "features": { "scale": { "min": 1, "max": 7 } }
+        });
+
+        let preview = add("s1", true, request.clone())
+            .await
+            .unwrap()
+            .into_response();
+        let body = axum::body::to_bytes(preview.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let preview: JsonValue = serde_json::from_slice(&body).unwrap();
+        assert_eq!(preview["count"], 2);
+        assert_eq!(preview["questions"][1]["title"], "Budget");
+        assert_eq!(preview["questions"][1]["position"], 9);
+        assert_eq!(preview["questions"][1]["type"], "likert");
+        assert_eq!(preview["questions"][1]["features"]["required"], true);
+        let stored = state.repos.forms.find_form("review").await.unwrap();
+        assert_eq!(stored.unwrap().questions.len(), 1);
+
+        add("s1", false, request.clone()).await.unwrap();
+        let form = state
+            .repos
+            .forms
+            .find_form("review")
+            .await
+            .unwrap()
+            .unwrap();
+        let added: Vec<_> = form
+            .questions
+            .iter()
+            .filter(|question| question.section_id.as_deref() == Some("s1"))
+            .map(|question| (question.title.as_str(), question.position))
+            .collect();
+        assert_eq!(added, vec![("Team", 8), ("Budget", 9)]);
+
+        let missing = add("s3", false, request).await;
+        assert!(matches!(missing, Err(AppError::BadRequest(_))));
+        let invalid = add(
+            "s1",
+            false,
+            json!({ "text": "Office", "question_type": "dropdown", "features": { "options": "Remote" } }),
+        )
+        .await;
+        match invalid {
+            Err(AppError::InvalidDefinition(errors)) => {
+                assert_eq!(errors[0].pointer, "/features/options")
+            }
+            _ => panic!("expected the options to be rejected"),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Copying a form: as a fresh draft, into a new review period, or together with its
+//! responses and invitation list.
+//!
+//! Every copied form, section, question and response gets a new UUID, and the response
+//! maps each original ID to its copy. The copy is written in one transaction, together
+//! with any responses, their search index entries and the invitation list it brings along.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value as JsonValue};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    handlers::{check_admin_auth, sanitize_optional_input, AuthQuery},
+    repository::{CopyOptions, Form, FormDefinition, Question, Section},
+    AppState,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CloneFormRequest {
+    /// ID of the copy; a new UUID unless given
+    pub form_id: Option<String>,
+    /// Title of the copy; unless given, the original's with the review period or "(Copy)"
+    pub title: Option<String>,
+    /// The review period the copy is for, stored as `settings.reviewPeriod`
+    pub review_period: Option<String>,
+    /// Copy the submitted responses and their answers
+    #[serde(default)]
+    pub copy_responses: bool,
+    /// Copy the invitation list, with new tokens
+    #[serde(default)]
+    pub copy_invitations: bool,
+}
+
+/// Original section and question IDs mapped to the IDs of their copies
+#[derive(Debug, Default)]
+struct IdMapping {
+    sections: HashMap<String, String>,
+    questions: HashMap<String, String>,
+}
+
+/// The default title of a copy. A new review period replaces the previous one where the
+/// title names it ("Review 2024" becomes "Review 2025"), and is appended otherwise.
+fn copy_title(original: &Form, review_period: Option<&str>) -> String {
+    let Some(period) = review_period else {
+        return format!("{} (Copy)", original.title);
+    };
+    let previous = original
+        .settings
+        .as_ref()
+        .and_then(|settings| settings.get("reviewPeriod"))
+        .and_then(JsonValue::as_str)
+        .filter(|previous| !previous.is_empty());
+
+    match previous {
+        Some(previous) if original.title.contains(previous) => {
+            original.title.replace(previous, period)
+        }
+        _ => format!("{} ({})", original.title, period),
+    }
+}
+
+/// The copy of `original` as a draft called `title` under `form_id`, with fresh section
+/// and question IDs
+fn copy_definition(
+    original: FormDefinition,
+    form_id: &str,
+    title: String,
+    review_period: Option<&str>,
+) -> (FormDefinition, IdMapping) {
+    let mut settings = original.form.settings.unwrap_or_else(|| json!({}));
+    if let Some(period) = review_period {
+        if !settings.is_object() {
+            settings = JsonValue::Object(Map::new());
+        }
+        settings["reviewPeriod"] = json!(period);
+    }
+
+    let mut ids = IdMapping::default();
+    let sections = original
+        .sections
+        .into_iter()
+        .map(|section| {
+            let id = Uuid::new_v4().to_string();
+            ids.sections.insert(section.id, id.clone());
+            Section {
+                id,
+                form_id: form_id.to_string(),
+                ..section
+            }
+        })
+        .collect();
+    let questions = original
+        .questions
+        .into_iter()
+        .map(|question| {
+            let id = Uuid::new_v4().to_string();
+            ids.questions.insert(question.id, id.clone());
+            Question {
+                id,
+                form_id: form_id.to_string(),
+                section_id: question
+                    .section_id
+                    .and_then(|section_id| ids.sections.get(&section_id).cloned()),
+                ..question
+            }
+        })
+        .collect();
+
+    let form = Form {
+        id: form_id.to_string(),
+        title,
+        status: "draft".to_string(),
+        settings: Some(settings),
+        ..original.form
+    };
+    (
+        FormDefinition {
+            form,
+            sections,
+            questions,
+        },
+        ids,
+    )
+}
+
+/// Copy a form as a new draft (admin only). The optional body picks the new ID and title,
+/// a review period, and whether responses and the invitation list come along.
+pub async fn clone_form(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    request: Option<Json<CloneFormRequest>>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+
+    let original = state
+        .repos
+        .forms
+        .find_form(&form_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+
+    let new_form_id = sanitize_optional_input(request.form_id.as_deref(), 255)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    if state.repos.forms.form_exists(&new_form_id).await? {
+        return Err(AppError::BadRequest(format!(
+            "A form with ID '{}' already exists",
+            new_form_id
+        )));
+    }
+    let review_period = sanitize_optional_input(request.review_period.as_deref(), 100);
+    let title = sanitize_optional_input(request.title.as_deref(), 255)
+        .unwrap_or_else(|| copy_title(&original.form, review_period.as_deref()));
+
+    let (copy, ids) = copy_definition(original, &new_form_id, title, review_period.as_deref());
+
+    let copied = state
+        .repos
+        .forms
+        .copy_form(
+            &copy,
+            &form_id,
+            &ids.questions,
+            CopyOptions {
+                responses: request.copy_responses,
+                invitations: request.copy_invitations,
+            },
+        )
+        .await?;
+
+    Ok(Json(json!({
+        "message": "Form cloned successfully",
+        "form_id": new_form_id,
+        "title": copy.form.title,
+        "review_period": review_period,
+        "id_mapping": {
+            "form": { "from": form_id, "to": new_form_id },
+            "sections": ids.sections,
+            "questions": ids.questions,
+            "responses": copied.responses
+        },
+        "copied": {
+            "sections": copy.sections.len(),
+            "questions": copy.questions.len(),
+            "responses": copied.responses.len(),
+            "invitations": copied.invitations
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+
+    use super::*;
+
+    #[test]
+    fn a_new_review_period_takes_the_place_of_the_old_one() {
+        let form = |title: &str, settings: JsonValue| Form {
+            id: "review".to_string(),
+            title: title.to_string(),
+            description: None,
+            instructions: None,
+            status: "finished".to_string(),
+            welcome_message: None,
+            closing_message: None,
+            settings: Some(settings),
+        };
+
+        let dated = form("ED Review 2024", json!({ "reviewPeriod": "2024" }));
+        assert_eq!(copy_title(&dated, Some("2025")), "ED Review 2025");
+        assert_eq!(copy_title(&dated, None), "ED Review 2024 (Copy)");
+        let undated = form("ED Review", json!({}));
+        assert_eq!(copy_title(&undated, Some("2025")), "ED Review (2025)");
+    }
+
+    async fn seeded_db() -> SqlitePool {
+        let db = crate::db::test_pool().await;
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO forms (id, title, status, welcome_message, closing_message, settings) VALUES
+                ('review', 'ED Review 2024', 'finished', 'Welcome', 'Thank you',
+                 '{"allowAnonymous": false, "reviewPeriod": "2024"}');
+            INSERT INTO sections (id, form_id, title, position) VALUES ('s1', 'review', 'Leadership', 1);
+            INSERT INTO questions (id, form_id, section_id, position, type, title, features) VALUES
+                ('q1', 'review', 's1', 1, 'likert', 'Vision', '{"required": true}'),
+                ('q2', 'review', 's1', 2, 'textarea', 'Comments', '{"required": false}');
+            INSERT INTO respondents (id, name, email, email_hash) VALUES
+                ('p1', 'Ada', 'ada@example.com', 'h1');
+            INSERT INTO responses (id, respondent_id, form_id, role, metadata) VALUES
+                ('r1', 'p1', 'review', 'Staff', '{}');
+            INSERT INTO answers (id, response_id, question_id, value) VALUES
+                ('a1', 'r1', 'q1', '4'),
+                ('a2', 'r1', 'q2', '"The budget was never shared"'),
+                ('a3', 'r1', 'removed-question', '2');
+            INSERT INTO invitations (id, form_id, name, email, role, token, response_id, used_at) VALUES
+                ('i1', 'review', 'Ada', 'ada@example.com', 'Staff', 't1', 'r1', '2024-11-01T10:00:00Z'),
+                ('i2', 'review', 'Ben', 'ben@example.com', 'Board', 't2', NULL, NULL);
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn copies_responses_and_invitations_into_a_new_period() {
+        let db = seeded_db().await;
+        let state = AppState::for_tests(db.clone());
+        let clone = |request: Option<JsonValue>| {
+            clone_form(
+                HeaderMap::new(),
+                Path("review".to_string()),
+                Query(AuthQuery {
+                    token: Some("admin".to_string()),
+                }),
+                State(state.clone()),
+                request.map(|request| Json(serde_json::from_value(request).unwrap())),
+            )
+        };
+        let body = |response: axum::response::Response| async {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<JsonValue>(&bytes).unwrap()
+        };
+
+        let cloned = body(
+            clone(Some(json!({
+                "form_id": "review-2025",
+                "review_period": "2025",
+                "copy_responses": true,
+                "copy_invitations": true
+            })))
+            .await
+            .unwrap()
+            .into_response(),
+        )
+        .await;
+        assert_eq!(cloned["title"], "ED Review 2025");
+        assert_eq!(
+            cloned["copied"],
+            json!({ "sections": 1, "questions": 2, "responses": 1, "invitations": 2 })
+        );
+        let mapping = &cloned["id_mapping"];
+        assert_eq!(
+            mapping["form"],
+            json!({ "from": "review", "to": "review-2025" })
+        );
+        let q1 = mapping["questions"]["q1"].as_str().unwrap();
+        let r1 = mapping["responses"]["r1"].as_str().unwrap();
+
+        let copy = state
+            .repos
+            .forms
+            .find_form("review-2025")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.form.status, "draft");
+        assert_eq!(copy.form.welcome_message.as_deref(), Some("Welcome"));
+        assert_eq!(copy.form.closing_message.as_deref(), Some("Thank you"));
+        assert_eq!(
+            copy.form.settings,
+            Some(json!({ "allowAnonymous": false, "reviewPeriod": "2025" }))
+        );
+        assert_eq!(
+            copy.questions[0].section_id.as_ref(),
+            mapping["sections"]["s1"]
+                .as_str()
+                .map(str::to_string)
+                .as_ref()
+        );
+
+        let answers: Vec<(String, String)> = sqlx::query_as(
+            "SELECT question_id, CAST(value AS TEXT) FROM answers WHERE response_id = ? ORDER BY value",
+        )
+        .bind(r1)
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(
+            answers.len(),
+            2,
+            "the answer to a removed question stays behind"
+        );
+        assert_eq!(answers[0], (q1.to_string(), "4".to_string()));
+        let (respondent,): (Option<String>,) =
+            sqlx::query_as("SELECT respondent_id FROM responses WHERE id = ?")
+                .bind(r1)
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(respondent, None, "copies are anonymous");
+        assert!(!state
+            .repos
+            .responses
+            .has_submitted("review-2025", "h1")
+            .await
+            .unwrap());
+        assert!(state
+            .repos
+            .responses
+            .has_submitted("review", "h1")
+            .await
+            .unwrap());
+        let (indexed,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM answer_search WHERE form_id = 'review-2025'")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(indexed, 1);
+
+        let invitations: Vec<(String, String, Option<String>)> = sqlx::query_as(
+            "SELECT name, token, response_id FROM invitations WHERE form_id = 'review-2025' ORDER BY name",
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(invitations[0].2.as_deref(), Some(r1));
+        assert_eq!(invitations[1].2, None);
+        assert!(invitations
+            .iter()
+            .all(|(_, token, _)| token != "t1" && token != "t2"));
+
+        // Without a body the copy is a plain draft with no responses
+        let plain = body(clone(None).await.unwrap().into_response()).await;
+        assert_eq!(plain["title"], "ED Review 2024 (Copy)");
+        assert_eq!(plain["copied"]["responses"], 0);
+        let plain_id = plain["form_id"].as_str().unwrap();
+        assert_eq!(
+            state
+                .repos
+                .responses
+                .count_responses(plain_id)
+                .await
+                .unwrap(),
+            0
+        );
+
+        let taken = clone(Some(json!({ "form_id": "review-2025" }))).await;
+        assert!(matches!(taken, Err(AppError::BadRequest(_))));
+    }
+}
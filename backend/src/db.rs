@@ -1,9 +1,43 @@
 use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 
-pub async fn init_db(database_url: &str) -> Result<SqlitePool> {
+/// The pool behind the repositories, chosen by the scheme of `DATABASE_URL`. PostgreSQL
+/// needs a server built with the `postgres` feature.
+#[derive(Clone)]
+pub enum Database {
+    Sqlite(SqlitePool),
+    #[cfg(feature = "postgres")]
+    Postgres(sqlx::PgPool),
+}
+
+/// Timestamp format used by SQLite's CURRENT_TIMESTAMP
+pub(crate) const DB_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Parse a stored timestamp, in [`DB_TIMESTAMP_FORMAT`] (UTC) or RFC 3339
+pub(crate) fn parse_db_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(value, DB_TIMESTAMP_FORMAT)
+        .map(|dt| dt.and_utc())
+        .ok()
+        .or_else(|| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|dt| dt.with_timezone(&Utc))
+                .ok()
+        })
+}
+
+fn is_postgres_url(database_url: &str) -> bool {
+    database_url.starts_with("postgres://") || database_url.starts_with("postgresql://")
+}
+
+/// Connect to `database_url` and run that backend's migrations
+pub async fn init_db(database_url: &str) -> Result<Database> {
     tracing::info!("Attempting to connect to database: {}", database_url);
 
+    if is_postgres_url(database_url) {
+        return init_postgres(database_url).await;
+    }
+
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
         .connect(database_url)
@@ -24,5 +58,61 @@ pub async fn init_db(database_url: &str) -> Result<SqlitePool> {
         })?;
 
     tracing::info!("Database migrations completed successfully");
-    Ok(pool)
+    Ok(Database::Sqlite(pool))
+}
+
+#[cfg(feature = "postgres")]
+async fn init_postgres(database_url: &str) -> Result<Database> {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(10)
+        .connect(database_url)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to connect to database: {}", e);
+            e
+        })?;
+
+    tracing::info!("Database connection successful, running migrations...");
+
+    sqlx::migrate!("./migrations_postgres")
+        .run(&pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("Migration failed: {}", e);
+            e
+        })?;
+
+    tracing::info!("Database migrations completed successfully");
+    Ok(Database::Postgres(pool))
+}
+
+#[cfg(not(feature = "postgres"))]
+async fn init_postgres(_database_url: &str) -> Result<Database> {
+    anyhow::bail!(
+        "DATABASE_URL points at PostgreSQL, but this server was built without the \
+         `postgres` feature; rebuild with `cargo build --features postgres`"
+    )
+}
+
+/// An in-memory database with every migration applied, for tests
+#[cfg(test)]
+pub async fn test_pool() -> SqlitePool {
+    use std::str::FromStr;
+
+    test_pool_with(sqlx::sqlite::SqliteConnectOptions::from_str("sqlite::memory:").unwrap()).await
+}
+
+/// [`test_pool`] with custom connect options, such as statement logging
+#[cfg(test)]
+pub async fn test_pool_with(options: sqlx::sqlite::SqliteConnectOptions) -> SqlitePool {
+    // One connection that never expires, as each new connection would get an empty database
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(options)
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    pool
 }
@@ -0,0 +1,603 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    db::{parse_db_timestamp, DB_TIMESTAMP_FORMAT},
+    email::{self, EmailMessage},
+    error::AppError,
+    handlers::{check_admin_auth, validate_email, AuthQuery},
+    models::*,
+    outbox,
+    pagination::PageParams,
+    repository::NewDigestRun,
+    AppState,
+};
+
+/// How often the background scheduler looks for due digests
+const SCHEDULER_INTERVAL_SECS: u64 = 15 * 60;
+
+/// Everything that goes into one digest email
+#[derive(Debug, Serialize)]
+pub struct DigestSummary {
+    pub form_id: String,
+    pub form_title: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub new_responses: i64,
+    pub total_responses: i64,
+    pub invited: i64,
+    pub responded: i64,
+    pub response_rate: Option<f64>,
+    pub average_score: Option<f64>,
+    pub previous_average_score: Option<f64>,
+    pub score_change: Option<f64>,
+}
+
+fn period_length(frequency: &str) -> Duration {
+    match frequency {
+        "weekly" => Duration::days(7),
+        _ => Duration::days(1),
+    }
+}
+
+/// Start of the next digest period: the end of the last run, or the subscription date
+async fn period_start(
+    state: &AppState,
+    subscription: &DigestSubscription,
+) -> Result<DateTime<Utc>, AppError> {
+    if let Some(end) = state
+        .repos
+        .digests
+        .last_period_end(&subscription.id)
+        .await?
+    {
+        return Ok(end);
+    }
+
+    parse_db_timestamp(&subscription.created_at).ok_or_else(|| {
+        AppError::InternalError(format!(
+            "Invalid digest timestamp '{}'",
+            subscription.created_at
+        ))
+    })
+}
+
+async fn build_summary(
+    state: &AppState,
+    form_id: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<DigestSummary, AppError> {
+    let period_start = start.format(DB_TIMESTAMP_FORMAT).to_string();
+    let period_end = end.format(DB_TIMESTAMP_FORMAT).to_string();
+
+    let form_title = state
+        .repos
+        .forms
+        .find_form(form_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Form not found".to_string()))?
+        .form
+        .title;
+
+    let digests = &state.repos.digests;
+    let (new_responses, total_responses) = digests.response_counts(form_id, start, end).await?;
+
+    let rates = state.repos.invitations.response_rates(form_id).await?;
+    let invited = rates.iter().map(|rate| rate.invited).sum::<i64>();
+    let responded = rates.iter().map(|rate| rate.responded).sum::<i64>();
+
+    // Mean Likert rating across all responses submitted up to each end of the period
+    let average = digests.average_rating(form_id, end).await?;
+    let previous_average = digests.average_rating(form_id, start).await?;
+
+    Ok(DigestSummary {
+        form_id: form_id.to_string(),
+        form_title,
+        period_start,
+        period_end,
+        new_responses,
+        total_responses,
+        invited,
+        responded,
+        response_rate: (invited > 0).then(|| responded as f64 / invited as f64),
+        average_score: average,
+        previous_average_score: previous_average,
+        score_change: average
+            .zip(previous_average)
+            .map(|(now, before)| now - before),
+    })
+}
+
+fn render_digest(summary: &DigestSummary, frequency: &str, to: &str) -> EmailMessage {
+    let response_rate = summary
+        .response_rate
+        .map(|rate| {
+            format!(
+                "{:.0}% ({} of {} invitees)",
+                rate * 100.0,
+                summary.responded,
+                summary.invited
+            )
+        })
+        .unwrap_or_else(|| "No invitation list".to_string());
+    let average = summary
+        .average_score
+        .map(|avg| format!("{:.2}", avg))
+        .unwrap_or_else(|| "No ratings yet".to_string());
+    let change = summary
+        .score_change
+        .map(|change| format!("{:+.2}", change))
+        .unwrap_or_else(|| "n/a".to_string());
+    let new_responses = summary.new_responses.to_string();
+    let total_responses = summary.total_responses.to_string();
+
+    let vars = [
+        ("form_title", summary.form_title.as_str()),
+        ("frequency", frequency),
+        ("period_start", summary.period_start.as_str()),
+        ("period_end", summary.period_end.as_str()),
+        ("new_responses", new_responses.as_str()),
+        ("total_responses", total_responses.as_str()),
+        ("response_rate", response_rate.as_str()),
+        ("average_score", average.as_str()),
+        ("score_change", change.as_str()),
+    ];
+
+    let html = email::render_html_template(
+        r#"<h2>{{form_title}}: {{frequency}} digest</h2>
+<p>Responses from {{period_start}} to {{period_end}} UTC.</p>
+<ul>
+    <li><strong>New responses:</strong> {{new_responses}}</li>
+    <li><strong>Total responses:</strong> {{total_responses}}</li>
+    <li><strong>Response rate:</strong> {{response_rate}}</li>
+    <li><strong>Average rating:</strong> {{average_score}} ({{score_change}} since last digest)</li>
+</ul>
+<p>Log in to your admin dashboard to view the full results.</p>"#,
+        &vars,
+    );
+    let subject = email::render_template("{{form_title}}: {{new_responses}} new responses", &vars);
+
+    EmailMessage::from_html(to, &subject, &html)
+}
+
+/// Queue one digest for a subscription and record the run in the same transaction.
+/// Returns `None` when another run already covered the period.
+async fn queue_digest(
+    state: &AppState,
+    subscription: &DigestSubscription,
+    end: DateTime<Utc>,
+) -> Result<Option<DigestSummary>, AppError> {
+    if state.mailer.is_none() {
+        return Err(AppError::BadRequest(
+            "Email is not configured (set EMAIL_TRANSPORT)".to_string(),
+        ));
+    }
+
+    let start = period_start(state, subscription).await?;
+    let summary = build_summary(state, &subscription.form_id, start, end).await?;
+
+    let message = render_digest(&summary, &subscription.frequency, &subscription.email);
+
+    let run = NewDigestRun {
+        subscription_id: subscription.id.clone(),
+        period_start: start,
+        period_end: end,
+        new_responses: summary.new_responses,
+        total_responses: summary.total_responses,
+        average_score: summary.average_score,
+    };
+    if !state.repos.digests.record_run(&run, &message).await? {
+        return Ok(None);
+    }
+    outbox::wake(state);
+
+    Ok(Some(summary))
+}
+
+/// Send every digest whose period has elapsed
+async fn run_due_digests(state: &AppState) -> Result<(), AppError> {
+    let subscriptions = state.repos.digests.all_subscriptions().await?;
+
+    let now = Utc::now();
+    for subscription in &subscriptions {
+        let start = match period_start(state, subscription).await {
+            Ok(start) => start,
+            Err(e) => {
+                tracing::error!(subscription_id = %subscription.id, error = %e, "Digest schedule lookup failed");
+                continue;
+            }
+        };
+        if now < start + period_length(&subscription.frequency) {
+            continue;
+        }
+
+        match queue_digest(state, subscription, now).await {
+            Ok(Some(summary)) => tracing::info!(
+                subscription_id = %subscription.id,
+                form_id = %subscription.form_id,
+                new_responses = summary.new_responses,
+                "Digest queued"
+            ),
+            Ok(None) => {}
+            Err(e) => {
+                // No run recorded, so the next tick retries the same period
+                tracing::warn!(subscription_id = %subscription.id, error = %e, "Failed to queue digest")
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Spawn the background task that sends due digests
+pub fn spawn_scheduler(state: AppState) {
+    if state.mailer.is_none() {
+        tracing::info!("Email not configured, digest scheduler disabled");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(SCHEDULER_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_due_digests(&state).await {
+                tracing::error!(error = %e, "Digest scheduler run failed");
+            }
+        }
+    });
+}
+
+async fn fetch_subscription(
+    state: &AppState,
+    subscription_id: &str,
+) -> Result<DigestSubscription, AppError> {
+    let subscription = state
+        .repos
+        .digests
+        .find_subscription(subscription_id)
+        .await?;
+
+    subscription.ok_or_else(|| AppError::NotFound("Digest subscription not found".to_string()))
+}
+
+/// Subscribe an admin email to a daily or weekly digest for a form (admin only)
+pub async fn create_subscription(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateDigestSubscriptionRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let email = req.email.trim().to_lowercase();
+    validate_email(&email)?;
+
+    if !["daily", "weekly"].contains(&req.frequency.as_str()) {
+        return Err(AppError::BadRequest(
+            "Invalid frequency. Must be daily or weekly".to_string(),
+        ));
+    }
+
+    if !state.repos.forms.form_exists(&form_id).await? {
+        return Err(AppError::BadRequest("Form not found".to_string()));
+    }
+
+    let subscription = DigestSubscription {
+        id: Uuid::new_v4().to_string(),
+        form_id,
+        email,
+        frequency: req.frequency,
+        created_at: String::new(),
+    };
+    if !state
+        .repos
+        .digests
+        .create_subscription(&subscription)
+        .await?
+    {
+        return Err(AppError::BadRequest(format!(
+            "{} is already subscribed to digests for this form",
+            subscription.email
+        )));
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "message": "Digest subscription created successfully",
+            "id": subscription.id,
+            "form_id": subscription.form_id,
+            "email": subscription.email,
+            "frequency": subscription.frequency
+        })),
+    ))
+}
+
+/// List digest subscriptions for a form with their most recent run (admin only)
+pub async fn list_subscriptions(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    Query(page): Query<PageParams>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let subscriptions = state
+        .repos
+        .digests
+        .list_subscriptions(&form_id, &page)
+        .await?;
+    let last_runs = state.repos.digests.last_runs(&form_id).await?;
+
+    let subscription_list = subscriptions.map(|s| {
+        json!({
+            "last_run": last_runs.iter().find(|r| r.subscription_id == s.id),
+            "subscription": s
+        })
+    });
+
+    Ok(Json(subscription_list))
+}
+
+/// Unsubscribe from a digest (admin only)
+pub async fn delete_subscription(
+    headers: HeaderMap,
+    Path(subscription_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    if !state
+        .repos
+        .digests
+        .delete_subscription(&subscription_id)
+        .await?
+    {
+        return Err(AppError::NotFound(
+            "Digest subscription not found".to_string(),
+        ));
+    }
+
+    Ok(Json(json!({
+        "message": "Digest subscription deleted successfully"
+    })))
+}
+
+/// Preview what the next digest would contain, without sending or recording it (admin only)
+pub async fn preview_digest(
+    headers: HeaderMap,
+    Path(subscription_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let subscription = fetch_subscription(&state, &subscription_id).await?;
+    let start = period_start(&state, &subscription).await?;
+    let summary = build_summary(&state, &subscription.form_id, start, Utc::now()).await?;
+    let next_due = start + period_length(&subscription.frequency);
+
+    Ok(Json(json!({
+        "subscription": subscription,
+        "next_due_at": next_due.format(DB_TIMESTAMP_FORMAT).to_string(),
+        "summary": summary
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::TimeZone;
+    use serde_json::Value as JsonValue;
+
+    use sqlx::SqlitePool;
+
+    use super::*;
+    use crate::email::TestSender;
+
+    /// A daily subscription from 1 September, with one response before it, one inside
+    /// its first day and one after that day. Ada's invitation is used; Ben's is not.
+    async fn digest_state(sender: &Arc<TestSender>) -> (AppState, SqlitePool) {
+        let db = crate::db::test_pool().await;
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO forms (id, title) VALUES ('review', 'Annual Review');
+            INSERT INTO questions (id, form_id, position, type, title) VALUES
+                ('q1', 'review', 1, 'likert', 'Vision'),
+                ('q2', 'review', 2, 'textarea', 'Comments');
+            INSERT INTO responses (id, form_id, submitted_at) VALUES
+                ('r1', 'review', '2025-08-31 12:00:00'),
+                ('r2', 'review', '2025-09-01 10:00:00'),
+                ('r3', 'review', '2025-09-02 08:00:00');
+            INSERT INTO answers (id, response_id, question_id, value) VALUES
+                ('a1', 'r1', 'q1', '2'),
+                ('a2', 'r2', 'q1', '{"rating": 4, "comment": "Better"}'),
+                ('a3', 'r2', 'q2', '"Fine"'),
+                ('a4', 'r3', 'q1', '5');
+            INSERT INTO invitations (id, form_id, name, email, token, response_id, used_at) VALUES
+                ('i1', 'review', 'Ada', 'ada@example.com', 't1', 'r2', '2025-09-01 10:00:00'),
+                ('i2', 'review', 'Ben', 'ben@example.com', 't2', NULL, NULL);
+            INSERT INTO digest_subscriptions (id, form_id, email, frequency, created_at) VALUES
+                ('d1', 'review', 'admin@example.com', 'daily', '2025-09-01 00:00:00');
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        let mut state = AppState::for_tests(db.clone());
+        state.mailer = Some(sender.clone());
+        (state, db)
+    }
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 9, day, hour, 0, 0).unwrap()
+    }
+
+    async fn preview(state: &AppState) -> JsonValue {
+        let response = preview_digest(
+            HeaderMap::new(),
+            Path("d1".to_string()),
+            Query(AuthQuery {
+                token: Some("admin".to_string()),
+            }),
+            State(state.clone()),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_summary_counts_only_the_period_and_compares_with_its_start() {
+        let sender = Arc::new(TestSender::default());
+        let (state, _) = digest_state(&sender).await;
+
+        let summary = build_summary(&state, "review", at(1, 0), at(2, 0))
+            .await
+            .unwrap();
+        assert_eq!(summary.period_start, "2025-09-01 00:00:00");
+        assert_eq!(summary.period_end, "2025-09-02 00:00:00");
+        // r3 falls after the period and r1 before it
+        assert_eq!((summary.new_responses, summary.total_responses), (1, 2));
+        assert_eq!((summary.invited, summary.responded), (2, 1));
+        assert_eq!(summary.response_rate, Some(0.5));
+        assert_eq!(summary.average_score, Some(3.0));
+        assert_eq!(summary.previous_average_score, Some(2.0));
+        assert_eq!(summary.score_change, Some(1.0));
+
+        let later = build_summary(&state, "review", at(2, 0), at(3, 0))
+            .await
+            .unwrap();
+        assert_eq!((later.new_responses, later.total_responses), (1, 3));
+        assert_eq!(later.average_score, Some(11.0 / 3.0));
+    }
+
+    #[tokio::test]
+    async fn each_digest_starts_where_the_last_one_ended() {
+        let sender = Arc::new(TestSender::default());
+        let (state, db) = digest_state(&sender).await;
+
+        // Never sent: the first period starts at the subscription date
+        let before = preview(&state).await;
+        assert_eq!(before["summary"]["period_start"], "2025-09-01 00:00:00");
+        assert_eq!(before["next_due_at"], "2025-09-02 00:00:00");
+
+        let subscription = fetch_subscription(&state, "d1").await.unwrap();
+        let queued = queue_digest(&state, &subscription, at(2, 0))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(queued.new_responses, 1);
+
+        let after = preview(&state).await;
+        assert_eq!(after["summary"]["period_start"], "2025-09-02 00:00:00");
+        assert_eq!(after["next_due_at"], "2025-09-03 00:00:00");
+        assert_eq!(after["summary"]["new_responses"], 1);
+
+        sqlx::query("UPDATE digest_subscriptions SET frequency = 'weekly'")
+            .execute(&db)
+            .await
+            .unwrap();
+        assert_eq!(preview(&state).await["next_due_at"], "2025-09-09 00:00:00");
+    }
+
+    #[tokio::test]
+    async fn the_scheduler_sends_overdue_digests_once() {
+        let sender = Arc::new(TestSender::default());
+        let (state, db) = digest_state(&sender).await;
+
+        run_due_digests(&state).await.unwrap();
+        run_due_digests(&state).await.unwrap();
+        // Digests go out through the outbox, which retries failed sends
+        assert!(sender.sent.lock().unwrap().is_empty());
+        assert_eq!(outbox::process_due(&state).await.unwrap(), 1);
+
+        let sent = sender.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, "admin@example.com");
+        assert_eq!(sent[0].subject, "Annual Review: 2 new responses");
+        let (runs,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM digest_runs")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(runs, 1);
+    }
+
+    #[tokio::test]
+    async fn a_period_claimed_by_another_run_is_not_queued_again() {
+        let sender = Arc::new(TestSender::default());
+        let (state, db) = digest_state(&sender).await;
+        let subscription = fetch_subscription(&state, "d1").await.unwrap();
+
+        // Both runs read the same period start before either records its run
+        let (first, second) = tokio::join!(
+            queue_digest(&state, &subscription, at(2, 0)),
+            queue_digest(&state, &subscription, at(2, 0))
+        );
+        let queued = [first.unwrap(), second.unwrap()];
+        assert_eq!(queued.iter().filter(|summary| summary.is_some()).count(), 1);
+
+        let (entries,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM outbox WHERE kind = 'email'")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn digests_render_the_summary_and_fall_back_when_data_is_missing() {
+        let mut summary = DigestSummary {
+            form_id: "review".to_string(),
+            form_title: "Annual <Review>".to_string(),
+            period_start: "2025-09-01 00:00:00".to_string(),
+            period_end: "2025-09-02 00:00:00".to_string(),
+            new_responses: 1,
+            total_responses: 2,
+            invited: 2,
+            responded: 1,
+            response_rate: Some(0.5),
+            average_score: Some(3.0),
+            previous_average_score: Some(2.0),
+            score_change: Some(1.0),
+        };
+
+        let message = render_digest(&summary, "daily", "admin@example.com");
+        assert_eq!(message.to, "admin@example.com");
+        assert_eq!(message.subject, "Annual <Review>: 1 new responses");
+        assert!(message
+            .html
+            .contains("<h2>Annual &lt;Review&gt;: daily digest</h2>"));
+        assert!(message
+            .html
+            .contains("from 2025-09-01 00:00:00 to 2025-09-02 00:00:00 UTC"));
+        assert!(message.html.contains("50% (1 of 2 invitees)"));
+        assert!(message.html.contains("3.00 (+1.00 since last digest)"));
+
+        summary.response_rate = None;
+        summary.average_score = None;
+        summary.score_change = None;
+        let message = render_digest(&summary, "weekly", "admin@example.com");
+        assert!(message.html.contains("No invitation list"));
+        assert!(message
+            .html
+            .contains("No ratings yet (n/a since last digest)"));
+    }
+}
@@ -1,47 +1,354 @@
-use serde_json::json;
-
-#[allow(dead_code)]
-pub async fn send_notification(
-    api_key: &str,
-    to_email: &str,
-    respondent_name: &str,
-    respondent_email: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
-
-    let body = json!({
-        "from": "Likert Form <onboarding@resend.dev>",
-        "to": [to_email],
-        "subject": "New Likert Form Response",
-        "html": format!(
-            r#"
-            <h2>New Form Response Received</h2>
-            <p>You have received a new response to your feedback form.</p>
-            <ul>
-                <li><strong>Respondent:</strong> {}</li>
-                <li><strong>Email:</strong> {}</li>
-                <li><strong>Submitted:</strong> {}</li>
-            </ul>
-            <p>Log in to your admin dashboard to view the full response.</p>
-            "#,
-            respondent_name,
-            respondent_email,
-            chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
-        ),
-    });
-
-    let response = client
-        .post("https://api.resend.com/emails")
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
-        .await?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await?;
-        return Err(format!("Failed to send email: {}", error_text).into());
-    }
-
-    Ok(())
+mod file;
+mod resend;
+mod smtp;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox, MultiPart, SinglePart};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use file::FileSender;
+pub use resend::ResendSender;
+pub use smtp::SmtpSender;
+
+/// Resend's sandbox sender, which works with any API key but only delivers to the
+/// account owner
+const RESEND_SANDBOX_FROM: &str = "Likert Form <onboarding@resend.dev>";
+
+/// Sender for the `file` transport, which never leaves the machine
+const FILE_DEFAULT_FROM: &str = "Likert Form <likert@localhost>";
+
+#[derive(Error, Debug)]
+pub enum EmailError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Provider rejected the message: {0}")]
+    Rejected(String),
+
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Email configuration error: {0}")]
+    Config(String),
+}
+
+/// A transport-agnostic email with both HTML and plain-text bodies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+impl EmailMessage {
+    /// Build a message whose text body is derived from the HTML
+    pub fn from_html(to: &str, subject: &str, html: &str) -> Self {
+        Self {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            html: html.to_string(),
+            text: html_to_text(html),
+        }
+    }
+
+    /// Convert into an RFC 5322 multipart/alternative message
+    pub(crate) fn to_mime(&self, from: &str) -> Result<lettre::Message, EmailError> {
+        let from: Mailbox = from
+            .parse()
+            .map_err(|e| EmailError::InvalidMessage(format!("from address '{}': {}", from, e)))?;
+        let to: Mailbox = self
+            .to
+            .parse()
+            .map_err(|e| EmailError::InvalidMessage(format!("to address '{}': {}", self.to, e)))?;
+
+        lettre::Message::builder()
+            .from(from)
+            .to(to)
+            .subject(&self.subject)
+            .multipart(
+                MultiPart::alternative()
+                    .singlepart(
+                        SinglePart::builder()
+                            .header(ContentType::TEXT_PLAIN)
+                            .body(self.text.clone()),
+                    )
+                    .singlepart(
+                        SinglePart::builder()
+                            .header(ContentType::TEXT_HTML)
+                            .body(self.html.clone()),
+                    ),
+            )
+            .map_err(|e| EmailError::InvalidMessage(e.to_string()))
+    }
+}
+
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    /// Short transport name for logs
+    fn name(&self) -> &'static str;
+
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError>;
+}
+
+/// Build the email transport selected by `EMAIL_TRANSPORT` (`resend`, `smtp` or `file`).
+///
+/// Without `EMAIL_TRANSPORT`, Resend is used when `RESEND_API_KEY` is set; otherwise
+/// email is disabled and `None` is returned. `EMAIL_FROM` is required for SMTP, since no
+/// default sender would be accepted by a real relay.
+pub fn sender_from_env() -> anyhow::Result<Option<Arc<dyn EmailSender>>> {
+    let env = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+
+    let from = env("EMAIL_FROM");
+    let resend_api_key = env("RESEND_API_KEY");
+
+    let transport = match env("EMAIL_TRANSPORT") {
+        Some(transport) => transport.to_lowercase(),
+        None if resend_api_key.is_some() => "resend".to_string(),
+        None => return Ok(None),
+    };
+
+    let sender: Arc<dyn EmailSender> = match transport.as_str() {
+        "resend" => {
+            let api_key = resend_api_key.ok_or_else(|| {
+                EmailError::Config("EMAIL_TRANSPORT=resend requires RESEND_API_KEY".to_string())
+            })?;
+            Arc::new(ResendSender::new(
+                api_key,
+                from.unwrap_or_else(|| RESEND_SANDBOX_FROM.to_string()),
+            ))
+        }
+        "smtp" => {
+            let host = env("SMTP_HOST").ok_or_else(|| {
+                EmailError::Config("EMAIL_TRANSPORT=smtp requires SMTP_HOST".to_string())
+            })?;
+            let from = from.ok_or_else(|| {
+                EmailError::Config("EMAIL_TRANSPORT=smtp requires EMAIL_FROM".to_string())
+            })?;
+            let port = env("SMTP_PORT")
+                .map(|p| p.parse::<u16>())
+                .transpose()
+                .map_err(|_| EmailError::Config("SMTP_PORT must be a valid port".to_string()))?;
+            let tls = env("SMTP_TLS").unwrap_or_else(|| "starttls".to_string());
+            Arc::new(SmtpSender::new(
+                &host,
+                port,
+                &tls,
+                env("SMTP_USERNAME"),
+                env("SMTP_PASSWORD"),
+                from,
+            )?)
+        }
+        "file" => {
+            let path =
+                env("EMAIL_SINK_PATH").unwrap_or_else(|| "./data/outgoing-mail.mbox".to_string());
+            Arc::new(FileSender::new(
+                path,
+                from.unwrap_or_else(|| FILE_DEFAULT_FROM.to_string()),
+            ))
+        }
+        other => {
+            return Err(EmailError::Config(format!(
+                "Unknown EMAIL_TRANSPORT '{}'. Must be resend, smtp, or file",
+                other
+            ))
+            .into())
+        }
+    };
+
+    tracing::info!(transport = sender.name(), "Email transport configured");
+    Ok(Some(sender))
+}
+
+/// Replace `{{key}}` placeholders with the given values (for subjects and plain text)
+pub fn render_template(template: &str, vars: &[(&str, &str)]) -> String {
+    substitute(template, vars, |value| value.to_string())
+}
+
+/// Replace `{{key}}` placeholders with HTML-escaped values
+pub fn render_html_template(template: &str, vars: &[(&str, &str)]) -> String {
+    substitute(template, vars, escape_html)
+}
+
+/// Render in one pass over the template, so placeholders inside values are left as they are.
+/// Unknown placeholders are kept verbatim.
+fn substitute(template: &str, vars: &[(&str, &str)], encode: impl Fn(&str) -> String) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let key = &after[..end];
+        match vars.iter().find(|(name, _)| *name == key) {
+            Some((_, value)) => rendered.push_str(&encode(value)),
+            None => rendered.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Rough plain-text rendering of an HTML body: tags dropped, block ends become newlines
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    let mut tag = String::new();
+
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                tag.clear();
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                let name = tag
+                    .trim_start_matches('/')
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or("")
+                    .to_lowercase();
+                if matches!(
+                    name.as_str(),
+                    "br" | "p" | "div" | "li" | "h1" | "h2" | "h3" | "tr"
+                ) {
+                    text.push('\n');
+                }
+            }
+            _ if in_tag => tag.push(c),
+            _ => text.push(c),
+        }
+    }
+
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Records every message it is given, or fails them all
+#[cfg(test)]
+#[derive(Default)]
+pub struct TestSender {
+    pub fail: bool,
+    pub sent: std::sync::Mutex<Vec<EmailMessage>>,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl EmailSender for TestSender {
+    fn name(&self) -> &'static str {
+        "test"
+    }
+
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError> {
+        if self.fail {
+            return Err(EmailError::Rejected("mailbox unavailable".to_string()));
+        }
+        self.sent.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_template_escapes_values_but_plain_does_not() {
+        let vars = [("name", "Tom & <Jerry>")];
+        assert_eq!(
+            render_html_template("<p>Hi {{name}}</p>", &vars),
+            "<p>Hi Tom &amp; &lt;Jerry&gt;</p>"
+        );
+        assert_eq!(render_template("Hi {{name}}", &vars), "Hi Tom & <Jerry>");
+    }
+
+    #[test]
+    fn placeholders_inside_values_are_not_expanded() {
+        let vars = [("name", "{{form_title}}"), ("form_title", "Survey")];
+        assert_eq!(
+            render_template("{{name}} answered {{form_title}} {{unknown}} {{", &vars),
+            "{{form_title}} answered Survey {{unknown}} {{"
+        );
+        assert_eq!(
+            render_html_template("<b>{{name}}</b>", &[("name", "{{<x>}}")]),
+            "<b>{{&lt;x&gt;}}</b>"
+        );
+    }
+
+    #[test]
+    fn text_body_is_derived_from_html() {
+        let message = EmailMessage::from_html(
+            "jane@example.com",
+            "Subject",
+            "<h2>Title</h2><p>First &amp; second</p><ul><li>One</li><li>Two</li></ul>",
+        );
+        assert_eq!(message.text, "Title\nFirst & second\nOne\nTwo");
+    }
+
+    #[tokio::test]
+    async fn file_sender_appends_mbox_entries() {
+        let path = std::env::temp_dir().join(format!("likert-mail-{}.mbox", uuid::Uuid::new_v4()));
+        let sender = FileSender::new(
+            path.to_string_lossy().to_string(),
+            FILE_DEFAULT_FROM.to_string(),
+        );
+
+        for subject in ["First", "Second"] {
+            sender
+                .send(&EmailMessage::from_html(
+                    "jane@example.com",
+                    subject,
+                    "<p>Body</p>",
+                ))
+                .await
+                .unwrap();
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(contents.matches("\nFrom likert@localhost ").count() + 1, 2);
+        assert!(contents.starts_with("From likert@localhost "));
+        assert!(contents.contains("Subject: First"));
+        assert!(contents.contains("Subject: Second"));
+        assert!(contents.contains("text/plain"));
+        assert!(contents.contains("text/html"));
+    }
 }
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use super::{EmailError, EmailMessage, EmailSender};
+
+/// Appends every message to a local mbox file instead of sending it.
+/// Useful for development and for testing notifications offline.
+pub struct FileSender {
+    path: String,
+    from: String,
+    // Serializes appends so concurrent sends don't interleave
+    lock: Mutex<()>,
+}
+
+impl FileSender {
+    pub fn new(path: String, from: String) -> Self {
+        Self {
+            path,
+            from,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Bare address for the mbox `From ` separator line
+    fn envelope_sender(&self) -> &str {
+        match (self.from.find('<'), self.from.rfind('>')) {
+            (Some(start), Some(end)) if start < end => &self.from[start + 1..end],
+            _ => self.from.trim(),
+        }
+    }
+}
+
+#[async_trait]
+impl EmailSender for FileSender {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError> {
+        let mime = message.to_mime(&self.from)?;
+        let formatted = String::from_utf8_lossy(&mime.formatted()).replace("\r\n", "\n");
+
+        // mboxrd: escape body lines that could be mistaken for a message separator
+        let body: Vec<String> = formatted
+            .lines()
+            .map(|line| {
+                if line.trim_start_matches('>').starts_with("From ") {
+                    format!(">{}", line)
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+
+        let entry = format!(
+            "From {} {}\n{}\n\n",
+            self.envelope_sender(),
+            chrono::Utc::now().format("%a %b %e %H:%M:%S %Y"),
+            body.join("\n")
+        );
+
+        if let Some(parent) = std::path::Path::new(&self.path).parent() {
+            if !parent.as_os_str().is_empty() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+        }
+
+        let _guard = self.lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(entry.as_bytes()).await?;
+        file.flush().await?;
+
+        tracing::info!(to = %message.to, subject = %message.subject, path = %self.path, "Email written to file sink");
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use serde_json::json;
+
+use super::{EmailError, EmailMessage, EmailSender};
+
+const RESEND_API_URL: &str = "https://api.resend.com/emails";
+
+/// Sends through the Resend HTTP API
+pub struct ResendSender {
+    client: reqwest::Client,
+    api_key: String,
+    from: String,
+}
+
+impl ResendSender {
+    pub fn new(api_key: String, from: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_key,
+            from,
+        }
+    }
+}
+
+#[async_trait]
+impl EmailSender for ResendSender {
+    fn name(&self) -> &'static str {
+        "resend"
+    }
+
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError> {
+        let body = json!({
+            "from": self.from,
+            "to": [message.to],
+            "subject": message.subject,
+            "html": message.html,
+            "text": message.text,
+        });
+
+        let response = self
+            .client
+            .post(RESEND_API_URL)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(EmailError::Rejected(error_text));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport,
+    Tokio1Executor,
+};
+
+use super::{EmailError, EmailMessage, EmailSender};
+
+/// Sends through any SMTP relay
+pub struct SmtpSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+impl SmtpSender {
+    /// `tls` is `starttls` (default port 587), `tls` (implicit TLS, port 465) or `none` (port 25)
+    pub fn new(
+        host: &str,
+        port: Option<u16>,
+        tls: &str,
+        username: Option<String>,
+        password: Option<String>,
+        from: String,
+    ) -> Result<Self, EmailError> {
+        let mut builder = match tls {
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            other => {
+                return Err(EmailError::Config(format!(
+                    "Unknown SMTP_TLS '{}'. Must be starttls, tls, or none",
+                    other
+                )))
+            }
+        };
+
+        if let Some(port) = port {
+            builder = builder.port(port);
+        }
+
+        match (username, password) {
+            (Some(username), Some(password)) => {
+                builder = builder.credentials(Credentials::new(username, password));
+            }
+            (None, None) => {}
+            _ => {
+                return Err(EmailError::Config(
+                    "SMTP_USERNAME and SMTP_PASSWORD must be set together".to_string(),
+                ))
+            }
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl EmailSender for SmtpSender {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    async fn send(&self, message: &EmailMessage) -> Result<(), EmailError> {
+        let mime = message.to_mime(&self.from)?;
+        self.transport.send(mime).await?;
+        Ok(())
+    }
+}
@@ -7,6 +7,8 @@ use serde_json::json;
 use thiserror::Error;
 use tracing::{error, warn};
 
+use crate::schema::FieldError;
+
 #[derive(Error, Debug)]
 #[allow(dead_code)]
 pub enum AppError {
@@ -33,6 +35,10 @@ pub enum AppError {
 
     #[error("Internal server error")]
     InternalServerError,
+
+    /// A form definition that breaks the schema or the rules beyond it
+    #[error("Invalid form definition: {} errors", .0.len())]
+    InvalidDefinition(Vec<FieldError>),
 }
 
 impl IntoResponse for AppError {
@@ -117,6 +123,18 @@ impl IntoResponse for AppError {
                 };
                 (StatusCode::INTERNAL_SERVER_ERROR, msg)
             }
+            AppError::InvalidDefinition(errors) => {
+                warn!(
+                    error_type = "invalid_definition",
+                    error_count = errors.len(),
+                    "Form definition rejected"
+                );
+                let body = Json(json!({
+                    "error": format!("The form definition has {} error(s)", errors.len()),
+                    "errors": errors,
+                }));
+                return (StatusCode::BAD_REQUEST, body).into_response();
+            }
             AppError::InternalServerError => {
                 error!(
                     error_type = "internal_generic",
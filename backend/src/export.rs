@@ -0,0 +1,530 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use tokio::sync::mpsc;
+
+use crate::{
+    error::AppError,
+    handlers::check_admin_auth,
+    repository::{ExportRow, ResponseRepository},
+    AppState,
+};
+
+/// Chunks buffered between the database cursor and the client; bounds memory per export
+const CHANNEL_CAPACITY: usize = 8;
+/// Flush to the client once this many bytes have been rendered
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub token: Option<String>,
+    pub format: Option<String>,
+    #[serde(default)]
+    pub include_pii: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn parse(format: Option<&str>) -> Result<Self, AppError> {
+        match format.unwrap_or("csv") {
+            "csv" => Ok(Self::Csv),
+            "ndjson" => Ok(Self::Ndjson),
+            other => Err(AppError::BadRequest(format!(
+                "Unknown export format '{}'. Must be csv or ndjson",
+                other
+            ))),
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Ndjson => "ndjson",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ExportedResponse {
+    id: String,
+    role: Option<String>,
+    submitted_at: String,
+    name: Option<String>,
+    email: Option<String>,
+    answers: Vec<(String, JsonValue)>,
+}
+
+impl ExportedResponse {
+    fn from_row(row: &ExportRow) -> Self {
+        Self {
+            id: row.response_id.clone(),
+            role: row.role.clone(),
+            submitted_at: row.submitted_at.clone(),
+            name: row.name.clone(),
+            email: row.email.clone(),
+            answers: Vec::new(),
+        }
+    }
+}
+
+/// Renders responses into the chosen format, one at a time
+struct Encoder {
+    format: ExportFormat,
+    form_id: String,
+    include_pii: bool,
+    /// Question ids and titles in form order; these are the CSV answer columns
+    questions: Vec<(String, String)>,
+}
+
+impl Encoder {
+    fn header(&self) -> Result<String, AppError> {
+        if self.format == ExportFormat::Ndjson {
+            return Ok(String::new());
+        }
+
+        let mut columns = vec!["response_id", "submitted_at", "role"];
+        if self.include_pii {
+            columns.extend(["respondent_name", "respondent_email"]);
+        }
+        columns.extend(self.questions.iter().map(|(_, title)| title.as_str()));
+
+        csv_record(columns)
+    }
+
+    fn encode(&self, response: &ExportedResponse) -> Result<String, AppError> {
+        match self.format {
+            ExportFormat::Csv => {
+                let mut fields = vec![
+                    response.id.clone(),
+                    response.submitted_at.clone(),
+                    response.role.clone().unwrap_or_default(),
+                ];
+                if self.include_pii {
+                    fields.push(response.name.clone().unwrap_or_default());
+                    fields.push(response.email.clone().unwrap_or_default());
+                }
+                fields.extend(self.questions.iter().map(|(question_id, _)| {
+                    response
+                        .answers
+                        .iter()
+                        .find(|(id, _)| id == question_id)
+                        .map(|(_, value)| csv_value(value))
+                        .unwrap_or_default()
+                }));
+
+                csv_record(fields.iter().map(|f| neutralize_formula(f)))
+            }
+            ExportFormat::Ndjson => {
+                let answers: Vec<_> = response
+                    .answers
+                    .iter()
+                    .map(|(question_id, value)| {
+                        let title = self
+                            .questions
+                            .iter()
+                            .find(|(id, _)| id == question_id)
+                            .map(|(_, title)| title.as_str());
+                        json!({
+                            "question_id": question_id,
+                            "question_title": title,
+                            "value": value
+                        })
+                    })
+                    .collect();
+
+                let mut line = json!({
+                    "id": response.id,
+                    "form_id": self.form_id,
+                    "role": response.role,
+                    "submitted_at": response.submitted_at,
+                    "answers": answers
+                });
+                if self.include_pii {
+                    line["respondent_name"] = json!(response.name);
+                    line["respondent_email"] = json!(response.email);
+                }
+
+                Ok(format!("{}\n", line))
+            }
+        }
+    }
+}
+
+fn csv_record<I, T>(fields: I) -> Result<String, AppError>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(fields)
+        .map_err(|e| AppError::InternalError(format!("Failed to write CSV: {}", e)))?;
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| AppError::InternalError(format!("Failed to write CSV: {}", e)))?;
+
+    String::from_utf8(bytes).map_err(|e| AppError::InternalError(e.to_string()))
+}
+
+/// Answer values as spreadsheet cells: plain strings and numbers, JSON for anything structured
+fn csv_value(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Null => String::new(),
+        JsonValue::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Keep free-text answers from being evaluated as spreadsheet formulas
+fn neutralize_formula(field: &str) -> String {
+    let is_number = field.parse::<f64>().is_ok();
+    match field.chars().next() {
+        Some('=' | '+' | '-' | '@' | '\t' | '\r') if !is_number => format!("'{}", field),
+        _ => field.to_string(),
+    }
+}
+
+/// Read the export cursor and push rendered chunks into the channel.
+/// Stops quietly if the client disconnects (the receiver is dropped).
+async fn write_rows(
+    responses: Arc<dyn ResponseRepository>,
+    encoder: Encoder,
+    chunks: mpsc::Sender<Result<String, AppError>>,
+) -> Result<(), AppError> {
+    let mut rows = responses.export_rows(&encoder.form_id, encoder.include_pii);
+
+    let mut buffer = encoder.header()?;
+    let mut current: Option<ExportedResponse> = None;
+
+    while let Some(row) = rows.try_next().await? {
+        if current.as_ref().is_some_and(|c| c.id != row.response_id) {
+            if let Some(done) = current.take() {
+                buffer.push_str(&encoder.encode(&done)?);
+            }
+        }
+
+        let response = current.get_or_insert_with(|| ExportedResponse::from_row(&row));
+        if let (Some(question_id), Some(value)) = (row.question_id, row.value) {
+            let value = serde_json::from_str(&value).unwrap_or(JsonValue::Null);
+            response.answers.push((question_id, value));
+        }
+
+        if buffer.len() >= CHUNK_SIZE && chunks.send(Ok(std::mem::take(&mut buffer))).await.is_err()
+        {
+            return Ok(());
+        }
+    }
+
+    if let Some(done) = current {
+        buffer.push_str(&encoder.encode(&done)?);
+    }
+    if !buffer.is_empty() {
+        let _ = chunks.send(Ok(buffer)).await;
+    }
+
+    Ok(())
+}
+
+/// Stream every response to a form as CSV or NDJSON (admin only).
+///
+/// Rows are read from a database cursor and flushed in chunks, so memory stays bounded and
+/// the request timeout only covers the time to the first byte.
+pub async fn export_responses(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(params): Query<ExportQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+
+    let format = ExportFormat::parse(params.format.as_deref())?;
+
+    let definition = state
+        .repos
+        .forms
+        .find_form(&form_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+
+    let questions: Vec<(String, String)> = definition
+        .questions
+        .into_iter()
+        .map(|question| (question.id, question.title))
+        .collect();
+
+    let filename = format!("{}-responses.{}", form_id, format.extension());
+    let encoder = Encoder {
+        format,
+        form_id,
+        include_pii: params.include_pii,
+        questions,
+    };
+
+    let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+    let responses = state.repos.responses.clone();
+    tokio::spawn(async move {
+        if let Err(e) = write_rows(responses, encoder, sender.clone()).await {
+            tracing::error!(error = %e, "Response export failed");
+            // Ending the body with an error lets the client see the export is incomplete
+            let _ = sender.send(Err(e)).await;
+        }
+    });
+
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|chunk| (chunk, receiver))
+    });
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .body(Body::from_stream(stream))
+        .map_err(|e| AppError::InternalError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+
+    use super::*;
+
+    async fn seeded_db() -> SqlitePool {
+        let db = crate::db::test_pool().await;
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO forms (id, title, status) VALUES ('f1', 'Review', 'published');
+            INSERT INTO questions (id, form_id, position, type, title, features) VALUES
+                ('q1', 'f1', 1, 'likert', 'Vision', '{}'),
+                ('q2', 'f1', 2, 'textarea', 'Comments', '{}');
+            INSERT INTO respondents (id, name, email, email_hash) VALUES
+                ('p1', 'Ada', 'ada@example.com', 'h1');
+            INSERT INTO responses (id, respondent_id, form_id, role, metadata, submitted_at) VALUES
+                ('r1', 'p1', 'f1', 'Staff', '{}', '2025-09-01 10:00:00'),
+                ('r2', NULL, 'f1', 'Board', '{}', '2025-09-02 10:00:00'),
+                ('r3', NULL, 'f1', NULL, '{}', '2025-09-03 10:00:00');
+            INSERT INTO answers (id, response_id, question_id, value) VALUES
+                ('a1', 'r1', 'q2', '"Clear goals"'),
+                ('a2', 'r1', 'q1', '4'),
+                ('a3', 'r3', 'q2', '"Too long, honestly"');
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        db
+    }
+
+    async fn export(state: &AppState, format: &str, include_pii: bool) -> String {
+        let response = export_responses(
+            HeaderMap::new(),
+            Path("f1".to_string()),
+            Query(ExportQuery {
+                token: Some("admin".to_string()),
+                format: Some(format.to_string()),
+                include_pii,
+            }),
+            State(state.clone()),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn exports_one_record_per_response_with_answers_in_question_order() {
+        let state = AppState::for_tests(seeded_db().await);
+
+        assert_eq!(
+            export(&state, "csv", true).await,
+            "response_id,submitted_at,role,respondent_name,respondent_email,Vision,Comments\n\
+             r1,2025-09-01 10:00:00,Staff,Ada,ada@example.com,4,Clear goals\n\
+             r2,2025-09-02 10:00:00,Board,,,,\n\
+             r3,2025-09-03 10:00:00,,,,,\"Too long, honestly\"\n"
+        );
+
+        let lines: Vec<JsonValue> = export(&state, "ndjson", false)
+            .await
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                json!({
+                    "id": "r1", "form_id": "f1", "role": "Staff",
+                    "submitted_at": "2025-09-01 10:00:00",
+                    "answers": [
+                        { "question_id": "q1", "question_title": "Vision", "value": 4 },
+                        { "question_id": "q2", "question_title": "Comments", "value": "Clear goals" }
+                    ]
+                }),
+                json!({
+                    "id": "r2", "form_id": "f1", "role": "Board",
+                    "submitted_at": "2025-09-02 10:00:00", "answers": []
+                }),
+                json!({
+                    "id": "r3", "form_id": "f1", "role": null,
+                    "submitted_at": "2025-09-03 10:00:00",
+                    "answers": [
+                        { "question_id": "q2", "question_title": "Comments", "value": "Too long, honestly" }
+                    ]
+                }),
+            ]
+        );
+    }
+
+    /// 40 responses of about 4 KB each, enough for a few chunks
+    async fn large_export() -> (Arc<dyn ResponseRepository>, Encoder) {
+        let db = seeded_db().await;
+        let comment = JsonValue::String("x".repeat(4000)).to_string();
+        for i in 0..40 {
+            let response_id = format!("big{:02}", i);
+            sqlx::query(
+                "INSERT INTO responses (id, form_id, metadata, submitted_at) VALUES (?, 'f1', '{}', '2025-09-10 10:00:00')",
+            )
+            .bind(&response_id)
+            .execute(&db)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO answers (id, response_id, question_id, value) VALUES (?, ?, 'q2', ?)",
+            )
+            .bind(format!("a-{}", response_id))
+            .bind(&response_id)
+            .bind(&comment)
+            .execute(&db)
+            .await
+            .unwrap();
+        }
+
+        let encoder = Encoder {
+            format: ExportFormat::Ndjson,
+            form_id: "f1".to_string(),
+            include_pii: false,
+            questions: vec![
+                ("q1".to_string(), "Vision".to_string()),
+                ("q2".to_string(), "Comments".to_string()),
+            ],
+        };
+        (AppState::for_tests(db).repos.responses, encoder)
+    }
+
+    #[tokio::test]
+    async fn large_exports_are_flushed_in_chunks() {
+        let (responses, encoder) = large_export().await;
+        let (sender, mut receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let writer = tokio::spawn(write_rows(responses, encoder, sender));
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = receiver.recv().await {
+            chunks.push(chunk.unwrap());
+        }
+        writer.await.unwrap().unwrap();
+
+        assert!(chunks.len() >= 3, "{} chunks", chunks.len());
+        let (last, full) = chunks.split_last().unwrap();
+        assert!(full.iter().all(|chunk| chunk.len() >= CHUNK_SIZE));
+        assert!(!last.is_empty());
+
+        // Chunks end on line boundaries and keep every response once, in order
+        assert!(chunks.iter().all(|chunk| chunk.ends_with('\n')));
+        let ids: Vec<String> = chunks
+            .concat()
+            .lines()
+            .map(|line| serde_json::from_str::<JsonValue>(line).unwrap()["id"].to_string())
+            .collect();
+        assert_eq!(ids.len(), 43);
+        assert_eq!(ids[..3], ["\"r1\"", "\"r2\"", "\"r3\""]);
+        assert_eq!(ids[42], "\"big39\"");
+    }
+
+    #[tokio::test]
+    async fn writing_stops_when_the_client_disconnects() {
+        let (responses, encoder) = large_export().await;
+        let (sender, receiver) = mpsc::channel(1);
+        drop(receiver);
+
+        // The first full chunk has nowhere to go, which ends the export quietly
+        assert!(write_rows(responses, encoder, sender).await.is_ok());
+    }
+
+    fn encoder(format: ExportFormat, include_pii: bool) -> Encoder {
+        Encoder {
+            format,
+            form_id: "f1".to_string(),
+            include_pii,
+            questions: vec![
+                ("q1".to_string(), "Rating".to_string()),
+                ("q2".to_string(), "Comments, if any".to_string()),
+            ],
+        }
+    }
+
+    fn response() -> ExportedResponse {
+        ExportedResponse {
+            id: "r1".to_string(),
+            role: Some("Staff".to_string()),
+            submitted_at: "2025-09-07 10:00:00".to_string(),
+            name: Some("Jane".to_string()),
+            email: Some("jane@example.com".to_string()),
+            answers: vec![
+                ("q1".to_string(), json!(-4)),
+                ("q2".to_string(), json!("=HYPERLINK(\"x\")\nsecond line")),
+            ],
+        }
+    }
+
+    #[test]
+    fn csv_rows_quote_fields_and_neutralize_formulas() {
+        let encoder = encoder(ExportFormat::Csv, false);
+        assert_eq!(
+            encoder.header().unwrap(),
+            "response_id,submitted_at,role,Rating,\"Comments, if any\"\n"
+        );
+        assert_eq!(
+            encoder.encode(&response()).unwrap(),
+            "r1,2025-09-07 10:00:00,Staff,-4,\"'=HYPERLINK(\"\"x\"\")\nsecond line\"\n"
+        );
+    }
+
+    #[test]
+    fn pii_columns_are_only_written_when_requested() {
+        let without = encoder(ExportFormat::Ndjson, false)
+            .encode(&response())
+            .unwrap();
+        assert!(!without.contains("jane@example.com"));
+
+        let with = encoder(ExportFormat::Ndjson, true)
+            .encode(&response())
+            .unwrap();
+        let line: JsonValue = serde_json::from_str(with.trim_end()).unwrap();
+        assert_eq!(line["respondent_email"], "jane@example.com");
+        assert_eq!(line["answers"][0]["question_title"], "Rating");
+    }
+}
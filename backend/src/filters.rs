@@ -0,0 +1,191 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::Deserialize;
+
+use crate::{db::DB_TIMESTAMP_FORMAT, error::AppError};
+
+/// Comparison operators for answer conditions, longest first so `<=` wins over `<`
+const OPERATORS: &[&str] = &["<=", ">=", "!=", "=", "<", ">"];
+
+/// Filters accepted by the response listings, e.g.
+/// `?role=Staff&submitted_after=2025-09-01&answer=q3<=2,q5>=4`
+#[derive(Debug, Default, Deserialize)]
+pub struct ResponseFilterQuery {
+    pub role: Option<String>,
+    /// Inclusive lower bound: a date, an RFC 3339 timestamp or `YYYY-MM-DD HH:MM:SS` (UTC)
+    pub submitted_after: Option<String>,
+    /// Exclusive upper bound, in the same formats
+    pub submitted_before: Option<String>,
+    /// Comma-separated answer conditions such as `q3<=2` or `q7=Yes`
+    pub answer: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AnswerValue {
+    Number(f64),
+    Text(String),
+}
+
+/// One `question op value` condition on a response's answers
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AnswerCondition {
+    pub(crate) question_id: String,
+    /// One of [`OPERATORS`], so backends may inline it in SQL
+    pub(crate) operator: &'static str,
+    pub(crate) value: AnswerValue,
+}
+
+/// Parsed response filters; each backend turns them into its own conditions
+#[derive(Debug, Clone, Default)]
+pub struct ResponseFilters {
+    pub(crate) form_id: Option<String>,
+    pub(crate) role: Option<String>,
+    pub(crate) submitted_after: Option<DateTime<Utc>>,
+    pub(crate) submitted_before: Option<DateTime<Utc>>,
+    pub(crate) answers: Vec<AnswerCondition>,
+}
+
+impl ResponseFilterQuery {
+    pub fn parse(&self) -> Result<ResponseFilters, AppError> {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+
+        let answers = match non_empty(&self.answer) {
+            Some(conditions) => conditions
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(parse_condition)
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(ResponseFilters {
+            form_id: None,
+            role: non_empty(&self.role),
+            submitted_after: non_empty(&self.submitted_after)
+                .map(|v| parse_timestamp("submitted_after", &v))
+                .transpose()?,
+            submitted_before: non_empty(&self.submitted_before)
+                .map(|v| parse_timestamp("submitted_before", &v))
+                .transpose()?,
+            answers,
+        })
+    }
+}
+
+fn parse_condition(condition: &str) -> Result<AnswerCondition, AppError> {
+    let invalid = || {
+        AppError::BadRequest(format!(
+            "Invalid answer filter '{}'. Expected <question_id><op><value> with op one of {}",
+            condition,
+            OPERATORS.join(" ")
+        ))
+    };
+
+    let start = condition.find(['<', '>', '=', '!']).ok_or_else(invalid)?;
+    let (question_id, rest) = condition.split_at(start);
+    let operator = OPERATORS
+        .iter()
+        .find(|op| rest.starts_with(*op))
+        .ok_or_else(invalid)?;
+    let question_id = question_id.trim();
+    let value = rest[operator.len()..].trim();
+    if question_id.is_empty() || value.is_empty() {
+        return Err(invalid());
+    }
+
+    let value = match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => AnswerValue::Number(number),
+        _ if matches!(*operator, "=" | "!=") => AnswerValue::Text(value.to_string()),
+        _ => {
+            return Err(AppError::BadRequest(format!(
+                "Answer filter '{}' compares text; only = and != are supported",
+                condition
+            )))
+        }
+    };
+
+    Ok(AnswerCondition {
+        question_id: question_id.to_string(),
+        operator,
+        value,
+    })
+}
+
+/// Read a filter bound given as a date, an RFC 3339 timestamp or in [`DB_TIMESTAMP_FORMAT`]
+fn parse_timestamp(name: &str, value: &str) -> Result<DateTime<Utc>, AppError> {
+    let parsed = DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc).naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, DB_TIMESTAMP_FORMAT))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default())
+        })
+        .map_err(|_| {
+            AppError::BadRequest(format!(
+                "Invalid {} '{}'. Use YYYY-MM-DD or an RFC 3339 timestamp",
+                name, value
+            ))
+        })?;
+
+    Ok(parsed.and_utc())
+}
+
+impl ResponseFilters {
+    /// Restrict to one form
+    pub fn for_form(mut self, form_id: Option<&str>) -> Self {
+        self.form_id = form_id.map(str::to_string);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answer_conditions_parse_operators_and_values() {
+        assert_eq!(
+            parse_condition("q3<=2").unwrap(),
+            AnswerCondition {
+                question_id: "q3".to_string(),
+                operator: "<=",
+                value: AnswerValue::Number(2.0),
+            }
+        );
+        assert_eq!(
+            parse_condition(" q7 != Not sure ").unwrap(),
+            AnswerCondition {
+                question_id: "q7".to_string(),
+                operator: "!=",
+                value: AnswerValue::Text("Not sure".to_string()),
+            }
+        );
+        assert!(parse_condition("q3").is_err());
+        assert!(parse_condition("<=2").is_err());
+        assert!(parse_condition("q3>high").is_err());
+    }
+
+    #[test]
+    fn timestamps_are_read_as_utc() {
+        let utc = |value: &str| value.parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(
+            parse_timestamp("submitted_after", "2025-09-01").unwrap(),
+            utc("2025-09-01T00:00:00Z")
+        );
+        assert_eq!(
+            parse_timestamp("submitted_after", "2025-09-01T12:30:00+02:00").unwrap(),
+            utc("2025-09-01T10:30:00Z")
+        );
+        assert_eq!(
+            parse_timestamp("submitted_before", "2025-09-01 08:15:00").unwrap(),
+            utc("2025-09-01T08:15:00Z")
+        );
+        assert!(parse_timestamp("submitted_after", "yesterday").is_err());
+    }
+}
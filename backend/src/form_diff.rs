@@ -0,0 +1,309 @@
+//! What an update would change in a stored form, reported by `update_form` in dry-run mode.
+//!
+//! Questions and sections are matched by ID. A question missing from the update is
+//! removed, and its stored answers are left pointing at a question that no longer
+//! exists, so each removal carries the number of answers it would orphan.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::repository::{FormDefinition, Question, Section};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Item {
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedItem {
+    pub id: String,
+    pub title: String,
+    /// Names of the fields that differ
+    pub fields: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RemovedQuestion {
+    pub id: String,
+    pub title: String,
+    /// Stored answers that would no longer belong to any question
+    pub orphaned_answers: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedQuestion {
+    pub id: String,
+    pub title: String,
+    pub fields: Vec<&'static str>,
+    /// Answers already given under the old definition
+    pub answers: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SectionChanges {
+    pub added: Vec<Item>,
+    pub removed: Vec<Item>,
+    pub changed: Vec<ChangedItem>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct QuestionChanges {
+    pub added: Vec<Item>,
+    pub removed: Vec<RemovedQuestion>,
+    pub changed: Vec<ChangedQuestion>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FormDiff {
+    /// Form-level fields that differ, such as `title` or `settings`
+    pub form_fields: Vec<&'static str>,
+    pub sections: SectionChanges,
+    pub questions: QuestionChanges,
+    /// Total over every removed question
+    pub orphaned_answers: i64,
+}
+
+impl FormDiff {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Names of the fields whose values differ, in the order given
+fn changed_fields<const N: usize>(fields: [(&'static str, bool); N]) -> Vec<&'static str> {
+    fields
+        .into_iter()
+        .filter(|(_, differs)| *differs)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+fn section_fields(current: &Section, updated: &Section) -> Vec<&'static str> {
+    changed_fields([
+        ("title", current.title != updated.title),
+        ("description", current.description != updated.description),
+        ("position", current.position != updated.position),
+    ])
+}
+
+/// Positions are renumbered on every save, so they are not compared; a question moved to
+/// another section shows up as a `section` change
+fn question_fields(current: &Question, updated: &Question) -> Vec<&'static str> {
+    changed_fields([
+        ("title", current.title != updated.title),
+        ("description", current.description != updated.description),
+        ("type", current.kind.type_name() != updated.kind.type_name()),
+        (
+            "features",
+            current.kind.features() != updated.kind.features(),
+        ),
+        ("section", current.section_id != updated.section_id),
+    ])
+}
+
+/// Compare a stored form with its replacement. `answer_counts` maps question IDs to the
+/// number of answers stored for them.
+pub fn diff(
+    current: &FormDefinition,
+    updated: &FormDefinition,
+    answer_counts: &HashMap<String, i64>,
+) -> FormDiff {
+    let (old, new) = (&current.form, &updated.form);
+    let form_fields = changed_fields([
+        ("title", old.title != new.title),
+        ("description", old.description != new.description),
+        ("instructions", old.instructions != new.instructions),
+        ("status", old.status != new.status),
+        (
+            "welcome_message",
+            old.welcome_message != new.welcome_message,
+        ),
+        (
+            "closing_message",
+            old.closing_message != new.closing_message,
+        ),
+        ("settings", old.settings != new.settings),
+    ]);
+
+    let mut sections = SectionChanges::default();
+    for section in &updated.sections {
+        match current.sections.iter().find(|s| s.id == section.id) {
+            None => sections.added.push(Item {
+                id: section.id.clone(),
+                title: section.title.clone(),
+            }),
+            Some(existing) => {
+                let fields = section_fields(existing, section);
+                if !fields.is_empty() {
+                    sections.changed.push(ChangedItem {
+                        id: section.id.clone(),
+                        title: section.title.clone(),
+                        fields,
+                    });
+                }
+            }
+        }
+    }
+    sections.removed = current
+        .sections
+        .iter()
+        .filter(|section| !updated.sections.iter().any(|s| s.id == section.id))
+        .map(|section| Item {
+            id: section.id.clone(),
+            title: section.title.clone(),
+        })
+        .collect();
+
+    let answers = |id: &str| answer_counts.get(id).copied().unwrap_or(0);
+    let mut questions = QuestionChanges::default();
+    for question in &updated.questions {
+        match current.questions.iter().find(|q| q.id == question.id) {
+            None => questions.added.push(Item {
+                id: question.id.clone(),
+                title: question.title.clone(),
+            }),
+            Some(existing) => {
+                let fields = question_fields(existing, question);
+                if !fields.is_empty() {
+                    questions.changed.push(ChangedQuestion {
+                        id: question.id.clone(),
+                        title: question.title.clone(),
+                        fields,
+                        answers: answers(&question.id),
+                    });
+                }
+            }
+        }
+    }
+    questions.removed = current
+        .questions
+        .iter()
+        .filter(|question| !updated.questions.iter().any(|q| q.id == question.id))
+        .map(|question| RemovedQuestion {
+            id: question.id.clone(),
+            title: question.title.clone(),
+            orphaned_answers: answers(&question.id),
+        })
+        .collect();
+
+    FormDiff {
+        form_fields,
+        orphaned_answers: questions.removed.iter().map(|q| q.orphaned_answers).sum(),
+        sections,
+        questions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{questions::QuestionKind, repository::Form};
+
+    fn question(id: &str, section_id: &str, question_type: &str) -> Question {
+        Question {
+            id: id.to_string(),
+            form_id: "review".to_string(),
+            section_id: Some(section_id.to_string()),
+            position: 1,
+            title: format!("Question {}", id),
+            description: None,
+            kind: QuestionKind::from_parts(question_type, &json!({ "required": true })).unwrap(),
+        }
+    }
+
+    fn section(id: &str, position: i32) -> Section {
+        Section {
+            id: id.to_string(),
+            form_id: "review".to_string(),
+            title: format!("Section {}", id),
+            description: None,
+            position,
+        }
+    }
+
+    fn definition() -> FormDefinition {
+        FormDefinition {
+            form: Form {
+                id: "review".to_string(),
+                title: "Annual Review".to_string(),
+                description: None,
+                instructions: None,
+                status: "published".to_string(),
+                welcome_message: None,
+                closing_message: None,
+                settings: Some(json!({})),
+            },
+            sections: vec![section("s1", 1), section("s2", 2)],
+            questions: vec![
+                question("q1", "s1", "likert"),
+                question("q2", "s1", "text"),
+                question("q3", "s2", "likert"),
+                question("q4", "s2", "textarea"),
+            ],
+        }
+    }
+
+    #[test]
+    fn an_unchanged_form_has_an_empty_diff() {
+        let mut renumbered = definition();
+        renumbered.questions[3].position = 9;
+        assert!(diff(&definition(), &renumbered, &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn reports_additions_removals_and_changes() {
+        let mut updated = definition();
+        updated.form.status = "finished".to_string();
+        updated.sections.remove(1);
+        updated.sections.push(section("s3", 2));
+        updated.questions.retain(|q| q.id != "q3" && q.id != "q4");
+        updated.questions[0].title = "How clear is the vision?".to_string();
+        updated.questions[1] = question("q2", "s3", "textarea");
+        updated.questions.push(question("q5", "s3", "yes_no"));
+
+        let answers = HashMap::from([
+            ("q1".to_string(), 12),
+            ("q3".to_string(), 10),
+            ("q4".to_string(), 4),
+        ]);
+        let diff = diff(&definition(), &updated, &answers);
+
+        assert_eq!(diff.form_fields, vec!["status"]);
+        assert_eq!(
+            diff.sections.added,
+            vec![Item {
+                id: "s3".to_string(),
+                title: "Section s3".to_string()
+            }]
+        );
+        assert_eq!(diff.sections.removed[0].id, "s2");
+        assert!(diff.sections.changed.is_empty());
+
+        assert_eq!(diff.questions.added[0].id, "q5");
+        let changed: Vec<_> = diff
+            .questions
+            .changed
+            .iter()
+            .map(|q| (q.id.as_str(), q.fields.clone(), q.answers))
+            .collect();
+        assert_eq!(
+            changed,
+            vec![
+                ("q1", vec!["title"], 12),
+                ("q2", vec!["type", "section"], 0)
+            ]
+        );
+        let removed: Vec<_> = diff
+            .questions
+            .removed
+            .iter()
+            .map(|q| (q.id.as_str(), q.orphaned_answers))
+            .collect();
+        assert_eq!(removed, vec![("q3", 10), ("q4", 4)]);
+        assert_eq!(diff.orphaned_answers, 14);
+    }
+}
@@ -5,14 +5,30 @@ use axum::{
     Json,
 };
 use chrono::Utc;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
+use std::collections::HashMap;
 use uuid::Uuid;
 
-use crate::{error::AppError, models::*, AppState};
+use crate::{
+    authoring::Definition,
+    db::parse_db_timestamp,
+    error::AppError,
+    filters::ResponseFilterQuery,
+    form_diff,
+    models::*,
+    outbox::OutboxEvent,
+    pagination::PageParams,
+    questions::QuestionKind,
+    repository::{Form, FormDefinition, NewSubmission, Question, Section, StoredSubmission},
+    schema::FieldError,
+    webhooks::{self, WebhookEvent},
+    AppState,
+};
 
 // Input validation and sanitization helper functions
-fn validate_email(email: &str) -> Result<(), AppError> {
+pub(crate) fn validate_email(email: &str) -> Result<(), AppError> {
     // Basic email validation
     if email.is_empty() || email.len() > 254 {
         return Err(AppError::BadRequest("Invalid email address".to_string()));
@@ -29,7 +45,7 @@ fn validate_email(email: &str) -> Result<(), AppError> {
     Ok(())
 }
 
-fn sanitize_text_input(input: &str, max_length: usize) -> String {
+pub(crate) fn sanitize_text_input(input: &str, max_length: usize) -> String {
     // Truncate to max length and trim whitespace
     let sanitized = input
         .chars()
@@ -41,13 +57,20 @@ fn sanitize_text_input(input: &str, max_length: usize) -> String {
     sanitized.replace('\0', "")
 }
 
+/// An optional text input, sanitized; `None` when nothing is left of it
+pub(crate) fn sanitize_optional_input(input: Option<&str>, max_length: usize) -> Option<String> {
+    input
+        .map(|input| sanitize_text_input(input, max_length))
+        .filter(|input| !input.is_empty())
+}
+
 /// Helper function to check admin authorization
 /// Accepts token from either Authorization header (preferred) or query parameter (legacy)
 ///
 /// Security explanation:
 /// - Headers are preferred because they don't appear in logs/history
 /// - Query params are kept for backward compatibility but should be phased out
-fn check_admin_auth(
+pub(crate) fn check_admin_auth(
     headers: &HeaderMap,
     query_token: Option<&str>,
     expected_token: &str,
@@ -56,11 +79,7 @@ fn check_admin_auth(
     if let Some(auth_header) = headers.get("authorization") {
         if let Ok(auth_str) = auth_header.to_str() {
             // Support both "Bearer TOKEN" and just "TOKEN" for flexibility
-            let token = if auth_str.starts_with("Bearer ") {
-                &auth_str[7..]
-            } else {
-                auth_str
-            };
+            let token = auth_str.strip_prefix("Bearer ").unwrap_or(auth_str);
 
             if token == expected_token {
                 return Ok(());
@@ -79,45 +98,37 @@ fn check_admin_auth(
 }
 
 /// List all forms (including archived)
-#[allow(clippy::type_complexity)]
 pub async fn list_forms(State(state): State<AppState>) -> Result<impl IntoResponse, AppError> {
-    let forms: Vec<(
-        String,
-        String,
-        Option<String>,
-        Option<String>,
-        String,
-        String,
-        String,
-    )> = sqlx::query_as(
-        r#"
-        SELECT id, title, description, instructions, status, created_at, updated_at
-        FROM forms
-        ORDER BY updated_at DESC
-        "#,
-    )
-    .fetch_all(&state.db)
-    .await
-    .map_err(AppError::Database)?;
+    let forms = state.repos.forms.list_forms().await?;
 
-    let forms_json: Vec<JsonValue> = forms
-        .into_iter()
-        .map(
-            |(id, title, desc, instructions, status, created_at, updated_at)| {
-                json!({
-                    "id": id,
-                    "title": title,
-                    "description": desc,
-                    "instructions": instructions,
-                    "status": status,
-                    "created_at": created_at,
-                    "updated_at": updated_at
-                })
-            },
-        )
-        .collect();
+    Ok(Json(forms))
+}
 
-    Ok(Json(forms_json))
+/// A question as `get_form` returns it
+#[derive(Serialize)]
+struct QuestionView<'a> {
+    id: &'a str,
+    position: i32,
+    title: &'a str,
+    description: Option<&'a str>,
+    #[serde(flatten)]
+    kind: &'a QuestionKind,
+}
+
+/// Read a question's type and features from a request body. Errors name the question's
+/// place in the body, e.g. `sections[1].questions[0].features.options`.
+fn question_kind(
+    section: usize,
+    question: usize,
+    question_type: &str,
+    features: &JsonValue,
+) -> Result<QuestionKind, AppError> {
+    QuestionKind::from_parts(question_type, features).map_err(|e| {
+        AppError::BadRequest(
+            e.at(&format!("sections[{}].questions[{}]", section, question))
+                .to_string(),
+        )
+    })
 }
 
 /// Get a specific form with its sections and questions
@@ -125,205 +136,139 @@ pub async fn get_form(
     Path(form_id): Path<String>,
     State(state): State<AppState>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Fetch the form
-    let form: Option<(String, String, Option<String>, Option<String>, String, Option<String>, Option<String>, Option<JsonValue>)> = sqlx::query_as(
-        r#"
-        SELECT id, title, description, instructions, status, welcome_message, closing_message, settings
-        FROM forms
-        WHERE id = ?
-        "#
-    )
-    .bind(&form_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(AppError::Database)?;
-
-    if let Some((
-        id,
-        title,
-        desc,
-        instructions,
-        status,
-        welcome_message,
-        closing_message,
-        settings,
-    )) = form
-    {
-        // Fetch sections
-        let sections: Vec<(String, String, String, Option<String>, i32)> = sqlx::query_as(
-            r#"
-            SELECT id, form_id, title, description, position
-            FROM sections
-            WHERE form_id = ?
-            ORDER BY position
-            "#,
-        )
-        .bind(&form_id)
-        .fetch_all(&state.db)
-        .await
-        .map_err(AppError::Database)?;
-
-        // Fetch questions
-        let questions: Vec<(
-            String,
-            Option<String>,
-            i32,
-            String,
-            String,
-            Option<String>,
-            JsonValue,
-        )> = sqlx::query_as(
-            r#"
-                SELECT id, section_id, position, type, title, description, features
-                FROM questions
-                WHERE form_id = ?
-                ORDER BY position
-                "#,
-        )
-        .bind(&form_id)
-        .fetch_all(&state.db)
-        .await
-        .map_err(AppError::Database)?;
-
-        // Build sections with questions
-        let mut sections_with_questions = Vec::new();
-        for (s_id, _, s_title, s_desc, s_pos) in sections {
-            let section_questions: Vec<JsonValue> = questions
+    let FormDefinition {
+        form,
+        sections,
+        questions,
+    } = state
+        .repos
+        .forms
+        .find_form(&form_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+
+    // Build sections with questions
+    let sections_with_questions: Vec<JsonValue> = sections
+        .into_iter()
+        .map(|section| {
+            let section_questions: Vec<QuestionView> = questions
                 .iter()
-                .filter(|(_, section_id, _, _, _, _, _)| section_id.as_ref() == Some(&s_id))
-                .map(|(q_id, _, pos, q_type, title, desc, features)| {
-                    json!({
-                        "id": q_id,
-                        "position": pos,
-                        "type": q_type,
-                        "title": title,
-                        "description": desc,
-                        "features": features
-                    })
+                .filter(|q| q.section_id.as_ref() == Some(&section.id))
+                .map(|q| QuestionView {
+                    id: &q.id,
+                    position: q.position,
+                    title: &q.title,
+                    description: q.description.as_deref(),
+                    kind: &q.kind,
                 })
                 .collect();
 
-            sections_with_questions.push(json!({
-                "id": s_id,
-                "title": s_title,
-                "description": s_desc,
-                "position": s_pos,
+            json!({
+                "id": section.id,
+                "title": section.title,
+                "description": section.description,
+                "position": section.position,
                 "questions": section_questions
-            }));
-        }
+            })
+        })
+        .collect();
 
-        Ok(Json(json!({
-            "id": id,
-            "title": title,
-            "description": desc,
-            "instructions": instructions,
-            "status": status,
-            "welcome_message": welcome_message,
-            "closing_message": closing_message,
-            "settings": settings,
-            "sections": sections_with_questions
-        })))
-    } else {
-        Err(AppError::BadRequest("Form not found".to_string()))
-    }
+    Ok(Json(json!({
+        "id": form.id,
+        "title": form.title,
+        "description": form.description,
+        "instructions": form.instructions,
+        "status": form.status,
+        "welcome_message": form.welcome_message,
+        "closing_message": form.closing_message,
+        "settings": form.settings,
+        "sections": sections_with_questions
+    })))
 }
 
 /// Submit a form with PII separation
 pub async fn submit_form_with_privacy(
     Path(form_id): Path<String>,
     State(state): State<AppState>,
-    Json(req): Json<SubmitFormRequest>,
+    Json(mut req): Json<SubmitFormRequest>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Validate request
-    req.validate().map_err(AppError::BadRequest)?;
-
-    // Generate email hash for duplicate checking
-    let email_hash = req.email_hash();
-
-    // Start transaction
-    let mut tx = state.db.begin().await.map_err(AppError::Database)?;
-
-    // Check for duplicate submission
-    let existing: Option<(String,)> =
-        sqlx::query_as("SELECT id FROM respondents WHERE email_hash = ?")
-            .bind(&email_hash)
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(AppError::Database)?;
-
-    // Get or create respondent
-    let respondent_id = if let Some((id,)) = existing {
-        // Check if they already submitted for this form
-        let already_submitted: Option<(i64,)> = sqlx::query_as(
-            "SELECT COUNT(*) FROM responses WHERE respondent_id = ? AND form_id = ?",
-        )
-        .bind(&id)
-        .bind(&form_id)
-        .fetch_optional(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
+    // Invited respondents submit under the identity on their invitation, not a typed email
+    let invitation = crate::invitations::resolve_for_submission(
+        state.repos.invitations.as_ref(),
+        &form_id,
+        req.invitation_token.as_deref(),
+    )
+    .await?;
 
-        if let Some((count,)) = already_submitted {
-            if count > 0 {
-                return Err(AppError::BadRequest(
-                    "You have already submitted a response for this form".to_string(),
-                ));
-            }
+    if let Some(invitation) = &invitation {
+        req.respondent_name = invitation.name.clone();
+        req.respondent_email = invitation.email.clone();
+        if invitation.role.is_some() {
+            req.role = invitation.role.clone();
         }
+    }
 
-        id
-    } else {
-        // Create new respondent
-        let new_id = Uuid::new_v4().to_string();
-        sqlx::query("INSERT INTO respondents (id, name, email, email_hash) VALUES (?, ?, ?, ?)")
-            .bind(&new_id)
-            .bind(&req.respondent_name)
-            .bind(&req.respondent_email)
-            .bind(&email_hash)
-            .execute(&mut *tx)
-            .await
-            .map_err(AppError::Database)?;
+    // Validate request
+    req.validate().map_err(AppError::BadRequest)?;
 
-        new_id
+    let submission = NewSubmission {
+        form_id: form_id.clone(),
+        respondent_name: req.respondent_name.clone(),
+        respondent_email: req.respondent_email.clone(),
+        email_hash: req.email_hash(),
+        role: req.role.clone(),
+        answers: req
+            .answers
+            .iter()
+            .map(|answer| (answer.question_id.clone(), answer.value.clone()))
+            .collect(),
+        // Spent in the same transaction as the response
+        invitation_id: invitation.map(|invitation| invitation.id),
     };
 
-    // Create response (without PII)
-    let response_id = Uuid::new_v4().to_string();
-    sqlx::query(
-        r#"
-        INSERT INTO responses (id, respondent_id, form_id, role, metadata)
-        VALUES (?, ?, ?, ?, ?)
-        "#,
-    )
-    .bind(&response_id)
-    .bind(&respondent_id)
-    .bind(&form_id)
-    .bind(&req.role)
-    .bind(json!({}))
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::Database)?;
-
-    // Insert answers
-    for answer in req.answers {
-        let answer_id = Uuid::new_v4().to_string();
-        sqlx::query(
-            r#"
-            INSERT INTO answers (id, response_id, question_id, value)
-            VALUES (?, ?, ?, ?)
-            "#,
-        )
-        .bind(&answer_id)
-        .bind(&response_id)
-        .bind(&answer.question_id)
-        .bind(&answer.value)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-    }
+    let notifier = crate::notifications::Notifier::for_form(&state, &form_id).await?;
+    let submitted_at = Utc::now();
+    let new_response = crate::notifications::NewResponse {
+        role: req.role.clone(),
+        respondent_name: req.respondent_name.clone(),
+        respondent_email: req.respondent_email.clone(),
+        submitted_at,
+    };
 
-    // Commit transaction
-    tx.commit().await.map_err(AppError::Database)?;
+    // Queue the admin email and webhooks with the response so neither can be lost
+    let StoredSubmission { response_id, .. } = state
+        .repos
+        .responses
+        .submit_response(&submission, &|stored: &StoredSubmission| {
+            let mut events = Vec::new();
+            if let Some(notifier) = &notifier {
+                events.push(OutboxEvent::Email(
+                    notifier.render(&new_response, stored.response_count),
+                ));
+            }
+            events.push(OutboxEvent::Webhook(WebhookEvent {
+                event: webhooks::RESPONSE_SUBMITTED,
+                form_ids: vec![form_id.clone()],
+                data: json!({
+                    "form_id": form_id,
+                    "response_id": stored.response_id,
+                    "role": req.role,
+                    "submitted_at": submitted_at.to_rfc3339(),
+                    "answers": req.answers.iter().map(|a| json!({
+                        "question_id": a.question_id,
+                        "value": a.value
+                    })).collect::<Vec<_>>()
+                }),
+                pii: Some(json!({
+                    "respondent_id": stored.respondent_id,
+                    "name": req.respondent_name,
+                    "email": req.respondent_email
+                })),
+            }));
+            events
+        })
+        .await?;
+    crate::outbox::wake(&state);
 
     Ok((
         StatusCode::CREATED,
@@ -353,21 +298,11 @@ pub async fn check_existing_submission(
     let email_hash = format!("{:x}", hasher.finalize());
 
     // Check if this email has already submitted for this form
-    let result: Option<(i64,)> = sqlx::query_as(
-        r#"
-        SELECT COUNT(*)
-        FROM responses r
-        JOIN respondents res ON res.id = r.respondent_id
-        WHERE res.email_hash = ? AND r.form_id = ?
-        "#,
-    )
-    .bind(&email_hash)
-    .bind(&form_id)
-    .fetch_optional(&state.db)
-    .await
-    .map_err(AppError::Database)?;
-
-    let has_submitted = result.map(|(count,)| count > 0).unwrap_or(false);
+    let has_submitted = state
+        .repos
+        .responses
+        .has_submitted(&form_id, &email_hash)
+        .await?;
 
     Ok(Json(json!({
         "has_submitted": has_submitted,
@@ -384,180 +319,40 @@ pub async fn get_form_stats_anonymous(
     Path(form_id): Path<String>,
     State(state): State<AppState>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Get total responses
-    let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM responses WHERE form_id = ?")
-        .bind(&form_id)
-        .fetch_one(&state.db)
-        .await
-        .map_err(AppError::Database)?;
-
-    // Get role distribution
-    let role_distribution: Vec<RoleCount> = sqlx::query_as(
-        r#"
-        SELECT role, COUNT(*) as count
-        FROM responses
-        WHERE form_id = ?
-        GROUP BY role
-        ORDER BY count DESC
-        "#,
-    )
-    .bind(&form_id)
-    .fetch_all(&state.db)
-    .await
-    .map_err(AppError::Database)?;
-
-    // Get question statistics
-    let question_stats_raw: Vec<(String, String, i64, Option<f64>)> = sqlx::query_as(
-        r#"
-        SELECT
-            q.id as question_id,
-            q.title as question_title,
-            COUNT(DISTINCT a.response_id) as response_count,
-            AVG(CASE
-                WHEN json_type(a.value) = 'integer' THEN CAST(a.value as REAL)
-                WHEN json_type(a.value) = 'object' AND json_extract(a.value, '$.rating') IS NOT NULL
-                    THEN CAST(json_extract(a.value, '$.rating') as REAL)
-                ELSE NULL
-            END) as average_rating
-        FROM questions q
-        LEFT JOIN answers a ON a.question_id = q.id
-        LEFT JOIN responses r ON r.id = a.response_id
-        WHERE q.form_id = ? AND q.type = 'likert'
-        GROUP BY q.id, q.title
-        ORDER BY q.position
-        "#,
-    )
-    .bind(&form_id)
-    .fetch_all(&state.db)
-    .await
-    .map_err(AppError::Database)?;
-
-    // Convert to proper structure
-    let mut question_stats = Vec::new();
-    for (q_id, q_title, count, avg) in question_stats_raw {
-        // Get rating distribution for this question
-        let rating_dist: Vec<RatingCount> = sqlx::query_as(
-            r#"
-            SELECT
-                CASE
-                    WHEN json_type(value) = 'integer' THEN CAST(value as INTEGER)
-                    WHEN json_type(value) = 'object' THEN CAST(json_extract(value, '$.rating') as INTEGER)
-                END as rating,
-                COUNT(*) as count
-            FROM answers a
-            JOIN responses r ON r.id = a.response_id
-            WHERE a.question_id = ? AND r.form_id = ?
-                AND (json_type(value) = 'integer' OR json_extract(value, '$.rating') IS NOT NULL)
-            GROUP BY rating
-            ORDER BY rating
-            "#
-        )
-        .bind(&q_id)
-        .bind(&form_id)
-        .fetch_all(&state.db)
-        .await
-        .map_err(AppError::Database)?;
-
-        question_stats.push(QuestionStat {
-            question_id: q_id,
-            question_title: q_title,
-            response_count: count,
-            average_rating: avg,
-            rating_distribution: rating_dist,
-        });
-    }
+    let stats = state.repos.responses.form_stats(&form_id).await?;
 
-    Ok(Json(AnonymousStats {
-        form_id,
-        total_responses: total.0,
-        role_distribution,
-        question_stats,
-    }))
+    Ok(Json(stats))
 }
 
 /// Get responses with PII (admin only, requires authentication)
 pub async fn get_responses_with_pii(
     Path(form_id): Path<String>,
+    Query(filters): Query<ResponseFilterQuery>,
+    Query(page): Query<PageParams>,
     State(state): State<AppState>,
     // Add auth check here in production
 ) -> Result<impl IntoResponse, AppError> {
-    // Fetch responses with PII joined
-    let responses_raw: Vec<(
-        String,
-        String,
-        Option<String>,
-        Option<String>,
-        Option<String>,
-        String,
-    )> = sqlx::query_as(
-        r#"
-            SELECT
-                r.id,
-                r.form_id,
-                res.name,
-                res.email,
-                r.role,
-                r.submitted_at
-            FROM responses r
-            LEFT JOIN respondents res ON res.id = r.respondent_id
-            WHERE r.form_id = ?
-            ORDER BY r.submitted_at DESC
-            "#,
-    )
-    .bind(&form_id)
-    .fetch_all(&state.db)
-    .await
-    .map_err(AppError::Database)?;
-
-    let mut responses = Vec::new();
-
-    for (id, form_id, name, email, role, submitted_at) in responses_raw {
-        // Get answers for this response
-        // Fetch as raw strings first, then parse JSON
-        let answers_raw: Vec<(String, String, String)> = sqlx::query_as(
-            r#"
-            SELECT
-                a.question_id,
-                q.title,
-                CAST(a.value AS TEXT)
-            FROM answers a
-            JOIN questions q ON q.id = a.question_id
-            WHERE a.response_id = ?
-            ORDER BY q.position
-            "#,
-        )
-        .bind(&id)
-        .fetch_all(&state.db)
-        .await
-        .map_err(AppError::Database)?;
-
-        let answers: Vec<(String, String, JsonValue)> = answers_raw
-            .into_iter()
-            .map(|(q_id, title, value_str)| {
-                let value: JsonValue = serde_json::from_str(&value_str).unwrap_or(JsonValue::Null);
-                (q_id, title, value)
-            })
-            .collect();
-
-        let answers_formatted = answers
-            .into_iter()
-            .map(|(q_id, q_title, value)| AnswerWithQuestion {
-                question_id: q_id,
-                question_title: q_title,
-                value,
-            })
-            .collect();
+    let filters = filters.parse()?.for_form(Some(&form_id));
 
-        responses.push(ResponseWithPII {
-            id,
-            form_id,
-            respondent_name: name,
-            respondent_email: email,
-            role,
-            submitted_at: submitted_at.parse().unwrap_or_else(|_| Utc::now()),
-            answers: answers_formatted,
-        });
-    }
+    // Fetch responses with PII joined
+    let page = state
+        .repos
+        .responses
+        .list_responses(&filters, &page, true)
+        .await?;
+
+    let ids: Vec<&str> = page.items.iter().map(|row| row.id.as_str()).collect();
+    let mut answers = state.repos.responses.answers_by_response(&ids).await?;
+
+    let responses = page.map(|row| ResponseWithPII {
+        answers: answers.remove(&row.id).unwrap_or_default(),
+        submitted_at: parse_db_timestamp(&row.submitted_at).unwrap_or_else(Utc::now),
+        id: row.id,
+        form_id: row.form_id,
+        respondent_name: row.respondent_name,
+        respondent_email: row.respondent_email,
+        role: row.role,
+    });
 
     Ok(Json(responses))
 }
@@ -565,52 +360,32 @@ pub async fn get_responses_with_pii(
 /// Get list of respondents for a form (PII included, admin only)
 pub async fn get_form_respondents(
     Path(form_id): Path<String>,
+    Query(filters): Query<ResponseFilterQuery>,
+    Query(page): Query<PageParams>,
     State(state): State<AppState>,
     // Add auth check here in production
 ) -> Result<impl IntoResponse, AppError> {
-    // Fetch respondents who submitted to this form
-    let respondents: Vec<(
-        String,
-        Option<String>,
-        Option<String>,
-        Option<String>,
-        String,
-    )> = sqlx::query_as(
-        r#"
-            SELECT DISTINCT
-                res.id,
-                res.name,
-                res.email,
-                r.role,
-                r.submitted_at
-            FROM responses r
-            LEFT JOIN respondents res ON res.id = r.respondent_id
-            WHERE r.form_id = ?
-            ORDER BY r.submitted_at DESC
-            "#,
-    )
-    .bind(&form_id)
-    .fetch_all(&state.db)
-    .await
-    .map_err(AppError::Database)?;
-
-    let respondent_list: Vec<_> = respondents
-        .into_iter()
-        .map(|(id, name, email, role, submitted_at)| {
-            json!({
-                "id": id,
-                "name": name.unwrap_or_else(|| "Anonymous".to_string()),
-                "email": email.unwrap_or_else(|| "No email".to_string()),
-                "role": role.unwrap_or_else(|| "Not specified".to_string()),
-                "submitted_at": submitted_at
-            })
+    let filters = filters.parse()?.for_form(Some(&form_id));
+
+    // Respondents who submitted to this form, one row per response; the respondent is
+    // gone once their PII is deleted
+    let page = state
+        .repos
+        .responses
+        .list_responses(&filters, &page, true)
+        .await?;
+
+    let respondents = page.map(|row| {
+        json!({
+            "id": row.respondent_id,
+            "name": row.respondent_name.unwrap_or_else(|| "Anonymous".to_string()),
+            "email": row.respondent_email.unwrap_or_else(|| "No email".to_string()),
+            "role": row.role.unwrap_or_else(|| "Not specified".to_string()),
+            "submitted_at": row.submitted_at
         })
-        .collect();
+    });
 
-    Ok(Json(json!({
-        "respondents": respondent_list,
-        "total": respondent_list.len()
-    })))
+    Ok(Json(respondents))
 }
 
 /// Delete PII for a specific respondent (GDPR compliance)
@@ -619,16 +394,25 @@ pub async fn delete_respondent_pii(
     State(state): State<AppState>,
     // Add auth check here in production
 ) -> Result<impl IntoResponse, AppError> {
-    // This deletes the PII but keeps the anonymous response data
-    let result = sqlx::query("DELETE FROM respondents WHERE id = ?")
-        .bind(&respondent_id)
-        .execute(&state.db)
-        .await
-        .map_err(AppError::Database)?;
-
-    if result.rows_affected() == 0 {
-        return Err(AppError::BadRequest("Respondent not found".to_string()));
-    }
+    // This deletes the PII but keeps the anonymous response data, and form-scoped webhooks
+    // hear about the deletion
+    state
+        .repos
+        .responses
+        .delete_respondent(&respondent_id, &|form_ids: &[String]| {
+            vec![OutboxEvent::Webhook(WebhookEvent {
+                event: webhooks::RESPONDENT_PII_DELETED,
+                form_ids: form_ids.to_vec(),
+                data: json!({
+                    "respondent_id": respondent_id,
+                    "form_ids": form_ids
+                }),
+                pii: None,
+            })]
+        })
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Respondent not found".to_string()))?;
+    crate::outbox::wake(&state);
 
     Ok(Json(json!({
         "message": "PII deleted successfully",
@@ -636,40 +420,67 @@ pub async fn delete_respondent_pii(
     })))
 }
 
-// Import form structures
-#[derive(Debug, Deserialize)]
+// Import form structures. The doc comments become descriptions in the served JSON Schema.
+
+/// A form definition as accepted by the import endpoint
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ImportFormRequest {
+    /// Unique identifier for the form, lowercase with hyphens
     pub id: String,
+    /// Display title shown to users
     pub title: String,
+    /// Brief description of the form's purpose
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Instructions shown at the beginning of the form
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub instructions: Option<String>,
+    #[schemars(schema_with = "crate::schema::form_status_schema")]
     pub status: String,
+    /// Message shown when starting the form
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub welcome_message: Option<String>,
+    /// Message shown after the form is submitted
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub closing_message: Option<String>,
+    /// Form-wide settings such as `allowAnonymous` or `estimatedTime`
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub settings: Option<JsonValue>,
     pub sections: Vec<ImportSection>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ImportSection {
     pub id: String,
     pub title: String,
+    /// Shown under the section title
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Display order, starting from 1
     pub position: i32,
     pub questions: Vec<ImportQuestion>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct ImportQuestion {
+    /// Unique within the form
     pub id: String,
+    /// The question text shown to users
     pub title: String,
+    /// See QUESTION_TYPES.md
+    #[schemars(schema_with = "crate::schema::question_type_schema")]
     pub question_type: String,
     pub is_required: bool,
     #[serde(default)]
     pub allow_comment: bool,
+    /// Guidance shown below the question
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub help_text: Option<String>,
-    #[allow(dead_code)]
+    /// Display order within the section
     pub position: i32,
+    /// Settings for the question type; see QUESTION_TYPES.md
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "crate::schema::features_schema")]
     pub features: Option<JsonValue>,
 }
 
@@ -721,6 +532,13 @@ pub struct AuthQuery {
     pub token: Option<String>,
 }
 
+/// `?dry_run=true` reports what a write would do without saving anything
+#[derive(Debug, Default, Deserialize)]
+pub struct DryRunQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AdminStatsQuery {
     pub token: Option<String>,
@@ -738,41 +556,22 @@ pub async fn get_admin_stats(
 
     // If form_id is provided, get stats for specific form
     if let Some(form_id) = params.form_id {
-        // Get total responses
-        let total: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM responses WHERE form_id = ?")
-            .bind(&form_id)
-            .fetch_one(&state.db)
-            .await
-            .map_err(AppError::Database)?;
+        let total = state.repos.responses.count_responses(&form_id).await?;
 
         return Ok(Json(json!({
             "form_id": form_id,
-            "total_responses": total.0,
+            "total_responses": total,
             "status": "success"
         })));
     }
 
     // Otherwise, return general admin stats
-    // Get total counts
-    let total_forms: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM forms")
-        .fetch_one(&state.db)
-        .await
-        .map_err(AppError::Database)?;
-
-    let total_responses: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM responses")
-        .fetch_one(&state.db)
-        .await
-        .map_err(AppError::Database)?;
-
-    let total_respondents: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM respondents")
-        .fetch_one(&state.db)
-        .await
-        .map_err(AppError::Database)?;
+    let totals = state.repos.responses.totals().await?;
 
     Ok(Json(json!({
-        "total_forms": total_forms.0,
-        "total_responses": total_responses.0,
-        "total_respondents": total_respondents.0,
+        "total_forms": totals.forms,
+        "total_responses": totals.responses,
+        "total_respondents": totals.respondents,
         "status": "success"
     })))
 }
@@ -781,103 +580,139 @@ pub async fn get_admin_stats(
 pub async fn get_admin_responses(
     headers: HeaderMap,
     Query(params): Query<AdminStatsQuery>,
+    Query(filters): Query<ResponseFilterQuery>,
+    Query(page): Query<PageParams>,
     State(state): State<AppState>,
 ) -> Result<impl IntoResponse, AppError> {
     // Check admin token (from header or query param)
     check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
 
-    // Build query based on whether form_id is provided
-    let responses_raw: Vec<(String, String, Option<String>, String)> =
-        if let Some(form_id) = params.form_id {
-            // Get responses for specific form
-            sqlx::query_as(
-                r#"
-                SELECT
-                    r.id,
-                    r.form_id,
-                    r.role,
-                    r.submitted_at
-                FROM responses r
-                WHERE r.form_id = ?
-                ORDER BY r.submitted_at DESC
-                "#,
-            )
-            .bind(&form_id)
-            .fetch_all(&state.db)
-            .await
-            .map_err(AppError::Database)?
-        } else {
-            // Get all responses across all forms
-            sqlx::query_as(
-                r#"
-                SELECT
-                    r.id,
-                    r.form_id,
-                    r.role,
-                    r.submitted_at
-                FROM responses r
-                ORDER BY r.submitted_at DESC
-                LIMIT 100
-                "#,
-            )
-            .fetch_all(&state.db)
-            .await
-            .map_err(AppError::Database)?
-        };
-
-    let mut responses = Vec::new();
-
-    for (id, form_id, role, submitted_at) in responses_raw {
-        // Get answers for this response
-        let answers_raw: Vec<(String, String, String)> = sqlx::query_as(
-            r#"
-            SELECT
-                a.question_id,
-                q.title,
-                CAST(a.value AS TEXT)
-            FROM answers a
-            JOIN questions q ON q.id = a.question_id
-            WHERE a.response_id = ?
-            ORDER BY q.position
-            "#,
-        )
-        .bind(&id)
-        .fetch_all(&state.db)
-        .await
-        .map_err(AppError::Database)?;
-
-        let answers: Vec<_> = answers_raw
-            .into_iter()
-            .map(|(q_id, title, value_str)| {
-                let value: serde_json::Value =
-                    serde_json::from_str(&value_str).unwrap_or(serde_json::Value::Null);
-                json!({
-                    "question_id": q_id,
-                    "question_title": title,
-                    "value": value
-                })
-            })
-            .collect();
-
-        responses.push(json!({
-            "id": id,
-            "form_id": form_id,
-            "role": role,
-            "submitted_at": submitted_at,
+    // All forms unless form_id is provided
+    let filters = filters.parse()?.for_form(params.form_id.as_deref());
+
+    let page = state
+        .repos
+        .responses
+        .list_responses(&filters, &page, false)
+        .await?;
+
+    let ids: Vec<&str> = page.items.iter().map(|row| row.id.as_str()).collect();
+    let mut answers = state.repos.responses.answers_by_response(&ids).await?;
+
+    let responses = page.map(|row| {
+        let answers = answers.remove(&row.id).unwrap_or_default();
+        json!({
+            "id": row.id,
+            "form_id": row.form_id,
+            "role": row.role,
+            "submitted_at": row.submitted_at,
             "answers": answers,
             "completed": true  // All submitted responses are considered complete
-        }));
-    }
+        })
+    });
 
     Ok(Json(responses))
 }
 
-/// Import a form from JSON configuration (admin only)
+/// The form an import document describes, stored under `form_id` with section and
+/// question IDs taken from `section_ids` and `question_ids`. Fails with every question
+/// whose `features` do not suit its type.
+pub(crate) fn import_definition(
+    form_data: ImportFormRequest,
+    form_id: &str,
+    section_ids: &HashMap<String, String>,
+    question_ids: &HashMap<String, String>,
+) -> Result<FormDefinition, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let form = Form {
+        id: form_id.to_string(),
+        title: form_data.title,
+        description: form_data.description,
+        instructions: form_data.instructions,
+        status: form_data.status, // Use the status from the import
+        welcome_message: form_data.welcome_message,
+        closing_message: form_data.closing_message,
+        settings: Some(form_data.settings.unwrap_or_else(|| json!({}))),
+    };
+
+    let mut sections = Vec::new();
+    let mut questions = Vec::new();
+    let mut global_question_position = 0;
+    // Questions are numbered in section order, as an export lists them; the document
+    // index is kept for error pointers
+    let mut ordered_sections: Vec<_> = form_data.sections.into_iter().enumerate().collect();
+    ordered_sections.sort_by_key(|(_, section)| section.position);
+    for (section_index, section) in ordered_sections {
+        let new_section_id = &section_ids[&section.id];
+
+        for (question_index, question) in section.questions.into_iter().enumerate() {
+            // Always use global counter to ensure unique positions across all sections
+            global_question_position += 1;
+
+            // Start with features from the template if provided
+            let kind = QuestionKind::from_parts(
+                &question.question_type,
+                question.features.as_ref().unwrap_or(&json!({})),
+            );
+            let mut kind = match kind {
+                Ok(kind) => kind,
+                Err(e) => {
+                    let e = e.at(&format!(
+                        "sections[{}].questions[{}]",
+                        section_index, question_index
+                    ));
+                    errors.push(FieldError {
+                        pointer: e.pointer(),
+                        message: e.message,
+                    });
+                    continue;
+                }
+            };
+
+            // Add/override with explicit fields
+            let common = kind.common_mut();
+            common.required = question.is_required;
+            common.help_text = question.help_text.clone();
+            // Only override allowComment if it's not already in features
+            common.allow_comment.get_or_insert(question.allow_comment);
+
+            questions.push(Question {
+                id: question_ids[&question.id].clone(),
+                form_id: form_id.to_string(),
+                section_id: Some(new_section_id.clone()),
+                position: global_question_position,
+                title: question.title,
+                description: question.help_text,
+                kind,
+            });
+        }
+
+        sections.push(Section {
+            id: new_section_id.clone(),
+            form_id: form_id.to_string(),
+            title: section.title,
+            description: section.description,
+            position: section.position,
+        });
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(FormDefinition {
+        form,
+        sections,
+        questions,
+    })
+}
+
+/// Import a form from a JSON, YAML or Markdown definition (admin only)
 pub async fn import_form(
     headers: HeaderMap,
     Query(auth): Query<AuthQuery>,
+    Query(options): Query<DryRunQuery>,
     State(state): State<AppState>,
-    Json(form_data): Json<ImportFormRequest>,
+    Definition(definition): Definition,
 ) -> Result<impl IntoResponse, AppError> {
     // Check admin token (from header or query param)
     check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
@@ -887,40 +722,47 @@ pub async fn import_form(
     // - created_by user_id for audit trail
     // - Check user has permission to create forms in this tenant
 
-    // Validate unique question IDs within the form
-    let mut question_ids = std::collections::HashSet::new();
-    for section in &form_data.sections {
-        for question in &section.questions {
-            if !question_ids.insert(question.id.clone()) {
-                return Err(AppError::BadRequest(
-                    format!("Duplicate question ID found: '{}'. All question IDs must be unique within a form.", question.id)
-                ));
-            }
-        }
+    // Check the document against the schema first, reporting every problem at once
+    let errors = crate::schema::validate(&definition);
+    if !errors.is_empty() {
+        return Err(AppError::InvalidDefinition(errors));
     }
+    let form_data: ImportFormRequest = serde_json::from_value(definition)
+        .map_err(|e| AppError::BadRequest(format!("Invalid form definition: {}", e)))?;
+
+    // Rules the schema can't express are collected the same way
+    let mut errors = Vec::new();
 
-    // Validate unique section IDs within the form
+    // Validate unique question and section IDs within the form
+    let mut question_ids = std::collections::HashSet::new();
     let mut section_ids = std::collections::HashSet::new();
-    for section in &form_data.sections {
+    for (section_index, section) in form_data.sections.iter().enumerate() {
         if !section_ids.insert(section.id.clone()) {
-            return Err(AppError::BadRequest(format!(
-                "Duplicate section ID found: '{}'. All section IDs must be unique within a form.",
-                section.id
-            )));
+            errors.push(FieldError {
+                pointer: format!("/sections/{}/id", section_index),
+                message: format!(
+                    "Duplicate section ID found: '{}'. All section IDs must be unique within a form.",
+                    section.id
+                ),
+            });
+        }
+        for (question_index, question) in section.questions.iter().enumerate() {
+            if !question_ids.insert(question.id.clone()) {
+                errors.push(FieldError {
+                    pointer: format!(
+                        "/sections/{}/questions/{}/id",
+                        section_index, question_index
+                    ),
+                    message: format!(
+                        "Duplicate question ID found: '{}'. All question IDs must be unique within a form.",
+                        question.id
+                    ),
+                });
+            }
         }
     }
 
-    // Start a transaction
-    let mut tx = state.db.begin().await.map_err(AppError::Database)?;
-
-    // Check if form with this ID already exists
-    let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM forms WHERE id = ?")
-        .bind(&form_data.id)
-        .fetch_optional(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-    let has_conflict = existing.is_some();
+    let has_conflict = state.repos.forms.form_exists(&form_data.id).await?;
     let (final_form_id, section_id_map, question_id_map) = if has_conflict {
         // Form ID conflict - generate new IDs for everything
         let new_form_id = Uuid::new_v4().to_string();
@@ -957,100 +799,60 @@ pub async fn import_form(
         (form_data.id.clone(), section_id_map, question_id_map)
     };
 
-    // Insert the form with all fields
-    let now = Utc::now();
-    let settings_json = if let Some(settings) = &form_data.settings {
-        settings.to_string()
-    } else {
-        "{}".to_string()
+    let original_form_id = form_data.id.clone();
+    let definition =
+        import_definition(form_data, &final_form_id, &section_id_map, &question_id_map);
+    let definition = match definition {
+        Ok(definition) if errors.is_empty() => definition,
+        Ok(_) => return Err(AppError::InvalidDefinition(errors)),
+        Err(invalid) => {
+            errors.extend(invalid);
+            return Err(AppError::InvalidDefinition(errors));
+        }
     };
 
-    sqlx::query(
-        r#"
-        INSERT INTO forms (id, title, description, instructions, status, welcome_message, closing_message, settings, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-        "#
-    )
-    .bind(&final_form_id)
-    .bind(&form_data.title)
-    .bind(&form_data.description)
-    .bind(&form_data.instructions)
-    .bind(&form_data.status)  // Use the status from the import
-    .bind(&form_data.welcome_message)
-    .bind(&form_data.closing_message)
-    .bind(settings_json)
-    .bind(now.to_rfc3339())
-    .bind(now.to_rfc3339())
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::Database)?;
-
-    // Insert sections and questions
-    let mut global_question_position = 0;
-    for section in form_data.sections {
-        let new_section_id = section_id_map.get(&section.id).unwrap();
-
-        // Insert section
-        sqlx::query(
-            r#"
-            INSERT INTO sections (id, form_id, title, description, position)
-            VALUES (?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(new_section_id)
-        .bind(&final_form_id)
-        .bind(&section.title)
-        .bind(&section.description)
-        .bind(section.position)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-        // Insert questions for this section
-        for question in section.questions {
-            let new_question_id = question_id_map.get(&question.id).unwrap();
-
-            // Always use global counter to ensure unique positions across all sections
-            global_question_position += 1;
-            let position = global_question_position;
-
-            // Start with features from the template if provided
-            let mut features = if let Some(template_features) = question.features {
-                template_features
-            } else {
-                json!({})
-            };
-
-            // Add/override with explicit fields
-            features["required"] = json!(question.is_required);
-            features["helpText"] = json!(question.help_text);
-            // Only override allowComment if it's not already in features
-            if features.get("allowComment").is_none() {
-                features["allowComment"] = json!(question.allow_comment);
-            }
+    if options.dry_run {
+        // Only the IDs that would change. Replacements are random UUIDs drawn on the real
+        // import, so the preview names what would be replaced but not the new values.
+        let renamed = |map: &HashMap<String, String>| -> HashMap<String, Option<String>> {
+            map.iter()
+                .filter(|(from, to)| from != to)
+                .map(|(from, _)| (from.clone(), None))
+                .collect()
+        };
+        let original_section_ids: HashMap<&String, &String> =
+            section_id_map.iter().map(|(from, to)| (to, from)).collect();
+        let section_summaries: Vec<JsonValue> = definition
+            .sections
+            .iter()
+            .map(|section| {
+                let count = definition
+                    .questions
+                    .iter()
+                    .filter(|q| q.section_id.as_ref() == Some(&section.id))
+                    .count();
+                let id = original_section_ids[&section.id];
+                json!({ "id": id, "title": section.title, "questions": count })
+            })
+            .collect();
 
-            sqlx::query(
-                r#"
-                INSERT INTO questions (id, form_id, section_id, position, type, title, description, features)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-                "#
-            )
-            .bind(new_question_id)
-            .bind(&final_form_id)
-            .bind(new_section_id)
-            .bind(position)
-            .bind(&question.question_type)
-            .bind(&question.title)
-            .bind(&question.help_text)
-            .bind(features.to_string())
-            .execute(&mut *tx)
-            .await
-            .map_err(AppError::Database)?;
-        }
+        return Ok(Json(json!({
+            "message": "Preview only; nothing was saved",
+            "dry_run": true,
+            "form_id": (!has_conflict).then_some(&final_form_id),
+            "title": definition.form.title,
+            "id_conflict": has_conflict,
+            "id_mapping": {
+                "form": has_conflict.then(|| json!({ "from": original_form_id, "to": null })),
+                "sections": renamed(&section_id_map),
+                "questions": renamed(&question_id_map)
+            },
+            "sections": section_summaries,
+            "question_count": definition.questions.len()
+        })));
     }
 
-    // Commit the transaction
-    tx.commit().await.map_err(AppError::Database)?;
+    state.repos.forms.create_form(&definition).await?;
 
     let message = if has_conflict {
         "Form imported successfully (new ID generated due to conflict)"
@@ -1061,7 +863,7 @@ pub async fn import_form(
     Ok(Json(json!({
         "message": message,
         "form_id": final_form_id,
-        "title": form_data.title,
+        "title": definition.form.title,
         "id_conflict": has_conflict
     })))
 }
@@ -1070,6 +872,7 @@ pub async fn import_form(
 pub async fn update_form(
     Path(form_id): Path<String>,
     Query(auth): Query<AuthQuery>,
+    Query(options): Query<DryRunQuery>,
     State(state): State<AppState>,
     Json(form_data): Json<UpdateFormRequest>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -1078,268 +881,129 @@ pub async fn update_form(
         return Err(AppError::Unauthorized("Invalid admin token".to_string()));
     }
 
-    // Start a transaction
-    let mut tx = state.db.begin().await.map_err(AppError::Database)?;
-
-    // Check if form exists
-    let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM forms WHERE id = ?")
-        .bind(&form_id)
-        .fetch_optional(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-    if existing.is_none() {
-        return Err(AppError::BadRequest("Form not found".to_string()));
-    }
-
-    // Update the form metadata with all fields
-    let now = Utc::now();
-    let settings_json = if let Some(settings) = &form_data.settings {
-        settings.to_string()
-    } else {
-        "{}".to_string()
+    let form = Form {
+        id: form_id.clone(),
+        title: form_data.title.clone(),
+        description: form_data.description,
+        instructions: form_data.instructions,
+        status: form_data.status.clone(),
+        welcome_message: form_data.welcome_message,
+        closing_message: form_data.closing_message,
+        settings: Some(form_data.settings.unwrap_or_else(|| json!({}))),
     };
 
-    sqlx::query(
-        r#"
-        UPDATE forms
-        SET title = ?, description = ?, instructions = ?, status = ?,
-            welcome_message = ?, closing_message = ?, settings = ?, updated_at = ?
-        WHERE id = ?
-        "#,
-    )
-    .bind(&form_data.title)
-    .bind(&form_data.description)
-    .bind(&form_data.instructions)
-    .bind(&form_data.status)
-    .bind(&form_data.welcome_message)
-    .bind(&form_data.closing_message)
-    .bind(settings_json)
-    .bind(now.to_rfc3339())
-    .bind(&form_id)
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::Database)?;
-
-    // Delete existing sections and questions (cascade will handle questions)
-    sqlx::query("DELETE FROM questions WHERE form_id = ?")
-        .bind(&form_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-    sqlx::query("DELETE FROM sections WHERE form_id = ?")
-        .bind(&form_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-    // Re-insert sections and questions
+    let mut sections = Vec::new();
+    let mut questions = Vec::new();
     let mut global_question_position = 0;
-    for section in form_data.sections {
-        // Insert section
-        sqlx::query(
-            r#"
-            INSERT INTO sections (id, form_id, title, description, position)
-            VALUES (?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&section.id)
-        .bind(&form_id)
-        .bind(&section.title)
-        .bind(&section.description)
-        .bind(section.position)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-        // Insert questions for this section
-        for question in section.questions {
+    // Questions are numbered in section order, as an export lists them; the document
+    // index is kept for error pointers
+    let mut ordered_sections: Vec<_> = form_data.sections.into_iter().enumerate().collect();
+    ordered_sections.sort_by_key(|(_, section)| section.position);
+    for (section_index, section) in ordered_sections {
+        for (question_index, question) in section.questions.into_iter().enumerate() {
             // Always use global counter to ensure unique positions across all sections
             global_question_position += 1;
-            let position = global_question_position;
-
-            // Start with features from the request if provided
-            let mut features = if let Some(template_features) = question.features {
-                template_features
-            } else {
-                json!({})
-            };
-
-            // Add/override with explicit fields
-            features["required"] = json!(question.is_required);
 
-            if let Some(allow_comment) = question.allow_comment {
-                features["allowComment"] = json!(allow_comment);
+            // Start with features from the request if provided, then let the
+            // editor's top-level fields override them
+            let mut features = question.features.unwrap_or_else(|| json!({}));
+            if let Some(features) = features.as_object_mut() {
+                if let Some(placeholder) = &question.placeholder {
+                    features.insert("placeholder".to_string(), json!(placeholder));
+                }
+                if let Some(char_limit) = question.char_limit {
+                    features.insert("charLimit".to_string(), json!(char_limit));
+                }
+                if let Some(rows) = question.rows {
+                    features.insert("rows".to_string(), json!(rows));
+                }
             }
-            if let Some(help_text) = &question.help_text {
-                features["helpText"] = json!(help_text);
+            let mut kind = question_kind(
+                section_index,
+                question_index,
+                &question.question_type,
+                &features,
+            )?;
+
+            let common = kind.common_mut();
+            common.required = question.is_required;
+            if question.allow_comment.is_some() {
+                common.allow_comment = question.allow_comment;
             }
-            if let Some(placeholder) = &question.placeholder {
-                features["placeholder"] = json!(placeholder);
-            }
-            if let Some(char_limit) = question.char_limit {
-                features["charLimit"] = json!(char_limit);
-            }
-            if let Some(rows) = question.rows {
-                features["rows"] = json!(rows);
+            if question.help_text.is_some() {
+                common.help_text = question.help_text.clone();
             }
 
-            sqlx::query(
-                r#"
-                INSERT INTO questions (id, form_id, section_id, position, type, title, description, features)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-                "#
-            )
-            .bind(&question.id)
-            .bind(&form_id)
-            .bind(&section.id)
-            .bind(position)
-            .bind(&question.question_type)
-            .bind(&question.title)
-            .bind(&question.help_text)
-            .bind(features.to_string())
-            .execute(&mut *tx)
-            .await
-            .map_err(AppError::Database)?;
+            questions.push(Question {
+                id: question.id,
+                form_id: form_id.clone(),
+                section_id: Some(section.id.clone()),
+                position: global_question_position,
+                title: question.title,
+                description: question.help_text,
+                kind,
+            });
         }
-    }
 
-    // Commit the transaction
-    tx.commit().await.map_err(AppError::Database)?;
-
-    Ok(Json(json!({
-        "message": "Form updated successfully",
-        "form_id": form_id
-    })))
-}
-
-/// Clone a form (admin only)
-pub async fn clone_form(
-    Path(form_id): Path<String>,
-    Query(auth): Query<AuthQuery>,
-    State(state): State<AppState>,
-) -> Result<impl IntoResponse, AppError> {
-    // Check admin token
-    if auth.token.as_deref() != Some(state.admin_token.as_str()) {
-        return Err(AppError::Unauthorized("Invalid admin token".to_string()));
+        sections.push(Section {
+            id: section.id,
+            form_id: form_id.clone(),
+            title: section.title,
+            description: section.description,
+            position: section.position,
+        });
     }
 
-    // Start a transaction
-    let mut tx = state.db.begin().await.map_err(AppError::Database)?;
-
-    // Get the original form
-    let original_form: Option<(String, String, Option<String>, Option<String>, String)> =
-        sqlx::query_as(
-            "SELECT id, title, description, instructions, status FROM forms WHERE id = ?",
-        )
-        .bind(&form_id)
-        .fetch_optional(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
+    let definition = FormDefinition {
+        form,
+        sections,
+        questions,
+    };
 
-    let original_form =
-        original_form.ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+    if options.dry_run {
+        let current = state
+            .repos
+            .forms
+            .find_form(&form_id)
+            .await?
+            .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+        let answer_counts = state.repos.responses.answer_counts(&form_id).await?;
+        let diff = form_diff::diff(&current, &definition, &answer_counts);
 
-    // Generate new IDs
-    let new_form_id = format!("{}-copy-{}", form_id, Utc::now().timestamp());
-    let new_title = format!("{} (Copy)", original_form.1);
+        return Ok(Json(json!({
+            "message": "Preview only; nothing was saved",
+            "dry_run": true,
+            "form_id": form_id,
+            "has_changes": !diff.is_empty(),
+            "diff": diff
+        })));
+    }
 
-    // Insert the cloned form
     let now = Utc::now();
-    sqlx::query(
-        r#"
-        INSERT INTO forms (id, title, description, instructions, status, created_at, updated_at)
-        VALUES (?, ?, ?, ?, 'draft', ?, ?)
-        "#,
-    )
-    .bind(&new_form_id)
-    .bind(&new_title)
-    .bind(&original_form.2)
-    .bind(&original_form.3)
-    .bind(now.to_rfc3339())
-    .bind(now.to_rfc3339())
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::Database)?;
-
-    // Clone sections
-    let sections: Vec<(String, String, Option<String>, i32)> = sqlx::query_as(
-        "SELECT id, title, description, position FROM sections WHERE form_id = ? ORDER BY position",
-    )
-    .bind(&form_id)
-    .fetch_all(&mut *tx)
-    .await
-    .map_err(AppError::Database)?;
-
-    for (old_section_id, title, description, position) in sections {
-        let new_section_id = format!(
-            "{}-{}",
-            old_section_id,
-            Utc::now().timestamp_nanos_opt().unwrap()
-        );
-
-        // Insert cloned section
-        sqlx::query(
-            r#"
-            INSERT INTO sections (id, form_id, title, description, position)
-            VALUES (?, ?, ?, ?, ?)
-            "#,
-        )
-        .bind(&new_section_id)
-        .bind(&new_form_id)
-        .bind(&title)
-        .bind(&description)
-        .bind(position)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-        // Clone questions for this section
-        let questions: Vec<(String, i32, String, String, Option<String>, String)> = sqlx::query_as(
-            r#"
-            SELECT id, position, type, title, description, features
-            FROM questions
-            WHERE form_id = ? AND section_id = ?
-            ORDER BY position
-            "#,
-        )
-        .bind(&form_id)
-        .bind(&old_section_id)
-        .fetch_all(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-        for (old_q_id, position, q_type, title, description, features) in questions {
-            let new_q_id = format!("{}-{}", old_q_id, Utc::now().timestamp_nanos_opt().unwrap());
-
-            sqlx::query(
-                r#"
-                INSERT INTO questions (id, form_id, section_id, position, type, title, description, features)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-                "#
-            )
-            .bind(&new_q_id)
-            .bind(&new_form_id)
-            .bind(&new_section_id)
-            .bind(position)
-            .bind(&q_type)
-            .bind(&title)
-            .bind(&description)
-            .bind(&features)
-            .execute(&mut *tx)
-            .await
-            .map_err(AppError::Database)?;
-        }
+    let updated = OutboxEvent::Webhook(WebhookEvent {
+        event: webhooks::FORM_UPDATED,
+        form_ids: vec![form_id.clone()],
+        data: json!({
+            "form_id": form_id,
+            "title": form_data.title,
+            "status": form_data.status,
+            "updated_at": now.to_rfc3339()
+        }),
+        pii: None,
+    });
+    if !state
+        .repos
+        .forms
+        .replace_form(&definition, now, &[updated])
+        .await?
+    {
+        return Err(AppError::BadRequest("Form not found".to_string()));
     }
-
-    // Commit the transaction
-    tx.commit().await.map_err(AppError::Database)?;
+    crate::outbox::wake(&state);
 
     Ok(Json(json!({
-        "message": "Form cloned successfully",
-        "form_id": new_form_id,
-        "title": new_title
+        "message": "Form updated successfully",
+        "form_id": form_id
     })))
 }
 
@@ -1364,23 +1028,30 @@ pub async fn update_form_status(
 
     // Update the form status
     let now = Utc::now();
-    let result = sqlx::query(
-        r#"
-        UPDATE forms
-        SET status = ?, updated_at = ?
-        WHERE id = ?
-        "#,
-    )
-    .bind(&status_update.status)
-    .bind(now.to_rfc3339())
-    .bind(&form_id)
-    .execute(&state.db)
-    .await
-    .map_err(AppError::Database)?;
-
-    if result.rows_affected() == 0 {
-        return Err(AppError::BadRequest("Form not found".to_string()));
-    }
+    state
+        .repos
+        .forms
+        .set_status(
+            &form_id,
+            &status_update.status,
+            now,
+            &|previous_status: &str| {
+                vec![OutboxEvent::Webhook(WebhookEvent {
+                    event: webhooks::FORM_STATUS_CHANGED,
+                    form_ids: vec![form_id.clone()],
+                    data: json!({
+                        "form_id": form_id,
+                        "status": status_update.status,
+                        "previous_status": previous_status,
+                        "changed_at": now.to_rfc3339()
+                    }),
+                    pii: None,
+                })]
+            },
+        )
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+    crate::outbox::wake(&state);
 
     Ok(Json(json!({
         "message": format!("Form status updated to {}", status_update.status),
@@ -1400,62 +1071,27 @@ pub async fn delete_form(
         return Err(AppError::Unauthorized("Invalid admin token".to_string()));
     }
 
-    // Start a transaction to ensure all deletions happen atomically
-    let mut tx = state.db.begin().await.map_err(AppError::Database)?;
-
-    // Check if form exists and get response count for logging
-    let response_count: Option<(i64,)> =
-        sqlx::query_as("SELECT COUNT(*) FROM responses WHERE form_id = ?")
-            .bind(&form_id)
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(AppError::Database)?;
-
-    let count = response_count.map(|c| c.0).unwrap_or(0);
-
-    // Delete all answers associated with responses to this form
-    sqlx::query(
-        "DELETE FROM answers WHERE response_id IN (SELECT id FROM responses WHERE form_id = ?)",
-    )
-    .bind(&form_id)
-    .execute(&mut *tx)
-    .await
-    .map_err(AppError::Database)?;
-
-    // Delete all responses for this form
-    sqlx::query("DELETE FROM responses WHERE form_id = ?")
-        .bind(&form_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-    // Delete all questions for this form
-    sqlx::query("DELETE FROM questions WHERE form_id = ?")
-        .bind(&form_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-    // Delete all sections for this form
-    sqlx::query("DELETE FROM sections WHERE form_id = ?")
-        .bind(&form_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-    // Finally, delete the form itself
-    let result = sqlx::query("DELETE FROM forms WHERE id = ?")
-        .bind(&form_id)
-        .execute(&mut *tx)
-        .await
-        .map_err(AppError::Database)?;
-
-    if result.rows_affected() == 0 {
-        return Err(AppError::BadRequest("Form not found".to_string()));
-    }
-
-    // Commit the transaction
-    tx.commit().await.map_err(AppError::Database)?;
+    // Delete the form with everything that hangs off it, atomically; only global webhooks
+    // remain to hear about it
+    let now = Utc::now();
+    let count = state
+        .repos
+        .forms
+        .delete_form(&form_id, &|responses_deleted: &i64| {
+            vec![OutboxEvent::Webhook(WebhookEvent {
+                event: webhooks::FORM_DELETED,
+                form_ids: vec![form_id.clone()],
+                data: json!({
+                    "form_id": form_id,
+                    "responses_deleted": responses_deleted,
+                    "deleted_at": now.to_rfc3339()
+                }),
+                pii: None,
+            })]
+        })
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+    crate::outbox::wake(&state);
 
     Ok(Json(json!({
         "message": format!("Form deleted successfully along with {} responses", count),
@@ -1478,3 +1114,774 @@ pub async fn get_form_template() -> Result<impl IntoResponse, AppError> {
 
     Ok(Json(template_json))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Once};
+    use std::time::Duration;
+
+    use axum::response::IntoResponse;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::ConnectOptions;
+    use tracing_subscriber::layer::{Context, SubscriberExt};
+    use tracing_subscriber::Layer;
+
+    use super::*;
+    use crate::repository::memory::{MemoryStore, StoredResponse};
+
+    const QUESTIONS: usize = 50;
+    const RESPONSES: usize = 300;
+
+    /// Statements run by the benchmark pool, which logs them at WARN so other pools don't count
+    static QUERIES: AtomicUsize = AtomicUsize::new(0);
+
+    struct QueryCounter;
+
+    impl<S: tracing::Subscriber> Layer<S> for QueryCounter {
+        fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+            let metadata = event.metadata();
+            if metadata.target() == "sqlx::query" && *metadata.level() == tracing::Level::WARN {
+                QUERIES.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Statement logging at WARN, which [`QueryCounter`] counts
+    fn counted_options() -> SqliteConnectOptions {
+        static SUBSCRIBER: Once = Once::new();
+        SUBSCRIBER.call_once(|| {
+            let _ = tracing::subscriber::set_global_default(
+                tracing_subscriber::registry().with(QueryCounter),
+            );
+        });
+
+        // sqlx runs statements on a worker thread, so counting goes through the global subscriber
+        SqliteConnectOptions::from_str("sqlite::memory:")
+            .unwrap()
+            .log_statements(log::LevelFilter::Warn)
+            .log_slow_statements(log::LevelFilter::Warn, Duration::from_secs(60))
+    }
+
+    async fn seeded_state(options: SqliteConnectOptions) -> AppState {
+        let db = crate::db::test_pool_with(options).await;
+
+        let mut tx = db.begin().await.unwrap();
+        sqlx::query("INSERT INTO forms (id, title, status) VALUES ('bench', 'Bench', 'published')")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        for q in 0..QUESTIONS {
+            sqlx::query(
+                "INSERT INTO questions (id, form_id, position, type, title) VALUES (?, 'bench', ?, 'likert', ?)",
+            )
+            .bind(format!("q{}", q))
+            .bind(q as i64)
+            .bind(format!("Question {}", q))
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        }
+        for r in 0..RESPONSES {
+            sqlx::query("INSERT INTO respondents (id, name, email) VALUES (?, ?, ?)")
+                .bind(format!("p{}", r))
+                .bind(format!("Person {}", r))
+                .bind(format!("person{}@example.com", r))
+                .execute(&mut *tx)
+                .await
+                .unwrap();
+            sqlx::query(
+                "INSERT INTO responses (id, respondent_id, form_id, role) VALUES (?, ?, 'bench', ?)",
+            )
+            .bind(format!("r{}", r))
+            .bind(format!("p{}", r))
+            .bind(if r % 2 == 0 { "Staff" } else { "Board" })
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+            for q in 0..QUESTIONS {
+                sqlx::query(
+                    "INSERT INTO answers (id, response_id, question_id, value) VALUES (?, ?, ?, ?)",
+                )
+                .bind(format!("a{}-{}", r, q))
+                .bind(format!("r{}", r))
+                .bind(format!("q{}", q))
+                .bind(json!((r + q) % 5 + 1).to_string())
+                .execute(&mut *tx)
+                .await
+                .unwrap();
+            }
+        }
+        tx.commit().await.unwrap();
+
+        AppState::for_tests(db)
+    }
+
+    /// A page large enough to hold every seeded response
+    fn one_page() -> PageParams {
+        PageParams {
+            limit: Some(RESPONSES as i64),
+            ..PageParams::default()
+        }
+    }
+
+    /// Run a handler and return its JSON body with the number of statements it executed
+    async fn measure<F>(handler: F) -> (JsonValue, usize)
+    where
+        F: std::future::Future<Output = Result<axum::response::Response, AppError>>,
+    {
+        QUERIES.store(0, Ordering::SeqCst);
+        let response = handler.await.unwrap();
+        let queries = QUERIES.load(Ordering::SeqCst);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (serde_json::from_slice(&body).unwrap(), queries)
+    }
+
+    #[tokio::test]
+    async fn large_form_listings_use_a_constant_number_of_queries() {
+        let state = seeded_state(counted_options()).await;
+
+        let (stats, queries) = measure(async {
+            get_form_stats_anonymous(Path("bench".to_string()), State(state.clone()))
+                .await
+                .map(IntoResponse::into_response)
+        })
+        .await;
+        assert_eq!(queries, 4, "stats ran {} queries", queries);
+        assert_eq!(stats["question_stats"].as_array().unwrap().len(), QUESTIONS);
+        let distribution_total: i64 = stats["question_stats"][0]["rating_distribution"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| d["count"].as_i64().unwrap())
+            .sum();
+        assert_eq!(distribution_total, RESPONSES as i64);
+
+        let (responses, queries) = measure(async {
+            get_responses_with_pii(
+                Path("bench".to_string()),
+                Query(ResponseFilterQuery::default()),
+                Query(one_page()),
+                State(state.clone()),
+            )
+            .await
+            .map(IntoResponse::into_response)
+        })
+        .await;
+        assert_eq!(queries, 3, "PII listing ran {} queries", queries);
+        let responses = responses["items"].as_array().unwrap();
+        assert_eq!(responses.len(), RESPONSES);
+        assert!(responses
+            .iter()
+            .all(|r| r["answers"].as_array().unwrap().len() == QUESTIONS));
+
+        let (responses, queries) = measure(async {
+            get_admin_responses(
+                HeaderMap::new(),
+                Query(AdminStatsQuery {
+                    token: Some("admin".to_string()),
+                    form_id: Some("bench".to_string()),
+                }),
+                Query(ResponseFilterQuery::default()),
+                Query(one_page()),
+                State(state.clone()),
+            )
+            .await
+            .map(IntoResponse::into_response)
+        })
+        .await;
+        assert_eq!(queries, 3, "admin listing ran {} queries", queries);
+        assert_eq!(responses["total"], RESPONSES as i64);
+        assert_eq!(responses["items"].as_array().unwrap().len(), RESPONSES);
+        assert_eq!(
+            responses["items"][0]["answers"][0]["question_title"],
+            "Question 0"
+        );
+    }
+
+    #[tokio::test]
+    async fn admin_responses_filter_and_page_by_cursor() {
+        // Not counted, so running alongside the query-count test does not skew it
+        let state = seeded_state(SqliteConnectOptions::from_str("sqlite::memory:").unwrap()).await;
+
+        // Answers are (response + question) % 5 + 1, and even responses are Staff
+        let mut cursor = None;
+        let mut seen = Vec::new();
+        loop {
+            let response = get_admin_responses(
+                HeaderMap::new(),
+                Query(AdminStatsQuery {
+                    token: Some("admin".to_string()),
+                    form_id: Some("bench".to_string()),
+                }),
+                Query(ResponseFilterQuery {
+                    role: Some("Staff".to_string()),
+                    answer: Some("q0<=2".to_string()),
+                    ..ResponseFilterQuery::default()
+                }),
+                Query(PageParams {
+                    cursor,
+                    limit: Some(25),
+                    sort: Some("role".to_string()),
+                }),
+                State(state.clone()),
+            )
+            .await
+            .unwrap()
+            .into_response();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let page: JsonValue = serde_json::from_slice(&body).unwrap();
+
+            assert_eq!(page["total"], 60);
+            for item in page["items"].as_array().unwrap() {
+                assert_eq!(item["role"], "Staff");
+                assert!(item["answers"][0]["value"].as_i64().unwrap() <= 2);
+                seen.push(item["id"].as_str().unwrap().to_string());
+            }
+
+            match page["next_cursor"].as_str() {
+                Some(next) => cursor = Some(next.to_string()),
+                None => break,
+            }
+        }
+
+        let unique: std::collections::HashSet<_> = seen.iter().collect();
+        assert_eq!(seen.len(), 60);
+        assert_eq!(unique.len(), 60);
+    }
+
+    /// State whose repositories are the in-memory fake; the pool stays unconnected
+    fn fake_state() -> (AppState, Arc<MemoryStore>) {
+        let store = Arc::new(MemoryStore::default());
+        (store.app_state(), store)
+    }
+
+    async fn body_json(response: impl IntoResponse) -> JsonValue {
+        let body = axum::body::to_bytes(response.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn admin() -> Query<AuthQuery> {
+        Query(AuthQuery {
+            token: Some("admin".to_string()),
+        })
+    }
+
+    #[tokio::test]
+    async fn import_get_and_clone_against_the_fake_repositories() {
+        let (state, _) = fake_state();
+        let request = json!({
+            "id": "review",
+            "title": "Annual Review",
+            "description": null,
+            "instructions": null,
+            "status": "published",
+            "welcome_message": null,
+            "closing_message": null,
+            "settings": null,
+            "sections": [{
+                "id": "s1",
+                "title": "Leadership",
+                "description": null,
+                "position": 1,
+                "questions": [
+                    { "id": "q1", "title": "Vision", "question_type": "likert", "is_required": true, "help_text": null, "position": 1, "features": null },
+                    { "id": "q2", "title": "Notes", "question_type": "textarea", "is_required": false, "help_text": "Optional", "position": 2, "features": null }
+                ]
+            }]
+        });
+
+        let imported = import_form(
+            HeaderMap::new(),
+            admin(),
+            Query(DryRunQuery::default()),
+            State(state.clone()),
+            Definition(request),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body_json(imported).await["form_id"], "review");
+
+        let form = body_json(
+            get_form(Path("review".to_string()), State(state.clone()))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(form["settings"], json!({}));
+        let questions = form["sections"][0]["questions"].as_array().unwrap();
+        assert_eq!(questions.len(), 2);
+        assert_eq!(questions[1]["features"]["helpText"], "Optional");
+
+        let cloned = body_json(
+            crate::cloning::clone_form(
+                HeaderMap::new(),
+                Path("review".to_string()),
+                admin(),
+                State(state.clone()),
+                None,
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(cloned["title"], "Annual Review (Copy)");
+        let copy_id = cloned["form_id"].as_str().unwrap().to_string();
+        let copy = body_json(get_form(Path(copy_id), State(state.clone())).await.unwrap()).await;
+        assert_eq!(copy["status"], "draft");
+        let copied = copy["sections"][0]["questions"].as_array().unwrap();
+        assert_eq!(copied.len(), 2);
+        assert_ne!(copied[0]["id"], "q1");
+        assert_eq!(copied[0]["title"], "Vision");
+
+        let forms = body_json(list_forms(State(state.clone())).await.unwrap()).await;
+        assert_eq!(forms.as_array().unwrap().len(), 2);
+
+        let missing = crate::cloning::clone_form(
+            HeaderMap::new(),
+            Path("missing".to_string()),
+            admin(),
+            State(state),
+            None,
+        )
+        .await;
+        assert!(matches!(missing, Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn import_reports_every_problem_before_saving() {
+        let (state, _) = fake_state();
+        let question = |id: &str, question_type: &str, features: JsonValue| json!({ "id": id, "title": id, "question_type": question_type, "is_required": false, "position": 1, "features": features });
+        let definition = |questions: Vec<JsonValue>| {
+            json!({
+                "id": "review",
+                "title": "Annual Review",
+                "status": "draft",
+                "sections": [
+                    { "id": "s1", "title": "One", "position": 1, "questions": [question("q1", "likert", json!(null))] },
+                    { "id": "s2", "title": "Two", "position": 2, "questions": questions }
+                ]
+            })
+        };
+        let import = |body: JsonValue| {
+            import_form(
+                HeaderMap::new(),
+                admin(),
+                Query(DryRunQuery::default()),
+                State(state.clone()),
+                Definition(body),
+            )
+        };
+        let pointers = |result: Result<_, AppError>| match result {
+            Err(AppError::InvalidDefinition(errors)) => errors
+                .into_iter()
+                .map(|error| error.pointer)
+                .collect::<Vec<_>>(),
+            Err(other) => panic!("expected an invalid definition, got {:?}", other),
+            Ok(_) => panic!("an invalid definition was imported"),
+        };
+
+        // Schema errors: an unknown type and a mistyped option list
+        let errors = pointers(
+            import(definition(vec![
+                question("q2", "slider", json!(null)),
+                question("q3", "checkbox", json!({ "options": "a, b" })),
+            ]))
+            .await,
+        );
+        assert!(errors.contains(&"/sections/1/questions/0/question_type".to_string()));
+        assert!(errors.contains(&"/sections/1/questions/1/features/options".to_string()));
+
+        // Rules beyond the schema: a reused ID and a backwards scale
+        let errors = pointers(
+            import(definition(vec![
+                question("q1", "text", json!(null)),
+                question("q3", "likert", json!({ "scale": { "min": 5, "max": 1 } })),
+            ]))
+            .await,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                "/sections/1/questions/0/id".to_string(),
+                "/sections/1/questions/1/features/scale".to_string()
+            ]
+        );
+
+        assert!(state.repos.forms.list_forms().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn dry_runs_report_without_saving() {
+        let (state, store) = fake_state();
+        let definition = json!({
+            "id": "review",
+            "title": "Annual Review",
+            "status": "published",
+            "sections": [{
+                "id": "s1", "title": "Leadership", "position": 1,
+                "questions": [
+                    { "id": "q1", "title": "Vision", "question_type": "likert", "is_required": true, "position": 1 },
+                    { "id": "q2", "title": "Notes", "question_type": "textarea", "is_required": false, "position": 2 }
+                ]
+            }]
+        });
+        let import = |dry_run: bool| {
+            import_form(
+                HeaderMap::new(),
+                admin(),
+                Query(DryRunQuery { dry_run }),
+                State(state.clone()),
+                Definition(definition.clone()),
+            )
+        };
+
+        let preview = body_json(import(true).await.unwrap()).await;
+        assert_eq!(preview["id_conflict"], false);
+        assert_eq!(preview["id_mapping"]["questions"], json!({}));
+        assert_eq!(
+            preview["sections"],
+            json!([{ "id": "s1", "title": "Leadership", "questions": 2 }])
+        );
+        assert!(state.repos.forms.list_forms().await.unwrap().is_empty());
+
+        import(false).await.unwrap();
+        let preview = body_json(import(true).await.unwrap()).await;
+        assert_eq!(preview["id_conflict"], true);
+        assert_eq!(preview["form_id"], JsonValue::Null);
+        assert_eq!(
+            preview["id_mapping"],
+            json!({
+                "form": { "from": "review", "to": null },
+                "sections": { "s1": null },
+                "questions": { "q1": null, "q2": null }
+            })
+        );
+        assert_eq!(
+            preview["sections"],
+            json!([{ "id": "s1", "title": "Leadership", "questions": 2 }])
+        );
+        assert_eq!(state.repos.forms.list_forms().await.unwrap().len(), 1);
+
+        store.add_response(StoredResponse {
+            form_id: "review".to_string(),
+            role: None,
+            email_hash: None,
+            answers: vec![
+                ("q1".to_string(), json!(4)),
+                ("q2".to_string(), json!("Fine")),
+            ],
+            ..Default::default()
+        });
+        let update: UpdateFormRequest = serde_json::from_value(json!({
+            "title": "Annual Review",
+            "status": "published",
+            "settings": {},
+            "sections": [{
+                "id": "s1", "title": "Leadership", "position": 1,
+                "questions": [
+                    { "id": "q1", "title": "Clear vision", "question_type": "likert", "is_required": true, "allow_comment": false, "position": 1 },
+                    { "id": "q3", "title": "Team", "question_type": "likert", "is_required": true, "position": 2 }
+                ]
+            }]
+        }))
+        .unwrap();
+        let preview = body_json(
+            update_form(
+                Path("review".to_string()),
+                admin(),
+                Query(DryRunQuery { dry_run: true }),
+                State(state.clone()),
+                Json(update),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        let diff = &preview["diff"];
+        assert_eq!(preview["has_changes"], true);
+        assert_eq!(diff["questions"]["added"][0]["id"], "q3");
+        assert_eq!(diff["questions"]["changed"][0]["fields"], json!(["title"]));
+        assert_eq!(diff["questions"]["changed"][0]["answers"], 1);
+        assert_eq!(diff["questions"]["removed"][0]["id"], "q2");
+        assert_eq!(diff["orphaned_answers"], 1);
+    }
+
+    /// A published form with one likert question
+    fn review_form() -> FormDefinition {
+        FormDefinition {
+            form: Form {
+                id: "review".to_string(),
+                title: "Annual Review".to_string(),
+                description: None,
+                instructions: None,
+                status: "published".to_string(),
+                welcome_message: None,
+                closing_message: None,
+                settings: None,
+            },
+            sections: Vec::new(),
+            questions: vec![Question {
+                id: "q1".to_string(),
+                form_id: "review".to_string(),
+                section_id: None,
+                position: 1,
+                title: "Vision".to_string(),
+                description: None,
+                kind: QuestionKind::from_parts("likert", &json!({})).unwrap(),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn submission_check_and_stats_against_the_fake_repositories() {
+        let (state, store) = fake_state();
+        state.repos.forms.create_form(&review_form()).await.unwrap();
+
+        let submitted = SubmitFormRequest {
+            respondent_name: "Ada".to_string(),
+            respondent_email: "ada@example.com".to_string(),
+            role: Some("Staff".to_string()),
+            answers: Vec::new(),
+            invitation_token: None,
+        };
+        for (email_hash, role, rating) in [
+            (submitted.email_hash(), "Staff", json!(5)),
+            (
+                "other".to_string(),
+                "Board",
+                json!({ "rating": 2, "comment": "Slow" }),
+            ),
+        ] {
+            store.add_response(StoredResponse {
+                form_id: "review".to_string(),
+                role: Some(role.to_string()),
+                email_hash: Some(email_hash),
+                answers: vec![("q1".to_string(), rating)],
+                ..Default::default()
+            });
+        }
+
+        let check = |email: &str| {
+            check_existing_submission(
+                Path("review".to_string()),
+                State(state.clone()),
+                Json(json!({ "email": email })),
+            )
+        };
+        assert_eq!(
+            body_json(check(" ADA@example.com").await.unwrap()).await["has_submitted"],
+            true
+        );
+        assert_eq!(
+            body_json(check("bob@example.com").await.unwrap()).await["has_submitted"],
+            false
+        );
+
+        let stats = body_json(
+            get_form_stats_anonymous(Path("review".to_string()), State(state.clone()))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(stats["total_responses"], 2);
+        assert_eq!(stats["question_stats"][0]["average_rating"], 3.5);
+        assert_eq!(
+            stats["question_stats"][0]["rating_distribution"],
+            json!([{ "rating": 2, "count": 1 }, { "rating": 5, "count": 1 }])
+        );
+
+        let totals = get_admin_stats(
+            HeaderMap::new(),
+            Query(AdminStatsQuery {
+                token: Some("admin".to_string()),
+                form_id: None,
+            }),
+            State(state),
+        )
+        .await
+        .unwrap();
+        assert_eq!(totals.0["total_forms"], 1);
+        assert_eq!(totals.0["total_respondents"], 2);
+    }
+
+    #[tokio::test]
+    async fn writes_queue_their_emails_and_webhooks_with_the_change() {
+        let (mut state, store) = fake_state();
+        state.mailer = Some(Arc::new(crate::email::TestSender::default()));
+        state.notification_email = "admin@example.com".to_string();
+        state.repos.forms.create_form(&review_form()).await.unwrap();
+
+        let webhook = |id: &str, form_id: Option<&str>| Webhook {
+            id: id.to_string(),
+            form_id: form_id.map(str::to_string),
+            url: format!("https://example.com/{}", id),
+            secret: "secret".to_string(),
+            events: sqlx::types::Json(Vec::new()),
+            include_pii: true,
+            active: true,
+            created_at: String::new(),
+        };
+        store.add_webhook(webhook("global", None));
+        store.add_webhook(webhook("form", Some("review")));
+        store.add_invitation(Invitation {
+            id: "i1".to_string(),
+            form_id: "review".to_string(),
+            name: "Ada Lovelace".to_string(),
+            email: "ada@example.com".to_string(),
+            role: Some("Board".to_string()),
+            token: "token-ada".to_string(),
+            response_id: None,
+            used_at: None,
+            created_at: String::new(),
+        });
+
+        // Every queued message as `(webhook ID or "email", event or subject)`
+        let drained = |from: usize| -> Vec<(String, String)> {
+            store.queued()[from..]
+                .iter()
+                .map(|message| match message {
+                    crate::outbox::OutboxMessage::Email(email) => {
+                        ("email".to_string(), email.subject.clone())
+                    }
+                    crate::outbox::OutboxMessage::Webhook(delivery) => {
+                        (delivery.webhook_id.clone(), delivery.event.clone())
+                    }
+                })
+                .collect()
+        };
+        let pair = |a: &str, b: &str| (a.to_string(), b.to_string());
+
+        let submitted = body_json(
+            submit_form_with_privacy(
+                Path("review".to_string()),
+                State(state.clone()),
+                Json(
+                    serde_json::from_value(json!({
+                        "invitation_token": "token-ada",
+                        "answers": [{ "question_id": "q1", "value": 4 }]
+                    }))
+                    .unwrap(),
+                ),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        let response_id = submitted["id"].as_str().unwrap().to_string();
+        assert_eq!(
+            drained(0),
+            vec![
+                pair("email", "New response: Annual Review"),
+                pair("global", webhooks::RESPONSE_SUBMITTED),
+                pair("form", webhooks::RESPONSE_SUBMITTED),
+            ]
+        );
+        let invitation = &store.invitations()[0];
+        assert!(invitation.used_at.is_some());
+        assert_eq!(invitation.response_id.as_ref(), Some(&response_id));
+
+        let listed = body_json(
+            get_responses_with_pii(
+                Path("review".to_string()),
+                Query(ResponseFilterQuery::default()),
+                Query(PageParams::default()),
+                State(state.clone()),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(listed["total"], 1);
+        let listed = &listed["items"][0];
+        assert_eq!(listed["respondent_name"], "Ada Lovelace");
+        assert_eq!(listed["answers"][0]["value"], 4);
+        let respondents = body_json(
+            get_form_respondents(
+                Path("review".to_string()),
+                Query(ResponseFilterQuery::default()),
+                Query(PageParams::default()),
+                State(state.clone()),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        let respondent_id = respondents["items"][0]["id"].as_str().unwrap().to_string();
+
+        // Deleting the respondent scrubs them from what was already queued
+        delete_respondent_pii(Path(respondent_id.clone()), State(state.clone()))
+            .await
+            .unwrap();
+        assert_eq!(
+            drained(3),
+            vec![
+                pair("global", webhooks::RESPONDENT_PII_DELETED),
+                pair("form", webhooks::RESPONDENT_PII_DELETED),
+            ]
+        );
+        assert!(store.queued().iter().all(|message| match message {
+            crate::outbox::OutboxMessage::Webhook(delivery) =>
+                delivery.data.get("respondent").is_none(),
+            crate::outbox::OutboxMessage::Email(_) => true,
+        }));
+        let anonymous = body_json(
+            get_admin_responses(
+                HeaderMap::new(),
+                Query(AdminStatsQuery {
+                    token: Some("admin".to_string()),
+                    form_id: Some("review".to_string()),
+                }),
+                Query(ResponseFilterQuery::default()),
+                Query(PageParams::default()),
+                State(state.clone()),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(anonymous["items"][0]["id"], response_id.as_str());
+
+        update_form_status(
+            Path("review".to_string()),
+            Query(AuthQuery {
+                token: Some("admin".to_string()),
+            }),
+            State(state.clone()),
+            Json(StatusUpdateRequest {
+                status: "finished".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(drained(5).len(), 2);
+        let status_changed = store.queued().pop().unwrap();
+        let crate::outbox::OutboxMessage::Webhook(status_changed) = status_changed else {
+            panic!("expected a webhook");
+        };
+        assert_eq!(status_changed.event, webhooks::FORM_STATUS_CHANGED);
+        assert_eq!(status_changed.data["previous_status"], "published");
+
+        // Only global endpoints outlive the form to hear about its deletion
+        delete_form(
+            Path("review".to_string()),
+            Query(AuthQuery {
+                token: Some("admin".to_string()),
+            }),
+            State(state.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(drained(7), vec![pair("global", webhooks::FORM_DELETED)]);
+        assert!(!state.repos.forms.form_exists("review").await.unwrap());
+        assert!(store.invitations().is_empty());
+    }
+}
@@ -0,0 +1,424 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    handlers::{check_admin_auth, sanitize_text_input, validate_email, AuthQuery},
+    models::*,
+    pagination::PageParams,
+    repository::{InvitationFilters, InvitationRepository},
+    AppState,
+};
+
+/// Generate an unguessable invitation token (two v4 UUIDs, ~244 random bits)
+pub(crate) fn generate_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Resolve the invitation a submission must present.
+///
+/// Forms without an invitation list stay open to anyone with the link and return `None`.
+/// Once a list has been uploaded, a valid unused token for this form is required.
+pub async fn resolve_for_submission(
+    invitations: &dyn InvitationRepository,
+    form_id: &str,
+    token: Option<&str>,
+) -> Result<Option<Invitation>, AppError> {
+    let Some(token) = token.map(str::trim).filter(|t| !t.is_empty()) else {
+        if invitations.count_invitations(form_id).await? > 0 {
+            return Err(AppError::Unauthorized(
+                "An invitation is required to respond to this form".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+
+    let invitation = invitations
+        .find_invitation(form_id, token)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Invalid invitation token".to_string()))?;
+
+    if invitation.used_at.is_some() {
+        return Err(AppError::BadRequest(
+            "This invitation has already been used to submit a response".to_string(),
+        ));
+    }
+
+    Ok(Some(invitation))
+}
+
+/// Look up an invitation by token so the form page can greet the invitee (public)
+pub async fn get_invitation(
+    Path((form_id, token)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let invitation = state
+        .repos
+        .invitations
+        .find_invitation(&form_id, &token)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invitation not found".to_string()))?;
+
+    Ok(Json(json!({
+        "form_id": invitation.form_id,
+        "name": invitation.name,
+        "email": invitation.email,
+        "role": invitation.role,
+        "has_submitted": invitation.used_at.is_some()
+    })))
+}
+
+/// Upload a recipient list for a form (admin only)
+pub async fn create_invitations(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateInvitationsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    if req.invitees.is_empty() {
+        return Err(AppError::BadRequest("No invitees provided".to_string()));
+    }
+
+    // Validate the whole list before writing anything
+    for (index, invitee) in req.invitees.iter().enumerate() {
+        if invitee.name.trim().is_empty() {
+            return Err(AppError::BadRequest(format!(
+                "Invitee {} is missing a name",
+                index + 1
+            )));
+        }
+        validate_email(invitee.email.trim()).map_err(|_| {
+            AppError::BadRequest(format!(
+                "Invitee {} has an invalid email address: '{}'",
+                index + 1,
+                invitee.email
+            ))
+        })?;
+    }
+
+    if !state.repos.forms.form_exists(&form_id).await? {
+        return Err(AppError::BadRequest("Form not found".to_string()));
+    }
+
+    let invitations: Vec<Invitation> = req
+        .invitees
+        .into_iter()
+        .map(|invitee| Invitation {
+            id: Uuid::new_v4().to_string(),
+            form_id: form_id.clone(),
+            name: sanitize_text_input(&invitee.name, 255),
+            email: invitee.email.trim().to_lowercase(),
+            role: invitee
+                .role
+                .as_deref()
+                .map(|r| sanitize_text_input(r, 100))
+                .filter(|r| !r.is_empty()),
+            token: generate_token(),
+            response_id: None,
+            used_at: None,
+            created_at: String::new(),
+        })
+        .collect();
+
+    // One invitation per email per form; re-uploading a list only adds new people
+    let inserted = state
+        .repos
+        .invitations
+        .create_invitations(&invitations)
+        .await?;
+
+    let mut created = Vec::new();
+    let mut skipped = Vec::new();
+    for (invitation, inserted) in invitations.into_iter().zip(inserted) {
+        if inserted {
+            created.push(json!({
+                "id": invitation.id,
+                "name": invitation.name,
+                "email": invitation.email,
+                "role": invitation.role,
+                "token": invitation.token
+            }));
+        } else {
+            skipped.push(invitation.email);
+        }
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "message": format!("{} invitations created", created.len()),
+            "created": created,
+            "skipped": skipped
+        })),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvitationListQuery {
+    pub token: Option<String>,
+    pub role: Option<String>,
+    /// `responded` or `pending`
+    pub status: Option<String>,
+}
+
+/// List the invitation list for a form with submission status (admin only)
+pub async fn list_invitations(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(params): Query<InvitationListQuery>,
+    Query(page): Query<PageParams>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+
+    let responded = match params.status.as_deref() {
+        None | Some("") => None,
+        Some("responded") => Some(true),
+        Some("pending") => Some(false),
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "Invalid status. Must be responded or pending".to_string(),
+            ))
+        }
+    };
+
+    let filters = InvitationFilters {
+        role: params.role,
+        responded,
+    };
+    let invitations = state
+        .repos
+        .invitations
+        .list_invitations(&form_id, &filters, &page)
+        .await?;
+
+    Ok(Json(invitations))
+}
+
+/// Revoke a single invitation (admin only)
+pub async fn delete_invitation(
+    headers: HeaderMap,
+    Path(invitation_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    if !state
+        .repos
+        .invitations
+        .delete_invitation(&invitation_id)
+        .await?
+    {
+        return Err(AppError::NotFound("Invitation not found".to_string()));
+    }
+
+    Ok(Json(json!({
+        "message": "Invitation deleted successfully"
+    })))
+}
+
+/// Response rate per role, measured against the invitation list (admin only)
+pub async fn get_response_rate(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let by_role = state.repos.invitations.response_rates(&form_id).await?;
+
+    let rate = |responded: i64, invited: i64| {
+        if invited > 0 {
+            responded as f64 / invited as f64
+        } else {
+            0.0
+        }
+    };
+
+    let invited: i64 = by_role.iter().map(|r| r.invited).sum();
+    let responded: i64 = by_role.iter().map(|r| r.responded).sum();
+
+    let roles: Vec<_> = by_role
+        .iter()
+        .map(|r| {
+            json!({
+                "role": r.role,
+                "invited": r.invited,
+                "responded": r.responded,
+                "response_rate": rate(r.responded, r.invited)
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "form_id": form_id,
+        "invited": invited,
+        "responded": responded,
+        "response_rate": rate(responded, invited),
+        "by_role": roles
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::to_bytes, extract::Path};
+    use serde_json::Value as JsonValue;
+    use sqlx::SqlitePool;
+
+    use super::*;
+    use crate::{handlers::submit_form_with_privacy, repository::NewSubmission};
+
+    async fn invited_state() -> (AppState, SqlitePool) {
+        let db = crate::db::test_pool().await;
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO forms (id, title, status) VALUES ('review', 'Annual Review', 'published');
+            INSERT INTO questions (id, form_id, position, type, title) VALUES ('q1', 'review', 1, 'likert', 'Vision');
+            INSERT INTO invitations (id, form_id, name, email, role, token) VALUES
+                ('i1', 'review', 'Ada Lovelace', 'ada@example.com', 'Board', 'token-ada'),
+                ('i2', 'review', 'Grace Hopper', 'grace@example.com', 'Staff', 'token-grace');
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        (AppState::for_tests(db.clone()), db)
+    }
+
+    async fn submit(state: &AppState, body: JsonValue) -> Result<JsonValue, AppError> {
+        let response = submit_form_with_privacy(
+            Path("review".to_string()),
+            State(state.clone()),
+            Json(serde_json::from_value(body).unwrap()),
+        )
+        .await?
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        Ok(serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn a_submission_consumes_its_invitation_once() {
+        let (state, db) = invited_state().await;
+        let answers = json!([{ "question_id": "q1", "value": 4 }]);
+
+        let without_token = submit(
+            &state,
+            json!({ "respondent_name": "Eve", "respondent_email": "eve@example.com", "answers": answers }),
+        )
+        .await;
+        assert!(matches!(without_token, Err(AppError::Unauthorized(_))));
+        let unknown = submit(
+            &state,
+            json!({ "invitation_token": "made-up", "answers": answers }),
+        )
+        .await;
+        assert!(matches!(unknown, Err(AppError::Unauthorized(_))));
+
+        let submitted = submit(
+            &state,
+            json!({ "invitation_token": "token-ada", "role": "Staff", "answers": answers }),
+        )
+        .await
+        .unwrap();
+        let response_id = submitted["id"].as_str().unwrap();
+
+        // The invitation is spent on this response, under the invitee's identity and role
+        let (used_at, used_for): (Option<String>, Option<String>) =
+            sqlx::query_as("SELECT used_at, response_id FROM invitations WHERE id = 'i1'")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert!(used_at.is_some());
+        assert_eq!(used_for.as_deref(), Some(response_id));
+        let (name, role): (String, Option<String>) = sqlx::query_as(
+            r#"
+            SELECT res.name, r.role
+            FROM responses r JOIN respondents res ON res.id = r.respondent_id
+            WHERE r.id = ?
+            "#,
+        )
+        .bind(response_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(
+            (name.as_str(), role.as_deref()),
+            ("Ada Lovelace", Some("Board"))
+        );
+
+        let reused = submit(
+            &state,
+            json!({ "invitation_token": "token-ada", "answers": answers }),
+        )
+        .await;
+        assert!(matches!(reused, Err(AppError::BadRequest(_))));
+        assert_eq!(
+            state
+                .repos
+                .responses
+                .count_responses("review")
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn only_the_first_of_two_racing_submissions_marks_the_invitation() {
+        let (state, db) = invited_state().await;
+
+        // Both submissions resolve the token before either commits
+        let invitations = state.repos.invitations.as_ref();
+        let first = resolve_for_submission(invitations, "review", Some("token-grace"))
+            .await
+            .unwrap()
+            .unwrap();
+        let second = resolve_for_submission(invitations, "review", Some("token-grace"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.id, second.id);
+
+        let submission = |invitation: Invitation| NewSubmission {
+            form_id: "review".to_string(),
+            respondent_name: invitation.name,
+            respondent_email: invitation.email,
+            email_hash: "hash-grace".to_string(),
+            role: invitation.role,
+            answers: vec![("q1".to_string(), json!(4))],
+            invitation_id: Some(invitation.id),
+        };
+        let responses = &state.repos.responses;
+        let stored = responses
+            .submit_response(&submission(first), &|_| Vec::new())
+            .await
+            .unwrap();
+        let lost = responses
+            .submit_response(&submission(second), &|_| Vec::new())
+            .await;
+        assert!(matches!(lost, Err(AppError::BadRequest(_))));
+
+        // The losing submission rolled back with its response
+        assert_eq!(responses.count_responses("review").await.unwrap(), 1);
+        let (response_id,): (Option<String>,) =
+            sqlx::query_as("SELECT response_id FROM invitations WHERE id = 'i2'")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        assert_eq!(response_id, Some(stored.response_id));
+    }
+}
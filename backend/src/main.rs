@@ -1,16 +1,38 @@
+mod analysis;
+mod authoring;
+mod bulk_questions;
+mod cloning;
 mod db;
+mod digests;
 mod email;
 mod error;
+mod export;
+mod filters;
+mod form_diff;
 mod handlers;
+mod invitations;
 mod models;
+mod notifications;
+mod outbox;
+mod pagination;
+mod question_bank;
+mod questions;
+mod reminders;
+mod repository;
+mod schema;
+mod search;
+mod templates;
+mod text_stats;
+mod trends;
+mod webhooks;
 
 use axum::{
     routing::{delete, get, patch, post, put},
     Router,
 };
 use http::header::{self, HeaderValue};
-use sqlx::SqlitePool;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tower_http::compression::CompressionLayer;
 use tower_http::cors::{Any, CorsLayer};
@@ -23,10 +45,29 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Clone)]
 pub struct AppState {
-    pub db: SqlitePool,
+    /// Every table, on whichever backend `DATABASE_URL` selected
+    pub repos: repository::Repositories,
     pub admin_token: String,
-    pub resend_api_key: String,
+    pub mailer: Option<Arc<dyn email::EmailSender>>,
     pub notification_email: String,
+    pub public_base_url: String,
+    /// Signalled after committing outbox entries so the worker delivers them right away
+    pub outbox_wakeup: Arc<tokio::sync::Notify>,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// State on a test database, with email turned off
+    pub fn for_tests(db: sqlx::SqlitePool) -> Self {
+        Self {
+            repos: repository::Repositories::new(&db::Database::Sqlite(db)),
+            admin_token: "admin".to_string(),
+            mailer: None,
+            notification_email: String::new(),
+            public_base_url: String::new(),
+            outbox_wakeup: Arc::new(tokio::sync::Notify::new()),
+        }
+    }
 }
 
 #[tokio::main]
@@ -75,8 +116,10 @@ async fn main() -> anyhow::Result<()> {
 
     let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let admin_token = std::env::var("ADMIN_TOKEN").expect("ADMIN_TOKEN must be set");
-    let resend_api_key = std::env::var("RESEND_API_KEY").unwrap_or_else(|_| "".to_string());
+    let mailer = email::sender_from_env()?;
     let notification_email = std::env::var("NOTIFICATION_EMAIL").unwrap_or_else(|_| "".to_string());
+    // Public URL of the frontend, used to build links in outgoing emails
+    let public_base_url = std::env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "".to_string());
     let port: u16 = std::env::var("PORT")
         .expect("PORT must be set")
         .parse()
@@ -87,15 +130,22 @@ async fn main() -> anyhow::Result<()> {
         std::env::var("CORS_ALLOWED_ORIGINS").expect("CORS_ALLOWED_ORIGINS must be set");
 
     tracing::info!("Connecting to database: {}", database_url);
-    let db = db::init_db(&database_url).await?;
+    let database = db::init_db(&database_url).await?;
+    let repos = repository::Repositories::new(&database);
 
     let app_state = AppState {
-        db,
+        repos,
         admin_token,
-        resend_api_key,
+        mailer,
         notification_email,
+        public_base_url,
+        outbox_wakeup: Arc::new(tokio::sync::Notify::new()),
     };
 
+    outbox::spawn_worker(app_state.clone());
+    reminders::spawn_scheduler(app_state.clone());
+    digests::spawn_scheduler(app_state.clone());
+
     // Build the full application with v2 routes
     let app = build_full_app(app_state, cors_origins);
 
@@ -132,7 +182,16 @@ fn build_full_app(app_state: AppState, cors_origins: String) -> Router {
             "/api/forms/{form_id}/stats",
             get(handlers::get_form_stats_anonymous),
         )
+        .route(
+            "/api/forms/{form_id}/questions/{question_id}/text-stats",
+            get(text_stats::get_text_question_stats),
+        )
+        .route(
+            "/api/forms/{form_id}/invitations/{token}",
+            get(invitations::get_invitation),
+        )
         .route("/api/template", get(handlers::get_form_template))
+        .route("/api/schema/form.json", get(schema::get_form_schema))
         // Admin routes (protected by auth)
         .route("/api/admin/stats", get(handlers::get_admin_stats))
         .route("/api/admin/responses", get(handlers::get_admin_responses))
@@ -141,20 +200,142 @@ fn build_full_app(app_state: AppState, cors_origins: String) -> Router {
         .route("/api/admin/forms/{form_id}", delete(handlers::delete_form))
         .route(
             "/api/admin/forms/{form_id}/clone",
-            post(handlers::clone_form),
+            post(cloning::clone_form),
         )
         .route(
             "/api/admin/forms/{form_id}/status",
             patch(handlers::update_form_status),
         )
+        .route(
+            "/api/admin/forms/{form_id}/template",
+            post(templates::save_template),
+        )
+        .route("/api/admin/templates", get(templates::list_templates))
+        .route(
+            "/api/admin/templates/{template_id}",
+            get(templates::get_template).delete(templates::delete_template),
+        )
+        .route(
+            "/api/admin/templates/{template_id}/instantiate",
+            post(templates::instantiate_template),
+        )
+        .route("/api/admin/trends", get(trends::get_trends))
+        .route(
+            "/api/admin/analysis/compare",
+            post(analysis::compare_samples),
+        )
+        .route(
+            "/api/admin/forms/{form_id}/analysis/roles",
+            get(analysis::analyze_roles),
+        )
+        .route(
+            "/api/admin/forms/{form_id}/definition",
+            get(authoring::export_definition),
+        )
+        .route(
+            "/api/admin/forms/{form_id}/sections/{section_id}/questions/bulk",
+            post(bulk_questions::bulk_add_questions),
+        )
+        .route(
+            "/api/admin/forms/{form_id}/sections/{section_id}/questions/from-bank",
+            post(question_bank::add_bank_questions),
+        )
+        .route(
+            "/api/admin/question-bank",
+            get(question_bank::list_bank_questions).post(question_bank::create_bank_question),
+        )
+        .route(
+            "/api/admin/question-bank/{question_id}",
+            get(question_bank::get_bank_question)
+                .put(question_bank::update_bank_question)
+                .delete(question_bank::delete_bank_question),
+        )
+        .route(
+            "/api/admin/question-bank/{question_id}/link",
+            post(question_bank::link_bank_question),
+        )
+        .route(
+            "/api/admin/question-bank/{question_id}/usage",
+            get(question_bank::bank_question_usage),
+        )
         .route(
             "/api/admin/forms/{form_id}/responses",
             get(handlers::get_responses_with_pii),
         )
+        .route(
+            "/api/admin/forms/{form_id}/export",
+            get(export::export_responses),
+        )
+        .route(
+            "/api/admin/forms/{form_id}/search",
+            get(search::search_answers),
+        )
         .route(
             "/api/admin/forms/{form_id}/respondents",
             get(handlers::get_form_respondents),
         )
+        .route(
+            "/api/admin/forms/{form_id}/invitations",
+            get(invitations::list_invitations).post(invitations::create_invitations),
+        )
+        .route(
+            "/api/admin/forms/{form_id}/response-rate",
+            get(invitations::get_response_rate),
+        )
+        .route(
+            "/api/admin/forms/{form_id}/notification-template",
+            get(notifications::get_notification_template)
+                .put(notifications::update_notification_template),
+        )
+        .route(
+            "/api/admin/forms/{form_id}/digests",
+            get(digests::list_subscriptions).post(digests::create_subscription),
+        )
+        .route(
+            "/api/admin/digests/{subscription_id}",
+            delete(digests::delete_subscription),
+        )
+        .route(
+            "/api/admin/digests/{subscription_id}/preview",
+            get(digests::preview_digest),
+        )
+        .route(
+            "/api/admin/forms/{form_id}/reminder-campaigns",
+            get(reminders::list_campaigns).post(reminders::create_campaign),
+        )
+        .route(
+            "/api/admin/reminder-campaigns/{campaign_id}",
+            patch(reminders::update_campaign).delete(reminders::delete_campaign),
+        )
+        .route(
+            "/api/admin/reminder-campaigns/{campaign_id}/run",
+            post(reminders::run_campaign_now),
+        )
+        .route(
+            "/api/admin/invitations/{invitation_id}",
+            delete(invitations::delete_invitation),
+        )
+        .route(
+            "/api/admin/webhooks",
+            get(webhooks::list_webhooks).post(webhooks::create_webhook),
+        )
+        .route(
+            "/api/admin/webhooks/{webhook_id}",
+            delete(webhooks::delete_webhook),
+        )
+        .route(
+            "/api/admin/webhooks/{webhook_id}/test",
+            post(webhooks::test_webhook),
+        )
+        .route("/api/admin/outbox", get(outbox::list_outbox))
+        .route(
+            "/api/admin/outbox/{entry_id}/retry",
+            post(outbox::retry_outbox_entry),
+        )
+        .route(
+            "/api/admin/outbox/{entry_id}/discard",
+            post(outbox::discard_outbox_entry),
+        )
         .route(
             "/api/admin/respondents/{respondent_id}",
             delete(handlers::delete_respondent_pii),
@@ -3,49 +3,254 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use sqlx::FromRow;
 
-// ===== Respondents (PII Storage) =====
+// ===== Invitations =====
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct Respondent {
+pub struct Invitation {
     pub id: String,
+    pub form_id: String,
     pub name: String,
     pub email: String,
-    pub email_hash: String,
-    pub created_at: DateTime<Utc>,
+    pub role: Option<String>,
+    pub token: String,
+    pub response_id: Option<String>,
+    pub used_at: Option<String>,
+    pub created_at: String,
 }
 
-// ===== Responses (Anonymous) =====
+#[derive(Debug, Deserialize)]
+pub struct InviteeInput {
+    pub name: String,
+    pub email: String,
+    pub role: Option<String>,
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct Response {
+#[derive(Debug, Deserialize)]
+pub struct CreateInvitationsRequest {
+    pub invitees: Vec<InviteeInput>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct RoleResponseRate {
+    pub role: Option<String>,
+    pub invited: i64,
+    pub responded: i64,
+}
+
+// ===== Reminder Campaigns =====
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct ReminderCampaign {
     pub id: String,
-    pub respondent_id: String,
     pub form_id: String,
-    pub role: Option<String>,
-    pub submitted_at: DateTime<Utc>,
-    pub ip_address: Option<String>,
-    pub metadata: JsonValue,
+    pub subject: String,
+    pub body_template: String,
+    pub closes_at: String,
+    pub days_before: sqlx::types::Json<Vec<i64>>,
+    pub active: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReminderCampaignRequest {
+    pub subject: String,
+    pub body_template: String,
+    /// RFC 3339 timestamp at which the form closes
+    pub closes_at: String,
+    /// Send a reminder this many days before `closes_at`, e.g. `[7, 2]`
+    pub days_before: Vec<i64>,
 }
 
-// ===== Answers =====
+/// Fields to change on a campaign; omitted ones are kept
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateReminderCampaignRequest {
+    /// `false` pauses the campaign; the scheduler skips inactive campaigns
+    pub active: Option<bool>,
+    pub subject: Option<String>,
+    pub body_template: Option<String>,
+    pub closes_at: Option<String>,
+    pub days_before: Option<Vec<i64>>,
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
-pub struct Answer {
+// ===== Notification Templates =====
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct NotificationTemplate {
+    pub form_id: String,
+    pub enabled: bool,
+    pub subject: String,
+    pub html_template: String,
+    pub include_pii: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotificationTemplateRequest {
+    pub enabled: bool,
+    pub subject: String,
+    pub html_template: String,
+    #[serde(default)]
+    pub include_pii: bool,
+}
+
+// ===== Digests =====
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DigestSubscription {
     pub id: String,
-    pub response_id: String,
-    pub question_id: String,
-    pub value: JsonValue,
-    pub created_at: DateTime<Utc>,
+    pub form_id: String,
+    pub email: String,
+    pub frequency: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct DigestRun {
+    pub id: String,
+    pub subscription_id: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub new_responses: i64,
+    pub total_responses: i64,
+    pub average_score: Option<f64>,
+    pub sent_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDigestSubscriptionRequest {
+    pub email: String,
+    /// `daily` or `weekly`
+    pub frequency: String,
+}
+
+// ===== Webhooks =====
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Webhook {
+    pub id: String,
+    pub form_id: Option<String>,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Subscribed event names; empty means every event
+    pub events: sqlx::types::Json<Vec<String>>,
+    pub include_pii: bool,
+    pub active: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// Restrict to one form; omit for a global endpoint
+    pub form_id: Option<String>,
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Shared secret for signatures; generated when omitted
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub include_pii: bool,
+}
+
+// ===== Outbox =====
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct OutboxEntry {
+    pub id: String,
+    pub kind: String,
+    pub payload: JsonValue,
+    pub status: String,
+    pub attempts: i64,
+    pub next_attempt_at: String,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub delivered_at: Option<String>,
+}
+
+// ===== Form Templates =====
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct FormTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    /// Form the template was saved from; it may have been deleted since
+    pub source_form_id: Option<String>,
+    /// An import document, as `import_form` accepts
+    pub definition: sqlx::types::Json<JsonValue>,
+    /// Shipped with the server rather than saved by an admin
+    #[sqlx(default)]
+    pub built_in: bool,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveTemplateRequest {
+    /// Defaults to the form's title
+    pub name: Option<String>,
+    /// Defaults to the form's description
+    pub description: Option<String>,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct InstantiateTemplateRequest {
+    /// ID of the new form; a new UUID unless given
+    pub form_id: Option<String>,
+    /// Defaults to the template's form title
+    pub title: Option<String>,
+}
+
+// ===== Question Bank =====
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct BankQuestion {
+    pub id: String,
+    pub question_type: String,
+    pub title: String,
+    /// The `features` every form copy starts with, without `bankId`
+    pub features: sqlx::types::Json<JsonValue>,
+    pub tags: sqlx::types::Json<Vec<String>>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveBankQuestionRequest {
+    /// Only read when creating; a new UUID unless given
+    pub id: Option<String>,
+    pub title: String,
+    pub question_type: String,
+    pub features: Option<JsonValue>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddBankQuestionsRequest {
+    /// Bank question IDs, added in this order
+    pub question_ids: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LinkBankQuestionRequest {
+    /// Only look in these forms; every form unless given
+    pub form_ids: Option<Vec<String>>,
 }
 
 // ===== Request/Response DTOs =====
 
 #[derive(Debug, Deserialize)]
 pub struct SubmitFormRequest {
+    #[serde(default)]
     pub respondent_name: String,
+    #[serde(default)]
     pub respondent_email: String,
     pub role: Option<String>,
     pub answers: Vec<AnswerInput>,
+    /// Single-use token from an invitation; required when the form has an invitation list
+    #[serde(default)]
+    pub invitation_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -94,6 +299,8 @@ pub struct RoleCount {
 pub struct QuestionStat {
     pub question_id: String,
     pub question_title: String,
+    /// Question-bank ID, the same for this question on every form that uses it
+    pub bank_question_id: Option<String>,
     pub response_count: i64,
     pub average_rating: Option<f64>,
     pub rating_distribution: Vec<RatingCount>,
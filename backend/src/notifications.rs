@@ -0,0 +1,312 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use crate::{
+    email::{self, EmailMessage},
+    error::AppError,
+    handlers::{check_admin_auth, AuthQuery},
+    models::*,
+    AppState,
+};
+
+const DEFAULT_SUBJECT: &str = "New response: {{form_title}}";
+
+const DEFAULT_HTML_TEMPLATE: &str = r#"<h2>New Form Response Received</h2>
+<p>A new response was submitted to <strong>{{form_title}}</strong>.</p>
+<ul>
+    <li><strong>Role:</strong> {{role}}</li>
+    <li><strong>Total responses:</strong> {{response_count}}</li>
+    <li><strong>Submitted:</strong> {{submitted_at}}</li>
+</ul>
+<p>Log in to your admin dashboard to view the full response.</p>"#;
+
+/// Placeholders available in notification templates.
+/// `respondent_name` and `respondent_email` are only filled in when the template opts into PII.
+pub const PLACEHOLDERS: &[&str] = &[
+    "form_title",
+    "response_count",
+    "role",
+    "submitted_at",
+    "respondent_name",
+    "respondent_email",
+];
+
+/// Details of a committed submission needed to render a notification
+#[derive(Debug, Clone)]
+pub struct NewResponse {
+    pub role: Option<String>,
+    pub respondent_name: String,
+    pub respondent_email: String,
+    pub submitted_at: DateTime<Utc>,
+}
+
+fn default_template(form_id: &str) -> NotificationTemplate {
+    NotificationTemplate {
+        form_id: form_id.to_string(),
+        enabled: true,
+        subject: DEFAULT_SUBJECT.to_string(),
+        html_template: DEFAULT_HTML_TEMPLATE.to_string(),
+        include_pii: false,
+    }
+}
+
+/// The admin's new-response email for one form, loaded before a submission so that
+/// the repository can queue it in the submission's own transaction
+#[derive(Debug)]
+pub struct Notifier {
+    to: String,
+    template: NotificationTemplate,
+    form_title: String,
+}
+
+impl Notifier {
+    /// `None` when nothing should be sent: no mailer or recipient is configured, the
+    /// form's template is disabled, or the form does not exist.
+    pub async fn for_form(state: &AppState, form_id: &str) -> Result<Option<Self>, AppError> {
+        if state.mailer.is_none() || state.notification_email.is_empty() {
+            return Ok(None);
+        }
+
+        let template = state
+            .repos
+            .notifications
+            .find_template(form_id)
+            .await?
+            .unwrap_or_else(|| default_template(form_id));
+        if !template.enabled {
+            return Ok(None);
+        }
+
+        let Some(form) = state.repos.forms.find_form(form_id).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            to: state.notification_email.clone(),
+            template,
+            form_title: form.form.title,
+        }))
+    }
+
+    /// Render the email for a submission that brought the form to `response_count`
+    pub fn render(&self, event: &NewResponse, response_count: i64) -> EmailMessage {
+        let response_count = response_count.to_string();
+        let submitted_at = event
+            .submitted_at
+            .format("%Y-%m-%d %H:%M:%S UTC")
+            .to_string();
+        let (name, email) = if self.template.include_pii {
+            (
+                event.respondent_name.as_str(),
+                event.respondent_email.as_str(),
+            )
+        } else {
+            ("", "")
+        };
+
+        let vars = [
+            ("form_title", self.form_title.as_str()),
+            ("response_count", response_count.as_str()),
+            ("role", event.role.as_deref().unwrap_or("Not specified")),
+            ("submitted_at", submitted_at.as_str()),
+            ("respondent_name", name),
+            ("respondent_email", email),
+        ];
+
+        EmailMessage::from_html(
+            &self.to,
+            &email::render_template(&self.template.subject, &vars),
+            &email::render_html_template(&self.template.html_template, &vars),
+        )
+    }
+}
+
+/// Get the notification template for a form, falling back to the default (admin only)
+pub async fn get_notification_template(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let template = state
+        .repos
+        .notifications
+        .find_template(&form_id)
+        .await?
+        .unwrap_or_else(|| default_template(&form_id));
+
+    Ok(Json(json!({
+        "template": template,
+        "placeholders": PLACEHOLDERS
+    })))
+}
+
+/// Create or replace the notification template for a form (admin only)
+pub async fn update_notification_template(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<UpdateNotificationTemplateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    if req.subject.trim().is_empty() || req.html_template.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "Subject and HTML template are required".to_string(),
+        ));
+    }
+
+    if !state.repos.forms.form_exists(&form_id).await? {
+        return Err(AppError::BadRequest("Form not found".to_string()));
+    }
+
+    state
+        .repos
+        .notifications
+        .save_template(&NotificationTemplate {
+            form_id: form_id.clone(),
+            enabled: req.enabled,
+            subject: req.subject,
+            html_template: req.html_template,
+            include_pii: req.include_pii,
+        })
+        .await?;
+
+    Ok(Json(json!({
+        "message": "Notification template updated successfully",
+        "form_id": form_id
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::TimeZone;
+    use serde_json::Value as JsonValue;
+    use sqlx::SqlitePool;
+
+    use super::*;
+    use crate::email::TestSender;
+
+    async fn seeded_db() -> SqlitePool {
+        let db = crate::db::test_pool().await;
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO forms (id, title) VALUES ('review', 'Annual Review');
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        db
+    }
+
+    fn notifying_state(db: SqlitePool) -> AppState {
+        let mut state = AppState::for_tests(db);
+        state.mailer = Some(Arc::new(TestSender::default()));
+        state.notification_email = "admin@example.com".to_string();
+        state
+    }
+
+    fn event() -> NewResponse {
+        NewResponse {
+            role: Some("Board".to_string()),
+            respondent_name: "Ada <Lovelace>".to_string(),
+            respondent_email: "ada@example.com".to_string(),
+            submitted_at: Utc.with_ymd_and_hms(2025, 9, 3, 12, 26, 40).unwrap(),
+        }
+    }
+
+    async fn save_template(state: &AppState, request: JsonValue) {
+        update_notification_template(
+            HeaderMap::new(),
+            Path("review".to_string()),
+            Query(AuthQuery {
+                token: Some("admin".to_string()),
+            }),
+            State(state.clone()),
+            Json(serde_json::from_value(request).unwrap()),
+        )
+        .await
+        .unwrap();
+    }
+
+    /// Render the notification for [`event`] as the first response, if one is sent
+    async fn queued(state: &AppState) -> Option<EmailMessage> {
+        Notifier::for_form(state, "review")
+            .await
+            .unwrap()
+            .map(|notifier| notifier.render(&event(), 1))
+    }
+
+    #[tokio::test]
+    async fn the_default_template_fills_in_the_response_details() {
+        let state = notifying_state(seeded_db().await);
+
+        let message = queued(&state).await.unwrap();
+        assert_eq!(message.to, "admin@example.com");
+        assert_eq!(message.subject, "New response: Annual Review");
+        assert!(message.html.contains("<strong>Role:</strong> Board"));
+        assert!(message.html.contains("<strong>Total responses:</strong> 1"));
+        assert!(message.html.contains("2025-09-03 12:26:40 UTC"));
+        assert!(!message.html.contains("{{"));
+    }
+
+    #[tokio::test]
+    async fn respondent_details_are_left_out_unless_the_template_opts_in() {
+        let state = notifying_state(seeded_db().await);
+        let template = json!({
+            "enabled": true,
+            "subject": "{{respondent_name}} answered {{form_title}}",
+            "html_template": "<p>{{respondent_name}} ({{respondent_email}}) said {{role}}</p>"
+        });
+
+        // `include_pii` is off when the request leaves it out
+        save_template(&state, template.clone()).await;
+        let message = queued(&state).await.unwrap();
+        assert_eq!(message.subject, " answered Annual Review");
+        assert_eq!(message.html, "<p> () said Board</p>");
+        assert!(!message.text.contains("ada@example.com"));
+
+        let mut with_pii = template;
+        with_pii["include_pii"] = json!(true);
+        save_template(&state, with_pii).await;
+        let message = queued(&state).await.unwrap();
+        // Subjects are plain text; the HTML body escapes values
+        assert_eq!(message.subject, "Ada <Lovelace> answered Annual Review");
+        assert_eq!(
+            message.html,
+            "<p>Ada &lt;Lovelace&gt; (ada@example.com) said Board</p>"
+        );
+    }
+
+    #[tokio::test]
+    async fn nothing_is_queued_when_disabled_or_without_a_recipient() {
+        let db = seeded_db().await;
+        let mut state = notifying_state(db.clone());
+        save_template(
+            &state,
+            json!({ "enabled": false, "subject": "New", "html_template": "<p>New</p>" }),
+        )
+        .await;
+        assert!(queued(&state).await.is_none());
+
+        sqlx::query("DELETE FROM notification_templates")
+            .execute(&db)
+            .await
+            .unwrap();
+        assert!(queued(&state).await.is_some());
+        state.notification_email = String::new();
+        assert!(queued(&state).await.is_none());
+    }
+}
@@ -0,0 +1,486 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+
+use crate::{
+    email::EmailMessage,
+    error::AppError,
+    handlers::{check_admin_auth, AuthQuery},
+    models::*,
+    pagination::PageParams,
+    webhooks::WebhookEvent,
+    AppState,
+};
+
+const POLL_INTERVAL_SECS: u64 = 30;
+const BATCH_SIZE: i64 = 20;
+/// Attempts before an entry is moved to the dead-letter state
+pub const MAX_ATTEMPTS: i64 = 8;
+const BASE_BACKOFF_SECS: i64 = 30;
+const MAX_BACKOFF_SECS: i64 = 6 * 60 * 60;
+const DELIVERED_RETENTION_DAYS: i64 = 30;
+
+const STATUSES: &[&str] = &["pending", "delivered", "dead", "discarded"];
+
+/// A webhook delivery, with the payload already filtered for the endpoint's PII setting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub webhook_id: String,
+    pub event: String,
+    pub data: JsonValue,
+}
+
+/// Something to send once the transaction that queued it has committed
+#[derive(Debug, Clone)]
+pub enum OutboxMessage {
+    Email(EmailMessage),
+    Webhook(WebhookDelivery),
+}
+
+/// What a repository write queues in its own transaction
+#[derive(Debug, Clone)]
+pub enum OutboxEvent {
+    Email(EmailMessage),
+    /// Queued once per active webhook that matches it; see [`crate::webhooks::deliveries`]
+    Webhook(WebhookEvent),
+}
+
+impl OutboxMessage {
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            OutboxMessage::Email(_) => "email",
+            OutboxMessage::Webhook(_) => "webhook",
+        }
+    }
+
+    pub(crate) fn payload(&self) -> JsonValue {
+        match self {
+            OutboxMessage::Email(message) => json!(message),
+            OutboxMessage::Webhook(delivery) => json!(delivery),
+        }
+    }
+
+    pub(crate) fn from_entry(entry: &OutboxEntry) -> Result<Self, String> {
+        let payload = entry.payload.clone();
+        match entry.kind.as_str() {
+            "email" => serde_json::from_value(payload).map(OutboxMessage::Email),
+            "webhook" => serde_json::from_value(payload).map(OutboxMessage::Webhook),
+            other => return Err(format!("Unknown outbox kind '{}'", other)),
+        }
+        .map_err(|e| format!("Malformed payload: {}", e))
+    }
+}
+
+/// Why a delivery attempt failed
+#[derive(Debug)]
+pub enum DeliveryError {
+    /// Worth trying again later (network errors, 5xx, 408/429, missing transport)
+    Retry(String),
+    /// Will never succeed, e.g. the webhook was deleted or the endpoint answered 4xx
+    Permanent(String),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OutboxListQuery {
+    pub token: Option<String>,
+    pub status: Option<String>,
+    pub kind: Option<String>,
+}
+
+/// Nudge the worker after committing new entries
+pub fn wake(state: &AppState) {
+    state.outbox_wakeup.notify_one();
+}
+
+/// Delay before the next attempt after `attempts` failures: 30s doubling up to 6 hours
+pub fn backoff_secs(attempts: i64) -> i64 {
+    let exponent = attempts.saturating_sub(1).clamp(0, 30) as u32;
+    BASE_BACKOFF_SECS
+        .saturating_mul(2_i64.saturating_pow(exponent))
+        .min(MAX_BACKOFF_SECS)
+}
+
+async fn deliver(
+    state: &AppState,
+    entry: &OutboxEntry,
+    message: &OutboxMessage,
+) -> Result<(), DeliveryError> {
+    match message {
+        OutboxMessage::Email(message) => {
+            let mailer = state.mailer.as_ref().ok_or_else(|| {
+                DeliveryError::Retry("Email transport is not configured".to_string())
+            })?;
+            mailer
+                .send(message)
+                .await
+                .map_err(|e| DeliveryError::Retry(e.to_string()))
+        }
+        OutboxMessage::Webhook(delivery) => {
+            crate::webhooks::deliver_queued(state, &entry.id, delivery).await
+        }
+    }
+}
+
+async fn record_failure(
+    state: &AppState,
+    entry: &OutboxEntry,
+    error: DeliveryError,
+) -> Result<(), AppError> {
+    let attempts = entry.attempts + 1;
+    let (status, error) = match error {
+        DeliveryError::Permanent(error) => ("dead", error),
+        DeliveryError::Retry(error) if attempts >= MAX_ATTEMPTS => ("dead", error),
+        DeliveryError::Retry(error) => ("pending", error),
+    };
+
+    tracing::warn!(
+        outbox_id = %entry.id,
+        kind = %entry.kind,
+        attempts,
+        status,
+        error = %error,
+        "Outbox delivery failed"
+    );
+
+    state
+        .repos
+        .outbox
+        .record_failure(&entry.id, status, attempts, &error, backoff_secs(attempts))
+        .await
+}
+
+/// Attempt every entry that is due, then prune old delivered entries
+pub async fn process_due(state: &AppState) -> Result<usize, AppError> {
+    let mut processed = 0;
+
+    loop {
+        let entries = state.repos.outbox.due_entries(BATCH_SIZE).await?;
+
+        for entry in &entries {
+            let result = match OutboxMessage::from_entry(entry) {
+                Ok(message) => deliver(state, entry, &message).await,
+                Err(e) => Err(DeliveryError::Permanent(e)),
+            };
+
+            match result {
+                Ok(()) => state.repos.outbox.record_delivered(&entry.id).await?,
+                Err(e) => record_failure(state, entry, e).await?,
+            }
+        }
+
+        processed += entries.len();
+        if (entries.len() as i64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    state
+        .repos
+        .outbox
+        .prune_delivered(DELIVERED_RETENTION_DAYS)
+        .await?;
+
+    Ok(processed)
+}
+
+/// Deliver outbox entries in the background, woken by [`wake`] or every 30 seconds
+pub fn spawn_worker(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            match process_due(&state).await {
+                Ok(0) => {}
+                Ok(processed) => tracing::debug!(processed, "Processed outbox entries"),
+                Err(e) => tracing::error!(error = %e, "Outbox worker failed"),
+            }
+
+            tokio::select! {
+                _ = state.outbox_wakeup.notified() => {}
+                _ = tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)) => {}
+            }
+        }
+    });
+}
+
+async fn find_entry(state: &AppState, entry_id: &str) -> Result<OutboxEntry, AppError> {
+    let entry = state.repos.outbox.find_entry(entry_id).await?;
+
+    entry.ok_or_else(|| AppError::NotFound("Outbox entry not found".to_string()))
+}
+
+/// List queued, failed and recently delivered messages (admin only)
+pub async fn list_outbox(
+    headers: HeaderMap,
+    Query(params): Query<OutboxListQuery>,
+    Query(page): Query<PageParams>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+
+    if let Some(status) = &params.status {
+        if !STATUSES.contains(&status.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Invalid status. Must be one of: {}",
+                STATUSES.join(", ")
+            )));
+        }
+    }
+
+    let entries = state
+        .repos
+        .outbox
+        .list_entries(params.status.as_deref(), params.kind.as_deref(), &page)
+        .await?;
+
+    Ok(Json(entries))
+}
+
+/// Put a dead or discarded entry back in the queue with a fresh attempt budget (admin only)
+pub async fn retry_outbox_entry(
+    headers: HeaderMap,
+    Path(entry_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let entry = find_entry(&state, &entry_id).await?;
+    if entry.status == "delivered" {
+        return Err(AppError::BadRequest(
+            "Delivered entries cannot be retried".to_string(),
+        ));
+    }
+
+    state.repos.outbox.requeue(&entry_id).await?;
+
+    wake(&state);
+
+    Ok(Json(json!({
+        "message": "Outbox entry queued for retry",
+        "id": entry_id
+    })))
+}
+
+/// Stop retrying an entry but keep it for reference (admin only)
+pub async fn discard_outbox_entry(
+    headers: HeaderMap,
+    Path(entry_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let entry = find_entry(&state, &entry_id).await?;
+    if entry.status == "delivered" {
+        return Err(AppError::BadRequest(
+            "Delivered entries cannot be discarded".to_string(),
+        ));
+    }
+
+    state.repos.outbox.discard(&entry_id).await?;
+
+    Ok(Json(json!({
+        "message": "Outbox entry discarded",
+        "id": entry_id
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use sqlx::SqlitePool;
+
+    use super::*;
+    use crate::{email::TestSender, repository::sqlite::enqueue};
+
+    async fn state_with(sender: &Arc<TestSender>) -> (AppState, SqlitePool) {
+        let db = crate::db::test_pool().await;
+        let mut state = AppState::for_tests(db.clone());
+        state.mailer = Some(sender.clone());
+        (state, db)
+    }
+
+    async fn queue_email(db: &SqlitePool, to: &str) -> OutboxEntry {
+        let message =
+            OutboxMessage::Email(EmailMessage::from_html(to, "New response", "<p>Hi</p>"));
+        let mut conn = db.acquire().await.unwrap();
+        enqueue(&mut conn, &message).await.unwrap();
+        sqlx::query_as("SELECT * FROM outbox ORDER BY created_at DESC LIMIT 1")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap()
+    }
+
+    async fn entry(state: &AppState, id: &str) -> OutboxEntry {
+        find_entry(state, id).await.unwrap()
+    }
+
+    #[test]
+    fn backoff_doubles_and_is_capped() {
+        assert_eq!(backoff_secs(1), 30);
+        assert_eq!(backoff_secs(2), 60);
+        assert_eq!(backoff_secs(3), 120);
+        assert_eq!(backoff_secs(MAX_ATTEMPTS), 30 * 128);
+        assert_eq!(backoff_secs(20), MAX_BACKOFF_SECS);
+        assert_eq!(backoff_secs(i64::MAX), MAX_BACKOFF_SECS);
+    }
+
+    #[tokio::test]
+    async fn a_successful_delivery_is_marked_delivered() {
+        let sender = Arc::new(TestSender::default());
+        let (state, db) = state_with(&sender).await;
+        let queued = queue_email(&db, "admin@example.com").await;
+        assert_eq!((queued.status.as_str(), queued.attempts), ("pending", 0));
+
+        assert_eq!(process_due(&state).await.unwrap(), 1);
+
+        let delivered = entry(&state, &queued.id).await;
+        assert_eq!(delivered.status, "delivered");
+        assert_eq!(delivered.attempts, 1);
+        assert!(delivered.delivered_at.is_some());
+        let sent: Vec<String> = sender
+            .sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|m| m.to.clone())
+            .collect();
+        assert_eq!(sent, vec!["admin@example.com"]);
+
+        // Nothing left to do
+        assert_eq!(process_due(&state).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn a_failed_delivery_is_retried_later() {
+        let sender = Arc::new(TestSender {
+            fail: true,
+            ..Default::default()
+        });
+        let (state, db) = state_with(&sender).await;
+        let queued = queue_email(&db, "admin@example.com").await;
+
+        assert_eq!(process_due(&state).await.unwrap(), 1);
+
+        let failed = entry(&state, &queued.id).await;
+        assert_eq!(failed.status, "pending");
+        assert_eq!(failed.attempts, 1);
+        assert!(failed
+            .last_error
+            .as_deref()
+            .unwrap()
+            .contains("mailbox unavailable"));
+
+        // Backed off, so the next pass leaves it alone
+        let (waiting,): (bool,) = sqlx::query_as(
+            "SELECT next_attempt_at > datetime('now', '+20 seconds') FROM outbox WHERE id = ?",
+        )
+        .bind(&queued.id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert!(waiting);
+        assert_eq!(process_due(&state).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn the_last_failed_attempt_moves_the_entry_to_dead() {
+        let sender = Arc::new(TestSender {
+            fail: true,
+            ..Default::default()
+        });
+        let (state, db) = state_with(&sender).await;
+        let queued = queue_email(&db, "admin@example.com").await;
+        sqlx::query("UPDATE outbox SET attempts = ? WHERE id = ?")
+            .bind(MAX_ATTEMPTS - 1)
+            .bind(&queued.id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(process_due(&state).await.unwrap(), 1);
+
+        let dead = entry(&state, &queued.id).await;
+        assert_eq!(dead.status, "dead");
+        assert_eq!(dead.attempts, MAX_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn scrubbing_a_respondent_removes_their_details_from_every_entry() {
+        let db = crate::db::test_pool().await;
+        let state = AppState::for_tests(db.clone());
+        sqlx::raw_sql(
+            "INSERT INTO respondents (id, name, email) VALUES ('p1', 'Ada', 'ada@example.com')",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let mut conn = db.acquire().await.unwrap();
+        let webhook = |respondent_id: &str| {
+            OutboxMessage::Webhook(WebhookDelivery {
+                webhook_id: "w1".to_string(),
+                event: "response.submitted".to_string(),
+                data: json!({
+                    "response_id": "r1",
+                    "respondent": { "respondent_id": respondent_id, "name": "Ada" }
+                }),
+            })
+        };
+        enqueue(&mut conn, &webhook("p1")).await.unwrap();
+        enqueue(&mut conn, &webhook("p2")).await.unwrap();
+        let notification = EmailMessage::from_html(
+            "admin@example.com",
+            "New response",
+            "<p>From ada@example.com</p>",
+        );
+        enqueue(&mut conn, &OutboxMessage::Email(notification))
+            .await
+            .unwrap();
+        let unrelated = EmailMessage::from_html("admin@example.com", "Digest", "<p>3 new</p>");
+        enqueue(&mut conn, &OutboxMessage::Email(unrelated))
+            .await
+            .unwrap();
+        sqlx::query("UPDATE outbox SET status = 'delivered', delivered_at = datetime('now')")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        state
+            .repos
+            .responses
+            .delete_respondent("p1", &|_| Vec::new())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let payloads: Vec<(String, JsonValue)> =
+            sqlx::query_as("SELECT kind, payload FROM outbox ORDER BY kind, id")
+                .fetch_all(&db)
+                .await
+                .unwrap();
+        let emails: Vec<&JsonValue> = payloads
+            .iter()
+            .filter(|(kind, _)| kind == "email")
+            .map(|(_, payload)| payload)
+            .collect();
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0]["subject"], "Digest");
+
+        let mut respondents: Vec<Option<&str>> = payloads
+            .iter()
+            .filter(|(kind, _)| kind == "webhook")
+            .map(|(_, payload)| payload["data"]["respondent"]["respondent_id"].as_str())
+            .collect();
+        respondents.sort();
+        assert_eq!(respondents, vec![None, Some("p2")]);
+    }
+}
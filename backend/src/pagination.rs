@@ -0,0 +1,398 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{ColumnIndex, Database, Decode, Encode, FromRow, QueryBuilder, Row, Type};
+
+use crate::error::AppError;
+
+pub const DEFAULT_LIMIT: i64 = 50;
+pub const MAX_LIMIT: i64 = 500;
+
+/// `cursor`, `limit` and `sort` accepted by every admin listing.
+/// Extract it next to the endpoint's own query struct; unknown keys are ignored by both.
+#[derive(Debug, Default, Deserialize)]
+pub struct PageParams {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    /// Field name, prefixed with `-` for descending order
+    pub sort: Option<String>,
+}
+
+/// The envelope returned by every admin listing
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub total: i64,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total: self.total,
+        }
+    }
+}
+
+/// A public sort name and the non-null SQL expression it orders by
+pub struct SortField {
+    pub name: &'static str,
+    pub column: &'static str,
+}
+
+/// How one endpoint pages through its rows
+pub struct Listing {
+    /// Unique column used to break ties between equal sort values
+    pub id_column: &'static str,
+    pub sorts: &'static [SortField],
+    /// Sort used when none is given, e.g. `-created_at`
+    pub default_sort: &'static str,
+}
+
+/// Position after the last item of a page, tied to the sort it was produced with
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor {
+    sort: String,
+    value: String,
+    id: String,
+}
+
+impl Cursor {
+    /// Opaque hex encoding so clients treat cursors as tokens
+    fn encode(&self) -> String {
+        serde_json::to_vec(self)
+            .unwrap_or_default()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        if !cursor.len().is_multiple_of(2) || !cursor.is_ascii() {
+            return None;
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// A row plus the sort key and id it was paged by
+pub struct Keyed<T> {
+    sort_key: String,
+    page_id: String,
+    row: T,
+}
+
+impl<'r, R, T> FromRow<'r, R> for Keyed<T>
+where
+    R: Row,
+    T: FromRow<'r, R>,
+    String: Decode<'r, R::Database> + Type<R::Database>,
+    &'static str: ColumnIndex<R>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            sort_key: row.try_get("sort_key")?,
+            page_id: row.try_get("page_id")?,
+            row: T::from_row(row)?,
+        })
+    }
+}
+
+/// Validated paging parameters for one listing
+pub struct PageRequest {
+    listing: &'static Listing,
+    sort: &'static SortField,
+    descending: bool,
+    limit: i64,
+    after: Option<Cursor>,
+}
+
+impl Listing {
+    pub fn request(&'static self, params: &PageParams) -> Result<PageRequest, AppError> {
+        let sort = params.sort.as_deref().unwrap_or(self.default_sort);
+        let (name, descending) = match sort.strip_prefix('-') {
+            Some(name) => (name, true),
+            None => (sort, false),
+        };
+
+        let field = self.sorts.iter().find(|s| s.name == name).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Invalid sort '{}'. Must be one of: {}",
+                name,
+                self.sorts
+                    .iter()
+                    .map(|s| s.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })?;
+
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return Err(AppError::BadRequest(format!(
+                "Limit must be between 1 and {}",
+                MAX_LIMIT
+            )));
+        }
+
+        let after = match params.cursor.as_deref().filter(|c| !c.is_empty()) {
+            Some(cursor) => {
+                let cursor = Cursor::decode(cursor)
+                    .ok_or_else(|| AppError::BadRequest("Invalid cursor".to_string()))?;
+                if cursor.sort != sort {
+                    return Err(AppError::BadRequest(
+                        "Cursor was issued for a different sort order".to_string(),
+                    ));
+                }
+                Some(cursor)
+            }
+            None => None,
+        };
+
+        Ok(PageRequest {
+            listing: self,
+            sort: field,
+            descending,
+            limit,
+            after,
+        })
+    }
+}
+
+impl PageRequest {
+    /// The sort field, without its direction
+    #[cfg(test)]
+    pub fn sort(&self) -> &'static str {
+        self.sort.name
+    }
+
+    fn sort_name(&self) -> String {
+        if self.descending {
+            format!("-{}", self.sort.name)
+        } else {
+            self.sort.name.to_string()
+        }
+    }
+
+    /// The count query and the page query for this request, for a backend to run: see
+    /// `repository::sqlite::fetch_page`.
+    ///
+    /// `select` is the column list for the row type, `from` the table expression, and
+    /// `filters` appends `AND ...` conditions (binding owned values) after a leading
+    /// `WHERE 1 = 1`. Sort columns must be text, as cursors carry the sort value as a string.
+    pub fn queries<'a, DB>(
+        &self,
+        select: &str,
+        from: &str,
+        filters: impl Fn(&mut QueryBuilder<'a, DB>),
+    ) -> (QueryBuilder<'a, DB>, QueryBuilder<'a, DB>)
+    where
+        DB: Database,
+        DB::Arguments<'a>: Default,
+        String: Encode<'a, DB> + Type<DB>,
+        i64: Encode<'a, DB> + Type<DB>,
+    {
+        let mut count = QueryBuilder::new(format!("SELECT COUNT(*) FROM {} WHERE 1 = 1", from));
+        filters(&mut count);
+
+        let (column, id_column) = (self.sort.column, self.listing.id_column);
+        let mut query = QueryBuilder::new(format!(
+            "SELECT {}, {} AS sort_key, {} AS page_id FROM {} WHERE 1 = 1",
+            select, column, id_column, from
+        ));
+        filters(&mut query);
+
+        let (comparison, direction) = if self.descending {
+            ("<", "DESC")
+        } else {
+            (">", "ASC")
+        };
+        if let Some(after) = &self.after {
+            query
+                .push(format!(" AND ({}, {}) {} (", column, id_column, comparison))
+                .push_bind(after.value.clone())
+                .push(", ")
+                .push_bind(after.id.clone())
+                .push(")");
+        }
+        // One extra row tells whether another page exists
+        query
+            .push(format!(
+                " ORDER BY {} {}, {} {} LIMIT ",
+                column, direction, id_column, direction
+            ))
+            .push_bind(self.limit + 1);
+
+        (count, query)
+    }
+
+    /// Page rows held in memory, as the fake repositories do. Each row comes with its
+    /// value for the requested [`sort`](Self::sort) and its ID.
+    #[cfg(test)]
+    pub fn slice<T>(&self, rows: impl IntoIterator<Item = (String, String, T)>) -> Page<T> {
+        let mut rows: Vec<Keyed<T>> = rows
+            .into_iter()
+            .map(|(sort_key, page_id, row)| Keyed {
+                sort_key,
+                page_id,
+                row,
+            })
+            .collect();
+        let total = rows.len() as i64;
+
+        let key = |keyed: &Keyed<T>| (keyed.sort_key.clone(), keyed.page_id.clone());
+        rows.sort_by_key(key);
+        if self.descending {
+            rows.reverse();
+        }
+        if let Some(after) = &self.after {
+            let after = (after.value.clone(), after.id.clone());
+            let past = if self.descending {
+                std::cmp::Ordering::Less
+            } else {
+                std::cmp::Ordering::Greater
+            };
+            rows.retain(|keyed| key(keyed).cmp(&after) == past);
+        }
+        rows.truncate(self.limit as usize + 1);
+
+        self.finish(rows, total)
+    }
+
+    /// The page from the rows fetched by the page query, trimming the extra row
+    pub fn finish<T>(&self, mut rows: Vec<Keyed<T>>, total: i64) -> Page<T> {
+        let next_cursor = if rows.len() as i64 > self.limit {
+            rows.truncate(self.limit as usize);
+            rows.last().map(|last| {
+                Cursor {
+                    sort: self.sort_name(),
+                    value: last.sort_key.clone(),
+                    id: last.page_id.clone(),
+                }
+                .encode()
+            })
+        } else {
+            None
+        };
+
+        Page {
+            items: rows.into_iter().map(|keyed| keyed.row).collect(),
+            next_cursor,
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static LISTING: Listing = Listing {
+        id_column: "id",
+        sorts: &[
+            SortField {
+                name: "created_at",
+                column: "created_at",
+            },
+            SortField {
+                name: "name",
+                column: "COALESCE(name, '')",
+            },
+        ],
+        default_sort: "-created_at",
+    };
+
+    fn params(cursor: Option<String>, limit: i64, sort: &str) -> PageParams {
+        PageParams {
+            cursor,
+            limit: Some(limit),
+            sort: Some(sort.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn pages_cover_every_row_once_in_order() {
+        let db = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE items (id TEXT PRIMARY KEY, name TEXT, created_at TEXT)")
+            .execute(&db)
+            .await
+            .unwrap();
+        for i in 0..23 {
+            // Repeated timestamps make the id tie-breaker matter
+            sqlx::query("INSERT INTO items VALUES (?, ?, ?)")
+                .bind(format!("id{:02}", i))
+                .bind((i % 3 != 0).then(|| format!("name{}", i % 5)))
+                .bind(format!("2025-09-0{} 10:00:00", i % 4 + 1))
+                .execute(&db)
+                .await
+                .unwrap();
+        }
+
+        for sort in ["-created_at", "name", "-name"] {
+            let mut seen: Vec<(String, String)> = Vec::new();
+            let mut cursor = None;
+            loop {
+                let request = LISTING.request(&params(cursor, 5, sort)).unwrap();
+                let page: Page<(String, Option<String>, String)> =
+                    crate::repository::sqlite::fetch_page(
+                        &db,
+                        &request,
+                        "id, name, created_at",
+                        "items",
+                        |_| {},
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(page.total, 23);
+                seen.extend(page.items.into_iter().map(|(id, name, created_at)| {
+                    let key = if sort.ends_with("name") {
+                        name.unwrap_or_default()
+                    } else {
+                        created_at
+                    };
+                    (key, id)
+                }));
+                match page.next_cursor {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+
+            let mut expected = seen.clone();
+            expected.sort();
+            if sort.starts_with('-') {
+                expected.reverse();
+            }
+            assert_eq!(seen, expected, "sort {}", sort);
+        }
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        assert!(LISTING.request(&params(None, 0, "name")).is_err());
+        assert!(LISTING.request(&params(None, 5, "email")).is_err());
+        assert!(LISTING
+            .request(&params(Some("zz".to_string()), 5, "name"))
+            .is_err());
+
+        let cursor = Cursor {
+            sort: "name".to_string(),
+            value: "a".to_string(),
+            id: "1".to_string(),
+        }
+        .encode();
+        assert!(LISTING
+            .request(&params(Some(cursor.clone()), 5, "name"))
+            .is_ok());
+        assert!(LISTING.request(&params(Some(cursor), 5, "-name")).is_err());
+    }
+}
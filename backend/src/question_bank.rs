@@ -0,0 +1,587 @@
+//! The question bank: tagged questions shared across forms.
+//!
+//! Adding a bank question to a form copies it and records the bank ID in the copy's
+//! `bankId` feature. Later edits to the bank entry leave existing forms alone, but the ID
+//! stays with every copy, so analytics can line a question up across forms whose own
+//! question IDs differ (see `bank_question_id` in the form statistics).
+
+use std::collections::{BTreeSet, HashMap};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use sqlx::types::Json as SqlJson;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    handlers::{
+        check_admin_auth, sanitize_optional_input, sanitize_text_input, AuthQuery, DryRunQuery,
+    },
+    models::*,
+    questions::QuestionKind,
+    repository::{FormQuestion, Question},
+    schema::FieldError,
+    AppState,
+};
+
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LENGTH: usize = 50;
+
+/// Trimmed, deduplicated and sorted tags
+fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, AppError> {
+    let tags: BTreeSet<String> = tags
+        .iter()
+        .map(|tag| sanitize_text_input(tag, MAX_TAG_LENGTH))
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    if tags.len() > MAX_TAGS {
+        return Err(AppError::BadRequest(format!(
+            "Too many tags ({}); use at most {}",
+            tags.len(),
+            MAX_TAGS
+        )));
+    }
+    Ok(tags.into_iter().collect())
+}
+
+/// Check a bank question's type and features; `bankId` is set per copy, so it is dropped
+fn checked_features(question_type: &str, features: &JsonValue) -> Result<JsonValue, AppError> {
+    let mut kind = QuestionKind::from_parts(question_type, features).map_err(|e| {
+        AppError::InvalidDefinition(vec![FieldError {
+            pointer: e.pointer(),
+            message: e.message,
+        }])
+    })?;
+    kind.common_mut().bank_id = None;
+    Ok(kind.features())
+}
+
+async fn find_bank_question(state: &AppState, question_id: &str) -> Result<BankQuestion, AppError> {
+    let question = state
+        .repos
+        .question_bank
+        .find_bank_question(question_id)
+        .await?;
+
+    question.ok_or_else(|| AppError::NotFound("Bank question not found".to_string()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BankListQuery {
+    pub token: Option<String>,
+    /// Only questions with this tag
+    pub tag: Option<String>,
+    /// Only questions whose title contains this text
+    pub q: Option<String>,
+}
+
+/// List bank questions, optionally filtered by tag or title, with every tag in use
+/// (admin only)
+pub async fn list_bank_questions(
+    headers: HeaderMap,
+    Query(params): Query<BankListQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+
+    let tag = sanitize_optional_input(params.tag.as_deref(), MAX_TAG_LENGTH);
+    let search = sanitize_optional_input(params.q.as_deref(), 255);
+    let bank = &state.repos.question_bank;
+    let questions = bank
+        .list_bank_questions(tag.as_deref(), search.as_deref())
+        .await?;
+    let tags = bank.tag_counts().await?;
+
+    Ok(Json(json!({ "questions": questions, "tags": tags })))
+}
+
+/// Get one bank question (admin only)
+pub async fn get_bank_question(
+    headers: HeaderMap,
+    Path(question_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    Ok(Json(find_bank_question(&state, &question_id).await?))
+}
+
+/// Add a question to the bank (admin only)
+pub async fn create_bank_question(
+    headers: HeaderMap,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<SaveBankQuestionRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let now = Utc::now().to_rfc3339();
+    let question = BankQuestion {
+        id: sanitize_optional_input(req.id.as_deref(), 255)
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
+        features: SqlJson(checked_features(
+            &req.question_type,
+            req.features.as_ref().unwrap_or(&json!({})),
+        )?),
+        title: bank_title(&req.title)?,
+        question_type: req.question_type,
+        tags: SqlJson(normalize_tags(req.tags)?),
+        created_at: now.clone(),
+        updated_at: now,
+    };
+
+    if !state
+        .repos
+        .question_bank
+        .create_bank_question(&question)
+        .await?
+    {
+        return Err(AppError::BadRequest(format!(
+            "A bank question with ID '{}' already exists",
+            question.id
+        )));
+    }
+
+    Ok((StatusCode::CREATED, Json(question)))
+}
+
+fn bank_title(title: &str) -> Result<String, AppError> {
+    let title = sanitize_text_input(title, 1000);
+    if title.is_empty() {
+        return Err(AppError::BadRequest("Title is required".to_string()));
+    }
+    Ok(title)
+}
+
+/// Replace a bank question's title, type, features and tags (admin only). Forms that
+/// already use it keep their copy.
+pub async fn update_bank_question(
+    headers: HeaderMap,
+    Path(question_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<SaveBankQuestionRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let existing = find_bank_question(&state, &question_id).await?;
+    let question = BankQuestion {
+        features: SqlJson(checked_features(
+            &req.question_type,
+            req.features.as_ref().unwrap_or(&json!({})),
+        )?),
+        title: bank_title(&req.title)?,
+        question_type: req.question_type,
+        tags: SqlJson(normalize_tags(req.tags)?),
+        updated_at: Utc::now().to_rfc3339(),
+        ..existing
+    };
+    if !state
+        .repos
+        .question_bank
+        .update_bank_question(&question)
+        .await?
+    {
+        return Err(AppError::NotFound("Bank question not found".to_string()));
+    }
+
+    Ok(Json(find_bank_question(&state, &question_id).await?))
+}
+
+/// Remove a question from the bank (admin only). Form copies keep their `bankId`, so
+/// their results still line up.
+pub async fn delete_bank_question(
+    headers: HeaderMap,
+    Path(question_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    if !state
+        .repos
+        .question_bank
+        .delete_bank_question(&question_id)
+        .await?
+    {
+        return Err(AppError::NotFound("Bank question not found".to_string()));
+    }
+
+    Ok(Json(json!({
+        "message": "Bank question deleted successfully",
+        "question_id": question_id
+    })))
+}
+
+/// Copy bank questions to the end of a section (admin only). Each copy gets a new
+/// question ID and the bank ID as its `bankId` feature. With `dry_run=true` nothing is
+/// saved.
+pub async fn add_bank_questions(
+    headers: HeaderMap,
+    Path((form_id, section_id)): Path<(String, String)>,
+    Query(auth): Query<AuthQuery>,
+    Query(options): Query<DryRunQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<AddBankQuestionsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let definition = state
+        .repos
+        .forms
+        .find_form(&form_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+    if !definition
+        .sections
+        .iter()
+        .any(|section| section.id == section_id)
+    {
+        return Err(AppError::BadRequest("Section not found".to_string()));
+    }
+    if req.question_ids.is_empty() {
+        return Err(AppError::BadRequest("No bank questions given".to_string()));
+    }
+
+    let last_position = definition
+        .questions
+        .iter()
+        .map(|question| question.position)
+        .max()
+        .unwrap_or(0);
+    let mut questions = Vec::with_capacity(req.question_ids.len());
+    for (bank_id, offset) in req.question_ids.iter().zip(1..) {
+        let bank_question = match find_bank_question(&state, bank_id).await {
+            Err(AppError::NotFound(_)) => {
+                return Err(AppError::BadRequest(format!(
+                    "Bank question '{}' not found",
+                    bank_id
+                )))
+            }
+            other => other?,
+        };
+        let mut kind =
+            QuestionKind::from_parts(&bank_question.question_type, &bank_question.features)
+                .map_err(|e| {
+                    AppError::InternalError(format!(
+                        "Bank question {} is stored with {}",
+                        bank_id, e
+                    ))
+                })?;
+        kind.common_mut().bank_id = Some(bank_question.id);
+
+        questions.push(Question {
+            id: Uuid::new_v4().to_string(),
+            form_id: form_id.clone(),
+            section_id: Some(section_id.clone()),
+            position: last_position + offset,
+            title: bank_question.title,
+            description: kind.common().help_text.clone(),
+            kind,
+        });
+    }
+
+    if !options.dry_run {
+        state
+            .repos
+            .forms
+            .add_questions(&form_id, &questions)
+            .await?;
+    }
+
+    let message = if options.dry_run {
+        "Preview only; nothing was saved"
+    } else {
+        "Questions added successfully"
+    };
+
+    Ok(Json(json!({
+        "message": message,
+        "dry_run": options.dry_run,
+        "form_id": form_id,
+        "section_id": section_id,
+        "count": questions.len(),
+        "questions": questions
+    })))
+}
+
+/// Link questions on existing forms to a bank question (admin only): every question of
+/// the same type with the identical title and no `bankId` yet gets this one. This is how
+/// forms from before the bank line up with newer ones. With `dry_run=true` nothing is
+/// saved.
+pub async fn link_bank_question(
+    headers: HeaderMap,
+    Path(question_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    Query(options): Query<DryRunQuery>,
+    State(state): State<AppState>,
+    req: Option<Json<LinkBankQuestionRequest>>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+
+    let bank_question = find_bank_question(&state, &question_id).await?;
+    let matches = state
+        .repos
+        .question_bank
+        .unlinked_questions(&bank_question.question_type, &bank_question.title)
+        .await?;
+    let matches: Vec<FormQuestion> = matches
+        .into_iter()
+        .filter(|found| {
+            req.form_ids
+                .as_ref()
+                .is_none_or(|form_ids| form_ids.contains(&found.form_id))
+        })
+        .collect();
+
+    if !options.dry_run && !matches.is_empty() {
+        state
+            .repos
+            .question_bank
+            .link_questions(&bank_question.id, &matches)
+            .await?;
+    }
+
+    let message = if options.dry_run {
+        "Preview only; nothing was saved"
+    } else {
+        "Questions linked successfully"
+    };
+
+    Ok(Json(json!({
+        "message": message,
+        "dry_run": options.dry_run,
+        "question_id": bank_question.id,
+        "count": matches.len(),
+        "linked": matches
+    })))
+}
+
+/// Every form that uses a bank question, oldest first, with the question's local ID and,
+/// for likert questions, its response count and average rating on that form (admin only)
+pub async fn bank_question_usage(
+    headers: HeaderMap,
+    Path(question_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let bank_question = find_bank_question(&state, &question_id).await?;
+    let uses = state
+        .repos
+        .question_bank
+        .bank_question_uses(&bank_question.id)
+        .await?;
+
+    let mut summaries = HashMap::new();
+    let mut forms = Vec::with_capacity(uses.len());
+    for found in uses {
+        if !summaries.contains_key(&found.form_id) {
+            let form_summaries = state
+                .repos
+                .responses
+                .likert_summaries(&found.form_id)
+                .await?;
+            summaries.insert(found.form_id.clone(), form_summaries);
+        }
+        let summary = summaries[&found.form_id]
+            .iter()
+            .find(|summary| summary.question_id == found.question_id);
+
+        forms.push(json!({
+            "form_id": found.form_id,
+            "form_title": found.form_title,
+            "review_period": found.review_period,
+            "question_id": found.question_id,
+            "question_title": found.question_title,
+            "response_count": summary.map(|summary| summary.response_count),
+            "average_rating": summary.and_then(|summary| summary.average_rating)
+        }));
+    }
+
+    Ok(Json(json!({
+        "question": bank_question,
+        "forms": forms
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admin() -> Query<AuthQuery> {
+        Query(AuthQuery {
+            token: Some("admin".to_string()),
+        })
+    }
+
+    async fn body(response: impl IntoResponse) -> JsonValue {
+        let bytes = axum::body::to_bytes(response.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn tags_are_trimmed_deduplicated_and_sorted() {
+        assert_eq!(
+            normalize_tags(vec![
+                " leadership ".to_string(),
+                "board".to_string(),
+                String::new(),
+                "leadership".to_string(),
+            ])
+            .unwrap(),
+            vec!["board", "leadership"]
+        );
+        let many = (0..=MAX_TAGS).map(|i| format!("tag-{}", i)).collect();
+        assert!(matches!(normalize_tags(many), Err(AppError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn bank_questions_line_up_across_forms() {
+        let db = crate::db::test_pool().await;
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO forms (id, title, settings, created_at) VALUES
+                ('ed-2024', 'ED Review 2024', '{"reviewPeriod": "2024"}', '2024-06-01'),
+                ('ed-2025', 'ED Review 2025', '{"reviewPeriod": "2025"}', '2025-06-01');
+            INSERT INTO sections (id, form_id, title, position) VALUES
+                ('s-2024', 'ed-2024', 'Leadership', 1),
+                ('s-2025', 'ed-2025', 'Leadership', 1);
+            INSERT INTO questions (id, form_id, section_id, position, type, title, features) VALUES
+                ('q1', 'ed-2024', 's-2024', 1, 'likert', 'Sets a clear vision', '{"required": true}'),
+                ('q2', 'ed-2024', 's-2024', 2, 'textarea', 'Sets a clear vision', '{}');
+            INSERT INTO respondents (id, name, email) VALUES
+                ('p1', 'Ada', 'ada@example.com'),
+                ('p2', 'Ben', 'ben@example.com');
+            INSERT INTO responses (id, respondent_id, form_id, role, metadata) VALUES
+                ('r1', 'p1', 'ed-2024', 'Staff', '{}'),
+                ('r2', 'p2', 'ed-2024', 'Board', '{}');
+            INSERT INTO answers (id, response_id, question_id, value) VALUES
+                ('a1', 'r1', 'q1', '4'),
+                ('a2', 'r2', 'q1', '2');
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        let state = AppState::for_tests(db);
+
+        let created = body(
+            create_bank_question(
+                HeaderMap::new(),
+                admin(),
+                State(state.clone()),
+                Json(SaveBankQuestionRequest {
+                    id: Some("vision".to_string()),
+                    title: "Sets a clear vision".to_string(),
+                    question_type: "likert".to_string(),
+                    features: Some(json!({ "required": true, "bankId": "ignored" })),
+                    tags: vec!["Leadership".to_string(), "ED review".to_string()],
+                }),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(created["features"], json!({ "required": true }));
+        assert_eq!(created["tags"], json!(["ED review", "Leadership"]));
+
+        let listed = body(
+            list_bank_questions(
+                HeaderMap::new(),
+                Query(BankListQuery {
+                    token: Some("admin".to_string()),
+                    tag: Some("Leadership".to_string()),
+                    q: None,
+                }),
+                State(state.clone()),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(listed["questions"][0]["id"], "vision");
+        assert_eq!(
+            listed["tags"][1],
+            json!({ "tag": "Leadership", "count": 1 })
+        );
+
+        // The older form predates the bank, so its question is linked by title; the
+        // textarea with the same title is a different question
+        let linked = body(
+            link_bank_question(
+                HeaderMap::new(),
+                Path("vision".to_string()),
+                admin(),
+                Query(DryRunQuery::default()),
+                State(state.clone()),
+                None,
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(linked["count"], 1);
+        assert_eq!(linked["linked"][0]["question_id"], "q1");
+
+        // The newer form takes the question from the bank, under a new local ID
+        let added = body(
+            add_bank_questions(
+                HeaderMap::new(),
+                Path(("ed-2025".to_string(), "s-2025".to_string())),
+                admin(),
+                Query(DryRunQuery::default()),
+                State(state.clone()),
+                Json(AddBankQuestionsRequest {
+                    question_ids: vec!["vision".to_string()],
+                }),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        let new_id = added["questions"][0]["id"].as_str().unwrap().to_string();
+        assert_ne!(new_id, "q1");
+        assert_eq!(added["questions"][0]["features"]["bankId"], "vision");
+
+        for (form_id, question_id) in [("ed-2024", "q1"), ("ed-2025", new_id.as_str())] {
+            let stats = state.repos.responses.form_stats(form_id).await.unwrap();
+            assert_eq!(stats.question_stats[0].question_id, question_id);
+            assert_eq!(
+                stats.question_stats[0].bank_question_id.as_deref(),
+                Some("vision")
+            );
+        }
+
+        let usage = body(
+            bank_question_usage(
+                HeaderMap::new(),
+                Path("vision".to_string()),
+                admin(),
+                State(state.clone()),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        let forms = usage["forms"].as_array().unwrap();
+        assert_eq!(forms.len(), 2);
+        assert_eq!(forms[0]["review_period"], "2024");
+        assert_eq!(forms[0]["response_count"], 2);
+        assert_eq!(forms[0]["average_rating"], 3.0);
+        assert_eq!(forms[1]["question_id"], new_id.as_str());
+        assert_eq!(forms[1]["response_count"], 0);
+    }
+}
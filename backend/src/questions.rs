@@ -0,0 +1,494 @@
+//! Question types and their settings.
+//!
+//! A question is stored as a `type` string plus a `features` JSON object. [`QuestionKind`]
+//! is the typed view of that pair: one variant per type, each carrying the options every
+//! question has ([`CommonFeatures`]) and its own settings. It serializes back to the same
+//! `{"type": ..., "features": {...}}` shape, so the API and the database are unchanged.
+//! Feature keys the server does not know about are kept as they are.
+
+use std::fmt;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Number, Value as JsonValue};
+
+/// Options shared by every question type
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommonFeatures {
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_comment: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help_text: Option<String>,
+    /// The question-bank entry this question was taken from, shared by every form that uses it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bank_id: Option<String>,
+}
+
+/// The `features` object of one question type
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+#[schemars(rename = "{T}Features")]
+pub struct Features<T> {
+    #[serde(flatten)]
+    pub common: CommonFeatures,
+    #[serde(flatten)]
+    pub config: T,
+    /// Keys that are neither common nor part of `T`, stored unchanged
+    #[serde(flatten)]
+    pub extra: Map<String, JsonValue>,
+}
+
+/// Types with nothing beyond the common options
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct NoConfig {}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TextInput {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub char_limit: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Choice {
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LikertScale {
+    pub min: i64,
+    pub max: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_label: Option<String>,
+}
+
+/// Likert questions without a `scale` use 1 to 5
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Likert {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<LikertScale>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RatingStyle {
+    Stars,
+    Numbers,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Rating {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating_style: Option<RatingStyle>,
+}
+
+/// `Number` keeps integers and decimals exactly as they were written
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct NumberRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+}
+
+/// Bounds for date, time and datetime questions, as ISO 8601 strings:
+/// `YYYY-MM-DD`, `HH:MM` and `YYYY-MM-DDTHH:MM` respectively (seconds are optional)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DateRange {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+}
+
+/// Why a `type`/`features` pair was rejected, with the JSON path of the offending value
+#[derive(Debug, Clone, PartialEq)]
+pub struct KindError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for KindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+impl KindError {
+    /// Put the location of the question in front of the path
+    pub fn at(self, prefix: &str) -> Self {
+        Self {
+            path: format!("{}.{}", prefix, self.path),
+            message: self.message,
+        }
+    }
+
+    /// The path as a JSON pointer, e.g. `features.options[1]` becomes `/features/options/1`
+    pub fn pointer(&self) -> String {
+        self.path
+            .replace('[', ".")
+            .replace(']', "")
+            .split('.')
+            .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+            .collect()
+    }
+}
+
+fn parse<T: DeserializeOwned>(features: &JsonValue) -> Result<T, KindError> {
+    serde_path_to_error::deserialize(features).map_err(|err| {
+        let path = err.path().to_string();
+        KindError {
+            path: if path == "." {
+                "features".to_string()
+            } else {
+                format!("features.{}", path)
+            },
+            message: err.into_inner().to_string(),
+        }
+    })
+}
+
+fn parse_features<T: DeserializeOwned + Serialize>(
+    features: &JsonValue,
+) -> Result<Features<T>, KindError> {
+    let common: CommonFeatures = parse(features)?;
+    let config: T = parse(features)?;
+
+    // Whatever neither struct picked up (nulls included) is left for `extra`
+    let mut known = Map::new();
+    for value in [json_object(&common), json_object(&config)] {
+        known.extend(value);
+    }
+    let extra = features
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, value)| !known.contains_key(*key) && !value.is_null())
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    Ok(Features {
+        common,
+        config,
+        extra,
+    })
+}
+
+fn json_object(value: &impl Serialize) -> Map<String, JsonValue> {
+    match serde_json::to_value(value) {
+        Ok(JsonValue::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+macro_rules! question_kinds {
+    ($($variant:ident($config:ty) = $name:literal,)*) => {
+        /// A question's type together with its typed `features`
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        #[serde(tag = "type", content = "features", try_from = "RawKind")]
+        pub enum QuestionKind {
+            $(
+                #[serde(rename = $name)]
+                $variant(Features<$config>),
+            )*
+        }
+
+        impl QuestionKind {
+            /// Every accepted value of `type`
+            pub const NAMES: &'static [&'static str] = &[$($name),*];
+
+            pub fn type_name(&self) -> &'static str {
+                match self {
+                    $(Self::$variant(_) => $name,)*
+                }
+            }
+
+            pub fn common(&self) -> &CommonFeatures {
+                match self {
+                    $(Self::$variant(features) => &features.common,)*
+                }
+            }
+
+            pub fn common_mut(&mut self) -> &mut CommonFeatures {
+                match self {
+                    $(Self::$variant(features) => &mut features.common,)*
+                }
+            }
+
+            /// The schema of `features` for every type, registered with `generator`
+            pub fn feature_schemas(generator: &mut SchemaGenerator) -> Vec<(&'static str, Schema)> {
+                vec![$(($name, generator.subschema_for::<Features<$config>>()),)*]
+            }
+
+            /// Read a stored or submitted `type`/`features` pair
+            pub fn from_parts(question_type: &str, features: &JsonValue) -> Result<Self, KindError> {
+                if !features.is_object() {
+                    return Err(KindError {
+                        path: "features".to_string(),
+                        message: "expected an object".to_string(),
+                    });
+                }
+
+                let kind = match question_type {
+                    $($name => Self::$variant(parse_features(features)?),)*
+                    other => {
+                        return Err(KindError {
+                            path: "question_type".to_string(),
+                            message: format!(
+                                "unknown question type `{}`, expected one of: {}",
+                                other,
+                                Self::NAMES.join(", ")
+                            ),
+                        })
+                    }
+                };
+                kind.check()?;
+                Ok(kind)
+            }
+        }
+    };
+}
+
+question_kinds! {
+    Text(TextInput) = "text",
+    Textarea(TextInput) = "textarea",
+    MultipleChoice(Choice) = "multiple_choice",
+    Checkbox(Choice) = "checkbox",
+    Dropdown(Choice) = "dropdown",
+    YesNo(NoConfig) = "yes_no",
+    Likert(Likert) = "likert",
+    Rating(Rating) = "rating",
+    Number(NumberRange) = "number",
+    Date(DateRange) = "date",
+    Time(DateRange) = "time",
+    Datetime(DateRange) = "datetime",
+    SectionHeader(NoConfig) = "section_header",
+}
+
+impl QuestionKind {
+    /// The `features` object as it is stored
+    pub fn features(&self) -> JsonValue {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|mut tagged| tagged.get_mut("features").map(JsonValue::take))
+            .unwrap_or_else(|| JsonValue::Object(Map::new()))
+    }
+
+    /// Rules that span fields, beyond what the types already enforce
+    fn check(&self) -> Result<(), KindError> {
+        let backwards = |path: &str, what: &str| KindError {
+            path: path.to_string(),
+            message: format!("{} must be below max", what),
+        };
+
+        match self {
+            Self::Likert(Features {
+                config: Likert {
+                    scale: Some(scale), ..
+                },
+                ..
+            }) if scale.min >= scale.max => Err(backwards("features.scale", "min")),
+            Self::Rating(Features {
+                config:
+                    Rating {
+                        min: Some(min),
+                        max: Some(max),
+                        ..
+                    },
+                ..
+            }) if min >= max => Err(backwards("features", "min")),
+            Self::Number(Features {
+                config:
+                    NumberRange {
+                        min: Some(min),
+                        max: Some(max),
+                        ..
+                    },
+                ..
+            }) if min.as_f64() > max.as_f64() => Err(KindError {
+                path: "features".to_string(),
+                message: "min must not be above max".to_string(),
+            }),
+            Self::Date(features) => check_date_range(&features.config, "YYYY-MM-DD", |value| {
+                NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+            }),
+            Self::Time(features) => check_date_range(&features.config, "HH:MM", |value| {
+                NaiveTime::parse_from_str(value, "%H:%M")
+                    .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M:%S"))
+                    .ok()
+            }),
+            Self::Datetime(features) => {
+                check_date_range(&features.config, "YYYY-MM-DDTHH:MM", |value| {
+                    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+                        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+                        .ok()
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Both bounds must parse in the type's format, and `min` must not come after `max`
+fn check_date_range<T: PartialOrd>(
+    range: &DateRange,
+    expected: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<(), KindError> {
+    let bound = |name: &str, value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|value| {
+                parse(value).ok_or_else(|| KindError {
+                    path: format!("features.{}", name),
+                    message: format!("expected {}, got `{}`", expected, value),
+                })
+            })
+            .transpose()
+    };
+
+    match (bound("min", &range.min)?, bound("max", &range.max)?) {
+        (Some(min), Some(max)) if min > max => Err(KindError {
+            path: "features.min".to_string(),
+            message: "min must not be after max".to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The untyped pair, as found in request bodies and rows
+#[derive(Deserialize)]
+struct RawKind {
+    #[serde(rename = "type")]
+    question_type: String,
+    #[serde(default)]
+    features: Option<JsonValue>,
+}
+
+impl TryFrom<RawKind> for QuestionKind {
+    type Error = KindError;
+
+    fn try_from(raw: RawKind) -> Result<Self, Self::Error> {
+        let features = raw
+            .features
+            .unwrap_or_else(|| JsonValue::Object(Map::new()));
+        Self::from_parts(&raw.question_type, &features)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn round_trips_the_stored_shape() {
+        let features = json!({
+            "required": true,
+            "allowComment": false,
+            "scale": { "min": 1, "max": 7, "minLabel": "Never", "maxLabel": "Always" },
+            "legacyColour": "blue"
+        });
+        let kind = QuestionKind::from_parts("likert", &features).unwrap();
+
+        let QuestionKind::Likert(likert) = &kind else {
+            panic!("expected a likert question, got {:?}", kind);
+        };
+        assert!(likert.common.required);
+        assert_eq!(likert.config.scale.as_ref().unwrap().max, 7);
+        assert_eq!(likert.extra["legacyColour"], "blue");
+        assert_eq!(kind.features(), features);
+
+        let tagged = serde_json::to_value(&kind).unwrap();
+        assert_eq!(tagged, json!({ "type": "likert", "features": features }));
+        assert_eq!(
+            serde_json::from_value::<QuestionKind>(tagged).unwrap(),
+            kind
+        );
+
+        let number = json!({ "min": 0, "max": 2.5, "step": 0.5 });
+        assert_eq!(
+            QuestionKind::from_parts("number", &number)
+                .unwrap()
+                .features(),
+            json!({ "required": false, "min": 0, "max": 2.5, "step": 0.5 })
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_value() {
+        let error = |question_type: &str, features: JsonValue| {
+            QuestionKind::from_parts(question_type, &features)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(error("slider", json!({}))
+            .starts_with("question_type: unknown question type `slider`, expected one of: text,"));
+        assert!(error("checkbox", json!({ "options": ["a", 2] }))
+            .starts_with("features.options[1]: invalid type: integer `2`"));
+        assert!(
+            error("likert", json!({ "scale": { "min": "1", "max": 5 } }))
+                .starts_with("features.scale.min: invalid type: string \"1\"")
+        );
+        assert_eq!(
+            error("likert", json!({ "scale": { "min": 5, "max": 5 } })),
+            "features.scale: min must be below max"
+        );
+        assert_eq!(
+            error("date", json!({ "min": "01/09/2025" })),
+            "features.min: expected YYYY-MM-DD, got `01/09/2025`"
+        );
+        assert_eq!(
+            error("time", json!({ "min": "17:00", "max": "09:00" })),
+            "features.min: min must not be after max"
+        );
+        assert_eq!(
+            error("datetime", json!({ "max": "2025-09-01" })),
+            "features.max: expected YYYY-MM-DDTHH:MM, got `2025-09-01`"
+        );
+        assert!(QuestionKind::from_parts(
+            "datetime",
+            &json!({ "min": "2025-09-01T09:00", "max": "2025-09-01T17:30:00" })
+        )
+        .is_ok());
+        assert!(error("rating", json!({ "ratingStyle": "hearts" }))
+            .starts_with("features.ratingStyle: unknown variant `hearts`"));
+        assert_eq!(error("text", json!([])), "features: expected an object");
+
+        let located = QuestionKind::from_parts("text", &json!({ "charLimit": -1 }))
+            .unwrap_err()
+            .at("sections[0].questions[2]");
+        assert_eq!(located.path, "sections[0].questions[2].features.charLimit");
+        assert_eq!(
+            located.pointer(),
+            "/sections/0/questions/2/features/charLimit"
+        );
+    }
+}
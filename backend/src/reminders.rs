@@ -0,0 +1,601 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use uuid::Uuid;
+
+use crate::{
+    email::{self, EmailMessage},
+    error::AppError,
+    handlers::{check_admin_auth, AuthQuery},
+    models::*,
+    outbox,
+    pagination::PageParams,
+    AppState,
+};
+
+/// How often the background scheduler looks for due reminders
+const SCHEDULER_INTERVAL_SECS: u64 = 15 * 60;
+
+#[derive(Debug, Deserialize)]
+pub struct RunCampaignQuery {
+    pub token: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn parse_closes_at(value: &str) -> Result<DateTime<Utc>, AppError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|_| {
+            AppError::BadRequest(format!(
+                "Invalid closes_at '{}': expected an RFC 3339 timestamp",
+                value
+            ))
+        })
+}
+
+/// The reminder step that is due right now, if any.
+///
+/// When several steps have passed (e.g. the campaign was created late), only the most
+/// recent one is returned so invitees get a single email instead of a burst.
+fn due_step(campaign: &ReminderCampaign, now: DateTime<Utc>) -> Option<i64> {
+    let closes_at = parse_closes_at(&campaign.closes_at).ok()?;
+    if now >= closes_at {
+        return None;
+    }
+
+    campaign
+        .days_before
+        .iter()
+        .copied()
+        .filter(|days| now >= closes_at - Duration::days(*days))
+        .min()
+}
+
+/// Invitees with no submission who have not yet received the reminder for this step
+/// Run a campaign's due step: list the recipients and, unless `dry_run`, queue their emails
+async fn run_campaign(
+    state: &AppState,
+    campaign: &ReminderCampaign,
+    dry_run: bool,
+) -> Result<JsonValue, AppError> {
+    let Some(days_before) = due_step(campaign, Utc::now()) else {
+        return Ok(json!({
+            "campaign_id": campaign.id,
+            "dry_run": dry_run,
+            "days_before": null,
+            "message": "No reminder is due for this campaign",
+            "recipients": [],
+            "queued": 0
+        }));
+    };
+
+    let recipients = state
+        .repos
+        .reminders
+        .pending_recipients(campaign, days_before)
+        .await?;
+
+    let recipient_list: Vec<_> = recipients
+        .iter()
+        .map(|i| {
+            json!({
+                "invitation_id": i.id,
+                "name": i.name,
+                "email": i.email,
+                "role": i.role
+            })
+        })
+        .collect();
+
+    if dry_run {
+        return Ok(json!({
+            "campaign_id": campaign.id,
+            "dry_run": true,
+            "days_before": days_before,
+            "recipients": recipient_list,
+            "queued": 0
+        }));
+    }
+
+    if state.mailer.is_none() {
+        return Err(AppError::BadRequest(
+            "Email is not configured (set EMAIL_TRANSPORT)".to_string(),
+        ));
+    }
+    // Without it every `{{link}}` would be a relative URL that mail clients cannot open
+    if state.public_base_url.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "PUBLIC_BASE_URL is not set, so reminder links cannot be built".to_string(),
+        ));
+    }
+
+    let form_title = state
+        .repos
+        .forms
+        .find_form(&campaign.form_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Form not found".to_string()))?
+        .form
+        .title;
+
+    let closes_at = parse_closes_at(&campaign.closes_at)?;
+    let closes_at_display = closes_at.format("%B %-d, %Y").to_string();
+    let days_left = (closes_at - Utc::now()).num_days().max(0).to_string();
+
+    let mut reminders = Vec::with_capacity(recipients.len());
+    for invitation in &recipients {
+        let link = format!(
+            "{}/form/{}?invite={}",
+            state.public_base_url.trim_end_matches('/'),
+            campaign.form_id,
+            invitation.token
+        );
+        let vars = [
+            ("name", invitation.name.as_str()),
+            ("form_title", form_title.as_str()),
+            ("link", link.as_str()),
+            ("closes_at", closes_at_display.as_str()),
+            ("days_left", days_left.as_str()),
+        ];
+        let message = EmailMessage::from_html(
+            &invitation.email,
+            &email::render_template(&campaign.subject, &vars),
+            &email::render_html_template(&campaign.body_template, &vars),
+        );
+        reminders.push((invitation.id.clone(), message));
+    }
+
+    // Delivery, retries and dead-lettering are left to the outbox
+    let queued = state
+        .repos
+        .reminders
+        .queue_reminders(&campaign.id, days_before, &reminders)
+        .await?;
+    outbox::wake(state);
+
+    tracing::info!(
+        campaign_id = %campaign.id,
+        days_before,
+        queued,
+        "Reminder campaign run completed"
+    );
+
+    Ok(json!({
+        "campaign_id": campaign.id,
+        "dry_run": false,
+        "days_before": days_before,
+        "recipients": recipient_list,
+        "queued": queued
+    }))
+}
+
+/// Run every active campaign that has a step due
+async fn run_due_campaigns(state: &AppState) -> Result<(), AppError> {
+    let campaigns = state.repos.reminders.active_campaigns().await?;
+
+    let now = Utc::now();
+    for campaign in campaigns.iter().filter(|c| due_step(c, now).is_some()) {
+        if let Err(e) = run_campaign(state, campaign, false).await {
+            tracing::error!(campaign_id = %campaign.id, error = %e, "Reminder campaign failed");
+        }
+    }
+
+    Ok(())
+}
+
+/// Spawn the background task that sends due reminders
+pub fn spawn_scheduler(state: AppState) {
+    if state.mailer.is_none() {
+        tracing::info!("Email not configured, reminder scheduler disabled");
+        return;
+    }
+    if state.public_base_url.trim().is_empty() {
+        tracing::warn!("PUBLIC_BASE_URL not set, reminder scheduler disabled");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(SCHEDULER_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_due_campaigns(&state).await {
+                tracing::error!(error = %e, "Reminder scheduler run failed");
+            }
+        }
+    });
+}
+
+async fn fetch_campaign(state: &AppState, campaign_id: &str) -> Result<ReminderCampaign, AppError> {
+    let campaign = state.repos.reminders.find_campaign(campaign_id).await?;
+
+    campaign.ok_or_else(|| AppError::NotFound("Reminder campaign not found".to_string()))
+}
+
+fn check_template(subject: &str, body_template: &str) -> Result<(), AppError> {
+    if subject.trim().is_empty() || body_template.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "Subject and body template are required".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Validate a schedule and order it from the earliest reminder to the last
+fn check_schedule(mut days_before: Vec<i64>) -> Result<Vec<i64>, AppError> {
+    if days_before.is_empty() || days_before.iter().any(|d| *d < 0) {
+        return Err(AppError::BadRequest(
+            "days_before must list at least one non-negative number of days".to_string(),
+        ));
+    }
+    days_before.sort_unstable_by(|a, b| b.cmp(a));
+    days_before.dedup();
+    Ok(days_before)
+}
+
+/// Create a reminder campaign for a form (admin only)
+pub async fn create_campaign(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateReminderCampaignRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    check_template(&req.subject, &req.body_template)?;
+    let days_before = check_schedule(req.days_before)?;
+    let closes_at = parse_closes_at(&req.closes_at)?;
+
+    if !state.repos.forms.form_exists(&form_id).await? {
+        return Err(AppError::BadRequest("Form not found".to_string()));
+    }
+
+    let campaign = ReminderCampaign {
+        id: Uuid::new_v4().to_string(),
+        form_id,
+        subject: req.subject,
+        body_template: req.body_template,
+        closes_at: closes_at.to_rfc3339(),
+        days_before: sqlx::types::Json(days_before),
+        active: true,
+        created_at: String::new(),
+    };
+    state.repos.reminders.create_campaign(&campaign).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "message": "Reminder campaign created successfully",
+            "id": campaign.id,
+            "form_id": campaign.form_id,
+            "closes_at": campaign.closes_at,
+            "days_before": campaign.days_before
+        })),
+    ))
+}
+
+/// List reminder campaigns for a form with how many reminders each has queued (admin only)
+pub async fn list_campaigns(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    Query(page): Query<PageParams>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let campaigns = state
+        .repos
+        .reminders
+        .list_campaigns(&form_id, &page)
+        .await?;
+    let send_counts = state.repos.reminders.send_counts(&form_id).await?;
+
+    let now = Utc::now();
+    let campaign_list = campaigns.map(|c| {
+        let queued = send_counts.get(&c.id).copied().unwrap_or(0);
+        json!({
+            "queued": queued,
+            "due_days_before": due_step(&c, now),
+            "campaign": c
+        })
+    });
+
+    Ok(Json(campaign_list))
+}
+
+/// Pause or resume a campaign, or change its template or schedule (admin only).
+/// Reminders already queued for a step are not sent again.
+pub async fn update_campaign(
+    headers: HeaderMap,
+    Path(campaign_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<UpdateReminderCampaignRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let mut campaign = fetch_campaign(&state, &campaign_id).await?;
+
+    if let Some(subject) = req.subject {
+        campaign.subject = subject;
+    }
+    if let Some(body_template) = req.body_template {
+        campaign.body_template = body_template;
+    }
+    check_template(&campaign.subject, &campaign.body_template)?;
+    if let Some(closes_at) = req.closes_at {
+        campaign.closes_at = parse_closes_at(&closes_at)?.to_rfc3339();
+    }
+    if let Some(days_before) = req.days_before {
+        campaign.days_before = sqlx::types::Json(check_schedule(days_before)?);
+    }
+    if let Some(active) = req.active {
+        campaign.active = active;
+    }
+
+    if !state.repos.reminders.update_campaign(&campaign).await? {
+        return Err(AppError::NotFound(
+            "Reminder campaign not found".to_string(),
+        ));
+    }
+
+    Ok(Json(json!({
+        "message": "Reminder campaign updated successfully",
+        "due_days_before": due_step(&campaign, Utc::now()),
+        "campaign": campaign
+    })))
+}
+
+/// Delete a reminder campaign and its send history (admin only)
+pub async fn delete_campaign(
+    headers: HeaderMap,
+    Path(campaign_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    if !state.repos.reminders.delete_campaign(&campaign_id).await? {
+        return Err(AppError::NotFound(
+            "Reminder campaign not found".to_string(),
+        ));
+    }
+
+    Ok(Json(json!({
+        "message": "Reminder campaign deleted successfully"
+    })))
+}
+
+/// Run a campaign's due step now; with `dry_run=true` only list who would be emailed (admin only)
+pub async fn run_campaign_now(
+    headers: HeaderMap,
+    Path(campaign_id): Path<String>,
+    Query(params): Query<RunCampaignQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+
+    let campaign = fetch_campaign(&state, &campaign_id).await?;
+    if !campaign.active && !params.dry_run {
+        return Err(AppError::BadRequest(
+            "Reminder campaign is paused; set active to true first".to_string(),
+        ));
+    }
+    let result = run_campaign(&state, &campaign, params.dry_run).await?;
+
+    Ok(Json(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::email::TestSender;
+
+    fn campaign(closes_at: DateTime<Utc>, days_before: Vec<i64>) -> ReminderCampaign {
+        ReminderCampaign {
+            id: "c1".to_string(),
+            form_id: "review".to_string(),
+            subject: "{{form_title}} closes in {{days_left}} days".to_string(),
+            body_template: "<p>Hi {{name}}, respond at {{link}}</p>".to_string(),
+            closes_at: closes_at.to_rfc3339(),
+            days_before: sqlx::types::Json(days_before),
+            active: true,
+            created_at: String::new(),
+        }
+    }
+
+    /// Three invitees: Ada has not responded, Grace has, and Linus was already
+    /// reminded a week before close
+    async fn invited_state() -> AppState {
+        let db = crate::db::test_pool().await;
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO forms (id, title, status) VALUES ('review', 'Annual Review', 'published');
+            INSERT INTO responses (id, form_id) VALUES ('r1', 'review');
+            INSERT INTO invitations (id, form_id, name, email, token, response_id, used_at) VALUES
+                ('i1', 'review', 'Ada', 'ada@example.com', 'token-ada', NULL, NULL),
+                ('i2', 'review', 'Grace', 'grace@example.com', 'token-grace', 'r1', datetime('now')),
+                ('i3', 'review', 'Linus', 'linus@example.com', 'token-linus', NULL, NULL);
+            INSERT INTO reminder_campaigns (id, form_id, subject, body_template, closes_at, days_before)
+                VALUES ('c1', 'review', 'Reminder', 'Body', '2030-01-01T00:00:00Z', '[7, 2]');
+            INSERT INTO reminder_sends (id, campaign_id, invitation_id, days_before)
+                VALUES ('s1', 'c1', 'i3', 7);
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        AppState::for_tests(db)
+    }
+
+    #[test]
+    fn the_most_recent_passed_step_is_due() {
+        let closes_at = Utc::now() + Duration::days(30);
+        let schedule = campaign(closes_at, vec![7, 2]);
+
+        assert_eq!(due_step(&schedule, closes_at - Duration::days(10)), None);
+        assert_eq!(due_step(&schedule, closes_at - Duration::days(7)), Some(7));
+        assert_eq!(due_step(&schedule, closes_at - Duration::days(5)), Some(7));
+        // Both steps have passed, but only the later one is sent
+        assert_eq!(due_step(&schedule, closes_at - Duration::hours(1)), Some(2));
+        assert_eq!(due_step(&schedule, closes_at), None);
+        assert_eq!(due_step(&campaign(closes_at, vec![]), closes_at), None);
+
+        let mut invalid = schedule;
+        invalid.closes_at = "next week".to_string();
+        assert_eq!(due_step(&invalid, closes_at - Duration::days(5)), None);
+    }
+
+    #[tokio::test]
+    async fn only_unsubmitted_invitees_not_yet_reminded_for_the_step_are_selected() {
+        let state = invited_state().await;
+        let campaign = fetch_campaign(&state, "c1").await.unwrap();
+
+        let reminders = &state.repos.reminders;
+        let names = |invitations: Vec<Invitation>| -> Vec<String> {
+            invitations.into_iter().map(|i| i.name).collect()
+        };
+        assert_eq!(
+            names(reminders.pending_recipients(&campaign, 7).await.unwrap()),
+            vec!["Ada"]
+        );
+        assert_eq!(
+            names(reminders.pending_recipients(&campaign, 2).await.unwrap()),
+            vec!["Ada", "Linus"]
+        );
+    }
+
+    #[tokio::test]
+    async fn reminders_link_to_the_invitation_and_are_sent_once() {
+        let sender = Arc::new(TestSender::default());
+        let mut state = invited_state().await;
+        state.mailer = Some(sender.clone());
+        let closes_in_a_day = campaign(Utc::now() + Duration::hours(36), vec![7, 2]);
+
+        // No public URL: nothing is sent rather than mailing relative links
+        assert!(matches!(
+            run_campaign(&state, &closes_in_a_day, false).await,
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(sender.sent.lock().unwrap().is_empty());
+
+        state.public_base_url = "https://reviews.example.com/".to_string();
+        let result = run_campaign(&state, &closes_in_a_day, false).await.unwrap();
+        assert_eq!(
+            (result["days_before"].clone(), result["queued"].clone()),
+            (json!(2), json!(2))
+        );
+        assert_eq!(crate::outbox::process_due(&state).await.unwrap(), 2);
+
+        let mut sent: Vec<(String, String)> = sender
+            .sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|m| (m.to.clone(), m.html.clone()))
+            .collect();
+        sent.sort();
+        assert_eq!(
+            sent,
+            vec![
+                (
+                    "ada@example.com".to_string(),
+                    "<p>Hi Ada, respond at https://reviews.example.com/form/review?invite=token-ada</p>"
+                        .to_string()
+                ),
+                (
+                    "linus@example.com".to_string(),
+                    "<p>Hi Linus, respond at https://reviews.example.com/form/review?invite=token-linus</p>"
+                        .to_string()
+                ),
+            ]
+        );
+
+        // Recorded, so a second run has nobody left to remind
+        let result = run_campaign(&state, &closes_in_a_day, false).await.unwrap();
+        assert_eq!(result["queued"], 0);
+    }
+
+    #[tokio::test]
+    async fn overlapping_runs_queue_each_reminder_once() {
+        let mut state = invited_state().await;
+        state.mailer = Some(Arc::new(TestSender::default()));
+        state.public_base_url = "https://reviews.example.com".to_string();
+        let closes_in_a_day = campaign(Utc::now() + Duration::hours(36), vec![7, 2]);
+
+        // Both runs may list Ada and Linus before either has claimed them
+        let (first, second) = tokio::join!(
+            run_campaign(&state, &closes_in_a_day, false),
+            run_campaign(&state, &closes_in_a_day, false)
+        );
+        let queued = first.unwrap()["queued"].as_i64().unwrap()
+            + second.unwrap()["queued"].as_i64().unwrap();
+        assert_eq!(queued, 2);
+
+        let entries = state
+            .repos
+            .outbox
+            .list_entries(None, Some("email"), &PageParams::default())
+            .await
+            .unwrap();
+        assert_eq!(entries.total, 2);
+    }
+
+    #[tokio::test]
+    async fn campaigns_can_be_paused_and_rescheduled() {
+        let state = invited_state().await;
+        let update = |request: JsonValue| {
+            update_campaign(
+                HeaderMap::new(),
+                Path("c1".to_string()),
+                Query(AuthQuery {
+                    token: Some("admin".to_string()),
+                }),
+                State(state.clone()),
+                Json(serde_json::from_value(request).unwrap()),
+            )
+        };
+
+        update(json!({ "active": false, "days_before": [1, 3, 3] }))
+            .await
+            .unwrap();
+        let paused = fetch_campaign(&state, "c1").await.unwrap();
+        assert!(!paused.active);
+        assert_eq!(paused.days_before.0, vec![3, 1]);
+        assert_eq!(paused.subject, "Reminder");
+
+        let run = run_campaign_now(
+            HeaderMap::new(),
+            Path("c1".to_string()),
+            Query(RunCampaignQuery {
+                token: Some("admin".to_string()),
+                dry_run: false,
+            }),
+            State(state.clone()),
+        )
+        .await;
+        assert!(matches!(run, Err(AppError::BadRequest(_))));
+
+        assert!(matches!(
+            update(json!({ "subject": " " })).await,
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(matches!(
+            update(json!({ "closes_at": "soon" })).await,
+            Err(AppError::BadRequest(_))
+        ));
+        update(json!({ "active": true })).await.unwrap();
+        assert!(fetch_campaign(&state, "c1").await.unwrap().active);
+    }
+}
@@ -0,0 +1,984 @@
+//! An in-memory backend for handler tests that have no use for SQL
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{stream::BoxStream, StreamExt};
+use serde_json::Value as JsonValue;
+use uuid::Uuid;
+
+use super::{
+    sqlite::{INVITATION_LISTING, RESPONSE_LISTING, RESPONSE_PII_LISTING},
+    CopiedForm, CopyOptions, ExportRow, FormDefinition, FormRepository, FormSummary,
+    InvitationFilters, InvitationRepository, LikertRating, LikertSummary, ListedResponse,
+    NewSubmission, NotificationRepository, OutboxHook, Question, RatingBucket, Repositories,
+    ResponseRepository, StoredSubmission, Totals,
+};
+use crate::{
+    db::{Database, DB_TIMESTAMP_FORMAT},
+    error::AppError,
+    filters::{AnswerCondition, AnswerValue, ResponseFilters},
+    invitations::generate_token,
+    models::{
+        AnswerWithQuestion, Invitation, NotificationTemplate, RoleCount, RoleResponseRate, Webhook,
+    },
+    outbox::{OutboxEvent, OutboxMessage},
+    pagination::{Page, PageParams},
+    webhooks, AppState,
+};
+
+/// A submitted response as the fake keeps it. Respondents are identified by their email
+/// hash, which doubles as the respondent ID.
+#[derive(Debug, Clone, Default)]
+pub struct StoredResponse {
+    /// Assigned as r1, r2, ... in the order added when left empty
+    pub id: String,
+    pub form_id: String,
+    pub role: Option<String>,
+    pub email_hash: Option<String>,
+    /// `(question_id, value)` pairs
+    pub answers: Vec<(String, JsonValue)>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+struct StoredForm {
+    definition: FormDefinition,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Default)]
+pub struct MemoryStore {
+    forms: Mutex<Vec<StoredForm>>,
+    responses: Mutex<Vec<StoredResponse>>,
+    /// `(name, email)` by respondent ID
+    respondents: Mutex<HashMap<String, (String, String)>>,
+    added: Mutex<usize>,
+    invitations: Mutex<Vec<Invitation>>,
+    webhooks: Mutex<Vec<Webhook>>,
+    templates: Mutex<HashMap<String, NotificationTemplate>>,
+    outbox: Mutex<Vec<OutboxMessage>>,
+}
+
+impl MemoryStore {
+    /// Forms, responses, invitations and notification templates on this store. The other
+    /// repositories sit on an unconnected SQLite pool, for handlers that never touch them.
+    pub fn repositories(self: &Arc<Self>) -> Repositories {
+        let unconnected = sqlx::SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        Repositories {
+            forms: self.clone(),
+            responses: self.clone(),
+            invitations: self.clone(),
+            notifications: self.clone(),
+            ..Repositories::new(&Database::Sqlite(unconnected))
+        }
+    }
+
+    /// App state on this store
+    pub fn app_state(self: &Arc<Self>) -> AppState {
+        AppState {
+            repos: self.repositories(),
+            admin_token: "admin".to_string(),
+            mailer: None,
+            notification_email: String::new(),
+            public_base_url: String::new(),
+            outbox_wakeup: Arc::new(tokio::sync::Notify::new()),
+        }
+    }
+
+    pub fn add_response(&self, mut response: StoredResponse) {
+        let mut added = self.added.lock().unwrap();
+        *added += 1;
+        if response.id.is_empty() {
+            response.id = format!("r{}", added);
+        }
+        self.responses.lock().unwrap().push(response);
+    }
+
+    pub fn add_respondent(&self, id: &str, name: &str, email: &str) {
+        self.respondents
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), (name.to_string(), email.to_string()));
+    }
+
+    pub fn add_invitation(&self, invitation: Invitation) {
+        self.invitations.lock().unwrap().push(invitation);
+    }
+
+    pub fn add_webhook(&self, webhook: Webhook) {
+        self.webhooks.lock().unwrap().push(webhook);
+    }
+
+    pub fn invitations(&self) -> Vec<Invitation> {
+        self.invitations.lock().unwrap().clone()
+    }
+
+    /// Everything queued for delivery so far, in order
+    pub fn queued(&self) -> Vec<OutboxMessage> {
+        self.outbox.lock().unwrap().clone()
+    }
+
+    /// Queue emails as they are and webhook events once per matching active endpoint
+    fn queue(&self, events: &[OutboxEvent]) {
+        let endpoints = self.webhooks.lock().unwrap();
+        let mut outbox = self.outbox.lock().unwrap();
+        for event in events {
+            match event {
+                OutboxEvent::Email(message) => outbox.push(OutboxMessage::Email(message.clone())),
+                OutboxEvent::Webhook(event) => outbox.extend(
+                    webhooks::deliveries(&endpoints, event)
+                        .into_iter()
+                        .map(OutboxMessage::Webhook),
+                ),
+            }
+        }
+    }
+
+    fn questions_of_type(&self, form_id: &str, question_type: &str) -> Vec<Question> {
+        self.forms
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|stored| stored.definition.form.id == form_id)
+            .flat_map(|stored| stored.definition.questions.iter())
+            .filter(|question| question.kind.type_name() == question_type)
+            .cloned()
+            .collect()
+    }
+
+    /// Every `(response index, rating)` given to a question of the form
+    fn ratings(&self, form_id: &str, question_id: &str) -> Vec<(usize, i64)> {
+        self.responses
+            .lock()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, response)| response.form_id == form_id)
+            .flat_map(|(index, response)| {
+                response
+                    .answers
+                    .iter()
+                    .filter(|(id, _)| id == question_id)
+                    .filter_map(move |(_, value)| {
+                        value
+                            .as_i64()
+                            .or_else(|| value.get("rating").and_then(JsonValue::as_i64))
+                            .map(|rating| (index, rating))
+                    })
+            })
+            .collect()
+    }
+}
+
+fn matches(response: &StoredResponse, filters: &ResponseFilters) -> bool {
+    filters
+        .form_id
+        .as_ref()
+        .is_none_or(|form_id| &response.form_id == form_id)
+        && filters
+            .role
+            .as_ref()
+            .is_none_or(|role| response.role.as_ref() == Some(role))
+        && filters
+            .submitted_after
+            .is_none_or(|after| response.submitted_at >= after)
+        && filters
+            .submitted_before
+            .is_none_or(|before| response.submitted_at < before)
+        && filters.answers.iter().all(|condition| {
+            response.answers.iter().any(|(question_id, value)| {
+                question_id == &condition.question_id && satisfies(value, condition)
+            })
+        })
+}
+
+/// Numbers compare with ratings and plain numbers, text only with strings, as in SQL
+fn satisfies(value: &JsonValue, condition: &AnswerCondition) -> bool {
+    fn compare<T: PartialOrd + ?Sized>(left: &T, operator: &str, right: &T) -> bool {
+        match operator {
+            "<=" => left <= right,
+            ">=" => left >= right,
+            "!=" => left != right,
+            "=" => left == right,
+            "<" => left < right,
+            ">" => left > right,
+            _ => false,
+        }
+    }
+
+    match &condition.value {
+        AnswerValue::Number(number) => value
+            .as_f64()
+            .or_else(|| value.get("rating").and_then(JsonValue::as_f64))
+            .is_some_and(|answer| compare(&answer, condition.operator, number)),
+        AnswerValue::Text(text) => value
+            .as_str()
+            .is_some_and(|answer| compare(answer, condition.operator, text.as_str())),
+    }
+}
+
+#[async_trait]
+impl FormRepository for MemoryStore {
+    async fn list_forms(&self) -> Result<Vec<FormSummary>, AppError> {
+        let forms = self.forms.lock().unwrap();
+        Ok(forms
+            .iter()
+            .rev()
+            .map(|stored| {
+                let form = &stored.definition.form;
+                FormSummary {
+                    id: form.id.clone(),
+                    title: form.title.clone(),
+                    description: form.description.clone(),
+                    instructions: form.instructions.clone(),
+                    status: form.status.clone(),
+                    created_at: stored.created_at.clone(),
+                    updated_at: stored.updated_at.clone(),
+                }
+            })
+            .collect())
+    }
+
+    async fn find_form(&self, form_id: &str) -> Result<Option<FormDefinition>, AppError> {
+        let forms = self.forms.lock().unwrap();
+        Ok(forms
+            .iter()
+            .find(|stored| stored.definition.form.id == form_id)
+            .map(|stored| stored.definition.clone()))
+    }
+
+    async fn form_exists(&self, form_id: &str) -> Result<bool, AppError> {
+        Ok(self.find_form(form_id).await?.is_some())
+    }
+
+    async fn create_form(&self, definition: &FormDefinition) -> Result<(), AppError> {
+        let mut definition = definition.clone();
+        definition.sections.sort_by_key(|section| section.position);
+        definition
+            .questions
+            .sort_by_key(|question| question.position);
+
+        let now = Utc::now().to_rfc3339();
+        self.forms.lock().unwrap().push(StoredForm {
+            definition,
+            created_at: now.clone(),
+            updated_at: now,
+        });
+        Ok(())
+    }
+
+    async fn add_questions(&self, form_id: &str, questions: &[Question]) -> Result<(), AppError> {
+        let mut forms = self.forms.lock().unwrap();
+        if let Some(stored) = forms
+            .iter_mut()
+            .find(|stored| stored.definition.form.id == form_id)
+        {
+            let definition = &mut stored.definition;
+            definition.questions.extend_from_slice(questions);
+            definition
+                .questions
+                .sort_by_key(|question| question.position);
+        }
+        Ok(())
+    }
+
+    async fn replace_form(
+        &self,
+        definition: &FormDefinition,
+        updated_at: DateTime<Utc>,
+        events: &[OutboxEvent],
+    ) -> Result<bool, AppError> {
+        {
+            let mut forms = self.forms.lock().unwrap();
+            let Some(stored) = forms
+                .iter_mut()
+                .find(|stored| stored.definition.form.id == definition.form.id)
+            else {
+                return Ok(false);
+            };
+            stored.definition = definition.clone();
+            stored.updated_at = updated_at.to_rfc3339();
+        }
+        self.queue(events);
+        Ok(true)
+    }
+
+    async fn set_status(
+        &self,
+        form_id: &str,
+        status: &str,
+        updated_at: DateTime<Utc>,
+        hook: &OutboxHook<'_, str>,
+    ) -> Result<Option<String>, AppError> {
+        let previous = {
+            let mut forms = self.forms.lock().unwrap();
+            let Some(stored) = forms
+                .iter_mut()
+                .find(|stored| stored.definition.form.id == form_id)
+            else {
+                return Ok(None);
+            };
+            stored.updated_at = updated_at.to_rfc3339();
+            std::mem::replace(&mut stored.definition.form.status, status.to_string())
+        };
+        self.queue(&hook(&previous));
+        Ok(Some(previous))
+    }
+
+    async fn delete_form(
+        &self,
+        form_id: &str,
+        hook: &OutboxHook<'_, i64>,
+    ) -> Result<Option<i64>, AppError> {
+        {
+            let mut forms = self.forms.lock().unwrap();
+            let before = forms.len();
+            forms.retain(|stored| stored.definition.form.id != form_id);
+            if forms.len() == before {
+                return Ok(None);
+            }
+        }
+
+        let mut responses = self.responses.lock().unwrap();
+        let before = responses.len();
+        responses.retain(|response| response.form_id != form_id);
+        let deleted = (before - responses.len()) as i64;
+        drop(responses);
+
+        self.invitations
+            .lock()
+            .unwrap()
+            .retain(|invitation| invitation.form_id != form_id);
+        self.webhooks
+            .lock()
+            .unwrap()
+            .retain(|webhook| webhook.form_id.as_deref() != Some(form_id));
+        self.templates.lock().unwrap().remove(form_id);
+
+        self.queue(&hook(&deleted));
+        Ok(Some(deleted))
+    }
+
+    async fn copy_form(
+        &self,
+        copy: &FormDefinition,
+        from_form_id: &str,
+        question_ids: &HashMap<String, String>,
+        options: CopyOptions,
+    ) -> Result<CopiedForm, AppError> {
+        self.create_form(copy).await?;
+        let to_form_id = &copy.form.id;
+
+        let mut copied = CopiedForm::default();
+        if options.responses {
+            let mut responses = self.responses.lock().unwrap();
+            let copies: Vec<StoredResponse> = responses
+                .iter()
+                .filter(|response| response.form_id == from_form_id)
+                .map(|response| {
+                    let id = Uuid::new_v4().to_string();
+                    copied.responses.insert(response.id.clone(), id.clone());
+                    StoredResponse {
+                        id,
+                        form_id: to_form_id.clone(),
+                        answers: response
+                            .answers
+                            .iter()
+                            .filter_map(|(question_id, value)| {
+                                Some((question_ids.get(question_id)?.clone(), value.clone()))
+                            })
+                            .collect(),
+                        ..response.clone()
+                    }
+                })
+                .collect();
+            responses.extend(copies);
+        }
+        if options.invitations {
+            let mut invitations = self.invitations.lock().unwrap();
+            let mut copies: Vec<Invitation> = invitations
+                .iter()
+                .filter(|invitation| invitation.form_id == from_form_id)
+                .map(|invitation| {
+                    let response_id = invitation
+                        .response_id
+                        .as_ref()
+                        .and_then(|id| copied.responses.get(id))
+                        .cloned();
+                    Invitation {
+                        id: Uuid::new_v4().to_string(),
+                        form_id: to_form_id.clone(),
+                        token: generate_token(),
+                        used_at: response_id.as_ref().and(invitation.used_at.clone()),
+                        response_id,
+                        ..invitation.clone()
+                    }
+                })
+                .collect();
+            copies.sort_by(|a, b| (&a.name, &a.email).cmp(&(&b.name, &b.email)));
+            copied.invitations = copies.len();
+            invitations.extend(copies);
+        }
+
+        Ok(copied)
+    }
+}
+
+#[async_trait]
+impl ResponseRepository for MemoryStore {
+    async fn count_responses(&self, form_id: &str) -> Result<i64, AppError> {
+        let responses = self.responses.lock().unwrap();
+        Ok(responses.iter().filter(|r| r.form_id == form_id).count() as i64)
+    }
+
+    async fn totals(&self) -> Result<Totals, AppError> {
+        let responses = self.responses.lock().unwrap();
+        let respondents: HashSet<_> = responses
+            .iter()
+            .filter_map(|r| r.email_hash.as_ref())
+            .collect();
+
+        Ok(Totals {
+            forms: self.forms.lock().unwrap().len() as i64,
+            responses: responses.len() as i64,
+            respondents: respondents.len() as i64,
+        })
+    }
+
+    async fn has_submitted(&self, form_id: &str, email_hash: &str) -> Result<bool, AppError> {
+        let responses = self.responses.lock().unwrap();
+        Ok(responses
+            .iter()
+            .any(|r| r.form_id == form_id && r.email_hash.as_deref() == Some(email_hash)))
+    }
+
+    async fn answer_counts(&self, form_id: &str) -> Result<HashMap<String, i64>, AppError> {
+        let mut counts = HashMap::new();
+        for response in self.responses.lock().unwrap().iter() {
+            if response.form_id == form_id {
+                for (question_id, _) in &response.answers {
+                    *counts.entry(question_id.clone()).or_insert(0) += 1;
+                }
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn role_distribution(&self, form_id: &str) -> Result<Vec<RoleCount>, AppError> {
+        let mut counts: Vec<RoleCount> = Vec::new();
+        for response in self.responses.lock().unwrap().iter() {
+            if response.form_id != form_id {
+                continue;
+            }
+            match counts.iter_mut().find(|c| c.role == response.role) {
+                Some(count) => count.count += 1,
+                None => counts.push(RoleCount {
+                    role: response.role.clone(),
+                    count: 1,
+                }),
+            }
+        }
+        counts.sort_by_key(|count| std::cmp::Reverse(count.count));
+        Ok(counts)
+    }
+
+    async fn likert_summaries(&self, form_id: &str) -> Result<Vec<LikertSummary>, AppError> {
+        Ok(self
+            .questions_of_type(form_id, "likert")
+            .into_iter()
+            .map(|question| {
+                let ratings = self.ratings(form_id, &question.id);
+                let respondents: HashSet<usize> = ratings.iter().map(|(index, _)| *index).collect();
+                let average_rating = (!ratings.is_empty()).then(|| {
+                    ratings
+                        .iter()
+                        .map(|(_, rating)| *rating as f64)
+                        .sum::<f64>()
+                        / ratings.len() as f64
+                });
+
+                LikertSummary {
+                    bank_question_id: question.kind.common().bank_id.clone(),
+                    question_id: question.id,
+                    question_title: question.title,
+                    response_count: respondents.len() as i64,
+                    average_rating,
+                }
+            })
+            .collect())
+    }
+
+    async fn rating_buckets(&self, form_id: &str) -> Result<Vec<RatingBucket>, AppError> {
+        let mut questions: Vec<String> = self
+            .questions_of_type(form_id, "likert")
+            .into_iter()
+            .map(|question| question.id)
+            .collect();
+        questions.sort();
+
+        let mut buckets = Vec::new();
+        for question_id in questions {
+            let mut ratings: Vec<i64> = self
+                .ratings(form_id, &question_id)
+                .into_iter()
+                .map(|(_, rating)| rating)
+                .collect();
+            ratings.sort_unstable();
+            for chunk in ratings.chunk_by(|a, b| a == b) {
+                buckets.push(RatingBucket {
+                    question_id: question_id.clone(),
+                    rating: chunk[0] as i32,
+                    count: chunk.len() as i64,
+                });
+            }
+        }
+        Ok(buckets)
+    }
+
+    async fn likert_ratings(&self, form_id: &str) -> Result<Vec<LikertRating>, AppError> {
+        let likert: HashSet<String> = self
+            .questions_of_type(form_id, "likert")
+            .into_iter()
+            .map(|question| question.id)
+            .collect();
+
+        let mut ratings = Vec::new();
+        for response in self.responses.lock().unwrap().iter() {
+            if response.form_id != form_id {
+                continue;
+            }
+            let mut answers: Vec<&(String, JsonValue)> = response
+                .answers
+                .iter()
+                .filter(|(question_id, _)| likert.contains(question_id))
+                .collect();
+            answers.sort_by(|a, b| a.0.cmp(&b.0));
+            for (question_id, value) in answers {
+                if let Some(rating) = value
+                    .as_i64()
+                    .or_else(|| value.get("rating").and_then(JsonValue::as_i64))
+                {
+                    ratings.push(LikertRating {
+                        response_id: response.id.clone(),
+                        role: response.role.clone(),
+                        question_id: question_id.clone(),
+                        rating: rating as i32,
+                    });
+                }
+            }
+        }
+        Ok(ratings)
+    }
+
+    async fn submit_response(
+        &self,
+        submission: &NewSubmission,
+        hook: &OutboxHook<'_, StoredSubmission>,
+    ) -> Result<StoredSubmission, AppError> {
+        let has_submitted = self.responses.lock().unwrap().iter().any(|r| {
+            r.form_id == submission.form_id
+                && r.email_hash.as_deref() == Some(submission.email_hash.as_str())
+        });
+        if has_submitted {
+            return Err(AppError::BadRequest(
+                "You have already submitted a response for this form".to_string(),
+            ));
+        }
+
+        let invitation = match &submission.invitation_id {
+            Some(invitation_id) => {
+                let invitations = self.invitations.lock().unwrap();
+                let index = invitations
+                    .iter()
+                    .position(|invitation| &invitation.id == invitation_id)
+                    .filter(|&index| invitations[index].used_at.is_none())
+                    .ok_or_else(|| {
+                        AppError::BadRequest(
+                            "This invitation has already been used to submit a response"
+                                .to_string(),
+                        )
+                    })?;
+                Some(index)
+            }
+            None => None,
+        };
+
+        let submitted_at = Utc::now();
+        self.add_response(StoredResponse {
+            form_id: submission.form_id.clone(),
+            role: submission.role.clone(),
+            email_hash: Some(submission.email_hash.clone()),
+            answers: submission.answers.clone(),
+            submitted_at,
+            ..Default::default()
+        });
+        self.respondents.lock().unwrap().insert(
+            submission.email_hash.clone(),
+            (
+                submission.respondent_name.clone(),
+                submission.respondent_email.clone(),
+            ),
+        );
+
+        let responses = self.responses.lock().unwrap();
+        let stored = StoredSubmission {
+            response_id: responses.last().map(|r| r.id.clone()).unwrap_or_default(),
+            respondent_id: submission.email_hash.clone(),
+            response_count: responses
+                .iter()
+                .filter(|r| r.form_id == submission.form_id)
+                .count() as i64,
+        };
+        drop(responses);
+
+        if let Some(index) = invitation {
+            let invitation = &mut self.invitations.lock().unwrap()[index];
+            invitation.used_at = Some(submitted_at.to_rfc3339());
+            invitation.response_id = Some(stored.response_id.clone());
+        }
+        self.queue(&hook(&stored));
+        Ok(stored)
+    }
+
+    async fn delete_respondent(
+        &self,
+        respondent_id: &str,
+        hook: &OutboxHook<'_, [String]>,
+    ) -> Result<Option<Vec<String>>, AppError> {
+        let mut form_ids = Vec::new();
+        for response in self.responses.lock().unwrap().iter_mut() {
+            if response.email_hash.as_deref() == Some(respondent_id) {
+                response.email_hash = None;
+                form_ids.push(response.form_id.clone());
+            }
+        }
+        if form_ids.is_empty() {
+            return Ok(None);
+        }
+        form_ids.sort();
+        form_ids.dedup();
+
+        // Scrub queued messages the way the SQL backends do
+        let email = self
+            .respondents
+            .lock()
+            .unwrap()
+            .remove(respondent_id)
+            .map(|(_, email)| email)
+            .filter(|email| !email.trim().is_empty());
+        self.outbox
+            .lock()
+            .unwrap()
+            .retain_mut(|message| match message {
+                OutboxMessage::Email(message) => email
+                    .as_ref()
+                    .is_none_or(|email| !message.html.contains(email.as_str())),
+                OutboxMessage::Webhook(delivery) => {
+                    let respondent = &delivery.data["respondent"]["respondent_id"];
+                    if respondent.as_str() == Some(respondent_id) {
+                        if let Some(data) = delivery.data.as_object_mut() {
+                            data.remove("respondent");
+                        }
+                    }
+                    true
+                }
+            });
+
+        self.queue(&hook(&form_ids));
+        Ok(Some(form_ids))
+    }
+
+    async fn list_responses(
+        &self,
+        filters: &ResponseFilters,
+        page: &PageParams,
+        include_pii: bool,
+    ) -> Result<Page<ListedResponse>, AppError> {
+        let listing = if include_pii {
+            &RESPONSE_PII_LISTING
+        } else {
+            &RESPONSE_LISTING
+        };
+        let request = listing.request(page)?;
+
+        let respondents = self.respondents.lock().unwrap();
+        let rows: Vec<(String, String, ListedResponse)> = self
+            .responses
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|response| matches(response, filters))
+            .map(|response| {
+                let respondent = response
+                    .email_hash
+                    .as_ref()
+                    .filter(|_| include_pii)
+                    .and_then(|id| {
+                        let (name, email) = respondents.get(id)?.clone();
+                        Some((id.clone(), name, email))
+                    });
+                let row = ListedResponse {
+                    id: response.id.clone(),
+                    form_id: response.form_id.clone(),
+                    respondent_id: respondent.as_ref().map(|(id, ..)| id.clone()),
+                    respondent_name: respondent.as_ref().map(|(_, name, _)| name.clone()),
+                    respondent_email: respondent.map(|(.., email)| email),
+                    role: response.role.clone(),
+                    submitted_at: response
+                        .submitted_at
+                        .format(DB_TIMESTAMP_FORMAT)
+                        .to_string(),
+                };
+                let sort_key = match request.sort() {
+                    "role" => row.role.clone(),
+                    "name" => row.respondent_name.clone(),
+                    "email" => row.respondent_email.clone(),
+                    _ => Some(row.submitted_at.clone()),
+                };
+                (sort_key.unwrap_or_default(), row.id.clone(), row)
+            })
+            .collect();
+
+        Ok(request.slice(rows))
+    }
+
+    async fn answers_by_response(
+        &self,
+        response_ids: &[&str],
+    ) -> Result<HashMap<String, Vec<AnswerWithQuestion>>, AppError> {
+        let forms = self.forms.lock().unwrap();
+        let mut answers = HashMap::new();
+        for response in self.responses.lock().unwrap().iter() {
+            if !response_ids.contains(&response.id.as_str()) {
+                continue;
+            }
+            let Some(form) = forms
+                .iter()
+                .find(|stored| stored.definition.form.id == response.form_id)
+            else {
+                continue;
+            };
+            let found: Vec<AnswerWithQuestion> = form
+                .definition
+                .questions
+                .iter()
+                .flat_map(|question| {
+                    response
+                        .answers
+                        .iter()
+                        .filter(|(question_id, _)| question_id == &question.id)
+                        .map(|(question_id, value)| AnswerWithQuestion {
+                            question_id: question_id.clone(),
+                            question_title: question.title.clone(),
+                            value: value.clone(),
+                        })
+                })
+                .collect();
+            if !found.is_empty() {
+                answers.insert(response.id.clone(), found);
+            }
+        }
+        Ok(answers)
+    }
+
+    async fn question_texts(
+        &self,
+        form_id: &str,
+        question_id: &str,
+        comments: bool,
+    ) -> Result<Vec<Option<String>>, AppError> {
+        let responses = self.responses.lock().unwrap();
+        Ok(responses
+            .iter()
+            .filter(|response| response.form_id == form_id)
+            .map(|response| {
+                let (_, value) = response.answers.iter().find(|(id, _)| id == question_id)?;
+                let text = if comments {
+                    value.get("comment")?
+                } else {
+                    value
+                };
+                text.as_str().map(str::to_string)
+            })
+            .collect())
+    }
+
+    fn export_rows<'a>(
+        &'a self,
+        form_id: &'a str,
+        include_pii: bool,
+    ) -> BoxStream<'a, Result<ExportRow, AppError>> {
+        let positions: HashMap<String, i32> = self
+            .forms
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|stored| stored.definition.form.id == form_id)
+            .flat_map(|stored| stored.definition.questions.iter())
+            .map(|question| (question.id.clone(), question.position))
+            .collect();
+        let respondents = self.respondents.lock().unwrap();
+
+        let mut responses: Vec<StoredResponse> = self
+            .responses
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|response| response.form_id == form_id)
+            .cloned()
+            .collect();
+        responses.sort_by(|a, b| (a.submitted_at, &a.id).cmp(&(b.submitted_at, &b.id)));
+
+        let mut rows = Vec::new();
+        for response in responses {
+            let respondent = response
+                .email_hash
+                .as_ref()
+                .filter(|_| include_pii)
+                .and_then(|id| respondents.get(id));
+            let row = |question_id: Option<String>, value: Option<String>| ExportRow {
+                response_id: response.id.clone(),
+                role: response.role.clone(),
+                submitted_at: response
+                    .submitted_at
+                    .format(DB_TIMESTAMP_FORMAT)
+                    .to_string(),
+                name: respondent.map(|(name, _)| name.clone()),
+                email: respondent.map(|(_, email)| email.clone()),
+                question_id,
+                value,
+            };
+
+            let mut answers = response.answers.clone();
+            answers.sort_by_key(|(question_id, _)| positions.get(question_id).copied());
+            if answers.is_empty() {
+                rows.push(Ok(row(None, None)));
+            }
+            for (question_id, value) in answers {
+                rows.push(Ok(row(Some(question_id), Some(value.to_string()))));
+            }
+        }
+
+        futures::stream::iter(rows).boxed()
+    }
+}
+
+#[async_trait]
+impl InvitationRepository for MemoryStore {
+    async fn count_invitations(&self, form_id: &str) -> Result<i64, AppError> {
+        let invitations = self.invitations.lock().unwrap();
+        Ok(invitations.iter().filter(|i| i.form_id == form_id).count() as i64)
+    }
+
+    async fn find_invitation(
+        &self,
+        form_id: &str,
+        token: &str,
+    ) -> Result<Option<Invitation>, AppError> {
+        let invitations = self.invitations.lock().unwrap();
+        Ok(invitations
+            .iter()
+            .find(|i| i.form_id == form_id && i.token == token)
+            .cloned())
+    }
+
+    async fn list_invitations(
+        &self,
+        form_id: &str,
+        filters: &InvitationFilters,
+        page: &PageParams,
+    ) -> Result<Page<Invitation>, AppError> {
+        let request = INVITATION_LISTING.request(page)?;
+
+        let rows: Vec<(String, String, Invitation)> = self
+            .invitations
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|i| i.form_id == form_id)
+            .filter(|i| filters.role.is_none() || i.role == filters.role)
+            .filter(|i| {
+                filters
+                    .responded
+                    .is_none_or(|responded| i.used_at.is_some() == responded)
+            })
+            .map(|i| {
+                let sort_key = match request.sort() {
+                    "email" => i.email.clone(),
+                    "role" => i.role.clone().unwrap_or_default(),
+                    "created_at" => i.created_at.clone(),
+                    _ => i.name.clone(),
+                };
+                (sort_key, i.id.clone(), i.clone())
+            })
+            .collect();
+
+        Ok(request.slice(rows))
+    }
+
+    async fn create_invitations(&self, invitations: &[Invitation]) -> Result<Vec<bool>, AppError> {
+        let mut stored = self.invitations.lock().unwrap();
+        Ok(invitations
+            .iter()
+            .map(|invitation| {
+                let taken = stored
+                    .iter()
+                    .any(|i| i.form_id == invitation.form_id && i.email == invitation.email);
+                if !taken {
+                    stored.push(Invitation {
+                        created_at: Utc::now().format(DB_TIMESTAMP_FORMAT).to_string(),
+                        ..invitation.clone()
+                    });
+                }
+                !taken
+            })
+            .collect())
+    }
+
+    async fn delete_invitation(&self, invitation_id: &str) -> Result<bool, AppError> {
+        let mut invitations = self.invitations.lock().unwrap();
+        let before = invitations.len();
+        invitations.retain(|i| i.id != invitation_id);
+        Ok(invitations.len() < before)
+    }
+
+    async fn response_rates(&self, form_id: &str) -> Result<Vec<RoleResponseRate>, AppError> {
+        let mut rates: BTreeMap<Option<String>, (i64, i64)> = BTreeMap::new();
+        for invitation in self.invitations.lock().unwrap().iter() {
+            if invitation.form_id == form_id {
+                let (invited, responded) = rates.entry(invitation.role.clone()).or_default();
+                *invited += 1;
+                *responded += invitation.used_at.is_some() as i64;
+            }
+        }
+
+        Ok(rates
+            .into_iter()
+            .map(|(role, (invited, responded))| RoleResponseRate {
+                role,
+                invited,
+                responded,
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for MemoryStore {
+    async fn find_template(&self, form_id: &str) -> Result<Option<NotificationTemplate>, AppError> {
+        Ok(self.templates.lock().unwrap().get(form_id).cloned())
+    }
+
+    async fn save_template(&self, template: &NotificationTemplate) -> Result<(), AppError> {
+        self.templates
+            .lock()
+            .unwrap()
+            .insert(template.form_id.clone(), template.clone());
+        Ok(())
+    }
+}
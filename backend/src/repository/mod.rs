@@ -0,0 +1,807 @@
+//! Storage behind backend-neutral traits.
+//!
+//! Handlers talk to these repositories instead of a pool, so the same code runs on SQLite
+//! or, with the `postgres` feature, on PostgreSQL. Each backend owns its SQL (the JSON
+//! functions differ) and its migrations.
+//!
+//! A write that must not be lost without its emails and webhooks takes the outbox events
+//! to queue, or an [`OutboxHook`] building them from what was stored, and the backend
+//! queues them in the write's own transaction.
+
+use std::{collections::HashMap, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use sqlx::FromRow;
+
+use crate::{
+    db::Database,
+    email::EmailMessage,
+    error::AppError,
+    filters::ResponseFilters,
+    models::{
+        AnonymousStats, AnswerWithQuestion, BankQuestion, DigestRun, DigestSubscription,
+        FormTemplate, Invitation, NotificationTemplate, OutboxEntry, QuestionStat, RatingCount,
+        ReminderCampaign, RoleCount, RoleResponseRate, Webhook,
+    },
+    outbox::OutboxEvent,
+    pagination::{Page, PageParams},
+    questions::QuestionKind,
+};
+
+#[cfg(test)]
+pub mod memory;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod sqlite;
+
+#[cfg(test)]
+mod tests;
+
+/// A row of the form list
+#[derive(Debug, Clone, Serialize, FromRow, PartialEq)]
+pub struct FormSummary {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub instructions: Option<String>,
+    pub status: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, PartialEq)]
+pub struct Form {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub instructions: Option<String>,
+    pub status: String,
+    pub welcome_message: Option<String>,
+    pub closing_message: Option<String>,
+    pub settings: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, PartialEq)]
+pub struct Section {
+    pub id: String,
+    pub form_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub position: i32,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Question {
+    pub id: String,
+    pub form_id: String,
+    pub section_id: Option<String>,
+    pub position: i32,
+    pub title: String,
+    pub description: Option<String>,
+    /// Serialized as the `type` and `features` fields
+    #[serde(flatten)]
+    pub kind: QuestionKind,
+}
+
+/// A `questions` row before its type and features are checked
+#[derive(FromRow)]
+pub(crate) struct QuestionRow {
+    id: String,
+    form_id: String,
+    section_id: Option<String>,
+    position: i32,
+    #[sqlx(rename = "type")]
+    question_type: String,
+    title: String,
+    description: Option<String>,
+    features: Option<JsonValue>,
+}
+
+impl TryFrom<QuestionRow> for Question {
+    type Error = AppError;
+
+    fn try_from(row: QuestionRow) -> Result<Self, AppError> {
+        let features = row
+            .features
+            .unwrap_or_else(|| JsonValue::Object(Default::default()));
+        let kind = QuestionKind::from_parts(&row.question_type, &features).map_err(|e| {
+            AppError::InternalError(format!("Question {} is stored with {}", row.id, e))
+        })?;
+
+        Ok(Self {
+            id: row.id,
+            form_id: row.form_id,
+            section_id: row.section_id,
+            position: row.position,
+            title: row.title,
+            description: row.description,
+            kind,
+        })
+    }
+}
+
+/// Decode question rows, failing on the first one with an unusable type or features
+pub(crate) fn questions_from_rows(rows: Vec<QuestionRow>) -> Result<Vec<Question>, AppError> {
+    rows.into_iter().map(Question::try_from).collect()
+}
+
+/// A form with its sections and questions, both in position order
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormDefinition {
+    pub form: Form,
+    pub sections: Vec<Section>,
+    pub questions: Vec<Question>,
+}
+
+#[async_trait]
+pub trait FormRepository: Send + Sync {
+    /// Every form, most recently updated first
+    async fn list_forms(&self) -> Result<Vec<FormSummary>, AppError>;
+
+    async fn find_form(&self, form_id: &str) -> Result<Option<FormDefinition>, AppError>;
+
+    async fn form_exists(&self, form_id: &str) -> Result<bool, AppError>;
+
+    /// Insert a form, its sections and its questions in one transaction
+    async fn create_form(&self, definition: &FormDefinition) -> Result<(), AppError>;
+
+    /// Insert questions into an existing form and bump its `updated_at`, in one transaction
+    async fn add_questions(&self, form_id: &str, questions: &[Question]) -> Result<(), AppError>;
+
+    /// Overwrite a form's metadata and replace its sections and questions, queueing `events`
+    /// in the same transaction. Returns false when the form does not exist.
+    async fn replace_form(
+        &self,
+        definition: &FormDefinition,
+        updated_at: DateTime<Utc>,
+        events: &[OutboxEvent],
+    ) -> Result<bool, AppError>;
+
+    /// Set a form's status and queue what `hook` builds from the previous one. Returns the
+    /// previous status, or `None` when the form does not exist.
+    async fn set_status(
+        &self,
+        form_id: &str,
+        status: &str,
+        updated_at: DateTime<Utc>,
+        hook: &OutboxHook<'_, str>,
+    ) -> Result<Option<String>, AppError>;
+
+    /// Delete a form with its responses, invitations, campaigns, webhooks, digests and
+    /// search entries, and queue what `hook` builds from the number of responses deleted.
+    /// Returns that number, or `None` when the form does not exist.
+    async fn delete_form(
+        &self,
+        form_id: &str,
+        hook: &OutboxHook<'_, i64>,
+    ) -> Result<Option<i64>, AppError>;
+
+    /// Insert `copy` of the form `from_form_id`, with whatever `options` brings along, in
+    /// one transaction. `question_ids` maps each original question ID to its copy's.
+    async fn copy_form(
+        &self,
+        copy: &FormDefinition,
+        from_form_id: &str,
+        question_ids: &HashMap<String, String>,
+        options: CopyOptions,
+    ) -> Result<CopiedForm, AppError>;
+}
+
+/// What a form copy brings along besides the structure
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CopyOptions {
+    /// The submitted responses and their answers, indexed for search
+    pub responses: bool,
+    /// The invitation list, with new tokens. Invitations stay used only when their
+    /// response is copied too.
+    pub invitations: bool,
+}
+
+/// What a form copy brought along
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CopiedForm {
+    /// Original response IDs mapped to the IDs of their copies
+    pub responses: HashMap<String, String>,
+    pub invitations: usize,
+}
+
+/// Builds the outbox events for a write from what it stored, inside its transaction
+pub type OutboxHook<'a, T> = dyn Fn(&T) -> Vec<OutboxEvent> + Send + Sync + 'a;
+
+/// Per-question figures for likert questions, in question order
+#[derive(Debug, Clone, FromRow)]
+pub struct LikertSummary {
+    pub question_id: String,
+    pub question_title: String,
+    /// The `bankId` feature, shared with the same question on other forms
+    pub bank_question_id: Option<String>,
+    pub response_count: i64,
+    pub average_rating: Option<f64>,
+}
+
+/// How many answers gave a question each rating
+#[derive(Debug, Clone, FromRow)]
+pub struct RatingBucket {
+    pub question_id: String,
+    pub rating: i32,
+    pub count: i64,
+}
+
+/// One likert rating with the response it came from, for analyses that need raw values
+#[derive(Debug, Clone, FromRow, PartialEq)]
+pub struct LikertRating {
+    pub response_id: String,
+    pub role: Option<String>,
+    pub question_id: String,
+    pub rating: i32,
+}
+
+/// Row counts for the admin dashboard
+#[derive(Debug, Clone, Copy, Default, FromRow, PartialEq)]
+pub struct Totals {
+    pub forms: i64,
+    pub responses: i64,
+    pub respondents: i64,
+}
+
+/// A checked submission, ready to store. The PII goes to `respondents` only.
+#[derive(Debug, Clone)]
+pub struct NewSubmission {
+    pub form_id: String,
+    pub respondent_name: String,
+    pub respondent_email: String,
+    /// Salted hash of the email, used to find a returning respondent
+    pub email_hash: String,
+    pub role: Option<String>,
+    /// `(question_id, value)` pairs
+    pub answers: Vec<(String, JsonValue)>,
+    /// Invitation spent on this response; a submission racing for it fails
+    pub invitation_id: Option<String>,
+}
+
+/// IDs given to a stored submission
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredSubmission {
+    pub response_id: String,
+    pub respondent_id: String,
+    /// Responses to the form, this one included
+    pub response_count: i64,
+}
+
+/// A row of the response listings. The respondent fields are `None` once their PII is
+/// deleted, and always for listings without PII.
+#[derive(Debug, Clone, FromRow, PartialEq)]
+pub struct ListedResponse {
+    pub id: String,
+    pub form_id: String,
+    pub respondent_id: Option<String>,
+    pub respondent_name: Option<String>,
+    pub respondent_email: Option<String>,
+    pub role: Option<String>,
+    pub submitted_at: String,
+}
+
+#[async_trait]
+pub trait ResponseRepository: Send + Sync {
+    async fn count_responses(&self, form_id: &str) -> Result<i64, AppError>;
+
+    async fn totals(&self) -> Result<Totals, AppError>;
+
+    /// Whether the respondent with this email hash has answered the form
+    async fn has_submitted(&self, form_id: &str, email_hash: &str) -> Result<bool, AppError>;
+
+    /// Stored answers per question ID, for questions with at least one
+    async fn answer_counts(&self, form_id: &str) -> Result<HashMap<String, i64>, AppError>;
+
+    /// Responses per role, largest group first
+    async fn role_distribution(&self, form_id: &str) -> Result<Vec<RoleCount>, AppError>;
+
+    async fn likert_summaries(&self, form_id: &str) -> Result<Vec<LikertSummary>, AppError>;
+
+    /// Rating counts for every likert question, ordered by question and rating
+    async fn rating_buckets(&self, form_id: &str) -> Result<Vec<RatingBucket>, AppError>;
+
+    /// Every likert rating given on the form, ordered by response and question
+    async fn likert_ratings(&self, form_id: &str) -> Result<Vec<LikertRating>, AppError>;
+
+    /// Store a response and its answers in one transaction, under the respondent with the
+    /// same email hash or a new one, spend its invitation and queue what `hook` builds.
+    /// Fails if that respondent already answered the form or the invitation was used.
+    async fn submit_response(
+        &self,
+        submission: &NewSubmission,
+        hook: &OutboxHook<'_, StoredSubmission>,
+    ) -> Result<StoredSubmission, AppError>;
+
+    /// Delete a respondent's name and email, keeping their responses unlinked, and scrub
+    /// them from queued messages. Queues what `hook` builds from the forms they answered
+    /// and returns those, or `None` when there is no such respondent.
+    async fn delete_respondent(
+        &self,
+        respondent_id: &str,
+        hook: &OutboxHook<'_, [String]>,
+    ) -> Result<Option<Vec<String>>, AppError>;
+
+    /// One page of the responses matching `filters`. With `include_pii` the rows carry the
+    /// respondent's details and can be sorted by `name` and `email` too.
+    async fn list_responses(
+        &self,
+        filters: &ResponseFilters,
+        page: &PageParams,
+        include_pii: bool,
+    ) -> Result<Page<ListedResponse>, AppError>;
+
+    /// Answers of the given responses with their question titles, grouped by response ID
+    /// in question order
+    async fn answers_by_response(
+        &self,
+        response_ids: &[&str],
+    ) -> Result<HashMap<String, Vec<AnswerWithQuestion>>, AppError>;
+
+    /// Text of a question's answers, one entry per response, `None` where the response has
+    /// no such answer. With `comments`, the comments on structured answers instead of text
+    /// answers.
+    async fn question_texts(
+        &self,
+        form_id: &str,
+        question_id: &str,
+        comments: bool,
+    ) -> Result<Vec<Option<String>>, AppError>;
+
+    /// Every answer of the form's responses, by submission then question position, as a
+    /// stream so exports of any size run in constant memory
+    fn export_rows<'a>(
+        &'a self,
+        form_id: &'a str,
+        include_pii: bool,
+    ) -> BoxStream<'a, Result<ExportRow, AppError>>;
+
+    /// Anonymous statistics assembled from the queries above
+    async fn form_stats(&self, form_id: &str) -> Result<AnonymousStats, AppError> {
+        let total_responses = self.count_responses(form_id).await?;
+        let role_distribution = self.role_distribution(form_id).await?;
+        let summaries = self.likert_summaries(form_id).await?;
+
+        let mut distributions: HashMap<String, Vec<RatingCount>> = HashMap::new();
+        for bucket in self.rating_buckets(form_id).await? {
+            distributions
+                .entry(bucket.question_id)
+                .or_default()
+                .push(RatingCount {
+                    rating: bucket.rating,
+                    count: bucket.count,
+                });
+        }
+
+        let question_stats = summaries
+            .into_iter()
+            .map(|summary| QuestionStat {
+                rating_distribution: distributions
+                    .remove(&summary.question_id)
+                    .unwrap_or_default(),
+                question_id: summary.question_id,
+                question_title: summary.question_title,
+                bank_question_id: summary.bank_question_id,
+                response_count: summary.response_count,
+                average_rating: summary.average_rating,
+            })
+            .collect();
+
+        Ok(AnonymousStats {
+            form_id: form_id.to_string(),
+            total_responses,
+            role_distribution,
+            question_stats,
+        })
+    }
+}
+
+/// One answer of a response for the export, or the response alone when it has none
+#[derive(Debug, Clone, FromRow, PartialEq)]
+pub struct ExportRow {
+    pub response_id: String,
+    pub role: Option<String>,
+    pub submitted_at: String,
+    /// Respondent details, `None` unless the export includes PII
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub question_id: Option<String>,
+    /// The answer value as JSON text
+    pub value: Option<String>,
+}
+
+/// Filters of the invitation listing
+#[derive(Debug, Clone, Default)]
+pub struct InvitationFilters {
+    pub role: Option<String>,
+    /// Only invitations that were, or were not, used for a response
+    pub responded: Option<bool>,
+}
+
+#[async_trait]
+pub trait InvitationRepository: Send + Sync {
+    /// How many invitations a form has; forms without any are open to anyone
+    async fn count_invitations(&self, form_id: &str) -> Result<i64, AppError>;
+
+    async fn find_invitation(
+        &self,
+        form_id: &str,
+        token: &str,
+    ) -> Result<Option<Invitation>, AppError>;
+
+    /// One page of a form's invitations, by name unless sorted otherwise
+    async fn list_invitations(
+        &self,
+        form_id: &str,
+        filters: &InvitationFilters,
+        page: &PageParams,
+    ) -> Result<Page<Invitation>, AppError>;
+
+    /// Insert unused invitations in one transaction, skipping emails the form already
+    /// invited. Returns whether each one was inserted.
+    async fn create_invitations(&self, invitations: &[Invitation]) -> Result<Vec<bool>, AppError>;
+
+    /// Returns false when there is no such invitation
+    async fn delete_invitation(&self, invitation_id: &str) -> Result<bool, AppError>;
+
+    /// Invited and responded counts per role, ordered by role with no role first
+    async fn response_rates(&self, form_id: &str) -> Result<Vec<RoleResponseRate>, AppError>;
+}
+
+#[async_trait]
+pub trait NotificationRepository: Send + Sync {
+    /// The form's saved new-response template, if it has one
+    async fn find_template(&self, form_id: &str) -> Result<Option<NotificationTemplate>, AppError>;
+
+    /// Create or replace the template for `template.form_id`
+    async fn save_template(&self, template: &NotificationTemplate) -> Result<(), AppError>;
+}
+
+#[async_trait]
+pub trait ReminderRepository: Send + Sync {
+    /// Insert a campaign; its `created_at` is set by the database
+    async fn create_campaign(&self, campaign: &ReminderCampaign) -> Result<(), AppError>;
+
+    async fn find_campaign(&self, campaign_id: &str) -> Result<Option<ReminderCampaign>, AppError>;
+
+    async fn active_campaigns(&self) -> Result<Vec<ReminderCampaign>, AppError>;
+
+    /// One page of a form's campaigns, newest first unless sorted otherwise
+    async fn list_campaigns(
+        &self,
+        form_id: &str,
+        page: &PageParams,
+    ) -> Result<Page<ReminderCampaign>, AppError>;
+
+    /// Reminders queued so far per campaign of the form, for campaigns with any
+    async fn send_counts(&self, form_id: &str) -> Result<HashMap<String, i64>, AppError>;
+
+    /// Overwrite a campaign's template, schedule and active flag. Returns false when it
+    /// does not exist.
+    async fn update_campaign(&self, campaign: &ReminderCampaign) -> Result<bool, AppError>;
+
+    /// Delete a campaign with its send history. Returns false when it does not exist.
+    async fn delete_campaign(&self, campaign_id: &str) -> Result<bool, AppError>;
+
+    /// Unused invitations of the campaign's form not yet reminded for the step, by name
+    async fn pending_recipients(
+        &self,
+        campaign: &ReminderCampaign,
+        days_before: i64,
+    ) -> Result<Vec<Invitation>, AppError>;
+
+    /// In one transaction, claim each `(invitation_id, email)` for the step and queue its
+    /// email, skipping invitations an overlapping run claimed first. Returns how many
+    /// emails were queued.
+    async fn queue_reminders(
+        &self,
+        campaign_id: &str,
+        days_before: i64,
+        reminders: &[(String, EmailMessage)],
+    ) -> Result<usize, AppError>;
+}
+
+/// A digest about to be queued, with the figures it reports
+#[derive(Debug, Clone)]
+pub struct NewDigestRun {
+    pub subscription_id: String,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub new_responses: i64,
+    pub total_responses: i64,
+    pub average_score: Option<f64>,
+}
+
+#[async_trait]
+pub trait DigestRepository: Send + Sync {
+    /// Insert a subscription; its `created_at` is set by the database. Returns false when
+    /// the email is already subscribed to the form.
+    async fn create_subscription(
+        &self,
+        subscription: &DigestSubscription,
+    ) -> Result<bool, AppError>;
+
+    async fn find_subscription(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Option<DigestSubscription>, AppError>;
+
+    async fn all_subscriptions(&self) -> Result<Vec<DigestSubscription>, AppError>;
+
+    /// One page of a form's subscriptions, by email unless sorted otherwise
+    async fn list_subscriptions(
+        &self,
+        form_id: &str,
+        page: &PageParams,
+    ) -> Result<Page<DigestSubscription>, AppError>;
+
+    /// The latest run of each subscription to the form
+    async fn last_runs(&self, form_id: &str) -> Result<Vec<DigestRun>, AppError>;
+
+    /// Delete a subscription with its runs. Returns false when it does not exist.
+    async fn delete_subscription(&self, subscription_id: &str) -> Result<bool, AppError>;
+
+    /// Where the subscription's latest run ended, if it has one
+    async fn last_period_end(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, AppError>;
+
+    /// Responses submitted in `(start, end]`, and all responses up to `end`
+    async fn response_counts(
+        &self,
+        form_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(i64, i64), AppError>;
+
+    /// Mean likert rating over the responses submitted up to `until`
+    async fn average_rating(
+        &self,
+        form_id: &str,
+        until: DateTime<Utc>,
+    ) -> Result<Option<f64>, AppError>;
+
+    /// Record a run and queue its email in one transaction. The period is claimed first,
+    /// so this returns false and queues nothing when another run already covered it.
+    async fn record_run(
+        &self,
+        run: &NewDigestRun,
+        message: &EmailMessage,
+    ) -> Result<bool, AppError>;
+}
+
+#[async_trait]
+pub trait WebhookRepository: Send + Sync {
+    /// Insert an endpoint; its `created_at` is set by the database
+    async fn create_webhook(&self, webhook: &Webhook) -> Result<(), AppError>;
+
+    async fn find_webhook(&self, webhook_id: &str) -> Result<Option<Webhook>, AppError>;
+
+    /// One page of endpoints, only those for `form_id` when given
+    async fn list_webhooks(
+        &self,
+        form_id: Option<&str>,
+        page: &PageParams,
+    ) -> Result<Page<Webhook>, AppError>;
+
+    /// Returns false when there is no such endpoint
+    async fn delete_webhook(&self, webhook_id: &str) -> Result<bool, AppError>;
+}
+
+#[async_trait]
+pub trait OutboxRepository: Send + Sync {
+    /// Up to `limit` pending entries whose next attempt is due, oldest first
+    async fn due_entries(&self, limit: i64) -> Result<Vec<OutboxEntry>, AppError>;
+
+    /// Count the attempt and mark the entry delivered now
+    async fn record_delivered(&self, entry_id: &str) -> Result<(), AppError>;
+
+    /// Store a failed attempt, with the next one `retry_in_secs` from now
+    async fn record_failure(
+        &self,
+        entry_id: &str,
+        status: &str,
+        attempts: i64,
+        error: &str,
+        retry_in_secs: i64,
+    ) -> Result<(), AppError>;
+
+    /// Delete entries delivered more than `days` ago
+    async fn prune_delivered(&self, days: i64) -> Result<(), AppError>;
+
+    async fn find_entry(&self, entry_id: &str) -> Result<Option<OutboxEntry>, AppError>;
+
+    /// One page of entries, newest first unless sorted otherwise
+    async fn list_entries(
+        &self,
+        status: Option<&str>,
+        kind: Option<&str>,
+        page: &PageParams,
+    ) -> Result<Page<OutboxEntry>, AppError>;
+
+    /// Make an entry pending and due now, with a fresh attempt budget. Returns false when
+    /// it does not exist.
+    async fn requeue(&self, entry_id: &str) -> Result<bool, AppError>;
+
+    /// Returns false when there is no such entry
+    async fn discard(&self, entry_id: &str) -> Result<bool, AppError>;
+}
+
+/// One word of a search; with `prefix`, words starting with it match too
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchTerm {
+    pub word: String,
+    pub prefix: bool,
+}
+
+/// An indexed answer matching a search
+#[derive(Debug, Clone, FromRow)]
+pub struct SearchMatch {
+    pub response_id: String,
+    pub question_id: String,
+    pub question_title: Option<String>,
+    /// `text` for a free-text answer, `comment` for the comment on a structured answer
+    pub field: String,
+    /// Excerpt around the matches, which are wrapped in `search::MATCH_START` and
+    /// `search::MATCH_END`
+    pub snippet: String,
+    pub role: Option<String>,
+    pub submitted_at: String,
+    pub respondent_name: Option<String>,
+    pub respondent_email: Option<String>,
+}
+
+#[async_trait]
+pub trait SearchRepository: Send + Sync {
+    /// Indexed answers of the responses matching `filters` that contain every term, best
+    /// matches first, with the total number of matches. The respondent fields are only
+    /// filled with `include_pii`.
+    async fn search_answers(
+        &self,
+        terms: &[SearchTerm],
+        filters: &ResponseFilters,
+        limit: i64,
+        include_pii: bool,
+    ) -> Result<(i64, Vec<SearchMatch>), AppError>;
+}
+
+#[async_trait]
+pub trait TemplateRepository: Send + Sync {
+    /// Saved templates by name, oldest first among equal names
+    async fn list_templates(&self) -> Result<Vec<FormTemplate>, AppError>;
+
+    async fn get_template(&self, template_id: &str) -> Result<Option<FormTemplate>, AppError>;
+
+    async fn create_template(&self, template: &FormTemplate) -> Result<(), AppError>;
+
+    /// Returns false when there is no such template
+    async fn delete_template(&self, template_id: &str) -> Result<bool, AppError>;
+}
+
+/// How many bank questions carry a tag
+#[derive(Debug, Clone, Serialize, FromRow, PartialEq)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// A question on some form, as found by `bankId` or by title
+#[derive(Debug, Clone, Serialize, FromRow, PartialEq)]
+pub struct FormQuestion {
+    pub form_id: String,
+    pub form_title: String,
+    pub review_period: Option<String>,
+    pub question_id: String,
+    pub question_title: String,
+}
+
+#[async_trait]
+pub trait QuestionBankRepository: Send + Sync {
+    /// Bank questions by title, only those with `tag` or whose title contains `search`
+    /// when given
+    async fn list_bank_questions(
+        &self,
+        tag: Option<&str>,
+        search: Option<&str>,
+    ) -> Result<Vec<BankQuestion>, AppError>;
+
+    /// Every tag in use, alphabetically
+    async fn tag_counts(&self) -> Result<Vec<TagCount>, AppError>;
+
+    async fn find_bank_question(&self, question_id: &str)
+        -> Result<Option<BankQuestion>, AppError>;
+
+    /// Returns false when the ID is taken
+    async fn create_bank_question(&self, question: &BankQuestion) -> Result<bool, AppError>;
+
+    /// Overwrite everything but the ID and `created_at`. Returns false when the question
+    /// does not exist.
+    async fn update_bank_question(&self, question: &BankQuestion) -> Result<bool, AppError>;
+
+    /// Returns false when there is no such question
+    async fn delete_bank_question(&self, question_id: &str) -> Result<bool, AppError>;
+
+    /// Questions of the type with exactly this title, ignoring surrounding whitespace, and
+    /// no `bankId` yet; oldest form first
+    async fn unlinked_questions(
+        &self,
+        question_type: &str,
+        title: &str,
+    ) -> Result<Vec<FormQuestion>, AppError>;
+
+    /// Questions whose `bankId` is this bank question, oldest form first
+    async fn bank_question_uses(&self, bank_id: &str) -> Result<Vec<FormQuestion>, AppError>;
+
+    /// Set `bankId` on the questions and bump their forms' `updated_at`, in one transaction
+    async fn link_questions(
+        &self,
+        bank_id: &str,
+        questions: &[FormQuestion],
+    ) -> Result<(), AppError>;
+}
+
+/// The repositories for one database, shared through `AppState`
+#[derive(Clone)]
+pub struct Repositories {
+    pub forms: Arc<dyn FormRepository>,
+    pub responses: Arc<dyn ResponseRepository>,
+    pub invitations: Arc<dyn InvitationRepository>,
+    pub notifications: Arc<dyn NotificationRepository>,
+    pub reminders: Arc<dyn ReminderRepository>,
+    pub digests: Arc<dyn DigestRepository>,
+    pub webhooks: Arc<dyn WebhookRepository>,
+    pub outbox: Arc<dyn OutboxRepository>,
+    pub search: Arc<dyn SearchRepository>,
+    pub templates: Arc<dyn TemplateRepository>,
+    pub question_bank: Arc<dyn QuestionBankRepository>,
+}
+
+impl Repositories {
+    pub fn new(database: &Database) -> Self {
+        match database {
+            Database::Sqlite(pool) => {
+                Self::from_store(Arc::new(sqlite::SqliteStore::new(pool.clone())))
+            }
+            #[cfg(feature = "postgres")]
+            Database::Postgres(pool) => {
+                Self::from_store(Arc::new(postgres::PgStore::new(pool.clone())))
+            }
+        }
+    }
+
+    /// Every repository served by one store
+    fn from_store<S>(store: Arc<S>) -> Self
+    where
+        S: FormRepository
+            + ResponseRepository
+            + InvitationRepository
+            + NotificationRepository
+            + ReminderRepository
+            + DigestRepository
+            + WebhookRepository
+            + OutboxRepository
+            + SearchRepository
+            + TemplateRepository
+            + QuestionBankRepository
+            + 'static,
+    {
+        Self {
+            forms: store.clone(),
+            responses: store.clone(),
+            invitations: store.clone(),
+            notifications: store.clone(),
+            reminders: store.clone(),
+            digests: store.clone(),
+            webhooks: store.clone(),
+            outbox: store.clone(),
+            search: store.clone(),
+            templates: store.clone(),
+            question_bank: store,
+        }
+    }
+}
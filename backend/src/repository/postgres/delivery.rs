@@ -0,0 +1,252 @@
+//! Webhook endpoints and the outbox their events are delivered through
+
+use async_trait::async_trait;
+
+use super::{fetch_page, PgStore};
+use crate::{
+    error::AppError,
+    models::{OutboxEntry, Webhook},
+    pagination::{Listing, Page, PageParams, SortField},
+    repository::{OutboxRepository, WebhookRepository},
+};
+
+/// Columns of [`Webhook`], with its timestamp as RFC 3339 text
+const WEBHOOK_COLUMNS: &str = r#"id, form_id, url, secret, events, include_pii, active,
+    to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS created_at"#;
+
+/// Columns of [`OutboxEntry`], with its timestamps as RFC 3339 text
+const OUTBOX_COLUMNS: &str = r#"id, kind, payload, status, attempts::int8 AS attempts,
+    to_char(next_attempt_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS next_attempt_at,
+    last_error,
+    to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS created_at,
+    to_char(delivered_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS delivered_at"#;
+
+static WEBHOOK_LISTING: Listing = Listing {
+    id_column: "id",
+    sorts: &[
+        SortField {
+            name: "created_at",
+            column: r#"to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"')"#,
+        },
+        SortField {
+            name: "url",
+            column: "url",
+        },
+    ],
+    default_sort: "created_at",
+};
+
+static OUTBOX_LISTING: Listing = Listing {
+    id_column: "id",
+    sorts: &[
+        SortField {
+            name: "created_at",
+            column: r#"to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"')"#,
+        },
+        SortField {
+            name: "next_attempt_at",
+            column: r#"to_char(next_attempt_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"')"#,
+        },
+    ],
+    default_sort: "-created_at",
+};
+
+#[async_trait]
+impl WebhookRepository for PgStore {
+    async fn create_webhook(&self, webhook: &Webhook) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO webhooks (id, form_id, url, secret, events, include_pii, active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(&webhook.id)
+        .bind(&webhook.form_id)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(&webhook.events)
+        .bind(webhook.include_pii)
+        .bind(webhook.active)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn find_webhook(&self, webhook_id: &str) -> Result<Option<Webhook>, AppError> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM webhooks WHERE id = $1",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(webhook_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn list_webhooks(
+        &self,
+        form_id: Option<&str>,
+        page: &PageParams,
+    ) -> Result<Page<Webhook>, AppError> {
+        fetch_page(
+            &self.db,
+            &WEBHOOK_LISTING.request(page)?,
+            WEBHOOK_COLUMNS,
+            "webhooks",
+            |query| {
+                if let Some(form_id) = form_id {
+                    query.push(" AND form_id = ").push_bind(form_id.to_string());
+                }
+            },
+        )
+        .await
+    }
+
+    async fn delete_webhook(&self, webhook_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = $1")
+            .bind(webhook_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for PgStore {
+    async fn due_entries(&self, limit: i64) -> Result<Vec<OutboxEntry>, AppError> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM outbox
+            WHERE status = 'pending' AND next_attempt_at <= now()
+            ORDER BY next_attempt_at, created_at
+            LIMIT $1
+            "#,
+            OUTBOX_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn record_delivered(&self, entry_id: &str) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE outbox
+            SET status = 'delivered', attempts = attempts + 1, last_error = NULL,
+                delivered_at = now()
+            WHERE id = $1
+            "#,
+        )
+        .bind(entry_id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn record_failure(
+        &self,
+        entry_id: &str,
+        status: &str,
+        attempts: i64,
+        error: &str,
+        retry_in_secs: i64,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE outbox
+            SET status = $1, attempts = $2, last_error = $3,
+                next_attempt_at = now() + $4 * interval '1 second'
+            WHERE id = $5
+            "#,
+        )
+        .bind(status)
+        .bind(attempts)
+        .bind(error)
+        .bind(retry_in_secs)
+        .bind(entry_id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn prune_delivered(&self, days: i64) -> Result<(), AppError> {
+        sqlx::query(
+            "DELETE FROM outbox WHERE status = 'delivered' AND delivered_at < now() - $1 * interval '1 day'",
+        )
+        .bind(days)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn find_entry(&self, entry_id: &str) -> Result<Option<OutboxEntry>, AppError> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM outbox WHERE id = $1",
+            OUTBOX_COLUMNS
+        ))
+        .bind(entry_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn list_entries(
+        &self,
+        status: Option<&str>,
+        kind: Option<&str>,
+        page: &PageParams,
+    ) -> Result<Page<OutboxEntry>, AppError> {
+        fetch_page(
+            &self.db,
+            &OUTBOX_LISTING.request(page)?,
+            OUTBOX_COLUMNS,
+            "outbox",
+            |query| {
+                if let Some(status) = status {
+                    query.push(" AND status = ").push_bind(status.to_string());
+                }
+                if let Some(kind) = kind {
+                    query.push(" AND kind = ").push_bind(kind.to_string());
+                }
+            },
+        )
+        .await
+    }
+
+    async fn requeue(&self, entry_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE outbox
+            SET status = 'pending', attempts = 0, next_attempt_at = now()
+            WHERE id = $1
+            "#,
+        )
+        .bind(entry_id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn discard(&self, entry_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("UPDATE outbox SET status = 'discarded' WHERE id = $1")
+            .bind(entry_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,260 @@
+//! Saved form templates and the question bank
+
+use async_trait::async_trait;
+
+use super::PgStore;
+use crate::{
+    error::AppError,
+    models::{BankQuestion, FormTemplate},
+    repository::{FormQuestion, QuestionBankRepository, TagCount, TemplateRepository},
+};
+
+/// Columns of [`FormTemplate`], with its timestamp as RFC 3339 text
+const TEMPLATE_COLUMNS: &str = r#"id, name, description, category, source_form_id, definition,
+    to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS created_at"#;
+
+/// Columns of [`BankQuestion`], with its timestamps as RFC 3339 text
+const BANK_QUESTION_COLUMNS: &str = r#"id, question_type, title, features, tags,
+    to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS created_at,
+    to_char(updated_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS updated_at"#;
+
+/// Columns of [`FormQuestion`] over `questions q JOIN forms f`
+const FORM_QUESTION_COLUMNS: &str = r#"
+    f.id as form_id,
+    f.title as form_title,
+    f.settings ->> 'reviewPeriod' as review_period,
+    q.id as question_id,
+    q.title as question_title
+"#;
+
+#[async_trait]
+impl TemplateRepository for PgStore {
+    async fn list_templates(&self) -> Result<Vec<FormTemplate>, AppError> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM form_templates ORDER BY name, created_at",
+            TEMPLATE_COLUMNS
+        ))
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn get_template(&self, template_id: &str) -> Result<Option<FormTemplate>, AppError> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM form_templates WHERE id = $1",
+            TEMPLATE_COLUMNS
+        ))
+        .bind(template_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn create_template(&self, template: &FormTemplate) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO form_templates (id, name, description, category, source_form_id, definition, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7::timestamptz, now()))
+            "#,
+        )
+        .bind(&template.id)
+        .bind(&template.name)
+        .bind(&template.description)
+        .bind(&template.category)
+        .bind(&template.source_form_id)
+        .bind(&template.definition)
+        .bind(&template.created_at)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn delete_template(&self, template_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM form_templates WHERE id = $1")
+            .bind(template_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl QuestionBankRepository for PgStore {
+    async fn list_bank_questions(
+        &self,
+        tag: Option<&str>,
+        search: Option<&str>,
+    ) -> Result<Vec<BankQuestion>, AppError> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {}
+            FROM question_bank
+            WHERE ($1::text IS NULL OR tags ? $1)
+                AND ($2::text IS NULL OR strpos(lower(title), lower($2)) > 0)
+            ORDER BY title, id
+            "#,
+            BANK_QUESTION_COLUMNS
+        ))
+        .bind(tag)
+        .bind(search)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn tag_counts(&self) -> Result<Vec<TagCount>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT tag, COUNT(*) as count
+            FROM question_bank, jsonb_array_elements_text(question_bank.tags) AS tag
+            GROUP BY tag
+            ORDER BY tag
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn find_bank_question(
+        &self,
+        question_id: &str,
+    ) -> Result<Option<BankQuestion>, AppError> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM question_bank WHERE id = $1",
+            BANK_QUESTION_COLUMNS
+        ))
+        .bind(question_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn create_bank_question(&self, question: &BankQuestion) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO question_bank (id, question_type, title, features, tags, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6::timestamptz, $7::timestamptz)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(&question.id)
+        .bind(&question.question_type)
+        .bind(&question.title)
+        .bind(&question.features)
+        .bind(&question.tags)
+        .bind(&question.created_at)
+        .bind(&question.updated_at)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn update_bank_question(&self, question: &BankQuestion) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE question_bank
+            SET question_type = $1, title = $2, features = $3, tags = $4,
+                updated_at = $5::timestamptz
+            WHERE id = $6
+            "#,
+        )
+        .bind(&question.question_type)
+        .bind(&question.title)
+        .bind(&question.features)
+        .bind(&question.tags)
+        .bind(&question.updated_at)
+        .bind(&question.id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_bank_question(&self, question_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM question_bank WHERE id = $1")
+            .bind(question_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn unlinked_questions(
+        &self,
+        question_type: &str,
+        title: &str,
+    ) -> Result<Vec<FormQuestion>, AppError> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {}
+            FROM questions q
+            JOIN forms f ON f.id = q.form_id
+            WHERE q.type = $1 AND trim(q.title) = $2
+                AND q.features ->> 'bankId' IS NULL
+            ORDER BY f.created_at, f.id, q.position
+            "#,
+            FORM_QUESTION_COLUMNS
+        ))
+        .bind(question_type)
+        .bind(title)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn bank_question_uses(&self, bank_id: &str) -> Result<Vec<FormQuestion>, AppError> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {}
+            FROM questions q
+            JOIN forms f ON f.id = q.form_id
+            WHERE q.features ->> 'bankId' = $1
+            ORDER BY f.created_at, f.id, q.position
+            "#,
+            FORM_QUESTION_COLUMNS
+        ))
+        .bind(bank_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn link_questions(
+        &self,
+        bank_id: &str,
+        questions: &[FormQuestion],
+    ) -> Result<(), AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        for found in questions {
+            sqlx::query(
+                r#"
+                UPDATE questions
+                SET features = jsonb_set(COALESCE(features, '{}'), '{bankId}', to_jsonb($1::text))
+                WHERE id = $2
+                "#,
+            )
+            .bind(bank_id)
+            .bind(&found.question_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+            sqlx::query("UPDATE forms SET updated_at = now() WHERE id = $1")
+                .bind(&found.form_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+        }
+        tx.commit().await.map_err(AppError::Database)?;
+
+        Ok(())
+    }
+}
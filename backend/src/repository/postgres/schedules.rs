@@ -0,0 +1,449 @@
+//! Reminder campaigns and digest subscriptions, the two email schedules
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{enqueue, fetch_page, PgStore, INVITATION_COLUMNS};
+use crate::{
+    email::EmailMessage,
+    error::AppError,
+    models::{DigestRun, DigestSubscription, Invitation, ReminderCampaign},
+    outbox::OutboxMessage,
+    pagination::{Listing, Page, PageParams, SortField},
+    repository::{DigestRepository, NewDigestRun, ReminderRepository},
+};
+
+/// Columns of [`ReminderCampaign`], with its timestamps as RFC 3339 text
+const CAMPAIGN_COLUMNS: &str = r#"id, form_id, subject, body_template,
+    to_char(closes_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS closes_at,
+    days_before, active,
+    to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS created_at"#;
+
+/// Columns of [`DigestSubscription`], with its timestamp as RFC 3339 text
+const SUBSCRIPTION_COLUMNS: &str = r#"id, form_id, email, frequency,
+    to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS created_at"#;
+
+static CAMPAIGN_LISTING: Listing = Listing {
+    id_column: "id",
+    sorts: &[
+        SortField {
+            name: "created_at",
+            column: r#"to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"')"#,
+        },
+        SortField {
+            name: "closes_at",
+            column: r#"to_char(closes_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"')"#,
+        },
+    ],
+    default_sort: "-created_at",
+};
+
+static SUBSCRIPTION_LISTING: Listing = Listing {
+    id_column: "id",
+    sorts: &[
+        SortField {
+            name: "email",
+            column: "email",
+        },
+        SortField {
+            name: "created_at",
+            column: r#"to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"')"#,
+        },
+    ],
+    default_sort: "email",
+};
+
+#[async_trait]
+impl ReminderRepository for PgStore {
+    async fn create_campaign(&self, campaign: &ReminderCampaign) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO reminder_campaigns (id, form_id, subject, body_template, closes_at, days_before, active)
+            VALUES ($1, $2, $3, $4, $5::timestamptz, $6, $7)
+            "#,
+        )
+        .bind(&campaign.id)
+        .bind(&campaign.form_id)
+        .bind(&campaign.subject)
+        .bind(&campaign.body_template)
+        .bind(&campaign.closes_at)
+        .bind(&campaign.days_before)
+        .bind(campaign.active)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn find_campaign(&self, campaign_id: &str) -> Result<Option<ReminderCampaign>, AppError> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM reminder_campaigns WHERE id = $1",
+            CAMPAIGN_COLUMNS
+        ))
+        .bind(campaign_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn active_campaigns(&self) -> Result<Vec<ReminderCampaign>, AppError> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM reminder_campaigns WHERE active",
+            CAMPAIGN_COLUMNS
+        ))
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn list_campaigns(
+        &self,
+        form_id: &str,
+        page: &PageParams,
+    ) -> Result<Page<ReminderCampaign>, AppError> {
+        fetch_page(
+            &self.db,
+            &CAMPAIGN_LISTING.request(page)?,
+            CAMPAIGN_COLUMNS,
+            "reminder_campaigns",
+            |query| {
+                query.push(" AND form_id = ").push_bind(form_id.to_string());
+            },
+        )
+        .await
+    }
+
+    async fn send_counts(&self, form_id: &str) -> Result<HashMap<String, i64>, AppError> {
+        let counts: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT s.campaign_id, COUNT(*)
+            FROM reminder_sends s
+            JOIN reminder_campaigns c ON c.id = s.campaign_id
+            WHERE c.form_id = $1
+            GROUP BY s.campaign_id
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(counts.into_iter().collect())
+    }
+
+    async fn update_campaign(&self, campaign: &ReminderCampaign) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE reminder_campaigns
+            SET subject = $1, body_template = $2, closes_at = $3::timestamptz, days_before = $4,
+                active = $5
+            WHERE id = $6
+            "#,
+        )
+        .bind(&campaign.subject)
+        .bind(&campaign.body_template)
+        .bind(&campaign.closes_at)
+        .bind(&campaign.days_before)
+        .bind(campaign.active)
+        .bind(&campaign.id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_campaign(&self, campaign_id: &str) -> Result<bool, AppError> {
+        // The send history goes with it through ON DELETE CASCADE
+        let result = sqlx::query("DELETE FROM reminder_campaigns WHERE id = $1")
+            .bind(campaign_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn pending_recipients(
+        &self,
+        campaign: &ReminderCampaign,
+        days_before: i64,
+    ) -> Result<Vec<Invitation>, AppError> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {}
+            FROM invitations i
+            WHERE i.form_id = $1
+                AND i.used_at IS NULL
+                AND NOT EXISTS (
+                    SELECT 1 FROM reminder_sends s
+                    WHERE s.campaign_id = $2 AND s.invitation_id = i.id AND s.days_before = $3
+                )
+            ORDER BY i.name
+            "#,
+            INVITATION_COLUMNS
+        ))
+        .bind(&campaign.form_id)
+        .bind(&campaign.id)
+        .bind(days_before)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn queue_reminders(
+        &self,
+        campaign_id: &str,
+        days_before: i64,
+        reminders: &[(String, EmailMessage)],
+    ) -> Result<usize, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        let mut queued = 0;
+
+        for (invitation_id, message) in reminders {
+            // Claim the invitee for this step; an overlapping run that got there first has
+            // already queued their reminder
+            let claim = sqlx::query(
+                r#"
+                INSERT INTO reminder_sends (id, campaign_id, invitation_id, days_before)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (campaign_id, invitation_id, days_before) DO NOTHING
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(campaign_id)
+            .bind(invitation_id)
+            .bind(days_before)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+            if claim.rows_affected() == 0 {
+                continue;
+            }
+
+            enqueue(&mut tx, &OutboxMessage::Email(message.clone())).await?;
+            queued += 1;
+        }
+
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(queued)
+    }
+}
+
+#[async_trait]
+impl DigestRepository for PgStore {
+    async fn create_subscription(
+        &self,
+        subscription: &DigestSubscription,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO digest_subscriptions (id, form_id, email, frequency)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (form_id, email) DO NOTHING
+            "#,
+        )
+        .bind(&subscription.id)
+        .bind(&subscription.form_id)
+        .bind(&subscription.email)
+        .bind(&subscription.frequency)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_subscription(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Option<DigestSubscription>, AppError> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM digest_subscriptions WHERE id = $1",
+            SUBSCRIPTION_COLUMNS
+        ))
+        .bind(subscription_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn all_subscriptions(&self) -> Result<Vec<DigestSubscription>, AppError> {
+        sqlx::query_as(&format!(
+            "SELECT {} FROM digest_subscriptions",
+            SUBSCRIPTION_COLUMNS
+        ))
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn list_subscriptions(
+        &self,
+        form_id: &str,
+        page: &PageParams,
+    ) -> Result<Page<DigestSubscription>, AppError> {
+        fetch_page(
+            &self.db,
+            &SUBSCRIPTION_LISTING.request(page)?,
+            SUBSCRIPTION_COLUMNS,
+            "digest_subscriptions",
+            |query| {
+                query.push(" AND form_id = ").push_bind(form_id.to_string());
+            },
+        )
+        .await
+    }
+
+    async fn last_runs(&self, form_id: &str) -> Result<Vec<DigestRun>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                dr.id,
+                dr.subscription_id,
+                to_char(dr.period_start AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS period_start,
+                to_char(dr.period_end AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS period_end,
+                dr.new_responses,
+                dr.total_responses,
+                dr.average_score,
+                to_char(dr.sent_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"+00:00"') AS sent_at
+            FROM digest_runs dr
+            JOIN digest_subscriptions ds ON ds.id = dr.subscription_id
+            WHERE ds.form_id = $1
+                AND dr.period_end = (
+                    SELECT MAX(period_end) FROM digest_runs WHERE subscription_id = dr.subscription_id
+                )
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn delete_subscription(&self, subscription_id: &str) -> Result<bool, AppError> {
+        // The runs go with it through ON DELETE CASCADE
+        let result = sqlx::query("DELETE FROM digest_subscriptions WHERE id = $1")
+            .bind(subscription_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn last_period_end(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, AppError> {
+        let last_run: Option<(DateTime<Utc>,)> = sqlx::query_as(
+            "SELECT period_end FROM digest_runs WHERE subscription_id = $1 ORDER BY period_end DESC LIMIT 1",
+        )
+        .bind(subscription_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(last_run.map(|(end,)| end))
+    }
+
+    async fn response_counts(
+        &self,
+        form_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(i64, i64), AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE submitted_at > $1),
+                COUNT(*)
+            FROM responses
+            WHERE form_id = $2 AND submitted_at <= $3
+            "#,
+        )
+        .bind(start)
+        .bind(form_id)
+        .bind(end)
+        .fetch_one(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn average_rating(
+        &self,
+        form_id: &str,
+        until: DateTime<Utc>,
+    ) -> Result<Option<f64>, AppError> {
+        let (average,): (Option<f64>,) = sqlx::query_as(
+            r#"
+            SELECT AVG(CASE
+                WHEN jsonb_typeof(a.value) = 'number' THEN (a.value #>> '{}')::float8
+                WHEN jsonb_typeof(a.value -> 'rating') = 'number'
+                    THEN (a.value ->> 'rating')::float8
+            END)
+            FROM answers a
+            JOIN responses r ON r.id = a.response_id
+            JOIN questions q ON q.id = a.question_id
+            WHERE r.form_id = $1 AND q.type = 'likert' AND r.submitted_at <= $2
+            "#,
+        )
+        .bind(form_id)
+        .bind(until)
+        .fetch_one(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(average)
+    }
+
+    async fn record_run(
+        &self,
+        run: &NewDigestRun,
+        message: &EmailMessage,
+    ) -> Result<bool, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+
+        // Locking the subscription makes an overlapping run wait here, and then see the
+        // period this one claims
+        sqlx::query("SELECT id FROM digest_subscriptions WHERE id = $1 FOR UPDATE")
+            .bind(&run.subscription_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        // Claim the period first, so overlapping runs queue it once
+        let claimed = sqlx::query(
+            r#"
+            INSERT INTO digest_runs (id, subscription_id, period_start, period_end, new_responses, total_responses, average_score)
+            SELECT $1, $2, $3, $4, $5, $6, $7
+            WHERE NOT EXISTS (
+                SELECT 1 FROM digest_runs WHERE subscription_id = $2 AND period_end > $3
+            )
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&run.subscription_id)
+        .bind(run.period_start)
+        .bind(run.period_end)
+        .bind(run.new_responses)
+        .bind(run.total_responses)
+        .bind(run.average_score)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+        if claimed.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        enqueue(&mut tx, &OutboxMessage::Email(message.clone())).await?;
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,131 @@
+//! Full-text search over answers, backed by the `answer_search` table's `tsvector` column
+
+use async_trait::async_trait;
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+
+use super::{push_response_filters, PgStore, RFC3339};
+use crate::{
+    error::AppError,
+    filters::ResponseFilters,
+    repository::{SearchMatch, SearchRepository, SearchTerm},
+    search::{MATCH_END, MATCH_START, SNIPPET_TOKENS},
+};
+
+/// Add a response's free-text answers to the search index, inside the submit or copy
+/// transaction: text answers to text/textarea questions and the comment on any
+/// structured answer
+pub(super) async fn index_response(
+    conn: &mut PgConnection,
+    response_id: &str,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO answer_search (response_id, question_id, field, body)
+        SELECT response_id, question_id, field, body
+        FROM (
+            SELECT
+                a.response_id,
+                a.question_id,
+                CASE WHEN jsonb_typeof(a.value) = 'object' THEN 'comment' ELSE 'text' END AS field,
+                CASE
+                    WHEN jsonb_typeof(a.value) = 'string' AND q.type IN ('text', 'textarea')
+                        THEN a.value #>> '{}'
+                    WHEN jsonb_typeof(a.value -> 'comment') = 'string' THEN a.value ->> 'comment'
+                END AS body
+            FROM answers a
+            LEFT JOIN questions q ON q.id = a.question_id
+            WHERE a.response_id = $1
+        ) indexed
+        WHERE body IS NOT NULL AND trim(body) <> ''
+        "#,
+    )
+    .bind(response_id)
+    .execute(conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    Ok(())
+}
+
+/// Push a `tsquery` matching every term. Prefix terms keep only their letters and digits,
+/// so nothing in the input is read as query syntax.
+fn push_tsquery(query: &mut QueryBuilder<'_, Postgres>, terms: &[SearchTerm]) {
+    for (i, term) in terms.iter().enumerate() {
+        if i > 0 {
+            query.push(" && ");
+        }
+        if term.prefix {
+            let stem: String = term.word.chars().filter(|c| c.is_alphanumeric()).collect();
+            query
+                .push("to_tsquery('english', ")
+                .push_bind(format!("{}:*", stem))
+                .push(")");
+        } else {
+            query
+                .push("plainto_tsquery('english', ")
+                .push_bind(term.word.clone())
+                .push(")");
+        }
+    }
+}
+
+#[async_trait]
+impl SearchRepository for PgStore {
+    async fn search_answers(
+        &self,
+        terms: &[SearchTerm],
+        filters: &ResponseFilters,
+        limit: i64,
+        include_pii: bool,
+    ) -> Result<(i64, Vec<SearchMatch>), AppError> {
+        let push_from = |query: &mut QueryBuilder<'_, Postgres>| {
+            query.push(" FROM answer_search s CROSS JOIN (SELECT ");
+            push_tsquery(query, terms);
+            query.push(
+                r#" AS query) m
+                JOIN responses r ON r.id = s.response_id
+                LEFT JOIN questions q ON q.id = s.question_id
+                LEFT JOIN respondents res ON res.id = r.respondent_id
+                WHERE s.document @@ m.query"#,
+            );
+            push_response_filters(query, filters);
+        };
+
+        let mut count = QueryBuilder::new("SELECT COUNT(*)");
+        push_from(&mut count);
+        let (total,): (i64,) = count
+            .build_query_as()
+            .fetch_one(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        let mut query = QueryBuilder::new(
+            "SELECT s.response_id, s.question_id, q.title AS question_title, s.field, ts_headline('english', s.body, m.query, ",
+        );
+        query
+            .push_bind(format!(
+                "StartSel={}, StopSel={}, MaxWords={}, MinWords=5",
+                MATCH_START, MATCH_END, SNIPPET_TOKENS
+            ))
+            .push(format!(
+                r#") AS snippet,
+                r.role,
+                to_char(r.submitted_at AT TIME ZONE 'UTC', {}) AS submitted_at,
+                CASE WHEN {pii} THEN res.name END AS respondent_name,
+                CASE WHEN {pii} THEN res.email END AS respondent_email"#,
+                RFC3339,
+                pii = include_pii,
+            ));
+        push_from(&mut query);
+        query.push(" ORDER BY ts_rank(s.document, m.query) DESC, r.submitted_at DESC LIMIT ");
+        query.push_bind(limit);
+
+        let matches = query
+            .build_query_as()
+            .fetch_all(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok((total, matches))
+    }
+}
@@ -0,0 +1,233 @@
+//! Webhook endpoints and the outbox their events are delivered through
+
+use async_trait::async_trait;
+
+use super::{fetch_page, SqliteStore};
+use crate::{
+    error::AppError,
+    models::{OutboxEntry, Webhook},
+    pagination::{Listing, Page, PageParams, SortField},
+    repository::{OutboxRepository, WebhookRepository},
+};
+
+static WEBHOOK_LISTING: Listing = Listing {
+    id_column: "id",
+    sorts: &[
+        SortField {
+            name: "created_at",
+            column: "COALESCE(created_at, '')",
+        },
+        SortField {
+            name: "url",
+            column: "url",
+        },
+    ],
+    default_sort: "created_at",
+};
+
+static OUTBOX_LISTING: Listing = Listing {
+    id_column: "id",
+    sorts: &[
+        SortField {
+            name: "created_at",
+            column: "COALESCE(created_at, '')",
+        },
+        SortField {
+            name: "next_attempt_at",
+            column: "next_attempt_at",
+        },
+    ],
+    default_sort: "-created_at",
+};
+
+#[async_trait]
+impl WebhookRepository for SqliteStore {
+    async fn create_webhook(&self, webhook: &Webhook) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO webhooks (id, form_id, url, secret, events, include_pii, active)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&webhook.id)
+        .bind(&webhook.form_id)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(&webhook.events)
+        .bind(webhook.include_pii)
+        .bind(webhook.active)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn find_webhook(&self, webhook_id: &str) -> Result<Option<Webhook>, AppError> {
+        sqlx::query_as("SELECT * FROM webhooks WHERE id = ?")
+            .bind(webhook_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn list_webhooks(
+        &self,
+        form_id: Option<&str>,
+        page: &PageParams,
+    ) -> Result<Page<Webhook>, AppError> {
+        fetch_page(
+            &self.db,
+            &WEBHOOK_LISTING.request(page)?,
+            "*",
+            "webhooks",
+            |query| {
+                if let Some(form_id) = form_id {
+                    query.push(" AND form_id = ").push_bind(form_id.to_string());
+                }
+            },
+        )
+        .await
+    }
+
+    async fn delete_webhook(&self, webhook_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = ?")
+            .bind(webhook_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for SqliteStore {
+    async fn due_entries(&self, limit: i64) -> Result<Vec<OutboxEntry>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM outbox
+            WHERE status = 'pending' AND next_attempt_at <= datetime('now')
+            ORDER BY next_attempt_at, created_at
+            LIMIT ?
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn record_delivered(&self, entry_id: &str) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE outbox
+            SET status = 'delivered', attempts = attempts + 1, last_error = NULL,
+                delivered_at = datetime('now')
+            WHERE id = ?
+            "#,
+        )
+        .bind(entry_id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn record_failure(
+        &self,
+        entry_id: &str,
+        status: &str,
+        attempts: i64,
+        error: &str,
+        retry_in_secs: i64,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE outbox
+            SET status = ?, attempts = ?, last_error = ?, next_attempt_at = datetime('now', ?)
+            WHERE id = ?
+            "#,
+        )
+        .bind(status)
+        .bind(attempts)
+        .bind(error)
+        .bind(format!("+{} seconds", retry_in_secs))
+        .bind(entry_id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn prune_delivered(&self, days: i64) -> Result<(), AppError> {
+        sqlx::query(
+            "DELETE FROM outbox WHERE status = 'delivered' AND delivered_at < datetime('now', ?)",
+        )
+        .bind(format!("-{} days", days))
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn find_entry(&self, entry_id: &str) -> Result<Option<OutboxEntry>, AppError> {
+        sqlx::query_as("SELECT * FROM outbox WHERE id = ?")
+            .bind(entry_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn list_entries(
+        &self,
+        status: Option<&str>,
+        kind: Option<&str>,
+        page: &PageParams,
+    ) -> Result<Page<OutboxEntry>, AppError> {
+        fetch_page(
+            &self.db,
+            &OUTBOX_LISTING.request(page)?,
+            "*",
+            "outbox",
+            |query| {
+                if let Some(status) = status {
+                    query.push(" AND status = ").push_bind(status.to_string());
+                }
+                if let Some(kind) = kind {
+                    query.push(" AND kind = ").push_bind(kind.to_string());
+                }
+            },
+        )
+        .await
+    }
+
+    async fn requeue(&self, entry_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE outbox
+            SET status = 'pending', attempts = 0, next_attempt_at = datetime('now')
+            WHERE id = ?
+            "#,
+        )
+        .bind(entry_id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn discard(&self, entry_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("UPDATE outbox SET status = 'discarded' WHERE id = ?")
+            .bind(entry_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
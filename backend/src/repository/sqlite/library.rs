@@ -0,0 +1,238 @@
+//! Saved form templates and the question bank
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use super::SqliteStore;
+use crate::{
+    error::AppError,
+    models::{BankQuestion, FormTemplate},
+    repository::{FormQuestion, QuestionBankRepository, TagCount, TemplateRepository},
+};
+
+#[async_trait]
+impl TemplateRepository for SqliteStore {
+    async fn list_templates(&self) -> Result<Vec<FormTemplate>, AppError> {
+        sqlx::query_as("SELECT * FROM form_templates ORDER BY name, created_at")
+            .fetch_all(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn get_template(&self, template_id: &str) -> Result<Option<FormTemplate>, AppError> {
+        sqlx::query_as("SELECT * FROM form_templates WHERE id = ?")
+            .bind(template_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn create_template(&self, template: &FormTemplate) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO form_templates (id, name, description, category, source_form_id, definition, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&template.id)
+        .bind(&template.name)
+        .bind(&template.description)
+        .bind(&template.category)
+        .bind(&template.source_form_id)
+        .bind(&template.definition)
+        .bind(&template.created_at)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn delete_template(&self, template_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM form_templates WHERE id = ?")
+            .bind(template_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Columns of [`FormQuestion`] over `questions q JOIN forms f`
+const FORM_QUESTION_COLUMNS: &str = r#"
+    f.id as form_id,
+    f.title as form_title,
+    json_extract(f.settings, '$.reviewPeriod') as review_period,
+    q.id as question_id,
+    q.title as question_title
+"#;
+
+#[async_trait]
+impl QuestionBankRepository for SqliteStore {
+    async fn list_bank_questions(
+        &self,
+        tag: Option<&str>,
+        search: Option<&str>,
+    ) -> Result<Vec<BankQuestion>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM question_bank
+            WHERE (?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(question_bank.tags) WHERE value = ?1))
+                AND (?2 IS NULL OR instr(lower(title), lower(?2)) > 0)
+            ORDER BY title, id
+            "#,
+        )
+        .bind(tag)
+        .bind(search)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn tag_counts(&self) -> Result<Vec<TagCount>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT tag.value as tag, COUNT(*) as count
+            FROM question_bank, json_each(question_bank.tags) AS tag
+            GROUP BY tag.value
+            ORDER BY tag.value
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn find_bank_question(
+        &self,
+        question_id: &str,
+    ) -> Result<Option<BankQuestion>, AppError> {
+        sqlx::query_as("SELECT * FROM question_bank WHERE id = ?")
+            .bind(question_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn create_bank_question(&self, question: &BankQuestion) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO question_bank (id, question_type, title, features, tags, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(&question.id)
+        .bind(&question.question_type)
+        .bind(&question.title)
+        .bind(&question.features)
+        .bind(&question.tags)
+        .bind(&question.created_at)
+        .bind(&question.updated_at)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn update_bank_question(&self, question: &BankQuestion) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE question_bank
+            SET question_type = ?, title = ?, features = ?, tags = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&question.question_type)
+        .bind(&question.title)
+        .bind(&question.features)
+        .bind(&question.tags)
+        .bind(&question.updated_at)
+        .bind(&question.id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_bank_question(&self, question_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM question_bank WHERE id = ?")
+            .bind(question_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn unlinked_questions(
+        &self,
+        question_type: &str,
+        title: &str,
+    ) -> Result<Vec<FormQuestion>, AppError> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {}
+            FROM questions q
+            JOIN forms f ON f.id = q.form_id
+            WHERE q.type = ? AND trim(q.title) = ?
+                AND json_extract(q.features, '$.bankId') IS NULL
+            ORDER BY f.created_at, f.id, q.position
+            "#,
+            FORM_QUESTION_COLUMNS
+        ))
+        .bind(question_type)
+        .bind(title)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn bank_question_uses(&self, bank_id: &str) -> Result<Vec<FormQuestion>, AppError> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {}
+            FROM questions q
+            JOIN forms f ON f.id = q.form_id
+            WHERE json_extract(q.features, '$.bankId') = ?
+            ORDER BY f.created_at, f.id, q.position
+            "#,
+            FORM_QUESTION_COLUMNS
+        ))
+        .bind(bank_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn link_questions(
+        &self,
+        bank_id: &str,
+        questions: &[FormQuestion],
+    ) -> Result<(), AppError> {
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        for found in questions {
+            sqlx::query(
+                "UPDATE questions SET features = json_set(COALESCE(features, '{}'), '$.bankId', ?) WHERE id = ?",
+            )
+            .bind(bank_id)
+            .bind(&found.question_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+            sqlx::query("UPDATE forms SET updated_at = ? WHERE id = ?")
+                .bind(&now)
+                .bind(&found.form_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+        }
+        tx.commit().await.map_err(AppError::Database)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1370 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use serde_json::Value as JsonValue;
+use sqlx::{sqlite::SqliteRow, FromRow, QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use super::{
+    questions_from_rows, CopiedForm, CopyOptions, ExportRow, Form, FormDefinition, FormRepository,
+    FormSummary, InvitationFilters, InvitationRepository, LikertRating, LikertSummary,
+    ListedResponse, NewSubmission, NotificationRepository, OutboxHook, Question, QuestionRow,
+    RatingBucket, ResponseRepository, Section, StoredSubmission, Totals,
+};
+use crate::{
+    db::DB_TIMESTAMP_FORMAT,
+    error::AppError,
+    filters::{AnswerValue, ResponseFilters},
+    invitations::generate_token,
+    models::{
+        AnswerWithQuestion, Invitation, NotificationTemplate, RoleCount, RoleResponseRate, Webhook,
+    },
+    outbox::{OutboxEvent, OutboxMessage},
+    pagination::{Keyed, Listing, Page, PageParams, PageRequest, SortField},
+    webhooks,
+};
+
+mod delivery;
+mod library;
+mod schedules;
+mod search;
+
+#[cfg(test)]
+pub(crate) use search::{index_response, remove_form};
+
+/// Repositories backed by SQLite, where answers are JSON text read with `json_type`/`json_extract`
+pub struct SqliteStore {
+    db: SqlitePool,
+}
+
+impl SqliteStore {
+    pub fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl FormRepository for SqliteStore {
+    async fn list_forms(&self) -> Result<Vec<FormSummary>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT id, title, description, instructions, status, created_at, updated_at
+            FROM forms
+            ORDER BY updated_at DESC
+            "#,
+        )
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn find_form(&self, form_id: &str) -> Result<Option<FormDefinition>, AppError> {
+        let form: Option<Form> = sqlx::query_as(
+            r#"
+            SELECT id, title, description, instructions, status, welcome_message, closing_message, settings
+            FROM forms
+            WHERE id = ?
+            "#,
+        )
+        .bind(form_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let Some(form) = form else {
+            return Ok(None);
+        };
+
+        let sections: Vec<Section> = sqlx::query_as(
+            r#"
+            SELECT id, form_id, title, description, position
+            FROM sections
+            WHERE form_id = ?
+            ORDER BY position
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        let questions: Vec<QuestionRow> = sqlx::query_as(
+            r#"
+            SELECT id, form_id, section_id, position, type, title, description, features
+            FROM questions
+            WHERE form_id = ?
+            ORDER BY position
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(Some(FormDefinition {
+            form,
+            sections,
+            questions: questions_from_rows(questions)?,
+        }))
+    }
+
+    async fn form_exists(&self, form_id: &str) -> Result<bool, AppError> {
+        let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM forms WHERE id = ?")
+            .bind(form_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(existing.is_some())
+    }
+
+    async fn create_form(&self, definition: &FormDefinition) -> Result<(), AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        insert_form(&mut tx, definition).await?;
+        tx.commit().await.map_err(AppError::Database)
+    }
+
+    async fn add_questions(&self, form_id: &str, questions: &[Question]) -> Result<(), AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+
+        sqlx::query("UPDATE forms SET updated_at = ? WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(form_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        insert_structure(&mut tx, &[], questions).await?;
+
+        tx.commit().await.map_err(AppError::Database)
+    }
+
+    async fn replace_form(
+        &self,
+        definition: &FormDefinition,
+        updated_at: DateTime<Utc>,
+        events: &[OutboxEvent],
+    ) -> Result<bool, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        if !replace_form(&mut tx, definition, &updated_at.to_rfc3339()).await? {
+            return Ok(false);
+        }
+        queue_events(&mut tx, events).await?;
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(true)
+    }
+
+    async fn set_status(
+        &self,
+        form_id: &str,
+        status: &str,
+        updated_at: DateTime<Utc>,
+        hook: &OutboxHook<'_, str>,
+    ) -> Result<Option<String>, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        let previous = set_form_status(&mut tx, form_id, status, &updated_at.to_rfc3339()).await?;
+        if let Some(previous) = &previous {
+            queue_events(&mut tx, &hook(previous)).await?;
+        }
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(previous)
+    }
+
+    async fn delete_form(
+        &self,
+        form_id: &str,
+        hook: &OutboxHook<'_, i64>,
+    ) -> Result<Option<i64>, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        search::remove_form(&mut tx, form_id).await?;
+        let deleted = delete_form(&mut tx, form_id).await?;
+        if let Some(responses) = &deleted {
+            queue_events(&mut tx, &hook(responses)).await?;
+        }
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(deleted)
+    }
+
+    async fn copy_form(
+        &self,
+        copy: &FormDefinition,
+        from_form_id: &str,
+        question_ids: &HashMap<String, String>,
+        options: CopyOptions,
+    ) -> Result<CopiedForm, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        insert_form(&mut tx, copy).await?;
+
+        let mut copied = CopiedForm::default();
+        if options.responses {
+            copied.responses =
+                copy_responses(&mut tx, from_form_id, &copy.form.id, question_ids).await?;
+            for response_id in copied.responses.values() {
+                search::index_response(&mut tx, response_id).await?;
+            }
+        }
+        if options.invitations {
+            copied.invitations =
+                copy_invitations(&mut tx, from_form_id, &copy.form.id, &copied.responses).await?;
+        }
+
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(copied)
+    }
+}
+
+#[async_trait]
+impl ResponseRepository for SqliteStore {
+    async fn count_responses(&self, form_id: &str) -> Result<i64, AppError> {
+        let (total,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM responses WHERE form_id = ?")
+            .bind(form_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(total)
+    }
+
+    async fn totals(&self) -> Result<Totals, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM forms) as forms,
+                (SELECT COUNT(*) FROM responses) as responses,
+                (SELECT COUNT(*) FROM respondents) as respondents
+            "#,
+        )
+        .fetch_one(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn has_submitted(&self, form_id: &str, email_hash: &str) -> Result<bool, AppError> {
+        let (count,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*)
+            FROM responses r
+            JOIN respondents res ON res.id = r.respondent_id
+            WHERE res.email_hash = ? AND r.form_id = ?
+            "#,
+        )
+        .bind(email_hash)
+        .bind(form_id)
+        .fetch_one(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(count > 0)
+    }
+
+    async fn answer_counts(&self, form_id: &str) -> Result<HashMap<String, i64>, AppError> {
+        let counts: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT a.question_id, COUNT(*)
+            FROM answers a
+            JOIN responses r ON r.id = a.response_id
+            WHERE r.form_id = ?
+            GROUP BY a.question_id
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(counts.into_iter().collect())
+    }
+
+    async fn role_distribution(&self, form_id: &str) -> Result<Vec<RoleCount>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT role, COUNT(*) as count
+            FROM responses
+            WHERE form_id = ?
+            GROUP BY role
+            ORDER BY count DESC
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn likert_summaries(&self, form_id: &str) -> Result<Vec<LikertSummary>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                q.id as question_id,
+                q.title as question_title,
+                json_extract(q.features, '$.bankId') as bank_question_id,
+                COUNT(DISTINCT a.response_id) as response_count,
+                AVG(CASE
+                    WHEN json_type(a.value) = 'integer' THEN CAST(a.value as REAL)
+                    WHEN json_type(a.value) = 'object' AND json_extract(a.value, '$.rating') IS NOT NULL
+                        THEN CAST(json_extract(a.value, '$.rating') as REAL)
+                    ELSE NULL
+                END) as average_rating
+            FROM questions q
+            LEFT JOIN answers a ON a.question_id = q.id
+            LEFT JOIN responses r ON r.id = a.response_id
+            WHERE q.form_id = ? AND q.type = 'likert'
+            GROUP BY q.id, q.title
+            ORDER BY q.position
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn rating_buckets(&self, form_id: &str) -> Result<Vec<RatingBucket>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                a.question_id,
+                CASE
+                    WHEN json_type(a.value) = 'integer' THEN CAST(a.value as INTEGER)
+                    WHEN json_type(a.value) = 'object' THEN CAST(json_extract(a.value, '$.rating') as INTEGER)
+                END as rating,
+                COUNT(*) as count
+            FROM answers a
+            JOIN responses r ON r.id = a.response_id
+            JOIN questions q ON q.id = a.question_id
+            WHERE r.form_id = ? AND q.type = 'likert'
+                AND (json_type(a.value) = 'integer' OR json_extract(a.value, '$.rating') IS NOT NULL)
+            GROUP BY a.question_id, rating
+            ORDER BY a.question_id, rating
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn likert_ratings(&self, form_id: &str) -> Result<Vec<LikertRating>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                a.response_id,
+                r.role,
+                a.question_id,
+                CASE
+                    WHEN json_type(a.value) = 'integer' THEN CAST(a.value as INTEGER)
+                    WHEN json_type(a.value) = 'object' THEN CAST(json_extract(a.value, '$.rating') as INTEGER)
+                END as rating
+            FROM answers a
+            JOIN responses r ON r.id = a.response_id
+            JOIN questions q ON q.id = a.question_id
+            WHERE r.form_id = ? AND q.type = 'likert'
+                AND (json_type(a.value) = 'integer' OR json_extract(a.value, '$.rating') IS NOT NULL)
+            ORDER BY a.response_id, a.question_id
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn submit_response(
+        &self,
+        submission: &NewSubmission,
+        hook: &OutboxHook<'_, StoredSubmission>,
+    ) -> Result<StoredSubmission, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        let stored = insert_submission(&mut tx, submission).await?;
+        search::index_response(&mut tx, &stored.response_id).await?;
+        queue_events(&mut tx, &hook(&stored)).await?;
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(stored)
+    }
+
+    async fn delete_respondent(
+        &self,
+        respondent_id: &str,
+        hook: &OutboxHook<'_, [String]>,
+    ) -> Result<Option<Vec<String>>, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        scrub_outbox(&mut tx, respondent_id).await?;
+        let form_ids = delete_respondent(&mut tx, respondent_id).await?;
+        if let Some(form_ids) = &form_ids {
+            queue_events(&mut tx, &hook(form_ids)).await?;
+        }
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(form_ids)
+    }
+
+    async fn list_responses(
+        &self,
+        filters: &ResponseFilters,
+        page: &PageParams,
+        include_pii: bool,
+    ) -> Result<Page<ListedResponse>, AppError> {
+        let (listing, respondent, from) = if include_pii {
+            (
+                &RESPONSE_PII_LISTING,
+                "res.id AS respondent_id, res.name AS respondent_name, res.email AS respondent_email",
+                "responses r LEFT JOIN respondents res ON res.id = r.respondent_id",
+            )
+        } else {
+            (
+                &RESPONSE_LISTING,
+                "NULL AS respondent_id, NULL AS respondent_name, NULL AS respondent_email",
+                "responses r",
+            )
+        };
+
+        fetch_page(
+            &self.db,
+            &listing.request(page)?,
+            &format!("r.id, r.form_id, {}, r.role, r.submitted_at", respondent),
+            from,
+            |query| push_response_filters(query, filters),
+        )
+        .await
+    }
+
+    async fn answers_by_response(
+        &self,
+        response_ids: &[&str],
+    ) -> Result<HashMap<String, Vec<AnswerWithQuestion>>, AppError> {
+        if response_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut query = QueryBuilder::<Sqlite>::new(
+            r#"
+            SELECT a.response_id, a.question_id, q.title, CAST(a.value AS TEXT)
+            FROM answers a
+            JOIN questions q ON q.id = a.question_id
+            WHERE a.response_id IN ("#,
+        );
+        let mut ids = query.separated(", ");
+        for id in response_ids {
+            ids.push_bind(id.to_string());
+        }
+        query.push(") ORDER BY q.position");
+
+        let rows: Vec<(String, String, String, String)> = query
+            .build_query_as()
+            .fetch_all(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        let mut answers: HashMap<String, Vec<AnswerWithQuestion>> = HashMap::new();
+        for (response_id, question_id, question_title, value) in rows {
+            answers
+                .entry(response_id)
+                .or_default()
+                .push(AnswerWithQuestion {
+                    question_id,
+                    question_title,
+                    value: serde_json::from_str(&value).unwrap_or(JsonValue::Null),
+                });
+        }
+
+        Ok(answers)
+    }
+
+    async fn question_texts(
+        &self,
+        form_id: &str,
+        question_id: &str,
+        comments: bool,
+    ) -> Result<Vec<Option<String>>, AppError> {
+        let rows: Vec<(Option<String>,)> = sqlx::query_as(
+            r#"
+            SELECT
+                CASE
+                    WHEN json_valid(a.value) = 0 THEN NULL
+                    WHEN ?1 = 0 AND json_type(a.value) = 'text' THEN json_extract(a.value, '$')
+                    WHEN ?1 = 1 AND json_type(a.value) = 'object'
+                        AND json_type(a.value, '$.comment') = 'text'
+                        THEN json_extract(a.value, '$.comment')
+                END AS text
+            FROM responses r
+            LEFT JOIN answers a ON a.response_id = r.id AND a.question_id = ?2
+            WHERE r.form_id = ?3
+            "#,
+        )
+        .bind(comments)
+        .bind(question_id)
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(rows.into_iter().map(|(text,)| text).collect())
+    }
+
+    fn export_rows<'a>(
+        &'a self,
+        form_id: &'a str,
+        include_pii: bool,
+    ) -> BoxStream<'a, Result<ExportRow, AppError>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                r.id AS response_id,
+                r.role,
+                r.submitted_at,
+                CASE WHEN ?1 THEN res.name END AS name,
+                CASE WHEN ?1 THEN res.email END AS email,
+                a.question_id,
+                CAST(a.value AS TEXT) AS value
+            FROM responses r
+            LEFT JOIN respondents res ON res.id = r.respondent_id
+            LEFT JOIN answers a ON a.response_id = r.id
+            LEFT JOIN questions q ON q.id = a.question_id
+            WHERE r.form_id = ?2
+            ORDER BY r.submitted_at, r.id, q.position
+            "#,
+        )
+        .bind(include_pii)
+        .bind(form_id)
+        .fetch(&self.db)
+        .map_err(AppError::Database)
+        .boxed()
+    }
+}
+
+#[async_trait]
+impl InvitationRepository for SqliteStore {
+    async fn count_invitations(&self, form_id: &str) -> Result<i64, AppError> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM invitations WHERE form_id = ?")
+            .bind(form_id)
+            .fetch_one(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(count)
+    }
+
+    async fn find_invitation(
+        &self,
+        form_id: &str,
+        token: &str,
+    ) -> Result<Option<Invitation>, AppError> {
+        sqlx::query_as("SELECT * FROM invitations WHERE token = ? AND form_id = ?")
+            .bind(token)
+            .bind(form_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn list_invitations(
+        &self,
+        form_id: &str,
+        filters: &InvitationFilters,
+        page: &PageParams,
+    ) -> Result<Page<Invitation>, AppError> {
+        fetch_page(
+            &self.db,
+            &INVITATION_LISTING.request(page)?,
+            "*",
+            "invitations",
+            |query| {
+                query.push(" AND form_id = ").push_bind(form_id.to_string());
+                if let Some(role) = &filters.role {
+                    query.push(" AND role = ").push_bind(role.clone());
+                }
+                match filters.responded {
+                    Some(true) => query.push(" AND used_at IS NOT NULL"),
+                    Some(false) => query.push(" AND used_at IS NULL"),
+                    None => query,
+                };
+            },
+        )
+        .await
+    }
+
+    async fn create_invitations(&self, invitations: &[Invitation]) -> Result<Vec<bool>, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+
+        let mut inserted = Vec::with_capacity(invitations.len());
+        for invitation in invitations {
+            let result = sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO invitations (id, form_id, name, email, role, token)
+                VALUES (?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&invitation.id)
+            .bind(&invitation.form_id)
+            .bind(&invitation.name)
+            .bind(&invitation.email)
+            .bind(&invitation.role)
+            .bind(&invitation.token)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+            inserted.push(result.rows_affected() > 0);
+        }
+
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(inserted)
+    }
+
+    async fn delete_invitation(&self, invitation_id: &str) -> Result<bool, AppError> {
+        let result = sqlx::query("DELETE FROM invitations WHERE id = ?")
+            .bind(invitation_id)
+            .execute(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn response_rates(&self, form_id: &str) -> Result<Vec<RoleResponseRate>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                role,
+                COUNT(*) as invited,
+                COUNT(used_at) as responded
+            FROM invitations
+            WHERE form_id = ?
+            GROUP BY role
+            ORDER BY role
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+}
+
+#[async_trait]
+impl NotificationRepository for SqliteStore {
+    async fn find_template(&self, form_id: &str) -> Result<Option<NotificationTemplate>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT form_id, enabled, subject, html_template, include_pii
+            FROM notification_templates
+            WHERE form_id = ?
+            "#,
+        )
+        .bind(form_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn save_template(&self, template: &NotificationTemplate) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO notification_templates (form_id, enabled, subject, html_template, include_pii, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(form_id) DO UPDATE SET
+                enabled = excluded.enabled,
+                subject = excluded.subject,
+                html_template = excluded.html_template,
+                include_pii = excluded.include_pii,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&template.form_id)
+        .bind(template.enabled)
+        .bind(&template.subject)
+        .bind(&template.html_template)
+        .bind(template.include_pii)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+}
+
+async fn insert_structure(
+    conn: &mut SqliteConnection,
+    sections: &[Section],
+    questions: &[Question],
+) -> Result<(), AppError> {
+    for section in sections {
+        sqlx::query(
+            r#"
+            INSERT INTO sections (id, form_id, title, description, position)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&section.id)
+        .bind(&section.form_id)
+        .bind(&section.title)
+        .bind(&section.description)
+        .bind(section.position)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+    }
+
+    for question in questions {
+        sqlx::query(
+            r#"
+            INSERT INTO questions (id, form_id, section_id, position, type, title, description, features)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&question.id)
+        .bind(&question.form_id)
+        .bind(&question.section_id)
+        .bind(question.position)
+        .bind(question.kind.type_name())
+        .bind(&question.title)
+        .bind(&question.description)
+        .bind(question.kind.features().to_string())
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+    }
+
+    Ok(())
+}
+
+/// Insert a new form with its sections and questions
+async fn insert_form(
+    conn: &mut SqliteConnection,
+    definition: &FormDefinition,
+) -> Result<(), AppError> {
+    let FormDefinition {
+        form,
+        sections,
+        questions,
+    } = definition;
+
+    let now = Utc::now().to_rfc3339();
+    sqlx::query(
+        r#"
+        INSERT INTO forms (id, title, description, instructions, status, welcome_message, closing_message, settings, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&form.id)
+    .bind(&form.title)
+    .bind(&form.description)
+    .bind(&form.instructions)
+    .bind(&form.status)
+    .bind(&form.welcome_message)
+    .bind(&form.closing_message)
+    .bind(form.settings.as_ref().map(|s| s.to_string()))
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    insert_structure(conn, sections, questions).await
+}
+
+/// See [`FormRepository::replace_form`]
+async fn replace_form(
+    conn: &mut SqliteConnection,
+    definition: &FormDefinition,
+    updated_at: &str,
+) -> Result<bool, AppError> {
+    let FormDefinition {
+        form,
+        sections,
+        questions,
+    } = definition;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE forms
+        SET title = ?, description = ?, instructions = ?, status = ?,
+            welcome_message = ?, closing_message = ?, settings = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(&form.title)
+    .bind(&form.description)
+    .bind(&form.instructions)
+    .bind(&form.status)
+    .bind(&form.welcome_message)
+    .bind(&form.closing_message)
+    .bind(form.settings.as_ref().map(|s| s.to_string()))
+    .bind(updated_at)
+    .bind(&form.id)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query("DELETE FROM questions WHERE form_id = ?")
+        .bind(&form.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    sqlx::query("DELETE FROM sections WHERE form_id = ?")
+        .bind(&form.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    insert_structure(conn, sections, questions).await?;
+
+    Ok(true)
+}
+
+/// See [`FormRepository::set_status`]
+async fn set_form_status(
+    conn: &mut SqliteConnection,
+    form_id: &str,
+    status: &str,
+    updated_at: &str,
+) -> Result<Option<String>, AppError> {
+    let previous: Option<(String,)> = sqlx::query_as("SELECT status FROM forms WHERE id = ?")
+        .bind(form_id)
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    if previous.is_some() {
+        sqlx::query("UPDATE forms SET status = ?, updated_at = ? WHERE id = ?")
+            .bind(status)
+            .bind(updated_at)
+            .bind(form_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+    }
+
+    Ok(previous.map(|(status,)| status))
+}
+
+/// Copy a form's responses to another form, with their answers moved onto the copied
+/// questions. Answers to questions missing from `question_ids` are left behind.
+/// The copies are not linked to a respondent, so nobody counts as having answered the
+/// new form. Returns the new ID of each copied response, keyed by the original.
+async fn copy_responses(
+    conn: &mut SqliteConnection,
+    from_form_id: &str,
+    to_form_id: &str,
+    question_ids: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, AppError> {
+    let responses: Vec<(String,)> =
+        sqlx::query_as("SELECT id FROM responses WHERE form_id = ? ORDER BY submitted_at, id")
+            .bind(from_form_id)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+    let mut response_ids = HashMap::new();
+    for (response_id,) in responses {
+        let new_id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO responses (id, respondent_id, form_id, role, metadata, submitted_at)
+            SELECT ?, NULL, ?, role, metadata, submitted_at
+            FROM responses WHERE id = ?
+            "#,
+        )
+        .bind(&new_id)
+        .bind(to_form_id)
+        .bind(&response_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+        response_ids.insert(response_id, new_id);
+    }
+
+    // Values are copied in SQL so they keep the type they were stored with
+    let answers: Vec<(String, String, String)> = sqlx::query_as(
+        r#"
+        SELECT a.id, a.response_id, a.question_id
+        FROM answers a
+        JOIN responses r ON r.id = a.response_id
+        WHERE r.form_id = ?
+        "#,
+    )
+    .bind(from_form_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    for (answer_id, response_id, question_id) in answers {
+        let Some(question_id) = question_ids.get(&question_id) else {
+            continue;
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO answers (id, response_id, question_id, value)
+            SELECT ?, ?, ?, value FROM answers WHERE id = ?
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&response_ids[&response_id])
+        .bind(question_id)
+        .bind(&answer_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+    }
+
+    Ok(response_ids)
+}
+
+/// Copy a form's invitation list to another form with new tokens. Invitations used for
+/// a response that was copied too stay used, now by the copy; the rest start unused.
+async fn copy_invitations(
+    conn: &mut SqliteConnection,
+    from_form_id: &str,
+    to_form_id: &str,
+    response_ids: &HashMap<String, String>,
+) -> Result<usize, AppError> {
+    let invitations: Vec<Invitation> =
+        sqlx::query_as("SELECT * FROM invitations WHERE form_id = ? ORDER BY name, email")
+            .bind(from_form_id)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+    for invitation in &invitations {
+        let response_id = invitation
+            .response_id
+            .as_ref()
+            .and_then(|id| response_ids.get(id));
+        sqlx::query(
+            r#"
+            INSERT INTO invitations (id, form_id, name, email, role, token, response_id, used_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(to_form_id)
+        .bind(&invitation.name)
+        .bind(&invitation.email)
+        .bind(&invitation.role)
+        .bind(generate_token())
+        .bind(response_id)
+        .bind(response_id.and(invitation.used_at.as_ref()))
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+    }
+
+    Ok(invitations.len())
+}
+
+/// See [`ResponseRepository::submit_response`]
+async fn insert_submission(
+    conn: &mut SqliteConnection,
+    submission: &NewSubmission,
+) -> Result<StoredSubmission, AppError> {
+    let existing: Option<(String,)> =
+        sqlx::query_as("SELECT id FROM respondents WHERE email_hash = ?")
+            .bind(&submission.email_hash)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+    let respondent_id = if let Some((id,)) = existing {
+        let (already_submitted,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM responses WHERE respondent_id = ? AND form_id = ?",
+        )
+        .bind(&id)
+        .bind(&submission.form_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+        if already_submitted > 0 {
+            return Err(AppError::BadRequest(
+                "You have already submitted a response for this form".to_string(),
+            ));
+        }
+
+        id
+    } else {
+        let new_id = Uuid::new_v4().to_string();
+        sqlx::query("INSERT INTO respondents (id, name, email, email_hash) VALUES (?, ?, ?, ?)")
+            .bind(&new_id)
+            .bind(&submission.respondent_name)
+            .bind(&submission.respondent_email)
+            .bind(&submission.email_hash)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+        new_id
+    };
+
+    let response_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        r#"
+        INSERT INTO responses (id, respondent_id, form_id, role, metadata)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&response_id)
+    .bind(&respondent_id)
+    .bind(&submission.form_id)
+    .bind(&submission.role)
+    .bind(serde_json::json!({}))
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    for (question_id, value) in &submission.answers {
+        sqlx::query(
+            r#"
+            INSERT INTO answers (id, response_id, question_id, value)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&response_id)
+        .bind(question_id)
+        .bind(value)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+    }
+
+    // The `used_at IS NULL` guard makes the loser of two racing submissions fail cleanly
+    if let Some(invitation_id) = &submission.invitation_id {
+        let result = sqlx::query(
+            "UPDATE invitations SET used_at = ?, response_id = ? WHERE id = ? AND used_at IS NULL",
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(&response_id)
+        .bind(invitation_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::BadRequest(
+                "This invitation has already been used to submit a response".to_string(),
+            ));
+        }
+    }
+
+    let (response_count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM responses WHERE form_id = ?")
+            .bind(&submission.form_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+    Ok(StoredSubmission {
+        response_id,
+        respondent_id,
+        response_count,
+    })
+}
+
+/// See [`ResponseRepository::delete_respondent`]
+async fn delete_respondent(
+    conn: &mut SqliteConnection,
+    respondent_id: &str,
+) -> Result<Option<Vec<String>>, AppError> {
+    let form_ids: Vec<(String,)> = sqlx::query_as(
+        "SELECT DISTINCT form_id FROM responses WHERE respondent_id = ? ORDER BY form_id",
+    )
+    .bind(respondent_id)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    // Unlink first, so no response points at a missing respondent
+    sqlx::query("UPDATE responses SET respondent_id = NULL WHERE respondent_id = ?")
+        .bind(respondent_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    let result = sqlx::query("DELETE FROM respondents WHERE id = ?")
+        .bind(respondent_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    Ok((result.rows_affected() > 0).then(|| form_ids.into_iter().map(|(id,)| id).collect()))
+}
+
+/// Everything that hangs off a form, children before parents
+const FORM_DEPENDENTS: &[&str] = &[
+    "DELETE FROM reminder_sends WHERE campaign_id IN (SELECT id FROM reminder_campaigns WHERE form_id = ?)",
+    "DELETE FROM reminder_campaigns WHERE form_id = ?",
+    "DELETE FROM webhooks WHERE form_id = ?",
+    "DELETE FROM digest_runs WHERE subscription_id IN (SELECT id FROM digest_subscriptions WHERE form_id = ?)",
+    "DELETE FROM digest_subscriptions WHERE form_id = ?",
+    "DELETE FROM notification_templates WHERE form_id = ?",
+    "DELETE FROM invitations WHERE form_id = ?",
+    "DELETE FROM answers WHERE response_id IN (SELECT id FROM responses WHERE form_id = ?)",
+    "DELETE FROM responses WHERE form_id = ?",
+    "DELETE FROM questions WHERE form_id = ?",
+    "DELETE FROM sections WHERE form_id = ?",
+];
+
+/// See [`FormRepository::delete_form`]
+async fn delete_form(conn: &mut SqliteConnection, form_id: &str) -> Result<Option<i64>, AppError> {
+    let (responses,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM responses WHERE form_id = ?")
+        .bind(form_id)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    for statement in FORM_DEPENDENTS {
+        sqlx::query(statement)
+            .bind(form_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+    }
+
+    let result = sqlx::query("DELETE FROM forms WHERE id = ?")
+        .bind(form_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    Ok((result.rows_affected() > 0).then_some(responses))
+}
+
+/// Queue outbox entries on the caller's transaction, a webhook event once per matching
+/// endpoint. The caller wakes the worker after committing.
+async fn queue_events(conn: &mut SqliteConnection, events: &[OutboxEvent]) -> Result<(), AppError> {
+    let endpoints: Vec<Webhook> = if events
+        .iter()
+        .any(|event| matches!(event, OutboxEvent::Webhook(_)))
+    {
+        sqlx::query_as("SELECT * FROM webhooks WHERE active = 1")
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(AppError::Database)?
+    } else {
+        Vec::new()
+    };
+
+    for event in events {
+        match event {
+            OutboxEvent::Email(message) => {
+                enqueue(conn, &OutboxMessage::Email(message.clone())).await?;
+            }
+            OutboxEvent::Webhook(event) => {
+                for delivery in webhooks::deliveries(&endpoints, event) {
+                    enqueue(conn, &OutboxMessage::Webhook(delivery)).await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Queue a message inside the caller's transaction.
+/// Call [`crate::outbox::wake`] after committing so the worker picks it up straight away.
+pub(crate) async fn enqueue(
+    conn: &mut SqliteConnection,
+    message: &OutboxMessage,
+) -> Result<(), AppError> {
+    sqlx::query("INSERT INTO outbox (id, kind, payload) VALUES (?, ?, ?)")
+        .bind(Uuid::new_v4().to_string())
+        .bind(message.kind())
+        .bind(message.payload())
+        .execute(conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    Ok(())
+}
+
+/// Remove a respondent's details from the outbox, whatever the entries' status.
+/// Webhook payloads lose their `respondent` object and emails mentioning the
+/// respondent's address are dropped. Runs before the respondent row is deleted.
+async fn scrub_outbox(conn: &mut SqliteConnection, respondent_id: &str) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE outbox SET payload = json_remove(payload, '$.data.respondent')
+        WHERE kind = 'webhook' AND json_extract(payload, '$.data.respondent.respondent_id') = ?
+        "#,
+    )
+    .bind(respondent_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+
+    let email: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT email FROM respondents WHERE id = ?")
+            .bind(respondent_id)
+            .fetch_optional(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+
+    if let Some(email) = email
+        .and_then(|(email,)| email)
+        .filter(|e| !e.trim().is_empty())
+    {
+        sqlx::query("DELETE FROM outbox WHERE kind = 'email' AND instr(payload, ?) > 0")
+            .bind(email)
+            .execute(&mut *conn)
+            .await
+            .map_err(AppError::Database)?;
+    }
+
+    Ok(())
+}
+
+/// Sorts shared by the response listings
+const RESPONSE_SORTS: &[SortField] = &[
+    SortField {
+        name: "submitted_at",
+        column: "COALESCE(r.submitted_at, '')",
+    },
+    SortField {
+        name: "role",
+        column: "COALESCE(r.role, '')",
+    },
+];
+
+/// Sorts for listings that join `respondents res` and may order by PII
+const RESPONSE_PII_SORTS: &[SortField] = &[
+    SortField {
+        name: "submitted_at",
+        column: "COALESCE(r.submitted_at, '')",
+    },
+    SortField {
+        name: "role",
+        column: "COALESCE(r.role, '')",
+    },
+    SortField {
+        name: "name",
+        column: "COALESCE(res.name, '')",
+    },
+    SortField {
+        name: "email",
+        column: "COALESCE(res.email, '')",
+    },
+];
+
+pub(crate) static RESPONSE_LISTING: Listing = Listing {
+    id_column: "r.id",
+    sorts: RESPONSE_SORTS,
+    default_sort: "-submitted_at",
+};
+
+pub(crate) static RESPONSE_PII_LISTING: Listing = Listing {
+    id_column: "r.id",
+    sorts: RESPONSE_PII_SORTS,
+    default_sort: "-submitted_at",
+};
+
+pub(crate) static INVITATION_LISTING: Listing = Listing {
+    id_column: "id",
+    sorts: &[
+        SortField {
+            name: "name",
+            column: "name",
+        },
+        SortField {
+            name: "email",
+            column: "email",
+        },
+        SortField {
+            name: "role",
+            column: "COALESCE(role, '')",
+        },
+        SortField {
+            name: "created_at",
+            column: "COALESCE(created_at, '')",
+        },
+    ],
+    default_sort: "name",
+};
+
+/// Append `AND ...` conditions for `filters` on the `responses r` alias
+pub(crate) fn push_response_filters(
+    query: &mut QueryBuilder<'_, Sqlite>,
+    filters: &ResponseFilters,
+) {
+    if let Some(form_id) = &filters.form_id {
+        query.push(" AND r.form_id = ").push_bind(form_id.clone());
+    }
+    if let Some(role) = &filters.role {
+        query.push(" AND r.role = ").push_bind(role.clone());
+    }
+    // Stored as CURRENT_TIMESTAMP text, so bounds compare in the same format
+    if let Some(after) = &filters.submitted_after {
+        query
+            .push(" AND r.submitted_at >= ")
+            .push_bind(after.format(DB_TIMESTAMP_FORMAT).to_string());
+    }
+    if let Some(before) = &filters.submitted_before {
+        query
+            .push(" AND r.submitted_at < ")
+            .push_bind(before.format(DB_TIMESTAMP_FORMAT).to_string());
+    }
+
+    for condition in &filters.answers {
+        query
+            .push(
+                " AND EXISTS (SELECT 1 FROM answers fa WHERE fa.response_id = r.id AND fa.question_id = ",
+            )
+            .push_bind(condition.question_id.clone());
+
+        // The operator comes from a fixed list, never from the request, so it is safe to inline
+        match &condition.value {
+            AnswerValue::Number(number) => {
+                query
+                    .push(
+                        r#" AND (CASE
+                            WHEN json_type(fa.value) IN ('integer', 'real') THEN CAST(fa.value AS REAL)
+                            WHEN json_type(fa.value) = 'object' THEN CAST(json_extract(fa.value, '$.rating') AS REAL)
+                        END) "#,
+                    )
+                    .push(condition.operator)
+                    .push(" ")
+                    .push_bind(*number);
+            }
+            AnswerValue::Text(text) => {
+                query
+                    .push(" AND json_type(fa.value) = 'text' AND json_extract(fa.value, '$') ")
+                    .push(condition.operator)
+                    .push(" ")
+                    .push_bind(text.clone());
+            }
+        }
+        query.push(")");
+    }
+}
+
+/// Count the rows of a listing and fetch one page of them; see [`PageRequest::queries`]
+pub async fn fetch_page<T>(
+    db: &SqlitePool,
+    request: &PageRequest,
+    select: &str,
+    from: &str,
+    filters: impl Fn(&mut QueryBuilder<'_, Sqlite>),
+) -> Result<Page<T>, AppError>
+where
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    let (mut count, mut query) = request.queries(select, from, filters);
+    let (total,): (i64,) = count
+        .build_query_as()
+        .fetch_one(db)
+        .await
+        .map_err(AppError::Database)?;
+    let rows: Vec<Keyed<T>> = query
+        .build_query_as()
+        .fetch_all(db)
+        .await
+        .map_err(AppError::Database)?;
+
+    Ok(request.finish(rows, total))
+}
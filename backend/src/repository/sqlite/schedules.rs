@@ -0,0 +1,444 @@
+//! Reminder campaigns and digest subscriptions, the two email schedules
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use super::{enqueue, fetch_page, SqliteStore};
+use crate::{
+    db::{parse_db_timestamp, DB_TIMESTAMP_FORMAT},
+    email::EmailMessage,
+    error::AppError,
+    models::{DigestRun, DigestSubscription, Invitation, ReminderCampaign},
+    outbox::OutboxMessage,
+    pagination::{Listing, Page, PageParams, SortField},
+    repository::{DigestRepository, NewDigestRun, ReminderRepository},
+};
+
+static CAMPAIGN_LISTING: Listing = Listing {
+    id_column: "id",
+    sorts: &[
+        SortField {
+            name: "created_at",
+            column: "COALESCE(created_at, '')",
+        },
+        SortField {
+            name: "closes_at",
+            column: "closes_at",
+        },
+    ],
+    default_sort: "-created_at",
+};
+
+static SUBSCRIPTION_LISTING: Listing = Listing {
+    id_column: "id",
+    sorts: &[
+        SortField {
+            name: "email",
+            column: "email",
+        },
+        SortField {
+            name: "created_at",
+            column: "COALESCE(created_at, '')",
+        },
+    ],
+    default_sort: "email",
+};
+
+#[async_trait]
+impl ReminderRepository for SqliteStore {
+    async fn create_campaign(&self, campaign: &ReminderCampaign) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO reminder_campaigns (id, form_id, subject, body_template, closes_at, days_before, active)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&campaign.id)
+        .bind(&campaign.form_id)
+        .bind(&campaign.subject)
+        .bind(&campaign.body_template)
+        .bind(&campaign.closes_at)
+        .bind(&campaign.days_before)
+        .bind(campaign.active)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(())
+    }
+
+    async fn find_campaign(&self, campaign_id: &str) -> Result<Option<ReminderCampaign>, AppError> {
+        sqlx::query_as("SELECT * FROM reminder_campaigns WHERE id = ?")
+            .bind(campaign_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn active_campaigns(&self) -> Result<Vec<ReminderCampaign>, AppError> {
+        sqlx::query_as("SELECT * FROM reminder_campaigns WHERE active = 1")
+            .fetch_all(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn list_campaigns(
+        &self,
+        form_id: &str,
+        page: &PageParams,
+    ) -> Result<Page<ReminderCampaign>, AppError> {
+        fetch_page(
+            &self.db,
+            &CAMPAIGN_LISTING.request(page)?,
+            "*",
+            "reminder_campaigns",
+            |query| {
+                query.push(" AND form_id = ").push_bind(form_id.to_string());
+            },
+        )
+        .await
+    }
+
+    async fn send_counts(&self, form_id: &str) -> Result<HashMap<String, i64>, AppError> {
+        let counts: Vec<(String, i64)> = sqlx::query_as(
+            r#"
+            SELECT s.campaign_id, COUNT(*)
+            FROM reminder_sends s
+            JOIN reminder_campaigns c ON c.id = s.campaign_id
+            WHERE c.form_id = ?
+            GROUP BY s.campaign_id
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(counts.into_iter().collect())
+    }
+
+    async fn update_campaign(&self, campaign: &ReminderCampaign) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE reminder_campaigns
+            SET subject = ?, body_template = ?, closes_at = ?, days_before = ?, active = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&campaign.subject)
+        .bind(&campaign.body_template)
+        .bind(&campaign.closes_at)
+        .bind(&campaign.days_before)
+        .bind(campaign.active)
+        .bind(&campaign.id)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_campaign(&self, campaign_id: &str) -> Result<bool, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM reminder_sends WHERE campaign_id = ?")
+            .bind(campaign_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        let result = sqlx::query("DELETE FROM reminder_campaigns WHERE id = ?")
+            .bind(campaign_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(true)
+    }
+
+    async fn pending_recipients(
+        &self,
+        campaign: &ReminderCampaign,
+        days_before: i64,
+    ) -> Result<Vec<Invitation>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT i.*
+            FROM invitations i
+            WHERE i.form_id = ?
+                AND i.used_at IS NULL
+                AND NOT EXISTS (
+                    SELECT 1 FROM reminder_sends s
+                    WHERE s.campaign_id = ? AND s.invitation_id = i.id AND s.days_before = ?
+                )
+            ORDER BY i.name
+            "#,
+        )
+        .bind(&campaign.form_id)
+        .bind(&campaign.id)
+        .bind(days_before)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn queue_reminders(
+        &self,
+        campaign_id: &str,
+        days_before: i64,
+        reminders: &[(String, EmailMessage)],
+    ) -> Result<usize, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+        let mut queued = 0;
+
+        for (invitation_id, message) in reminders {
+            // Claim the invitee for this step; an overlapping run that got there first has
+            // already queued their reminder
+            let claim = sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO reminder_sends (id, campaign_id, invitation_id, days_before)
+                VALUES (?, ?, ?, ?)
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(campaign_id)
+            .bind(invitation_id)
+            .bind(days_before)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+            if claim.rows_affected() == 0 {
+                continue;
+            }
+
+            enqueue(&mut tx, &OutboxMessage::Email(message.clone())).await?;
+            queued += 1;
+        }
+
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(queued)
+    }
+}
+
+#[async_trait]
+impl DigestRepository for SqliteStore {
+    async fn create_subscription(
+        &self,
+        subscription: &DigestSubscription,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO digest_subscriptions (id, form_id, email, frequency)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(&subscription.id)
+        .bind(&subscription.form_id)
+        .bind(&subscription.email)
+        .bind(&subscription.frequency)
+        .execute(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn find_subscription(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Option<DigestSubscription>, AppError> {
+        sqlx::query_as("SELECT * FROM digest_subscriptions WHERE id = ?")
+            .bind(subscription_id)
+            .fetch_optional(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn all_subscriptions(&self) -> Result<Vec<DigestSubscription>, AppError> {
+        sqlx::query_as("SELECT * FROM digest_subscriptions")
+            .fetch_all(&self.db)
+            .await
+            .map_err(AppError::Database)
+    }
+
+    async fn list_subscriptions(
+        &self,
+        form_id: &str,
+        page: &PageParams,
+    ) -> Result<Page<DigestSubscription>, AppError> {
+        fetch_page(
+            &self.db,
+            &SUBSCRIPTION_LISTING.request(page)?,
+            "*",
+            "digest_subscriptions",
+            |query| {
+                query.push(" AND form_id = ").push_bind(form_id.to_string());
+            },
+        )
+        .await
+    }
+
+    async fn last_runs(&self, form_id: &str) -> Result<Vec<DigestRun>, AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT dr.*
+            FROM digest_runs dr
+            JOIN digest_subscriptions ds ON ds.id = dr.subscription_id
+            WHERE ds.form_id = ?
+                AND dr.period_end = (
+                    SELECT MAX(period_end) FROM digest_runs WHERE subscription_id = dr.subscription_id
+                )
+            "#,
+        )
+        .bind(form_id)
+        .fetch_all(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn delete_subscription(&self, subscription_id: &str) -> Result<bool, AppError> {
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+
+        sqlx::query("DELETE FROM digest_runs WHERE subscription_id = ?")
+            .bind(subscription_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        let result = sqlx::query("DELETE FROM digest_subscriptions WHERE id = ?")
+            .bind(subscription_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(true)
+    }
+
+    async fn last_period_end(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, AppError> {
+        let last_run: Option<(String,)> = sqlx::query_as(
+            "SELECT period_end FROM digest_runs WHERE subscription_id = ? ORDER BY period_end DESC LIMIT 1",
+        )
+        .bind(subscription_id)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        last_run
+            .map(|(end,)| {
+                parse_db_timestamp(&end).ok_or_else(|| {
+                    AppError::InternalError(format!("Invalid digest timestamp '{}'", end))
+                })
+            })
+            .transpose()
+    }
+
+    async fn response_counts(
+        &self,
+        form_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(i64, i64), AppError> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                COUNT(CASE WHEN datetime(submitted_at) > datetime(?) THEN 1 END),
+                COUNT(*)
+            FROM responses
+            WHERE form_id = ? AND datetime(submitted_at) <= datetime(?)
+            "#,
+        )
+        .bind(start.format(DB_TIMESTAMP_FORMAT).to_string())
+        .bind(form_id)
+        .bind(end.format(DB_TIMESTAMP_FORMAT).to_string())
+        .fetch_one(&self.db)
+        .await
+        .map_err(AppError::Database)
+    }
+
+    async fn average_rating(
+        &self,
+        form_id: &str,
+        until: DateTime<Utc>,
+    ) -> Result<Option<f64>, AppError> {
+        let (average,): (Option<f64>,) = sqlx::query_as(
+            r#"
+            SELECT AVG(CASE
+                WHEN json_type(a.value) = 'integer' THEN CAST(a.value as REAL)
+                WHEN json_type(a.value) = 'object' AND json_extract(a.value, '$.rating') IS NOT NULL
+                    THEN CAST(json_extract(a.value, '$.rating') as REAL)
+                ELSE NULL
+            END)
+            FROM answers a
+            JOIN responses r ON r.id = a.response_id
+            JOIN questions q ON q.id = a.question_id
+            WHERE r.form_id = ? AND q.type = 'likert' AND datetime(r.submitted_at) <= datetime(?)
+            "#,
+        )
+        .bind(form_id)
+        .bind(until.format(DB_TIMESTAMP_FORMAT).to_string())
+        .fetch_one(&self.db)
+        .await
+        .map_err(AppError::Database)?;
+
+        Ok(average)
+    }
+
+    async fn record_run(
+        &self,
+        run: &NewDigestRun,
+        message: &EmailMessage,
+    ) -> Result<bool, AppError> {
+        let period_start = run.period_start.format(DB_TIMESTAMP_FORMAT).to_string();
+        let period_end = run.period_end.format(DB_TIMESTAMP_FORMAT).to_string();
+
+        let mut tx = self.db.begin().await.map_err(AppError::Database)?;
+
+        // Claim the period first, so overlapping runs queue it once
+        let claimed = sqlx::query(
+            r#"
+            INSERT INTO digest_runs (id, subscription_id, period_start, period_end, new_responses, total_responses, average_score)
+            SELECT ?, ?, ?, ?, ?, ?, ?
+            WHERE NOT EXISTS (
+                SELECT 1 FROM digest_runs WHERE subscription_id = ? AND period_end > ?
+            )
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&run.subscription_id)
+        .bind(&period_start)
+        .bind(&period_end)
+        .bind(run.new_responses)
+        .bind(run.total_responses)
+        .bind(run.average_score)
+        .bind(&run.subscription_id)
+        .bind(&period_start)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::Database)?;
+
+        if claimed.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        enqueue(&mut tx, &OutboxMessage::Email(message.clone())).await?;
+        tx.commit().await.map_err(AppError::Database)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,178 @@
+//! Full-text search over answers, backed by the `answer_search` FTS5 table
+
+use async_trait::async_trait;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+
+use super::{push_response_filters, SqliteStore};
+use crate::{
+    error::AppError,
+    filters::ResponseFilters,
+    repository::{SearchMatch, SearchRepository, SearchTerm},
+    search::{MATCH_END, MATCH_START, SNIPPET_TOKENS},
+};
+
+/// Text answers to text/textarea questions and the comment on any structured answer
+const INDEX_RESPONSE: &str = r#"
+    INSERT INTO answer_search (body, form_id, response_id, question_id, field)
+    SELECT body, form_id, response_id, question_id, field
+    FROM (
+        SELECT
+            CASE
+                WHEN json_valid(a.value) = 0 THEN NULL
+                WHEN json_type(a.value) = 'text' AND q.type IN ('text', 'textarea')
+                    THEN json_extract(a.value, '$')
+                WHEN json_type(a.value) = 'object' AND json_type(a.value, '$.comment') = 'text'
+                    THEN json_extract(a.value, '$.comment')
+            END AS body,
+            r.form_id,
+            a.response_id,
+            a.question_id,
+            CASE
+                WHEN json_valid(a.value) = 0 THEN 'text'
+                WHEN json_type(a.value) = 'object' THEN 'comment'
+                ELSE 'text'
+            END AS field
+        FROM answers a
+        JOIN responses r ON r.id = a.response_id
+        LEFT JOIN questions q ON q.id = a.question_id
+        WHERE a.response_id = ?
+    )
+    WHERE body IS NOT NULL AND trim(body) != ''
+"#;
+
+/// Add a response's free-text answers to the search index, inside the submit transaction
+pub(crate) async fn index_response(
+    conn: &mut SqliteConnection,
+    response_id: &str,
+) -> Result<(), AppError> {
+    sqlx::query(INDEX_RESPONSE)
+        .bind(response_id)
+        .execute(conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    Ok(())
+}
+
+/// Drop every indexed answer for a form, inside the delete transaction
+pub(crate) async fn remove_form(
+    conn: &mut SqliteConnection,
+    form_id: &str,
+) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM answer_search WHERE form_id = ?")
+        .bind(form_id)
+        .execute(conn)
+        .await
+        .map_err(AppError::Database)?;
+
+    Ok(())
+}
+
+/// Turn search terms into an FTS5 query where every term must match.
+/// Words are quoted so punctuation and FTS operators in the input are taken literally.
+fn match_expression(terms: &[SearchTerm]) -> String {
+    terms
+        .iter()
+        .map(|term| {
+            let quoted = format!("\"{}\"", term.word.replace('"', "\"\""));
+            if term.prefix {
+                format!("{}*", quoted)
+            } else {
+                quoted
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[async_trait]
+impl SearchRepository for SqliteStore {
+    async fn search_answers(
+        &self,
+        terms: &[SearchTerm],
+        filters: &ResponseFilters,
+        limit: i64,
+        include_pii: bool,
+    ) -> Result<(i64, Vec<SearchMatch>), AppError> {
+        let expression = match_expression(terms);
+        let push_match = |query: &mut QueryBuilder<'_, Sqlite>| {
+            query
+                .push(" WHERE answer_search MATCH ")
+                .push_bind(expression.clone());
+            if let Some(form_id) = &filters.form_id {
+                query
+                    .push(" AND answer_search.form_id = ")
+                    .push_bind(form_id.clone());
+            }
+            push_response_filters(query, filters);
+        };
+
+        let mut count = QueryBuilder::new(
+            "SELECT COUNT(*) FROM answer_search JOIN responses r ON r.id = answer_search.response_id",
+        );
+        push_match(&mut count);
+        let (total,): (i64,) = count
+            .build_query_as()
+            .fetch_one(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        let mut query = QueryBuilder::new(format!(
+            r#"
+            SELECT
+                answer_search.response_id,
+                answer_search.question_id,
+                q.title AS question_title,
+                answer_search.field,
+                snippet(answer_search, 0, char({}), char({}), '…', {}) AS snippet,
+                r.role,
+                r.submitted_at,
+                CASE WHEN {pii} THEN res.name END AS respondent_name,
+                CASE WHEN {pii} THEN res.email END AS respondent_email
+            FROM answer_search
+            JOIN responses r ON r.id = answer_search.response_id
+            LEFT JOIN questions q ON q.id = answer_search.question_id
+            LEFT JOIN respondents res ON res.id = r.respondent_id
+            "#,
+            MATCH_START as u32,
+            MATCH_END as u32,
+            SNIPPET_TOKENS,
+            pii = include_pii as i32,
+        ));
+        push_match(&mut query);
+        query.push(" ORDER BY bm25(answer_search), r.submitted_at DESC LIMIT ");
+        query.push_bind(limit);
+
+        let matches = query
+            .build_query_as()
+            .fetch_all(&self.db)
+            .await
+            .map_err(AppError::Database)?;
+
+        Ok((total, matches))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(word: &str, prefix: bool) -> SearchTerm {
+        SearchTerm {
+            word: word.to_string(),
+            prefix,
+        }
+    }
+
+    #[test]
+    fn terms_are_quoted_and_support_prefixes() {
+        assert_eq!(
+            match_expression(&[term("budget", false), term("overrun", false)]),
+            "\"budget\" \"overrun\""
+        );
+        assert_eq!(
+            match_expression(&[term("budg", true), term("NOT", false), term("\"x", false)]),
+            "\"budg\"* \"NOT\" \"\"\"x\""
+        );
+    }
+}
@@ -0,0 +1,1268 @@
+//! The same checks run against every backend: SQLite in memory, and PostgreSQL when
+//! `TEST_POSTGRES_URL` is set (CI starts a container for it). Both databases get their
+//! backend's real migrations.
+
+use chrono::{Duration, TimeZone, Utc};
+use futures::TryStreamExt;
+use serde_json::json;
+
+use super::*;
+use crate::{
+    email::EmailMessage,
+    filters::ResponseFilterQuery,
+    models::{
+        BankQuestion, DigestSubscription, FormTemplate, Invitation, ReminderCampaign, Webhook,
+    },
+};
+
+/// Plain SQL literals, so the same script seeds both backends
+const SEED: &str = r#"
+    INSERT INTO respondents (id, name, email, email_hash) VALUES ('p1', 'Ada', 'ada@example.com', 'h1');
+    INSERT INTO responses (id, respondent_id, form_id, role, submitted_at) VALUES
+        ('r1', 'p1', 'review', 'Staff', '2025-09-01 10:00:00'),
+        ('r2', NULL, 'review', 'Staff', '2025-09-01 10:00:00'),
+        ('r3', NULL, 'review', 'Board', '2025-09-01 10:00:00');
+    INSERT INTO answers (id, response_id, question_id, value) VALUES
+        ('a1', 'r1', 'q1', '4'),
+        ('a2', 'r2', 'q1', '{"rating": 2, "comment": "Needs work"}'),
+        ('a3', 'r3', 'q1', '4'),
+        ('a4', 'r1', 'q2', '"Great year"');
+    INSERT INTO invitations (id, form_id, name, email, token) VALUES
+        ('i1', 'review', 'Grace', 'grace@example.com', 'token-grace');
+"#;
+
+fn invitation() -> Invitation {
+    Invitation {
+        id: "i1".to_string(),
+        form_id: "review".to_string(),
+        name: "Grace".to_string(),
+        email: "grace@example.com".to_string(),
+        role: None,
+        token: "token-grace".to_string(),
+        response_id: None,
+        used_at: None,
+        created_at: String::new(),
+    }
+}
+
+/// Rows of a response listing as `(id, respondent name)`
+async fn listed(
+    repos: &Repositories,
+    query: ResponseFilterQuery,
+    page: PageParams,
+    include_pii: bool,
+) -> (Vec<(String, Option<String>)>, Option<String>, i64) {
+    let filters = query.parse().unwrap().for_form(Some("review"));
+    let page = repos
+        .responses
+        .list_responses(&filters, &page, include_pii)
+        .await
+        .unwrap();
+    let rows = page
+        .items
+        .into_iter()
+        .map(|row| (row.id, row.respondent_name))
+        .collect();
+    (rows, page.next_cursor, page.total)
+}
+
+fn definition() -> FormDefinition {
+    let question = |id: &str, position: i32, question_type: &str| Question {
+        id: id.to_string(),
+        form_id: "review".to_string(),
+        section_id: Some("s1".to_string()),
+        position,
+        title: format!("Question {}", id),
+        description: None,
+        kind: QuestionKind::from_parts(
+            question_type,
+            &json!({ "required": true, "bankId": format!("bank-{}", id) }),
+        )
+        .unwrap(),
+    };
+
+    FormDefinition {
+        form: Form {
+            id: "review".to_string(),
+            title: "Annual Review".to_string(),
+            description: Some("Board review".to_string()),
+            instructions: None,
+            status: "published".to_string(),
+            welcome_message: None,
+            closing_message: Some("Thanks".to_string()),
+            settings: Some(json!({ "allowAnonymous": false })),
+        },
+        sections: vec![Section {
+            id: "s1".to_string(),
+            form_id: "review".to_string(),
+            title: "Leadership".to_string(),
+            description: None,
+            position: 1,
+        }],
+        questions: vec![question("q1", 1, "likert"), question("q2", 2, "textarea")],
+    }
+}
+
+async fn check_backend(repos: Repositories, seed: impl std::future::Future<Output = ()>) {
+    assert!(repos.forms.list_forms().await.unwrap().is_empty());
+    assert!(!repos.forms.form_exists("review").await.unwrap());
+
+    repos.forms.create_form(&definition()).await.unwrap();
+    seed.await;
+
+    assert!(repos.forms.form_exists("review").await.unwrap());
+    let forms = repos.forms.list_forms().await.unwrap();
+    assert_eq!(forms.len(), 1);
+    assert_eq!(forms[0].title, "Annual Review");
+    assert!(!forms[0].created_at.is_empty());
+
+    let found = repos.forms.find_form("review").await.unwrap().unwrap();
+    assert_eq!(found, definition());
+    assert!(repos.forms.find_form("missing").await.unwrap().is_none());
+
+    let stats = repos.responses.form_stats("review").await.unwrap();
+    assert_eq!(stats.total_responses, 3);
+    assert_eq!(stats.role_distribution[0].role.as_deref(), Some("Staff"));
+    assert_eq!(stats.role_distribution[0].count, 2);
+    assert_eq!(stats.question_stats.len(), 1);
+    let likert = &stats.question_stats[0];
+    assert_eq!(likert.question_id, "q1");
+    assert_eq!(likert.bank_question_id.as_deref(), Some("bank-q1"));
+    assert_eq!(likert.response_count, 3);
+    assert!((likert.average_rating.unwrap() - 10.0 / 3.0).abs() < 1e-9);
+    let distribution: Vec<(i32, i64)> = likert
+        .rating_distribution
+        .iter()
+        .map(|bucket| (bucket.rating, bucket.count))
+        .collect();
+    assert_eq!(distribution, vec![(2, 1), (4, 2)]);
+
+    let ratings: Vec<(String, Option<String>, String, i32)> = repos
+        .responses
+        .likert_ratings("review")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|rating| {
+            (
+                rating.response_id,
+                rating.role,
+                rating.question_id,
+                rating.rating,
+            )
+        })
+        .collect();
+    let rating = |response_id: &str, role: &str, value: i32| {
+        (
+            response_id.to_string(),
+            Some(role.to_string()),
+            "q1".to_string(),
+            value,
+        )
+    };
+    assert_eq!(
+        ratings,
+        vec![
+            rating("r1", "Staff", 4),
+            rating("r2", "Staff", 2),
+            rating("r3", "Board", 4)
+        ]
+    );
+
+    let mut added = definition().questions.remove(1);
+    added.id = "q3".to_string();
+    added.position = 3;
+    repos
+        .forms
+        .add_questions("review", std::slice::from_ref(&added))
+        .await
+        .unwrap();
+    let found = repos.forms.find_form("review").await.unwrap().unwrap();
+    assert_eq!(found.questions.last(), Some(&added));
+
+    let answers = repos.responses.answer_counts("review").await.unwrap();
+    assert_eq!(answers.len(), 2);
+    assert_eq!((answers["q1"], answers["q2"]), (3, 1));
+    assert!(repos
+        .responses
+        .answer_counts("other")
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert!(repos.responses.has_submitted("review", "h1").await.unwrap());
+    assert!(!repos.responses.has_submitted("review", "h2").await.unwrap());
+    assert!(!repos.responses.has_submitted("other", "h1").await.unwrap());
+    assert_eq!(
+        repos.responses.totals().await.unwrap(),
+        Totals {
+            forms: 1,
+            responses: 3,
+            respondents: 1
+        }
+    );
+
+    let submission = NewSubmission {
+        form_id: "review".to_string(),
+        respondent_name: "Grace".to_string(),
+        respondent_email: "grace@example.com".to_string(),
+        email_hash: "h2".to_string(),
+        role: Some("Board".to_string()),
+        answers: vec![
+            ("q2".to_string(), json!("Clear priorities")),
+            ("q1".to_string(), json!(5)),
+        ],
+        invitation_id: Some("i1".to_string()),
+    };
+    assert_eq!(
+        repos.invitations.count_invitations("review").await.unwrap(),
+        1
+    );
+    let found = repos
+        .invitations
+        .find_invitation("review", "token-grace")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((found.id.as_str(), found.used_at), ("i1", None));
+    assert!(repos
+        .invitations
+        .find_invitation("other", "token-grace")
+        .await
+        .unwrap()
+        .is_none());
+
+    let stored = repos
+        .responses
+        .submit_response(&submission, &|_| Vec::new())
+        .await
+        .unwrap();
+    assert_eq!(stored.response_count, 4);
+    assert!(repos.responses.has_submitted("review", "h2").await.unwrap());
+    assert!(matches!(
+        repos
+            .responses
+            .submit_response(&submission, &|_| Vec::new())
+            .await,
+        Err(AppError::BadRequest(_))
+    ));
+
+    // The invitation is spent, so another respondent cannot use it
+    let used = repos
+        .invitations
+        .find_invitation("review", "token-grace")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(used.used_at.is_some());
+    assert_eq!(used.response_id.as_ref(), Some(&stored.response_id));
+    let reused = NewSubmission {
+        email_hash: "h3".to_string(),
+        ..submission.clone()
+    };
+    assert!(matches!(
+        repos
+            .responses
+            .submit_response(&reused, &|_| Vec::new())
+            .await,
+        Err(AppError::BadRequest(_))
+    ));
+    assert_eq!(repos.responses.count_responses("review").await.unwrap(), 4);
+
+    // Newest first by default, with PII only when asked for
+    let all = || ResponseFilterQuery::default();
+    let (rows, next, total) = listed(&repos, all(), PageParams::default(), true).await;
+    let ids: Vec<&str> = rows.iter().map(|(id, _)| id.as_str()).collect();
+    assert_eq!(ids, vec![stored.response_id.as_str(), "r3", "r2", "r1"]);
+    assert_eq!(rows[0].1.as_deref(), Some("Grace"));
+    assert_eq!(rows[3].1.as_deref(), Some("Ada"));
+    assert_eq!((next, total), (None, 4));
+    let (rows, ..) = listed(&repos, all(), PageParams::default(), false).await;
+    assert!(rows.iter().all(|(_, name)| name.is_none()));
+
+    let by_name = |cursor| PageParams {
+        cursor,
+        limit: Some(2),
+        sort: Some("name".to_string()),
+    };
+    let (first, next, _) = listed(&repos, all(), by_name(None), true).await;
+    let (second, last, _) = listed(&repos, all(), by_name(next), true).await;
+    let names: Vec<Option<&str>> = first
+        .iter()
+        .chain(&second)
+        .map(|(_, name)| name.as_deref())
+        .collect();
+    assert_eq!(names, vec![None, None, Some("Ada"), Some("Grace")]);
+    assert!(last.is_none());
+    assert!(repos
+        .responses
+        .list_responses(&ResponseFilters::default(), &by_name(None), false)
+        .await
+        .is_err());
+
+    let filtered = |role: Option<&str>, answer: &str| ResponseFilterQuery {
+        role: role.map(str::to_string),
+        answer: Some(answer.to_string()),
+        ..Default::default()
+    };
+    let (rows, _, total) =
+        listed(&repos, filtered(None, "q1>=4"), PageParams::default(), true).await;
+    assert_eq!(total, 3);
+    assert!(rows.iter().all(|(id, _)| id != "r2"));
+    let (rows, ..) = listed(
+        &repos,
+        filtered(Some("Staff"), "q1<3"),
+        PageParams::default(),
+        true,
+    )
+    .await;
+    assert_eq!(rows, vec![("r2".to_string(), None)]);
+    let (rows, ..) = listed(
+        &repos,
+        filtered(None, "q2=Great year"),
+        PageParams::default(),
+        true,
+    )
+    .await;
+    assert_eq!(rows, vec![("r1".to_string(), Some("Ada".to_string()))]);
+    let future = ResponseFilterQuery {
+        submitted_after: Some("2999-01-01".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        listed(&repos, future, PageParams::default(), true).await.2,
+        0
+    );
+
+    let mut answers = repos
+        .responses
+        .answers_by_response(&[stored.response_id.as_str()])
+        .await
+        .unwrap();
+    let answers: Vec<(String, String, JsonValue)> = answers
+        .remove(&stored.response_id)
+        .unwrap()
+        .into_iter()
+        .map(|answer| (answer.question_id, answer.question_title, answer.value))
+        .collect();
+    assert_eq!(
+        answers,
+        vec![
+            ("q1".to_string(), "Question q1".to_string(), json!(5)),
+            (
+                "q2".to_string(),
+                "Question q2".to_string(),
+                json!("Clear priorities")
+            ),
+        ]
+    );
+    assert!(repos
+        .responses
+        .answers_by_response(&[])
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(
+        repos
+            .responses
+            .delete_respondent(&stored.respondent_id, &|form_ids| {
+                assert_eq!(form_ids, ["review".to_string()]);
+                Vec::new()
+            })
+            .await
+            .unwrap(),
+        Some(vec!["review".to_string()])
+    );
+    assert!(!repos.responses.has_submitted("review", "h2").await.unwrap());
+    assert_eq!(repos.responses.count_responses("review").await.unwrap(), 4);
+    assert_eq!(
+        repos
+            .responses
+            .delete_respondent(&stored.respondent_id, &|_| unreachable!())
+            .await
+            .unwrap(),
+        None
+    );
+
+    let texts = |question_id: &'static str, comments: bool| {
+        let responses = repos.responses.clone();
+        async move {
+            let mut texts = responses
+                .question_texts("review", question_id, comments)
+                .await
+                .unwrap();
+            texts.sort();
+            texts
+        }
+    };
+    assert_eq!(
+        texts("q2", false).await,
+        vec![
+            None,
+            None,
+            Some("Clear priorities".to_string()),
+            Some("Great year".to_string())
+        ]
+    );
+    assert_eq!(
+        texts("q1", true).await,
+        vec![None, None, None, Some("Needs work".to_string())]
+    );
+
+    // One row per answer in question order, and a bare row for a response without any
+    let rows: Vec<ExportRow> = repos
+        .responses
+        .export_rows("review", true)
+        .try_collect()
+        .await
+        .unwrap();
+    let exported: Vec<(&str, Option<&str>, Option<JsonValue>)> = rows
+        .iter()
+        .map(|row| {
+            (
+                row.response_id.as_str(),
+                row.question_id.as_deref(),
+                row.value
+                    .as_deref()
+                    .map(|value| serde_json::from_str(value).unwrap()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        exported,
+        vec![
+            ("r1", Some("q1"), Some(json!(4))),
+            ("r1", Some("q2"), Some(json!("Great year"))),
+            (
+                "r2",
+                Some("q1"),
+                Some(json!({ "rating": 2, "comment": "Needs work" }))
+            ),
+            ("r3", Some("q1"), Some(json!(4))),
+            (stored.response_id.as_str(), Some("q1"), Some(json!(5))),
+            (
+                stored.response_id.as_str(),
+                Some("q2"),
+                Some(json!("Clear priorities"))
+            ),
+        ]
+    );
+    assert_eq!(rows[0].name.as_deref(), Some("Ada"));
+    assert_eq!(rows[0].email.as_deref(), Some("ada@example.com"));
+    assert!(rows[2].name.is_none());
+    let anonymous: Vec<ExportRow> = repos
+        .responses
+        .export_rows("review", false)
+        .try_collect()
+        .await
+        .unwrap();
+    assert!(anonymous.iter().all(|row| row.name.is_none()));
+
+    let invite = |id: &str, name: &str, email: &str, role: Option<&str>| Invitation {
+        id: id.to_string(),
+        name: name.to_string(),
+        email: email.to_string(),
+        role: role.map(str::to_string),
+        token: format!("token-{}", id),
+        ..invitation()
+    };
+    assert_eq!(
+        repos
+            .invitations
+            .create_invitations(&[
+                invite("i2", "Linus", "linus@example.com", Some("Board")),
+                invite("i3", "Grace again", "grace@example.com", None),
+            ])
+            .await
+            .unwrap(),
+        vec![true, false]
+    );
+    let invited = |filters: InvitationFilters| {
+        let invitations = repos.invitations.clone();
+        async move {
+            let page = invitations
+                .list_invitations("review", &filters, &PageParams::default())
+                .await
+                .unwrap();
+            page.items
+                .into_iter()
+                .map(|invitation| invitation.name)
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(
+        invited(InvitationFilters::default()).await,
+        vec!["Grace", "Linus"]
+    );
+    let pending = InvitationFilters {
+        responded: Some(false),
+        ..Default::default()
+    };
+    assert_eq!(invited(pending).await, vec!["Linus"]);
+    let board = InvitationFilters {
+        role: Some("Board".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(invited(board).await, vec!["Linus"]);
+    let rates: Vec<(Option<String>, i64, i64)> = repos
+        .invitations
+        .response_rates("review")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|rate| (rate.role, rate.invited, rate.responded))
+        .collect();
+    assert_eq!(rates, vec![(None, 1, 1), (Some("Board".to_string()), 1, 0)]);
+    assert!(repos.invitations.delete_invitation("i2").await.unwrap());
+    assert!(!repos.invitations.delete_invitation("i2").await.unwrap());
+
+    assert!(repos
+        .notifications
+        .find_template("review")
+        .await
+        .unwrap()
+        .is_none());
+    let mut template = NotificationTemplate {
+        form_id: "review".to_string(),
+        enabled: true,
+        subject: "New response".to_string(),
+        html_template: "<p>{{role}}</p>".to_string(),
+        include_pii: false,
+    };
+    repos.notifications.save_template(&template).await.unwrap();
+    template.enabled = false;
+    repos.notifications.save_template(&template).await.unwrap();
+    let saved = repos
+        .notifications
+        .find_template("review")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (saved.enabled, saved.subject.as_str()),
+        (false, "New response")
+    );
+
+    let now = Utc.with_ymd_and_hms(2025, 9, 10, 12, 0, 0).unwrap();
+    let previous = repos
+        .forms
+        .set_status("review", "closed", now, &|previous| {
+            assert_eq!(previous, "published");
+            Vec::new()
+        })
+        .await
+        .unwrap();
+    assert_eq!(previous.as_deref(), Some("published"));
+    assert!(repos
+        .forms
+        .set_status("missing", "closed", now, &|_| unreachable!())
+        .await
+        .unwrap()
+        .is_none());
+
+    let mut replaced = definition();
+    replaced.form.title = "Annual Review 2025".to_string();
+    replaced.questions.truncate(1);
+    assert!(repos.forms.replace_form(&replaced, now, &[]).await.unwrap());
+    assert_eq!(
+        repos.forms.find_form("review").await.unwrap().unwrap(),
+        replaced
+    );
+    replaced.form.id = "missing".to_string();
+    assert!(!repos.forms.replace_form(&replaced, now, &[]).await.unwrap());
+
+    // A copy brings the responses along, and the invitation stays used with its response
+    let mut copy = replaced.clone();
+    copy.form.id = "review-copy".to_string();
+    for section in &mut copy.sections {
+        section.id = "s2".to_string();
+        section.form_id = "review-copy".to_string();
+    }
+    for question in &mut copy.questions {
+        question.id = "c1".to_string();
+        question.form_id = "review-copy".to_string();
+        question.section_id = Some("s2".to_string());
+    }
+    let question_ids = HashMap::from([("q1".to_string(), "c1".to_string())]);
+    let copied = repos
+        .forms
+        .copy_form(
+            &copy,
+            "review",
+            &question_ids,
+            CopyOptions {
+                responses: true,
+                invitations: true,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!((copied.responses.len(), copied.invitations), (4, 1));
+    assert_eq!(
+        repos.forms.find_form("review-copy").await.unwrap().unwrap(),
+        copy
+    );
+    let answers = repos.responses.answer_counts("review-copy").await.unwrap();
+    assert_eq!(answers, HashMap::from([("c1".to_string(), 4)]));
+    let used = repos
+        .invitations
+        .list_invitations(
+            "review-copy",
+            &InvitationFilters {
+                responded: Some(true),
+                ..Default::default()
+            },
+            &PageParams::default(),
+        )
+        .await
+        .unwrap();
+    let copied_invitation = &used.items[0];
+    assert_eq!(used.total, 1);
+    assert_ne!(copied_invitation.token, "token-grace");
+    assert_eq!(
+        copied_invitation.response_id.as_ref(),
+        copied.responses.get(&stored.response_id)
+    );
+    let structure_only = FormDefinition {
+        form: Form {
+            id: "review-draft".to_string(),
+            ..copy.form.clone()
+        },
+        sections: Vec::new(),
+        questions: Vec::new(),
+    };
+    let copied = repos
+        .forms
+        .copy_form(
+            &structure_only,
+            "review",
+            &HashMap::new(),
+            CopyOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(copied, CopiedForm::default());
+    assert_eq!(
+        repos
+            .responses
+            .count_responses("review-draft")
+            .await
+            .unwrap(),
+        0
+    );
+    for form_id in ["review-copy", "review-draft"] {
+        repos
+            .forms
+            .delete_form(form_id, &|_| Vec::new())
+            .await
+            .unwrap();
+    }
+
+    let deleted = repos
+        .forms
+        .delete_form("review", &|responses| {
+            assert_eq!(*responses, 4);
+            Vec::new()
+        })
+        .await
+        .unwrap();
+    assert_eq!(deleted, Some(4));
+    assert!(!repos.forms.form_exists("review").await.unwrap());
+    assert_eq!(repos.responses.count_responses("review").await.unwrap(), 0);
+    assert_eq!(
+        repos.invitations.count_invitations("review").await.unwrap(),
+        0
+    );
+    assert!(repos
+        .notifications
+        .find_template("review")
+        .await
+        .unwrap()
+        .is_none());
+    assert!(repos
+        .forms
+        .delete_form("review", &|_| unreachable!())
+        .await
+        .unwrap()
+        .is_none());
+}
+
+/// The repositories only the SQL backends implement, run after `check_backend`
+async fn check_sql_backend(repos: Repositories) {
+    repos.forms.create_form(&definition()).await.unwrap();
+    let submission = |email_hash: &str, answers: Vec<(&str, JsonValue)>| NewSubmission {
+        form_id: "review".to_string(),
+        respondent_name: format!("Respondent {}", email_hash),
+        respondent_email: format!("{}@example.com", email_hash),
+        email_hash: email_hash.to_string(),
+        role: Some("Staff".to_string()),
+        answers: answers
+            .into_iter()
+            .map(|(question_id, value)| (question_id.to_string(), value))
+            .collect(),
+        invitation_id: None,
+    };
+    for new in [
+        submission("h4", vec![("q2", json!("Budget overrun expected"))]),
+        submission(
+            "h5",
+            vec![(
+                "q1",
+                json!({ "rating": 3, "comment": "Hiring plan is unclear" }),
+            )],
+        ),
+    ] {
+        repos
+            .responses
+            .submit_response(&new, &|_| Vec::new())
+            .await
+            .unwrap();
+    }
+
+    // Text answers and comments are indexed, and prefix terms match longer words
+    let search = |words: &[(&str, bool)], include_pii: bool| {
+        let search = repos.search.clone();
+        let terms: Vec<SearchTerm> = words
+            .iter()
+            .map(|(word, prefix)| SearchTerm {
+                word: word.to_string(),
+                prefix: *prefix,
+            })
+            .collect();
+        async move {
+            let filters = ResponseFilters::default().for_form(Some("review"));
+            search
+                .search_answers(&terms, &filters, 10, include_pii)
+                .await
+                .unwrap()
+        }
+    };
+    let (total, matches) = search(&[("budg", true)], true).await;
+    assert_eq!((total, matches.len()), (1, 1));
+    assert_eq!(
+        (matches[0].question_id.as_str(), matches[0].field.as_str()),
+        ("q2", "text")
+    );
+    assert!(matches[0].snippet.contains(crate::search::MATCH_START));
+    assert_eq!(
+        matches[0].respondent_email.as_deref(),
+        Some("h4@example.com")
+    );
+    let (total, matches) = search(&[("hiring", false), ("unclear", false)], false).await;
+    assert_eq!(total, 1);
+    assert_eq!(
+        (matches[0].question_id.as_str(), matches[0].field.as_str()),
+        ("q1", "comment")
+    );
+    assert_eq!(matches[0].question_title.as_deref(), Some("Question q1"));
+    assert!(matches[0].respondent_name.is_none());
+    assert_eq!(search(&[("budg", false)], false).await.0, 0);
+    assert_eq!(
+        search(&[("budget", false), ("hiring", false)], false)
+            .await
+            .0,
+        0
+    );
+
+    let template = FormTemplate {
+        id: "t1".to_string(),
+        name: "Annual".to_string(),
+        description: Some("Yearly review".to_string()),
+        category: None,
+        source_form_id: Some("review".to_string()),
+        definition: sqlx::types::Json(json!({ "form": { "title": "Annual Review" } })),
+        built_in: false,
+        created_at: Some("2025-09-01T00:00:00+00:00".to_string()),
+    };
+    repos.templates.create_template(&template).await.unwrap();
+    let found = repos.templates.get_template("t1").await.unwrap().unwrap();
+    assert_eq!(found.definition.0, template.definition.0);
+    assert_eq!(
+        chrono::DateTime::parse_from_rfc3339(&found.created_at.unwrap()).unwrap(),
+        Utc.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap()
+    );
+    let listed = repos.templates.list_templates().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert!(repos.templates.delete_template("t1").await.unwrap());
+    assert!(!repos.templates.delete_template("t1").await.unwrap());
+    assert!(repos.templates.get_template("t1").await.unwrap().is_none());
+
+    let bank_question = BankQuestion {
+        id: "b1".to_string(),
+        question_type: "likert".to_string(),
+        title: "Sets a clear vision".to_string(),
+        features: sqlx::types::Json(json!({ "required": true })),
+        tags: sqlx::types::Json(vec!["leadership".to_string(), "strategy".to_string()]),
+        created_at: "2025-09-01T00:00:00+00:00".to_string(),
+        updated_at: "2025-09-01T00:00:00+00:00".to_string(),
+    };
+    assert!(repos
+        .question_bank
+        .create_bank_question(&bank_question)
+        .await
+        .unwrap());
+    assert!(!repos
+        .question_bank
+        .create_bank_question(&bank_question)
+        .await
+        .unwrap());
+    let other = BankQuestion {
+        id: "b2".to_string(),
+        title: "Communicates openly".to_string(),
+        tags: sqlx::types::Json(vec!["leadership".to_string()]),
+        ..bank_question.clone()
+    };
+    assert!(repos
+        .question_bank
+        .create_bank_question(&other)
+        .await
+        .unwrap());
+    let bank = |tag: Option<&'static str>, search: Option<&'static str>| {
+        let question_bank = repos.question_bank.clone();
+        async move {
+            question_bank
+                .list_bank_questions(tag, search)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|question| question.id)
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(bank(None, None).await, vec!["b2", "b1"]);
+    assert_eq!(bank(Some("strategy"), None).await, vec!["b1"]);
+    assert_eq!(bank(Some("leadership"), Some("VISION")).await, vec!["b1"]);
+    assert_eq!(
+        repos.question_bank.tag_counts().await.unwrap(),
+        vec![
+            TagCount {
+                tag: "leadership".to_string(),
+                count: 2
+            },
+            TagCount {
+                tag: "strategy".to_string(),
+                count: 1
+            },
+        ]
+    );
+    let updated = BankQuestion {
+        tags: sqlx::types::Json(vec!["vision".to_string()]),
+        updated_at: "2025-09-02T00:00:00+00:00".to_string(),
+        ..bank_question.clone()
+    };
+    assert!(repos
+        .question_bank
+        .update_bank_question(&updated)
+        .await
+        .unwrap());
+    let found = repos
+        .question_bank
+        .find_bank_question("b1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.tags.0, vec!["vision"]);
+    assert_eq!(
+        chrono::DateTime::parse_from_rfc3339(&found.updated_at).unwrap(),
+        Utc.with_ymd_and_hms(2025, 9, 2, 0, 0, 0).unwrap()
+    );
+
+    // A matching question without a bankId gets linked to the bank question
+    let unlinked = Question {
+        id: "q9".to_string(),
+        form_id: "review".to_string(),
+        section_id: Some("s1".to_string()),
+        position: 3,
+        title: " Sets a clear vision ".to_string(),
+        description: None,
+        kind: QuestionKind::from_parts("likert", &json!({ "required": true })).unwrap(),
+    };
+    repos
+        .forms
+        .add_questions("review", &[unlinked])
+        .await
+        .unwrap();
+    let candidates = repos
+        .question_bank
+        .unlinked_questions("likert", "Sets a clear vision")
+        .await
+        .unwrap();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(
+        (
+            candidates[0].question_id.as_str(),
+            candidates[0].form_title.as_str()
+        ),
+        ("q9", "Annual Review")
+    );
+    repos
+        .question_bank
+        .link_questions("b1", &candidates)
+        .await
+        .unwrap();
+    assert!(repos
+        .question_bank
+        .unlinked_questions("likert", "Sets a clear vision")
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        repos.question_bank.bank_question_uses("b1").await.unwrap(),
+        candidates
+    );
+    assert!(repos
+        .question_bank
+        .delete_bank_question("b2")
+        .await
+        .unwrap());
+    assert!(!repos
+        .question_bank
+        .delete_bank_question("b2")
+        .await
+        .unwrap());
+
+    let webhook = |id: &str, form_id: Option<&str>| Webhook {
+        id: id.to_string(),
+        form_id: form_id.map(str::to_string),
+        url: format!("https://example.com/{}", id),
+        secret: "secret".to_string(),
+        events: sqlx::types::Json(vec!["response.submitted".to_string()]),
+        include_pii: false,
+        active: true,
+        created_at: String::new(),
+    };
+    repos
+        .webhooks
+        .create_webhook(&webhook("w1", Some("review")))
+        .await
+        .unwrap();
+    repos
+        .webhooks
+        .create_webhook(&webhook("w2", None))
+        .await
+        .unwrap();
+    let found = repos.webhooks.find_webhook("w1").await.unwrap().unwrap();
+    assert_eq!(found.events.0, vec!["response.submitted"]);
+    assert!(!found.created_at.is_empty());
+    let all = repos
+        .webhooks
+        .list_webhooks(None, &PageParams::default())
+        .await
+        .unwrap();
+    assert_eq!(all.total, 2);
+    let for_form = repos
+        .webhooks
+        .list_webhooks(Some("review"), &PageParams::default())
+        .await
+        .unwrap();
+    assert_eq!(for_form.items.len(), 1);
+    assert_eq!(for_form.items[0].id, "w1");
+    assert!(repos.webhooks.delete_webhook("w2").await.unwrap());
+    assert!(!repos.webhooks.delete_webhook("w2").await.unwrap());
+
+    // Each invitation is reminded once per step
+    let campaign = ReminderCampaign {
+        id: "c1".to_string(),
+        form_id: "review".to_string(),
+        subject: "Reminder".to_string(),
+        body_template: "Please respond".to_string(),
+        closes_at: "2099-01-01T00:00:00+00:00".to_string(),
+        days_before: sqlx::types::Json(vec![7, 1]),
+        active: true,
+        created_at: String::new(),
+    };
+    repos.reminders.create_campaign(&campaign).await.unwrap();
+    let found = repos.reminders.find_campaign("c1").await.unwrap().unwrap();
+    assert_eq!(found.days_before.0, vec![7, 1]);
+    assert_eq!(
+        chrono::DateTime::parse_from_rfc3339(&found.closes_at).unwrap(),
+        Utc.with_ymd_and_hms(2099, 1, 1, 0, 0, 0).unwrap()
+    );
+    let listed = repos
+        .reminders
+        .list_campaigns("review", &PageParams::default())
+        .await
+        .unwrap();
+    assert_eq!(listed.total, 1);
+    repos
+        .invitations
+        .create_invitations(&[invitation()])
+        .await
+        .unwrap();
+    let pending = repos.reminders.pending_recipients(&found, 7).await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].email, "grace@example.com");
+    let reminders = vec![(
+        pending[0].id.clone(),
+        EmailMessage::from_html("grace@example.com", "Reminder", "<p>Please respond</p>"),
+    )];
+    assert_eq!(
+        repos
+            .reminders
+            .queue_reminders("c1", 7, &reminders)
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        repos
+            .reminders
+            .queue_reminders("c1", 7, &reminders)
+            .await
+            .unwrap(),
+        0
+    );
+    assert!(repos
+        .reminders
+        .pending_recipients(&found, 7)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        repos.reminders.send_counts("review").await.unwrap(),
+        HashMap::from([("c1".to_string(), 1)])
+    );
+    assert_eq!(repos.reminders.active_campaigns().await.unwrap().len(), 1);
+    let paused = ReminderCampaign {
+        active: false,
+        ..found.clone()
+    };
+    assert!(repos.reminders.update_campaign(&paused).await.unwrap());
+    assert!(repos.reminders.active_campaigns().await.unwrap().is_empty());
+    assert!(repos.reminders.delete_campaign("c1").await.unwrap());
+    assert!(!repos.reminders.delete_campaign("c1").await.unwrap());
+    assert!(!repos.reminders.update_campaign(&paused).await.unwrap());
+
+    let subscription = DigestSubscription {
+        id: "d1".to_string(),
+        form_id: "review".to_string(),
+        email: "board@example.com".to_string(),
+        frequency: "weekly".to_string(),
+        created_at: String::new(),
+    };
+    assert!(repos
+        .digests
+        .create_subscription(&subscription)
+        .await
+        .unwrap());
+    let duplicate = DigestSubscription {
+        id: "d2".to_string(),
+        ..subscription.clone()
+    };
+    assert!(!repos.digests.create_subscription(&duplicate).await.unwrap());
+    let found = repos
+        .digests
+        .find_subscription("d1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.email, "board@example.com");
+    assert_eq!(repos.digests.all_subscriptions().await.unwrap().len(), 1);
+    let listed = repos
+        .digests
+        .list_subscriptions("review", &PageParams::default())
+        .await
+        .unwrap();
+    assert_eq!(listed.total, 1);
+    let now = Utc::now();
+    assert_eq!(
+        repos
+            .digests
+            .response_counts("review", now - Duration::hours(1), now + Duration::hours(1))
+            .await
+            .unwrap(),
+        (2, 2)
+    );
+    assert_eq!(
+        repos
+            .digests
+            .response_counts("review", now + Duration::hours(1), now + Duration::hours(2))
+            .await
+            .unwrap(),
+        (0, 2)
+    );
+    assert_eq!(
+        repos
+            .digests
+            .average_rating("review", now + Duration::hours(1))
+            .await
+            .unwrap(),
+        Some(3.0)
+    );
+    assert!(repos
+        .digests
+        .average_rating("review", now - Duration::hours(1))
+        .await
+        .unwrap()
+        .is_none());
+
+    // A period is only reported once
+    assert!(repos.digests.last_period_end("d1").await.unwrap().is_none());
+    let run = NewDigestRun {
+        subscription_id: "d1".to_string(),
+        period_start: Utc.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap(),
+        period_end: Utc.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap(),
+        new_responses: 2,
+        total_responses: 2,
+        average_score: Some(3.0),
+    };
+    let digest = EmailMessage::from_html("board@example.com", "Digest", "<p>2 responses</p>");
+    assert!(repos.digests.record_run(&run, &digest).await.unwrap());
+    assert!(!repos.digests.record_run(&run, &digest).await.unwrap());
+    assert_eq!(
+        repos.digests.last_period_end("d1").await.unwrap(),
+        Some(run.period_end)
+    );
+    let runs = repos.digests.last_runs("review").await.unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(
+        (runs[0].new_responses, runs[0].average_score),
+        (2, Some(3.0))
+    );
+    assert!(repos.digests.delete_subscription("d1").await.unwrap());
+    assert!(!repos.digests.delete_subscription("d1").await.unwrap());
+
+    // The reminder and the digest were queued with their writes
+    let due = repos.outbox.due_entries(10).await.unwrap();
+    assert_eq!(due.len(), 2);
+    assert!(due.iter().all(|entry| entry.kind == "email"));
+    let (first, second) = (&due[0].id, &due[1].id);
+    repos
+        .outbox
+        .record_failure(first, "pending", 1, "Connection refused", 3600)
+        .await
+        .unwrap();
+    assert_eq!(repos.outbox.due_entries(10).await.unwrap().len(), 1);
+    let failed = repos.outbox.find_entry(first).await.unwrap().unwrap();
+    assert_eq!(
+        (failed.attempts, failed.last_error.as_deref()),
+        (1, Some("Connection refused"))
+    );
+    let pending = repos
+        .outbox
+        .list_entries(Some("pending"), Some("email"), &PageParams::default())
+        .await
+        .unwrap();
+    assert_eq!(pending.total, 2);
+    assert!(repos.outbox.requeue(first).await.unwrap());
+    let requeued = repos.outbox.find_entry(first).await.unwrap().unwrap();
+    assert_eq!(
+        (requeued.status.as_str(), requeued.attempts),
+        ("pending", 0)
+    );
+    assert_eq!(repos.outbox.due_entries(10).await.unwrap().len(), 2);
+    repos.outbox.record_delivered(second).await.unwrap();
+    let delivered = repos.outbox.find_entry(second).await.unwrap().unwrap();
+    assert_eq!(delivered.status, "delivered");
+    assert!(delivered.delivered_at.is_some());
+    repos.outbox.prune_delivered(30).await.unwrap();
+    assert!(repos.outbox.find_entry(second).await.unwrap().is_some());
+    assert_eq!(
+        repos
+            .outbox
+            .list_entries(None, Some("webhook"), &PageParams::default())
+            .await
+            .unwrap()
+            .total,
+        0
+    );
+    assert!(repos.outbox.discard(first).await.unwrap());
+    let discarded = repos.outbox.find_entry(first).await.unwrap().unwrap();
+    assert_eq!(discarded.status, "discarded");
+    assert!(repos.outbox.due_entries(10).await.unwrap().is_empty());
+    assert!(!repos.outbox.discard("missing").await.unwrap());
+    assert!(!repos.outbox.requeue("missing").await.unwrap());
+    assert!(repos.outbox.find_entry("missing").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn memory_repositories() {
+    let store = std::sync::Arc::new(memory::MemoryStore::default());
+    let seed = {
+        let store = store.clone();
+        async move {
+            let response =
+                |role: &str, email_hash: Option<&str>, answers: Vec<(&str, JsonValue)>| {
+                    memory::StoredResponse {
+                        form_id: "review".to_string(),
+                        role: Some(role.to_string()),
+                        email_hash: email_hash.map(str::to_string),
+                        answers: answers
+                            .into_iter()
+                            .map(|(question_id, value)| (question_id.to_string(), value))
+                            .collect(),
+                        ..Default::default()
+                    }
+                };
+            store.add_response(response(
+                "Staff",
+                Some("h1"),
+                vec![("q1", json!(4)), ("q2", json!("Great year"))],
+            ));
+            store.add_response(response(
+                "Staff",
+                None,
+                vec![("q1", json!({ "rating": 2, "comment": "Needs work" }))],
+            ));
+            store.add_response(response("Board", None, vec![("q1", json!(4))]));
+            store.add_respondent("h1", "Ada", "ada@example.com");
+            store.add_invitation(invitation());
+        }
+    };
+
+    check_backend(store.repositories(), seed).await;
+}
+
+#[tokio::test]
+async fn sqlite_repositories() {
+    let pool = crate::db::test_pool().await;
+    let repos = Repositories::new(&Database::Sqlite(pool.clone()));
+    check_backend(repos.clone(), async {
+        sqlx::raw_sql(SEED).execute(&pool).await.unwrap();
+    })
+    .await;
+    check_sql_backend(repos).await;
+}
+
+#[cfg(feature = "postgres")]
+#[tokio::test]
+async fn postgres_repositories() {
+    use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+    use std::str::FromStr;
+
+    let Ok(url) = std::env::var("TEST_POSTGRES_URL") else {
+        eprintln!("TEST_POSTGRES_URL is not set; skipping the PostgreSQL repository tests");
+        return;
+    };
+
+    // A throwaway schema keeps runs independent of each other and of any existing data
+    let schema = format!("test_{}", uuid::Uuid::new_v4().simple());
+    let admin = PgPoolOptions::new().connect(&url).await.unwrap();
+    sqlx::query(&format!("CREATE SCHEMA {}", schema))
+        .execute(&admin)
+        .await
+        .unwrap();
+
+    let options = PgConnectOptions::from_str(&url)
+        .unwrap()
+        .options([("search_path", schema.as_str())]);
+    let pool = PgPoolOptions::new().connect_with(options).await.unwrap();
+    sqlx::migrate!("./migrations_postgres")
+        .run(&pool)
+        .await
+        .unwrap();
+
+    let repos = Repositories::new(&Database::Postgres(pool.clone()));
+    check_backend(repos.clone(), async {
+        sqlx::raw_sql(SEED).execute(&pool).await.unwrap();
+    })
+    .await;
+    check_sql_backend(repos).await;
+
+    pool.close().await;
+    sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+        .execute(&admin)
+        .await
+        .unwrap();
+}
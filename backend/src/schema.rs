@@ -0,0 +1,154 @@
+//! JSON Schema for the form definition that `import_form` accepts.
+//!
+//! The schema is generated from the import types in `handlers`, with one `features` rule
+//! per question type taken from [`QuestionKind`]. It is served at `/api/schema/form.json`
+//! and every import is checked against it, so all problems come back in one response.
+//! Rules that span fields (a scale's `min` below its `max`, unique IDs) are checked by
+//! `import_form` itself and reported the same way.
+
+use std::sync::LazyLock;
+
+use axum::{response::IntoResponse, Json};
+use schemars::{generate::SchemaSettings, json_schema, Schema, SchemaGenerator};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+
+use crate::{handlers::ImportFormRequest, questions::QuestionKind};
+
+static FORM_SCHEMA: LazyLock<JsonValue> = LazyLock::new(build_form_schema);
+
+static VALIDATOR: LazyLock<jsonschema::Validator> = LazyLock::new(|| {
+    jsonschema::draft202012::new(&FORM_SCHEMA).expect("the generated form schema is valid")
+});
+
+/// One problem with a submitted definition
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    /// JSON pointer to the offending value; empty for the document itself
+    pub pointer: String,
+    pub message: String,
+}
+
+/// `question_type` is one of the known question types
+pub(crate) fn question_type_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "string",
+        "enum": QuestionKind::NAMES,
+    })
+}
+
+/// `features` is an object or null; its contents depend on `question_type`
+pub(crate) fn features_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({ "type": ["object", "null"] })
+}
+
+pub(crate) fn form_status_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "string",
+        "enum": ["draft", "published", "finished", "archived"],
+    })
+}
+
+fn build_form_schema() -> JsonValue {
+    // Inlined so nullable objects get `"type": ["object", "null"]` rather than an `anyOf`,
+    // which would report a bad nested value at its parent
+    let mut generator = SchemaSettings::draft2020_12()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator();
+    let mut schema = generator.root_schema_for::<ImportFormRequest>().to_value();
+
+    // `features` depends on the sibling `question_type`, which derive can't express
+    let rules: Vec<JsonValue> = QuestionKind::feature_schemas(&mut generator)
+        .into_iter()
+        .map(|(question_type, features)| {
+            json!({
+                "if": {
+                    "properties": {
+                        "question_type": { "const": question_type },
+                        "features": { "type": "object" }
+                    },
+                    "required": ["question_type", "features"]
+                },
+                "then": { "properties": { "features": features } }
+            })
+        })
+        .collect();
+
+    schema["$id"] = json!("/api/schema/form.json");
+    schema["properties"]["sections"]["items"]["properties"]["questions"]["items"]["allOf"] =
+        JsonValue::Array(rules);
+    schema
+}
+
+pub fn form_schema() -> &'static JsonValue {
+    &FORM_SCHEMA
+}
+
+/// Every way `definition` breaks the schema, in document order
+pub fn validate(definition: &JsonValue) -> Vec<FieldError> {
+    VALIDATOR
+        .iter_errors(definition)
+        .map(|error| FieldError {
+            pointer: error.instance_path.to_string(),
+            message: error.to_string(),
+        })
+        .collect()
+}
+
+/// Serve the form definition schema
+pub async fn get_form_schema() -> impl IntoResponse {
+    Json(form_schema().clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_bundled_template_matches_the_schema() {
+        let template: JsonValue =
+            serde_json::from_str(include_str!("../config/form-template.json")).unwrap();
+        assert_eq!(validate(&template), Vec::new());
+    }
+
+    #[test]
+    fn reports_every_error_with_its_pointer() {
+        let definition = json!({
+            "id": "review",
+            "title": 42,
+            "status": "live",
+            "sections": [{
+                "id": "s1",
+                "title": "Leadership",
+                "position": 1,
+                "questions": [
+                    { "id": "q1", "title": "Vision", "question_type": "slider", "is_required": true, "position": 1 },
+                    { "id": "q2", "title": "Team", "question_type": "likert", "is_required": true, "position": 2,
+                      "features": { "scale": { "min": "1", "max": 5 } } },
+                    { "id": "q3", "title": "Office", "question_type": "dropdown", "position": 3,
+                      "features": { "options": "Remote" } }
+                ]
+            }]
+        });
+
+        let pointers: Vec<String> = validate(&definition)
+            .into_iter()
+            .map(|error| error.pointer)
+            .collect();
+        for expected in [
+            "/title",
+            "/status",
+            "/sections/0/questions/0/question_type",
+            "/sections/0/questions/1/features/scale/min",
+            "/sections/0/questions/2",
+            "/sections/0/questions/2/features/options",
+        ] {
+            assert!(
+                pointers.iter().any(|pointer| pointer == expected),
+                "no error at {} in {:?}",
+                expected,
+                pointers
+            );
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::AppError,
+    filters::ResponseFilterQuery,
+    handlers::check_admin_auth,
+    pagination::{DEFAULT_LIMIT, MAX_LIMIT},
+    repository::SearchTerm,
+    AppState,
+};
+
+/// Markers the database puts around matches in a snippet; control characters cannot
+/// appear in escaped output, so the highlight tags are only added after the answer text
+/// has been HTML-escaped
+pub(crate) const MATCH_START: char = '\u{2}';
+pub(crate) const MATCH_END: char = '\u{3}';
+/// Tokens of context in each snippet
+pub(crate) const SNIPPET_TOKENS: i64 = 16;
+
+/// Split free text into search terms: every word must match, `word*` matches a prefix.
+/// Words without a letter or digit are dropped.
+fn search_terms(query: &str) -> Vec<SearchTerm> {
+    query
+        .split_whitespace()
+        .filter_map(|word| {
+            let (word, prefix) = match word.strip_suffix('*') {
+                Some(stem) => (stem, true),
+                None => (word, false),
+            };
+            word.chars().any(char::is_alphanumeric).then(|| SearchTerm {
+                word: word.to_string(),
+                prefix,
+            })
+        })
+        .collect()
+}
+
+/// Escape the snippet for HTML, then turn the match markers into `<mark>` tags
+fn highlight(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len() + 16);
+    for c in snippet.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub token: Option<String>,
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub include_pii: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub response_id: String,
+    pub question_id: String,
+    pub question_title: Option<String>,
+    /// `text` for a free-text answer, `comment` for the comment on a rating or choice
+    pub field: String,
+    /// HTML-escaped excerpt with matches wrapped in `<mark>`
+    pub snippet: String,
+    pub role: Option<String>,
+    pub submitted_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub respondent_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub respondent_email: Option<String>,
+}
+
+/// Search a form's text answers and comments, best matches first (admin only).
+/// Hits are anonymous unless `include_pii=true`; accepts the response filters.
+pub async fn search_answers(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(params): Query<SearchQuery>,
+    Query(filters): Query<ResponseFilterQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+
+    let terms = search_terms(params.q.as_deref().unwrap_or_default());
+    if terms.is_empty() {
+        return Err(AppError::BadRequest(
+            "Search query 'q' is required".to_string(),
+        ));
+    }
+
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(AppError::BadRequest(format!(
+            "Limit must be between 1 and {}",
+            MAX_LIMIT
+        )));
+    }
+
+    let filters = filters.parse()?.for_form(Some(&form_id));
+    let (total, matches) = state
+        .repos
+        .search
+        .search_answers(&terms, &filters, limit, params.include_pii)
+        .await?;
+
+    let hits: Vec<SearchHit> = matches
+        .into_iter()
+        .map(|row| SearchHit {
+            snippet: highlight(&row.snippet),
+            response_id: row.response_id,
+            question_id: row.question_id,
+            question_title: row.question_title,
+            field: row.field,
+            role: row.role,
+            submitted_at: row.submitted_at,
+            respondent_name: row.respondent_name,
+            respondent_email: row.respondent_email,
+        })
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "form_id": form_id,
+        "query": params.q,
+        "total": total,
+        "hits": hits
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::sqlite::{index_response, remove_form};
+    use sqlx::SqlitePool;
+
+    #[test]
+    fn queries_split_into_words_and_prefixes() {
+        let term = |word: &str, prefix: bool| SearchTerm {
+            word: word.to_string(),
+            prefix,
+        };
+        assert_eq!(
+            search_terms("budget  overrun"),
+            vec![term("budget", false), term("overrun", false)]
+        );
+        assert_eq!(
+            search_terms("budg* NOT \"x"),
+            vec![term("budg", true), term("NOT", false), term("\"x", false)]
+        );
+        assert!(search_terms(" * - ").is_empty());
+    }
+
+    #[test]
+    fn snippets_are_escaped_before_highlighting() {
+        assert_eq!(
+            highlight("the \u{2}budget\u{3} <script>"),
+            "the <mark>budget</mark> &lt;script&gt;"
+        );
+    }
+
+    #[tokio::test]
+    async fn submitted_answers_are_indexed_and_removed_with_their_form() {
+        let db = crate::db::test_pool().await;
+        sqlx::raw_sql(
+            r#"
+            INSERT INTO forms (id, title) VALUES ('f1', 'Annual Review');
+            INSERT INTO questions (id, form_id, position, type, title) VALUES
+                ('q1', 'f1', 1, 'likert', 'Leadership'),
+                ('q2', 'f1', 2, 'textarea', 'Comments'),
+                ('q3', 'f1', 3, 'multiple_choice', 'Area');
+            INSERT INTO respondents (id, name, email) VALUES ('p1', 'Jane', 'jane@example.com');
+            INSERT INTO responses (id, form_id, respondent_id, role, submitted_at) VALUES
+                ('r1', 'f1', 'p1', 'Staff', '2025-09-07 10:00:00');
+            INSERT INTO answers (id, response_id, question_id, value) VALUES
+                ('a1', 'r1', 'q1', '{"rating": 2, "comment": "The budget was never shared"}'),
+                ('a2', 'r1', 'q2', '"Budgeting needs work"'),
+                ('a3', 'r1', 'q3', '"budget"'),
+                ('a4', 'r1', 'q2', 'not json');
+            "#,
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        // Running the migration again backfills the answers seeded after it
+        sqlx::raw_sql(include_str!(
+            "../migrations/20250907000000_answer_search.sql"
+        ))
+        .execute(&db)
+        .await
+        .unwrap();
+
+        async fn indexed(db: &SqlitePool) -> Vec<(String, String)> {
+            sqlx::query_as(
+                "SELECT question_id, field FROM answer_search WHERE answer_search MATCH 'budget' ORDER BY question_id",
+            )
+            .fetch_all(db)
+            .await
+            .unwrap()
+        }
+
+        // Stems match "Budgeting"
+        let expected = vec![
+            ("q1".to_string(), "comment".to_string()),
+            ("q2".to_string(), "text".to_string()),
+        ];
+        assert_eq!(indexed(&db).await, expected);
+
+        let state = AppState::for_tests(db.clone());
+        let search = |include_pii: bool| {
+            search_answers(
+                HeaderMap::new(),
+                Path("f1".to_string()),
+                Query(SearchQuery {
+                    token: Some("admin".to_string()),
+                    q: Some("never budget".to_string()),
+                    limit: None,
+                    include_pii,
+                }),
+                Query(ResponseFilterQuery::default()),
+                State(state.clone()),
+            )
+        };
+        let body = |response: axum::response::Response| async {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        };
+
+        let anonymous = body(search(false).await.unwrap().into_response()).await;
+        assert_eq!(anonymous["total"], 1);
+        assert_eq!(
+            anonymous["hits"][0]["snippet"],
+            "The <mark>budget</mark> was <mark>never</mark> shared"
+        );
+        assert!(anonymous["hits"][0].get("respondent_email").is_none());
+
+        let with_pii = body(search(true).await.unwrap().into_response()).await;
+        assert_eq!(with_pii["hits"][0]["respondent_email"], "jane@example.com");
+
+        sqlx::query("DELETE FROM answer_search")
+            .execute(&db)
+            .await
+            .unwrap();
+        let mut conn = db.acquire().await.unwrap();
+        index_response(&mut conn, "r1").await.unwrap();
+        drop(conn);
+        assert_eq!(indexed(&db).await, expected);
+
+        let mut conn = db.acquire().await.unwrap();
+        remove_form(&mut conn, "f1").await.unwrap();
+        drop(conn);
+        assert!(indexed(&db).await.is_empty());
+    }
+}
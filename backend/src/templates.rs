@@ -0,0 +1,431 @@
+//! The form template library: reusable form definitions that new draft forms start from.
+//!
+//! Templates are import documents (see `import_form`). The built-in ones are embedded
+//! from `config/templates`; admins add their own by saving an existing form, which
+//! stores its exported definition in `form_templates`.
+
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use sqlx::types::Json as SqlJson;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    handlers::{
+        check_admin_auth, import_definition, sanitize_optional_input, AuthQuery, ImportFormRequest,
+    },
+    models::*,
+    AppState,
+};
+
+/// ID, category and definition of each template shipped with the server
+const BUILT_IN: &[(&str, &str, &str)] = &[
+    (
+        "ed-review",
+        "Performance review",
+        include_str!("../config/templates/ed-review.json"),
+    ),
+    (
+        "peer-review-360",
+        "Performance review",
+        include_str!("../config/templates/peer-review-360.json"),
+    ),
+    (
+        "event-feedback",
+        "Feedback",
+        include_str!("../config/templates/event-feedback.json"),
+    ),
+    (
+        "employee-engagement",
+        "Engagement",
+        include_str!("../config/templates/employee-engagement.json"),
+    ),
+];
+
+/// The built-in templates, named after the forms they define
+fn built_in() -> impl Iterator<Item = FormTemplate> {
+    BUILT_IN.iter().map(|(id, category, definition)| {
+        let definition: JsonValue =
+            serde_json::from_str(definition).expect("built-in templates are valid JSON");
+        FormTemplate {
+            id: id.to_string(),
+            name: definition["title"].as_str().unwrap_or(id).to_string(),
+            description: definition["description"].as_str().map(str::to_string),
+            category: Some(category.to_string()),
+            source_form_id: None,
+            definition: SqlJson(definition),
+            built_in: true,
+            created_at: None,
+        }
+    })
+}
+
+async fn find_template(state: &AppState, template_id: &str) -> Result<FormTemplate, AppError> {
+    if let Some(template) = built_in().find(|template| template.id == template_id) {
+        return Ok(template);
+    }
+
+    let template = state.repos.templates.get_template(template_id).await?;
+
+    template.ok_or_else(|| AppError::NotFound("Template not found".to_string()))
+}
+
+/// A template without its definition, with the size of the form it creates
+fn summary(template: &FormTemplate) -> JsonValue {
+    let sections = template.definition["sections"].as_array();
+    let questions: usize = sections
+        .into_iter()
+        .flatten()
+        .filter_map(|section| section["questions"].as_array())
+        .map(Vec::len)
+        .sum();
+
+    json!({
+        "id": template.id,
+        "name": template.name,
+        "description": template.description,
+        "category": template.category,
+        "built_in": template.built_in,
+        "source_form_id": template.source_form_id,
+        "created_at": template.created_at,
+        "sections": sections.map_or(0, Vec::len),
+        "questions": questions
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TemplateListQuery {
+    pub token: Option<String>,
+    pub category: Option<String>,
+}
+
+/// List the built-in and saved templates, optionally in one category (admin only)
+pub async fn list_templates(
+    headers: HeaderMap,
+    Query(params): Query<TemplateListQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+
+    let saved = state.repos.templates.list_templates().await?;
+
+    let templates: Vec<JsonValue> = built_in()
+        .chain(saved)
+        .filter(|template| {
+            params.category.is_none() || template.category.as_deref() == params.category.as_deref()
+        })
+        .map(|template| summary(&template))
+        .collect();
+
+    Ok(Json(json!({ "templates": templates })))
+}
+
+/// Get a template with its full definition (admin only)
+pub async fn get_template(
+    headers: HeaderMap,
+    Path(template_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let template = find_template(&state, &template_id).await?;
+    let mut body = summary(&template);
+    body["definition"] = template.definition.0;
+
+    Ok(Json(body))
+}
+
+/// Save a form's current definition as a template (admin only)
+pub async fn save_template(
+    headers: HeaderMap,
+    Path(form_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<SaveTemplateRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let form = state
+        .repos
+        .forms
+        .find_form(&form_id)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Form not found".to_string()))?;
+
+    let mut definition = ImportFormRequest::from(&form);
+    definition.status = "draft".to_string();
+
+    let template = FormTemplate {
+        id: Uuid::new_v4().to_string(),
+        name: sanitize_optional_input(req.name.as_deref(), 255)
+            .unwrap_or_else(|| form.form.title.clone()),
+        description: sanitize_optional_input(req.description.as_deref(), 1000)
+            .or_else(|| form.form.description.clone()),
+        category: sanitize_optional_input(req.category.as_deref(), 100),
+        source_form_id: Some(form_id),
+        definition: SqlJson(
+            serde_json::to_value(&definition)
+                .map_err(|e| AppError::InternalError(e.to_string()))?,
+        ),
+        built_in: false,
+        created_at: Some(chrono::Utc::now().to_rfc3339()),
+    };
+
+    state.repos.templates.create_template(&template).await?;
+
+    Ok((StatusCode::CREATED, Json(summary(&template))))
+}
+
+/// Delete a saved template; built-in templates cannot be deleted (admin only)
+pub async fn delete_template(
+    headers: HeaderMap,
+    Path(template_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    if built_in().any(|template| template.id == template_id) {
+        return Err(AppError::BadRequest(
+            "Built-in templates cannot be deleted".to_string(),
+        ));
+    }
+
+    if !state.repos.templates.delete_template(&template_id).await? {
+        return Err(AppError::NotFound("Template not found".to_string()));
+    }
+
+    Ok(Json(json!({
+        "message": "Template deleted successfully",
+        "template_id": template_id
+    })))
+}
+
+/// Create a new draft form from a template (admin only). Every section and question gets
+/// a new ID, so one template can be used any number of times.
+pub async fn instantiate_template(
+    headers: HeaderMap,
+    Path(template_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    req: Option<Json<InstantiateTemplateRequest>>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+    let req = req.map(|Json(req)| req).unwrap_or_default();
+
+    let template = find_template(&state, &template_id).await?;
+
+    let form_id = sanitize_optional_input(req.form_id.as_deref(), 255)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    if state.repos.forms.form_exists(&form_id).await? {
+        return Err(AppError::BadRequest(format!(
+            "A form with ID '{}' already exists",
+            form_id
+        )));
+    }
+
+    // Saved templates were valid when saved, but the schema may have moved on since
+    let errors = crate::schema::validate(&template.definition);
+    if !errors.is_empty() {
+        return Err(AppError::InvalidDefinition(errors));
+    }
+    let mut form_data: ImportFormRequest = serde_json::from_value(template.definition.0)
+        .map_err(|e| AppError::BadRequest(format!("Invalid template definition: {}", e)))?;
+    form_data.status = "draft".to_string();
+    if let Some(title) = sanitize_optional_input(req.title.as_deref(), 255) {
+        form_data.title = title;
+    }
+
+    let fresh_ids = |ids: Vec<&String>| -> HashMap<String, String> {
+        ids.into_iter()
+            .map(|id| (id.clone(), Uuid::new_v4().to_string()))
+            .collect()
+    };
+    let section_ids = fresh_ids(form_data.sections.iter().map(|s| &s.id).collect());
+    let question_ids = fresh_ids(
+        form_data
+            .sections
+            .iter()
+            .flat_map(|s| &s.questions)
+            .map(|q| &q.id)
+            .collect(),
+    );
+
+    let definition = import_definition(form_data, &form_id, &section_ids, &question_ids)
+        .map_err(AppError::InvalidDefinition)?;
+    state.repos.forms.create_form(&definition).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "message": "Form created from template",
+            "template_id": template_id,
+            "form_id": form_id,
+            "title": definition.form.title
+        })),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::repository::{memory::MemoryStore, sqlite::SqliteStore};
+
+    fn admin() -> Query<AuthQuery> {
+        Query(AuthQuery {
+            token: Some("admin".to_string()),
+        })
+    }
+
+    async fn body(response: impl IntoResponse) -> JsonValue {
+        let bytes = axum::body::to_bytes(response.into_response().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn built_in_templates_match_the_schema() {
+        for template in built_in() {
+            assert_eq!(
+                crate::schema::validate(&template.definition),
+                Vec::new(),
+                "{}",
+                template.id
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn templates_are_saved_listed_and_instantiated() {
+        let store = Arc::new(MemoryStore::default());
+        // Forms live in the fake repositories; templates are stored in a migrated pool
+        let mut state = store.app_state();
+        state.repos.templates = Arc::new(SqliteStore::new(crate::db::test_pool().await));
+        let instantiate = |template_id: &str, req: Option<JsonValue>| {
+            instantiate_template(
+                HeaderMap::new(),
+                Path(template_id.to_string()),
+                admin(),
+                State(state.clone()),
+                req.map(|req| Json(serde_json::from_value(req).unwrap())),
+            )
+        };
+
+        // A built-in template can be used any number of times
+        let first = body(instantiate("ed-review", None).await.unwrap()).await;
+        let second = body(
+            instantiate(
+                "ed-review",
+                Some(json!({ "form_id": "ed-2025", "title": "ED Review 2025" })),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(first["title"], "Executive Director Performance Review");
+        assert_eq!(second["form_id"], "ed-2025");
+        let copy = state
+            .repos
+            .forms
+            .find_form("ed-2025")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.form.title, "ED Review 2025");
+        assert_eq!(copy.form.status, "draft");
+        let original = state
+            .repos
+            .forms
+            .find_form(first["form_id"].as_str().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(original.questions.len(), copy.questions.len());
+        assert_ne!(original.questions[0].id, copy.questions[0].id);
+        let taken = instantiate("ed-review", Some(json!({ "form_id": "ed-2025" }))).await;
+        assert!(matches!(taken, Err(AppError::BadRequest(_))));
+
+        let saved = body(
+            save_template(
+                HeaderMap::new(),
+                Path("ed-2025".to_string()),
+                admin(),
+                State(state.clone()),
+                Json(SaveTemplateRequest {
+                    name: Some("Our ED review".to_string()),
+                    description: None,
+                    category: Some("Board".to_string()),
+                }),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(saved["built_in"], false);
+        assert_eq!(saved["questions"], original.questions.len());
+        let saved_id = saved["id"].as_str().unwrap().to_string();
+
+        let list = |category: Option<&str>| {
+            list_templates(
+                HeaderMap::new(),
+                Query(TemplateListQuery {
+                    token: Some("admin".to_string()),
+                    category: category.map(str::to_string),
+                }),
+                State(state.clone()),
+            )
+        };
+        let all = body(list(None).await.unwrap()).await;
+        assert_eq!(
+            all["templates"].as_array().unwrap().len(),
+            BUILT_IN.len() + 1
+        );
+        let board = body(list(Some("Board")).await.unwrap()).await;
+        assert_eq!(board["templates"][0]["name"], "Our ED review");
+
+        let full = body(
+            get_template(
+                HeaderMap::new(),
+                Path(saved_id.clone()),
+                admin(),
+                State(state.clone()),
+            )
+            .await
+            .unwrap(),
+        )
+        .await;
+        assert_eq!(full["definition"]["title"], "ED Review 2025");
+        let from_saved = body(instantiate(&saved_id, None).await.unwrap()).await;
+        assert_eq!(from_saved["title"], "ED Review 2025");
+
+        let delete = |template_id: &str| {
+            delete_template(
+                HeaderMap::new(),
+                Path(template_id.to_string()),
+                admin(),
+                State(state.clone()),
+            )
+        };
+        assert!(matches!(
+            delete("ed-review").await,
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(delete(&saved_id).await.is_ok());
+        assert!(matches!(
+            instantiate(&saved_id, None).await,
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
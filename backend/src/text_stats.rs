@@ -0,0 +1,334 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::AppError, AppState};
+
+/// Fewer non-blank answers than this and only counts are reported
+pub const ANONYMITY_THRESHOLD: usize = 5;
+/// Terms must appear in at least this many answers, so one respondent's phrasing is never shown
+const MIN_TERM_ANSWERS: usize = 2;
+const DEFAULT_TOP: usize = 20;
+const MAX_TOP: usize = 100;
+
+/// Common English words left out of term and bigram counts
+#[rustfmt::skip]
+const STOPWORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "against", "all", "also", "am", "an", "and", "any",
+    "are", "aren't", "as", "at", "be", "because", "been", "before", "being", "below", "between",
+    "both", "but", "by", "can", "can't", "could", "couldn't", "did", "didn't", "do", "does",
+    "doesn't", "doing", "don't", "down", "during", "each", "even", "few", "for", "from",
+    "further", "get", "got", "had", "hadn't", "has", "hasn't", "have", "haven't", "having",
+    "he", "her", "here", "hers", "herself", "him", "himself", "his", "how", "i", "i'm", "i've",
+    "if", "in", "into", "is", "isn't", "it", "it's", "its", "itself", "just", "let's", "me",
+    "more", "most", "much", "my", "myself", "no", "nor", "not", "now", "of", "off", "on",
+    "once", "only", "or", "other", "our", "ours", "ourselves", "out", "over", "own", "really",
+    "same", "she", "should", "shouldn't", "so", "some", "such", "than", "that", "that's", "the",
+    "their", "theirs", "them", "themselves", "then", "there", "there's", "these", "they",
+    "they're", "this", "those", "through", "to", "too", "under", "until", "up", "very", "was",
+    "wasn't", "we", "we're", "were", "weren't", "what", "when", "where", "which", "while",
+    "who", "whom", "why", "will", "with", "won't", "would", "wouldn't", "you", "you're", "your",
+    "yours", "yourself", "yourselves",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct TextStatsQuery {
+    /// Number of terms and bigrams to return
+    pub top: Option<usize>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TermCount {
+    pub term: String,
+    /// Occurrences across all answers
+    pub count: usize,
+    /// Answers containing the term at least once
+    pub answers: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LengthStats {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    pub median: f64,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TextAnalysis {
+    pub token_count: usize,
+    pub unique_terms: usize,
+    pub top_terms: Vec<TermCount>,
+    pub top_bigrams: Vec<TermCount>,
+    pub words: LengthStats,
+    pub characters: LengthStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TextQuestionStats {
+    pub form_id: String,
+    pub question_id: String,
+    pub question_title: String,
+    /// `answer` for text questions, `comment` for the comments on other question types
+    pub source: &'static str,
+    pub total_responses: usize,
+    pub answered: usize,
+    pub blank: usize,
+    pub blank_share: f64,
+    /// True when fewer than `min_answers` answers were given; `analysis` is then omitted
+    pub suppressed: bool,
+    pub min_answers: usize,
+    pub analysis: Option<TextAnalysis>,
+}
+
+fn is_stopword(word: &str) -> bool {
+    STOPWORDS.binary_search(&word).is_ok()
+}
+
+/// Lowercased words; apostrophes inside a word are kept so "don't" stays one token
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’'))
+        .map(|word| word.replace('’', "'").trim_matches('\'').to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Terms used for counting: no stopwords, no bare numbers, no single letters
+fn is_term(word: &str) -> bool {
+    word.chars().count() > 1 && !word.chars().all(|c| c.is_numeric()) && !is_stopword(word)
+}
+
+fn length_stats(mut lengths: Vec<usize>) -> LengthStats {
+    lengths.sort_unstable();
+    let n = lengths.len();
+    let median = if n % 2 == 1 {
+        lengths[n / 2] as f64
+    } else {
+        (lengths[n / 2 - 1] + lengths[n / 2]) as f64 / 2.0
+    };
+
+    LengthStats {
+        min: lengths[0],
+        max: lengths[n - 1],
+        mean: lengths.iter().sum::<usize>() as f64 / n as f64,
+        median,
+    }
+}
+
+/// Most frequent entries first, then most widespread, then alphabetical for stable output
+fn top_counts(counts: HashMap<String, (usize, usize)>, top: usize) -> Vec<TermCount> {
+    let mut terms: Vec<TermCount> = counts
+        .into_iter()
+        .filter(|(_, (_, answers))| *answers >= MIN_TERM_ANSWERS)
+        .map(|(term, (count, answers))| TermCount {
+            term,
+            count,
+            answers,
+        })
+        .collect();
+    terms.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then(b.answers.cmp(&a.answers))
+            .then(a.term.cmp(&b.term))
+    });
+    terms.truncate(top);
+    terms
+}
+
+/// Term, bigram and length statistics over non-blank answers
+fn analyze(answers: &[String], top: usize) -> Option<TextAnalysis> {
+    if answers.is_empty() {
+        return None;
+    }
+
+    let mut token_count = 0;
+    let mut terms: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut bigrams: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut words = Vec::with_capacity(answers.len());
+    let mut characters = Vec::with_capacity(answers.len());
+
+    for answer in answers {
+        let tokens = tokenize(answer);
+        token_count += tokens.len();
+        words.push(tokens.len());
+        characters.push(answer.trim().chars().count());
+
+        let mut seen_terms = HashSet::new();
+        for token in tokens.iter().filter(|t| is_term(t)) {
+            let entry = terms.entry(token.clone()).or_default();
+            entry.0 += 1;
+            if seen_terms.insert(token) {
+                entry.1 += 1;
+            }
+        }
+
+        // Only adjacent terms form a bigram; a removed stopword breaks the pair
+        let mut seen_bigrams = HashSet::new();
+        for pair in tokens.windows(2) {
+            if !(is_term(&pair[0]) && is_term(&pair[1])) {
+                continue;
+            }
+            let bigram = format!("{} {}", pair[0], pair[1]);
+            let entry = bigrams.entry(bigram.clone()).or_default();
+            entry.0 += 1;
+            if seen_bigrams.insert(bigram) {
+                entry.1 += 1;
+            }
+        }
+    }
+
+    Some(TextAnalysis {
+        token_count,
+        unique_terms: terms.len(),
+        top_terms: top_counts(terms, top),
+        top_bigrams: top_counts(bigrams, top),
+        words: length_stats(words),
+        characters: length_stats(characters),
+    })
+}
+
+/// Word frequencies, bigrams and answer lengths for one question's free text (no PII).
+/// Text questions are analysed by their answers, other questions by their comments.
+pub async fn get_text_question_stats(
+    Path((form_id, question_id)): Path<(String, String)>,
+    Query(params): Query<TextStatsQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let top = params.top.unwrap_or(DEFAULT_TOP);
+    if !(1..=MAX_TOP).contains(&top) {
+        return Err(AppError::BadRequest(format!(
+            "top must be between 1 and {}",
+            MAX_TOP
+        )));
+    }
+
+    let question = state
+        .repos
+        .forms
+        .find_form(&form_id)
+        .await?
+        .and_then(|definition| {
+            definition
+                .questions
+                .into_iter()
+                .find(|question| question.id == question_id)
+        })
+        .ok_or_else(|| AppError::NotFound("Question not found".to_string()))?;
+
+    let question_title = question.title;
+    let source = match question.kind.type_name() {
+        "text" | "textarea" => "answer",
+        _ => "comment",
+    };
+
+    // One entry per response, with the question's free text if any
+    let rows = state
+        .repos
+        .responses
+        .question_texts(&form_id, &question_id, source == "comment")
+        .await?;
+
+    let total_responses = rows.len();
+    let answers: Vec<String> = rows
+        .into_iter()
+        .flatten()
+        .filter(|text| !text.trim().is_empty())
+        .collect();
+    let answered = answers.len();
+    let blank = total_responses - answered;
+    let suppressed = answered < ANONYMITY_THRESHOLD;
+
+    Ok(Json(TextQuestionStats {
+        form_id,
+        question_id,
+        question_title,
+        source,
+        total_responses,
+        answered,
+        blank,
+        blank_share: if total_responses > 0 {
+            blank as f64 / total_responses as f64
+        } else {
+            0.0
+        },
+        suppressed,
+        min_answers: ANONYMITY_THRESHOLD,
+        analysis: if suppressed {
+            None
+        } else {
+            analyze(&answers, top)
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stopwords_are_sorted_for_binary_search() {
+        assert!(STOPWORDS.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn tokens_keep_inner_apostrophes() {
+        assert_eq!(
+            tokenize("Don’t cut the 'budget' -- ever!"),
+            vec!["don't", "cut", "the", "budget", "ever"]
+        );
+    }
+
+    #[test]
+    fn terms_and_bigrams_skip_stopwords_and_rare_phrases() {
+        let answers: Vec<String> = [
+            "The budget process is unclear",
+            "Budget process needs more board input",
+            "Unclear budget priorities",
+            "Ask Jane about the budget process",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let analysis = analyze(&answers, 3).unwrap();
+        assert_eq!(analysis.token_count, 20);
+        assert_eq!(
+            analysis.top_terms,
+            vec![
+                TermCount {
+                    term: "budget".to_string(),
+                    count: 4,
+                    answers: 4
+                },
+                TermCount {
+                    term: "process".to_string(),
+                    count: 3,
+                    answers: 3
+                },
+                TermCount {
+                    term: "unclear".to_string(),
+                    count: 2,
+                    answers: 2
+                },
+            ]
+        );
+        // "jane" and "board input" appear in a single answer and are never reported
+        assert_eq!(
+            analysis.top_bigrams,
+            vec![TermCount {
+                term: "budget process".to_string(),
+                count: 3,
+                answers: 3
+            }]
+        );
+        assert_eq!(analysis.words.min, 3);
+        assert_eq!(analysis.words.max, 6);
+        assert_eq!(analysis.words.median, 5.5);
+    }
+}
@@ -0,0 +1,546 @@
+//! Longitudinal comparison: how the same likert questions score across a run of forms,
+//! such as an ED review repeated every six months.
+//!
+//! Questions are matched across forms by their question-bank ID (the `bankId` feature) or
+//! else by identical title, and sections by identical title. Each point of a trend carries
+//! the change from the previous point and a Welch t-test of the two samples.
+
+use std::collections::{BTreeSet, HashMap};
+
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    analysis,
+    error::AppError,
+    handlers::check_admin_auth,
+    repository::{FormDefinition, LikertRating},
+    AppState,
+};
+
+/// A change is flagged as significant below this p-value. Each comparison is tested on
+/// its own, without correcting for the number of questions.
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+#[derive(Debug, Deserialize)]
+pub struct TrendQuery {
+    pub token: Option<String>,
+    /// Comma-separated form IDs, in chronological order
+    pub form_ids: Option<String>,
+    /// Every form whose `settings.series` is this value, oldest first
+    pub series: Option<String>,
+}
+
+/// One form of the series with its ratings
+struct Cycle {
+    definition: FormDefinition,
+    created_at: String,
+    total_responses: i64,
+    ratings: Vec<LikertRating>,
+}
+
+/// A likert question followed across the series
+struct Track {
+    bank_question_id: Option<String>,
+    title: String,
+    section_title: Option<String>,
+    /// The question's ID on each form, where the form has it
+    question_ids: Vec<Option<String>>,
+}
+
+/// Follow every likert question across the forms: same `bankId`, or same title where at
+/// most one side has a `bankId`. A question joins the first track that matches and has no
+/// question from its form yet.
+fn match_questions(cycles: &[Cycle]) -> Vec<Track> {
+    let mut tracks: Vec<Track> = Vec::new();
+    for (index, cycle) in cycles.iter().enumerate() {
+        for question in &cycle.definition.questions {
+            if question.kind.type_name() != "likert" {
+                continue;
+            }
+            let bank_id = question.kind.common().bank_id.as_ref();
+            let title = question.title.trim();
+
+            let matching = tracks.iter().position(|track| {
+                track.question_ids[index].is_none()
+                    && match (bank_id, &track.bank_question_id) {
+                        (Some(bank_id), Some(track_id)) => bank_id == track_id,
+                        _ => track.title == title,
+                    }
+            });
+            let track = match matching {
+                Some(position) => &mut tracks[position],
+                None => {
+                    tracks.push(Track {
+                        bank_question_id: None,
+                        title: title.to_string(),
+                        section_title: question.section_id.as_ref().and_then(|section_id| {
+                            cycle
+                                .definition
+                                .sections
+                                .iter()
+                                .find(|section| &section.id == section_id)
+                                .map(|section| section.title.clone())
+                        }),
+                        question_ids: vec![None; cycles.len()],
+                    });
+                    tracks.last_mut().expect("just pushed")
+                }
+            };
+            track.question_ids[index] = Some(question.id.clone());
+            if track.bank_question_id.is_none() {
+                track.bank_question_id = bank_id.cloned();
+            }
+        }
+    }
+    tracks
+}
+
+#[derive(Debug, Serialize)]
+struct TrendPoint {
+    form_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    question_id: Option<String>,
+    n: usize,
+    mean: Option<f64>,
+    /// Change in mean from the previous point that has one
+    delta: Option<f64>,
+    /// Welch t-test against that previous point
+    p_value: Option<f64>,
+    significant: bool,
+}
+
+/// The points of one trend from its sample on each form, where the form has one
+fn trend_points(samples: Vec<(String, Option<String>, Vec<f64>)>) -> Vec<TrendPoint> {
+    let mut points = Vec::with_capacity(samples.len());
+    let mut previous: Option<(f64, Vec<f64>)> = None;
+    for (form_id, question_id, sample) in samples {
+        let mean = analysis::mean(&sample);
+        let (delta, p_value) = match (&previous, mean) {
+            (Some((previous_mean, previous_sample)), Some(mean)) => (
+                Some(mean - previous_mean),
+                analysis::welch_t_test(previous_sample, &sample).map(|test| test.p_value),
+            ),
+            _ => (None, None),
+        };
+
+        points.push(TrendPoint {
+            form_id,
+            question_id,
+            n: sample.len(),
+            mean,
+            delta,
+            p_value,
+            significant: p_value.is_some_and(|p| p < SIGNIFICANCE_LEVEL),
+        });
+        if let Some(mean) = mean {
+            previous = Some((mean, sample));
+        }
+    }
+    points
+}
+
+/// A question's ratings on each form that has it, from responses with `role` (or all)
+fn question_samples(
+    cycles: &[Cycle],
+    track: &Track,
+    role: Option<&str>,
+) -> Vec<(String, Option<String>, Vec<f64>)> {
+    cycles
+        .iter()
+        .zip(&track.question_ids)
+        .filter_map(|(cycle, question_id)| {
+            let question_id = question_id.as_ref()?;
+            let sample = cycle
+                .ratings
+                .iter()
+                .filter(|rating| &rating.question_id == question_id)
+                .filter(|rating| role.is_none() || rating.role.as_deref() == role)
+                .map(|rating| rating.rating as f64)
+                .collect();
+            Some((
+                cycle.definition.form.id.clone(),
+                Some(question_id.clone()),
+                sample,
+            ))
+        })
+        .collect()
+}
+
+/// Each respondent's mean rating over the likert questions of the section titled `title`,
+/// on each form that has such a section
+fn section_samples(
+    cycles: &[Cycle],
+    title: &str,
+    role: Option<&str>,
+) -> Vec<(String, Option<String>, Vec<f64>)> {
+    cycles
+        .iter()
+        .filter_map(|cycle| {
+            let definition = &cycle.definition;
+            let section = definition
+                .sections
+                .iter()
+                .find(|section| section.title.trim() == title)?;
+            let questions: BTreeSet<&str> = definition
+                .questions
+                .iter()
+                .filter(|question| question.section_id.as_deref() == Some(section.id.as_str()))
+                .map(|question| question.id.as_str())
+                .collect();
+
+            let mut by_response: HashMap<&str, Vec<f64>> = HashMap::new();
+            for rating in &cycle.ratings {
+                if questions.contains(rating.question_id.as_str())
+                    && (role.is_none() || rating.role.as_deref() == role)
+                {
+                    by_response
+                        .entry(&rating.response_id)
+                        .or_default()
+                        .push(rating.rating as f64);
+                }
+            }
+            let sample = by_response
+                .values()
+                .filter_map(|ratings| analysis::mean(ratings))
+                .collect();
+            Some((definition.form.id.clone(), None, sample))
+        })
+        .collect()
+}
+
+/// The overall trend and one per role, leaving out roles with no ratings in it
+fn with_roles(
+    roles: &[String],
+    samples: impl Fn(Option<&str>) -> Vec<(String, Option<String>, Vec<f64>)>,
+) -> (Vec<TrendPoint>, Vec<serde_json::Value>) {
+    let role_trends = roles
+        .iter()
+        .filter_map(|role| {
+            let samples = samples(Some(role));
+            samples
+                .iter()
+                .any(|(_, _, sample)| !sample.is_empty())
+                .then(|| json!({ "role": role, "points": trend_points(samples) }))
+        })
+        .collect();
+    (trend_points(samples(None)), role_trends)
+}
+
+async fn load_cycle(
+    state: &AppState,
+    definition: FormDefinition,
+    created_at: String,
+) -> Result<Cycle, AppError> {
+    let form_id = &definition.form.id;
+    Ok(Cycle {
+        total_responses: state.repos.responses.count_responses(form_id).await?,
+        ratings: state.repos.responses.likert_ratings(form_id).await?,
+        definition,
+        created_at,
+    })
+}
+
+/// Per-question and per-section means across a series of forms, with the change between
+/// forms, significance flags and the same split by role (admin only). The forms are
+/// either listed in `form_ids` or share a `settings.series`.
+pub async fn get_trends(
+    headers: HeaderMap,
+    Query(params): Query<TrendQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+
+    let forms = state.repos.forms.list_forms().await?;
+    let created_at = |form_id: &str| {
+        forms
+            .iter()
+            .find(|form| form.id == form_id)
+            .map(|form| form.created_at.clone())
+            .unwrap_or_default()
+    };
+
+    let mut cycles = Vec::new();
+    match (&params.form_ids, &params.series) {
+        (Some(form_ids), None) => {
+            for form_id in form_ids
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+            {
+                let definition =
+                    state.repos.forms.find_form(form_id).await?.ok_or_else(|| {
+                        AppError::BadRequest(format!("Form '{}' not found", form_id))
+                    })?;
+                cycles.push(load_cycle(&state, definition, created_at(form_id)).await?);
+            }
+        }
+        (None, Some(series)) => {
+            for form in &forms {
+                let Some(definition) = state.repos.forms.find_form(&form.id).await? else {
+                    continue;
+                };
+                let settings = definition.form.settings.as_ref();
+                if settings.and_then(|s| s["series"].as_str()) == Some(series.as_str()) {
+                    cycles.push(load_cycle(&state, definition, form.created_at.clone()).await?);
+                }
+            }
+            cycles.sort_by(|a, b| {
+                (&a.created_at, &a.definition.form.id).cmp(&(&b.created_at, &b.definition.form.id))
+            });
+        }
+        _ => {
+            return Err(AppError::BadRequest(
+                "Give either form_ids or series".to_string(),
+            ))
+        }
+    }
+    if cycles.len() < 2 {
+        return Err(AppError::BadRequest(
+            "A trend needs at least two forms".to_string(),
+        ));
+    }
+
+    let roles: Vec<String> = cycles
+        .iter()
+        .flat_map(|cycle| &cycle.ratings)
+        .filter_map(|rating| rating.role.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let questions: Vec<_> = match_questions(&cycles)
+        .into_iter()
+        .map(|track| {
+            let (points, role_trends) =
+                with_roles(&roles, |role| question_samples(&cycles, &track, role));
+            json!({
+                "bank_question_id": track.bank_question_id,
+                "title": track.title,
+                "section_title": track.section_title,
+                "points": points,
+                "roles": role_trends
+            })
+        })
+        .collect();
+
+    // Sections in order of first appearance, leaving out those without likert questions
+    let mut section_titles: Vec<String> = Vec::new();
+    for cycle in &cycles {
+        let definition = &cycle.definition;
+        for section in &definition.sections {
+            let title = section.title.trim().to_string();
+            let has_likert = definition.questions.iter().any(|question| {
+                question.section_id.as_deref() == Some(section.id.as_str())
+                    && question.kind.type_name() == "likert"
+            });
+            if has_likert && !section_titles.contains(&title) {
+                section_titles.push(title);
+            }
+        }
+    }
+    let sections: Vec<_> = section_titles
+        .into_iter()
+        .map(|title| {
+            let (points, role_trends) =
+                with_roles(&roles, |role| section_samples(&cycles, &title, role));
+            json!({ "title": title, "points": points, "roles": role_trends })
+        })
+        .collect();
+
+    let forms: Vec<_> = cycles
+        .iter()
+        .map(|cycle| {
+            let form = &cycle.definition.form;
+            json!({
+                "form_id": form.id,
+                "title": form.title,
+                "review_period": form.settings.as_ref().and_then(|s| s.get("reviewPeriod")),
+                "created_at": cycle.created_at,
+                "total_responses": cycle.total_responses
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "significance_level": SIGNIFICANCE_LEVEL,
+        "forms": forms,
+        "questions": questions,
+        "sections": sections
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{json, Value as JsonValue};
+
+    use super::*;
+    use crate::{
+        questions::QuestionKind,
+        repository::{
+            memory::{MemoryStore, StoredResponse},
+            Form, Question, Section,
+        },
+    };
+
+    /// A form in the `ed` series with one Leadership section of likert questions, given as
+    /// `(id, title, bankId)`
+    fn review(form_id: &str, questions: &[(&str, &str, Option<&str>)]) -> FormDefinition {
+        FormDefinition {
+            form: Form {
+                id: form_id.to_string(),
+                title: "ED Review".to_string(),
+                description: None,
+                instructions: None,
+                status: "published".to_string(),
+                welcome_message: None,
+                closing_message: None,
+                settings: Some(json!({ "series": "ed", "reviewPeriod": form_id })),
+            },
+            sections: vec![Section {
+                id: format!("{}-leadership", form_id),
+                form_id: form_id.to_string(),
+                title: "Leadership".to_string(),
+                description: None,
+                position: 1,
+            }],
+            questions: questions
+                .iter()
+                .zip(1..)
+                .map(|((id, title, bank_id), position)| Question {
+                    id: id.to_string(),
+                    form_id: form_id.to_string(),
+                    section_id: Some(format!("{}-leadership", form_id)),
+                    position,
+                    title: title.to_string(),
+                    description: None,
+                    kind: QuestionKind::from_parts("likert", &json!({ "bankId": bank_id }))
+                        .unwrap(),
+                })
+                .collect(),
+        }
+    }
+
+    async fn trends(state: &AppState, form_ids: Option<&str>, series: Option<&str>) -> JsonValue {
+        let response = get_trends(
+            HeaderMap::new(),
+            Query(TrendQuery {
+                token: Some("admin".to_string()),
+                form_ids: form_ids.map(str::to_string),
+                series: series.map(str::to_string),
+            }),
+            State(state.clone()),
+        )
+        .await
+        .unwrap()
+        .into_response();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn questions_line_up_by_bank_id_or_title() {
+        let store = Arc::new(MemoryStore::default());
+        let state = store.app_state();
+        state
+            .repos
+            .forms
+            .create_form(&review(
+                "ed-2024",
+                &[
+                    ("a", "Sets a clear vision", Some("vision")),
+                    ("b", "Manages the budget", None),
+                ],
+            ))
+            .await
+            .unwrap();
+        state
+            .repos
+            .forms
+            .create_form(&review(
+                "ed-2025",
+                &[
+                    ("x", "Communicates a clear vision", Some("vision")),
+                    ("y", "Manages the budget", None),
+                ],
+            ))
+            .await
+            .unwrap();
+        let respond = |form_id: &str, role: &str, answers: &[(&str, i64)]| {
+            store.add_response(StoredResponse {
+                form_id: form_id.to_string(),
+                role: Some(role.to_string()),
+                email_hash: None,
+                answers: answers
+                    .iter()
+                    .map(|(question_id, rating)| (question_id.to_string(), json!(rating)))
+                    .collect(),
+                ..Default::default()
+            });
+        };
+        for (vision, budget) in [(1, 3), (2, 4), (1, 3), (2, 4)] {
+            respond("ed-2024", "Staff", &[("a", vision), ("b", budget)]);
+        }
+        for (vision, budget) in [(4, 3), (5, 4), (4, 3), (5, 4)] {
+            respond("ed-2025", "Staff", &[("x", vision), ("y", budget)]);
+        }
+        respond("ed-2025", "Board", &[("x", 3)]);
+
+        let body = trends(&state, Some("ed-2024, ed-2025"), None).await;
+        assert_eq!(body["forms"][1]["total_responses"], 5);
+        let questions = body["questions"].as_array().unwrap();
+        assert_eq!(questions.len(), 2);
+
+        let vision = &questions[0];
+        assert_eq!(vision["bank_question_id"], "vision");
+        assert_eq!(vision["section_title"], "Leadership");
+        let points = vision["points"].as_array().unwrap();
+        assert_eq!(points[0]["question_id"], "a");
+        assert_eq!(points[1]["question_id"], "x");
+        assert_eq!(points[1]["n"], 5);
+        assert!((points[1]["delta"].as_f64().unwrap() - 2.7).abs() < 1e-9);
+        assert_eq!(points[1]["significant"], true);
+        assert_eq!(vision["roles"][0]["role"], "Board");
+        assert_eq!(vision["roles"][0]["points"][1]["n"], 1);
+        assert_eq!(vision["roles"][1]["points"][1]["delta"], 3.0);
+
+        let budget = &questions[1];
+        assert_eq!(budget["points"][1]["question_id"], "y");
+        assert_eq!(budget["points"][1]["delta"], 0.0);
+        assert_eq!(budget["points"][1]["p_value"], 1.0);
+        assert_eq!(budget["points"][1]["significant"], false);
+
+        // Respondents' section means: 2.5 on average in 2024, 3.8 in 2025
+        let leadership = &body["sections"][0];
+        assert_eq!(leadership["title"], "Leadership");
+        assert_eq!(leadership["points"][0]["mean"], 2.5);
+        assert!((leadership["points"][1]["mean"].as_f64().unwrap() - 3.8).abs() < 1e-9);
+
+        let by_series = trends(&state, None, Some("ed")).await;
+        assert_eq!(by_series["questions"], body["questions"]);
+
+        for (form_ids, series) in [(Some("ed-2024"), None), (Some("ed-2024"), Some("ed"))] {
+            let error = get_trends(
+                HeaderMap::new(),
+                Query(TrendQuery {
+                    token: Some("admin".to_string()),
+                    form_ids: form_ids.map(str::to_string),
+                    series: series.map(str::to_string),
+                }),
+                State(state.clone()),
+            )
+            .await;
+            assert!(matches!(error, Err(AppError::BadRequest(_))));
+        }
+    }
+}
@@ -0,0 +1,514 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    handlers::{check_admin_auth, AuthQuery},
+    models::*,
+    outbox::{DeliveryError, WebhookDelivery},
+    pagination::PageParams,
+    AppState,
+};
+
+pub const RESPONSE_SUBMITTED: &str = "response.submitted";
+pub const FORM_STATUS_CHANGED: &str = "form.status_changed";
+pub const FORM_UPDATED: &str = "form.updated";
+pub const RESPONDENT_PII_DELETED: &str = "respondent.pii_deleted";
+/// Only global endpoints hear it, as the form's own webhooks are deleted with it
+pub const FORM_DELETED: &str = "form.deleted";
+
+pub const EVENTS: &[&str] = &[
+    RESPONSE_SUBMITTED,
+    FORM_STATUS_CHANGED,
+    FORM_UPDATED,
+    RESPONDENT_PII_DELETED,
+    FORM_DELETED,
+];
+
+const DELIVERY_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookListQuery {
+    pub token: Option<String>,
+    pub form_id: Option<String>,
+}
+
+/// An event to deliver to every matching webhook
+#[derive(Debug, Clone)]
+pub struct WebhookEvent {
+    pub event: &'static str,
+    /// Forms the event concerns; global endpoints always match
+    pub form_ids: Vec<String>,
+    /// Anonymous payload sent to every endpoint
+    pub data: JsonValue,
+    /// Respondent details, only added for endpoints with `include_pii`
+    pub pii: Option<JsonValue>,
+}
+
+/// Hex HMAC-SHA256 of `message` keyed by the webhook secret
+pub fn signature(secret: &str, message: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(message);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Event data for one endpoint, with respondent details only if it opted into PII
+fn payload_for(webhook: &Webhook, event: &WebhookEvent) -> JsonValue {
+    let mut data = event.data.clone();
+    if webhook.include_pii {
+        if let (Some(pii), Some(obj)) = (&event.pii, data.as_object_mut()) {
+            obj.insert("respondent".to_string(), pii.clone());
+        }
+    }
+    data
+}
+
+/// Body and headers for one delivery.
+///
+/// Receivers verify `X-Webhook-Signature` as `sha256=` + HMAC of `"{timestamp}.{body}"`
+/// and should reject timestamps that are too old to prevent replays. Retries of one
+/// delivery reuse its `delivery_id`, so receivers can use `X-Webhook-Id` to deduplicate.
+fn build_delivery(
+    secret: &str,
+    delivery_id: &str,
+    event: &str,
+    data: &JsonValue,
+) -> (String, Vec<(&'static str, String)>) {
+    let timestamp = Utc::now().timestamp().to_string();
+    let body = json!({
+        "id": delivery_id,
+        "event": event,
+        "created_at": Utc::now().to_rfc3339(),
+        "data": data
+    })
+    .to_string();
+
+    let signed = format!("{}.{}", timestamp, body);
+    let headers = vec![
+        ("X-Webhook-Id", delivery_id.to_string()),
+        ("X-Webhook-Event", event.to_string()),
+        ("X-Webhook-Timestamp", timestamp),
+        (
+            "X-Webhook-Signature",
+            format!("sha256={}", signature(secret, signed.as_bytes())),
+        ),
+    ];
+
+    (body, headers)
+}
+
+/// Whether a failed delivery is worth retrying: client errors other than
+/// timeouts and rate limiting will fail the same way next time
+fn is_retryable(status: StatusCode) -> bool {
+    !status.is_client_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+async fn deliver(
+    webhook: &Webhook,
+    delivery_id: &str,
+    event: &str,
+    data: &JsonValue,
+) -> Result<u16, DeliveryError> {
+    let (body, headers) = build_delivery(&webhook.secret, delivery_id, event, data);
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(DELIVERY_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| DeliveryError::Retry(e.to_string()))?;
+
+    let mut request = client
+        .post(&webhook.url)
+        .header("Content-Type", "application/json")
+        .body(body);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| DeliveryError::Retry(e.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        let error = format!("Endpoint responded with {}", status);
+        return Err(if is_retryable(status) {
+            DeliveryError::Retry(error)
+        } else {
+            DeliveryError::Permanent(error)
+        });
+    }
+
+    Ok(status.as_u16())
+}
+
+/// One delivery per webhook among `webhooks` that is active and subscribed to the event,
+/// for one of its forms or globally. Backends queue these with the triggering write.
+pub fn deliveries(webhooks: &[Webhook], event: &WebhookEvent) -> Vec<WebhookDelivery> {
+    webhooks
+        .iter()
+        .filter(|w| w.active)
+        .filter(|w| match &w.form_id {
+            Some(form_id) => event.form_ids.contains(form_id),
+            None => true,
+        })
+        .filter(|w| w.events.is_empty() || w.events.iter().any(|e| e == event.event))
+        .map(|webhook| WebhookDelivery {
+            webhook_id: webhook.id.clone(),
+            event: event.event.to_string(),
+            data: payload_for(webhook, event),
+        })
+        .collect()
+}
+
+/// Deliver a queued event, signing it with the endpoint's current secret.
+/// `delivery_id` is the outbox entry ID, so it stays the same across retries.
+pub async fn deliver_queued(
+    state: &AppState,
+    delivery_id: &str,
+    delivery: &WebhookDelivery,
+) -> Result<(), DeliveryError> {
+    let webhook = state
+        .repos
+        .webhooks
+        .find_webhook(&delivery.webhook_id)
+        .await
+        .map_err(|e| DeliveryError::Retry(e.to_string()))?;
+
+    let webhook = webhook.filter(|w| w.active).ok_or_else(|| {
+        DeliveryError::Permanent("Webhook was deleted or deactivated".to_string())
+    })?;
+
+    deliver(&webhook, delivery_id, &delivery.event, &delivery.data)
+        .await
+        .map(|_| ())
+}
+
+/// Register a webhook endpoint (admin only)
+pub async fn create_webhook(
+    headers: HeaderMap,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let url = req.url.trim().to_string();
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Err(AppError::BadRequest(
+            "Webhook URL must start with http:// or https://".to_string(),
+        ));
+    }
+
+    if let Some(unknown) = req.events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+        return Err(AppError::BadRequest(format!(
+            "Unknown event '{}'. Must be one of: {}",
+            unknown,
+            EVENTS.join(", ")
+        )));
+    }
+
+    if let Some(form_id) = &req.form_id {
+        if !state.repos.forms.form_exists(form_id).await? {
+            return Err(AppError::BadRequest("Form not found".to_string()));
+        }
+    }
+
+    let secret = match req.secret {
+        Some(secret) if secret.len() < 16 => {
+            return Err(AppError::BadRequest(
+                "Webhook secret must be at least 16 characters".to_string(),
+            ))
+        }
+        Some(secret) => secret,
+        None => format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+    };
+
+    let webhook = Webhook {
+        id: Uuid::new_v4().to_string(),
+        form_id: req.form_id,
+        url,
+        secret,
+        events: sqlx::types::Json(req.events),
+        include_pii: req.include_pii,
+        active: true,
+        created_at: String::new(),
+    };
+    state.repos.webhooks.create_webhook(&webhook).await?;
+
+    // The secret is only ever returned here
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "message": "Webhook created successfully",
+            "id": webhook.id,
+            "form_id": webhook.form_id,
+            "url": webhook.url,
+            "events": webhook.events,
+            "include_pii": webhook.include_pii,
+            "secret": webhook.secret
+        })),
+    ))
+}
+
+/// List webhook endpoints, optionally only those for one form (admin only)
+pub async fn list_webhooks(
+    headers: HeaderMap,
+    Query(params): Query<WebhookListQuery>,
+    Query(page): Query<PageParams>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, params.token.as_deref(), &state.admin_token)?;
+
+    let webhooks = state
+        .repos
+        .webhooks
+        .list_webhooks(params.form_id.as_deref(), &page)
+        .await?;
+
+    Ok(Json(webhooks))
+}
+
+/// Remove a webhook endpoint (admin only)
+pub async fn delete_webhook(
+    headers: HeaderMap,
+    Path(webhook_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    if !state.repos.webhooks.delete_webhook(&webhook_id).await? {
+        return Err(AppError::NotFound("Webhook not found".to_string()));
+    }
+
+    Ok(Json(json!({
+        "message": "Webhook deleted successfully"
+    })))
+}
+
+/// Send a signed `ping` event right away and report the outcome (admin only)
+pub async fn test_webhook(
+    headers: HeaderMap,
+    Path(webhook_id): Path<String>,
+    Query(auth): Query<AuthQuery>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    check_admin_auth(&headers, auth.token.as_deref(), &state.admin_token)?;
+
+    let webhook = state.repos.webhooks.find_webhook(&webhook_id).await?;
+
+    let webhook = webhook.ok_or_else(|| AppError::NotFound("Webhook not found".to_string()))?;
+
+    // Sent directly rather than through the outbox so the result can be reported
+    let data = json!({ "webhook_id": webhook.id });
+
+    let delivery_id = Uuid::new_v4().to_string();
+
+    Ok(Json(
+        match deliver(&webhook, &delivery_id, "ping", &data).await {
+            Ok(status) => json!({ "delivered": true, "status": status }),
+            Err(DeliveryError::Retry(error) | DeliveryError::Permanent(error)) => {
+                json!({ "delivered": false, "error": error })
+            }
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{routing::post, Router};
+
+    use super::*;
+    use crate::{models::OutboxEntry, outbox, repository::sqlite::enqueue};
+
+    /// A local endpoint answering with the given statuses in turn, recording `X-Webhook-Id`
+    async fn receiver(statuses: Vec<StatusCode>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let ids = Arc::new(Mutex::new(Vec::new()));
+        let replies = Arc::new(Mutex::new(statuses.into_iter()));
+        let app = Router::new().route(
+            "/hook",
+            post({
+                let ids = ids.clone();
+                move |headers: HeaderMap| async move {
+                    let id = headers["X-Webhook-Id"].to_str().unwrap().to_string();
+                    ids.lock().unwrap().push(id);
+                    replies.lock().unwrap().next().unwrap()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, ids)
+    }
+
+    #[test]
+    fn signature_matches_hmac_sha256_reference() {
+        assert_eq!(
+            signature("key", b"The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn pii_is_only_attached_for_opted_in_endpoints() {
+        let mut webhook = Webhook {
+            id: "w1".to_string(),
+            form_id: None,
+            url: "https://example.com/hook".to_string(),
+            secret: "0123456789abcdef".to_string(),
+            events: sqlx::types::Json(vec![]),
+            include_pii: false,
+            active: true,
+            created_at: String::new(),
+        };
+        let event = WebhookEvent {
+            event: RESPONSE_SUBMITTED,
+            form_ids: vec!["f1".to_string()],
+            data: json!({ "response_id": "r1" }),
+            pii: Some(json!({ "name": "Jane", "email": "jane@example.com" })),
+        };
+
+        let data = payload_for(&webhook, &event);
+        assert!(data.get("respondent").is_none());
+
+        let (body, headers) = build_delivery(&webhook.secret, "d1", event.event, &data);
+
+        let timestamp = &headers
+            .iter()
+            .find(|(k, _)| *k == "X-Webhook-Timestamp")
+            .unwrap()
+            .1;
+        let sent_signature = &headers
+            .iter()
+            .find(|(k, _)| *k == "X-Webhook-Signature")
+            .unwrap()
+            .1;
+        let expected = signature(
+            &webhook.secret,
+            format!("{}.{}", timestamp, body).as_bytes(),
+        );
+        assert_eq!(sent_signature, &format!("sha256={}", expected));
+
+        webhook.include_pii = true;
+        assert_eq!(payload_for(&webhook, &event)["respondent"]["name"], "Jane");
+    }
+
+    #[test]
+    fn deliveries_go_to_active_endpoints_for_the_form_and_event() {
+        let webhook = |id: &str, form_id: Option<&str>, events: &[&str], active: bool| Webhook {
+            id: id.to_string(),
+            form_id: form_id.map(str::to_string),
+            url: "https://example.com/hook".to_string(),
+            secret: "0123456789abcdef".to_string(),
+            events: sqlx::types::Json(events.iter().map(|e| e.to_string()).collect()),
+            include_pii: false,
+            active,
+            created_at: String::new(),
+        };
+        let webhooks = [
+            webhook("global", None, &[], true),
+            webhook("form", Some("f1"), &[FORM_UPDATED], true),
+            webhook("other-form", Some("f2"), &[], true),
+            webhook("other-event", None, &[RESPONSE_SUBMITTED], true),
+            webhook("inactive", None, &[], false),
+        ];
+        let event = WebhookEvent {
+            event: FORM_UPDATED,
+            form_ids: vec!["f1".to_string()],
+            data: json!({ "form_id": "f1" }),
+            pii: None,
+        };
+
+        let ids: Vec<String> = deliveries(&webhooks, &event)
+            .into_iter()
+            .map(|delivery| delivery.webhook_id)
+            .collect();
+        assert_eq!(ids, vec!["global", "form"]);
+    }
+
+    #[tokio::test]
+    async fn retries_reuse_the_entry_id_and_client_errors_are_not_retried() {
+        let (url, ids) = receiver(vec![
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::TOO_MANY_REQUESTS,
+            StatusCode::NOT_FOUND,
+        ])
+        .await;
+        let db = crate::db::test_pool().await;
+        let state = AppState::for_tests(db.clone());
+        sqlx::query("INSERT INTO webhooks (id, url, secret) VALUES ('w1', ?, '0123456789abcdef')")
+            .bind(&url)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        let mut conn = db.acquire().await.unwrap();
+        enqueue(
+            &mut conn,
+            &outbox::OutboxMessage::Webhook(WebhookDelivery {
+                webhook_id: "w1".to_string(),
+                event: RESPONSE_SUBMITTED.to_string(),
+                data: json!({ "response_id": "r1" }),
+            }),
+        )
+        .await
+        .unwrap();
+        drop(conn);
+
+        let attempt = || async {
+            outbox::process_due(&state).await.unwrap();
+            let entry: OutboxEntry = sqlx::query_as("SELECT * FROM outbox")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+            sqlx::query("UPDATE outbox SET next_attempt_at = datetime('now')")
+                .execute(&db)
+                .await
+                .unwrap();
+            entry
+        };
+
+        // 503 and 429 are worth another try
+        for attempts in 1..=2 {
+            let entry = attempt().await;
+            assert_eq!(
+                (entry.status.as_str(), entry.attempts),
+                ("pending", attempts)
+            );
+        }
+        // 404 will not get better
+        let entry = attempt().await;
+        assert_eq!((entry.status.as_str(), entry.attempts), ("dead", 3));
+
+        assert_eq!(*ids.lock().unwrap(), vec![entry.id.clone(); 3]);
+    }
+
+    #[test]
+    fn only_timeouts_and_rate_limits_are_retried_among_client_errors() {
+        assert!(is_retryable(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(is_retryable(StatusCode::REQUEST_TIMEOUT));
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable(StatusCode::GONE));
+    }
+}
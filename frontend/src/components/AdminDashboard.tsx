@@ -10,6 +10,7 @@ import {
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
+import { Checkbox } from "@/components/ui/checkbox";
 import {
   FileSpreadsheet,
   Upload,
@@ -24,6 +25,7 @@ import {
   Plus,
 } from "lucide-react";
 import * as XLSX from "xlsx";
+import { fetchAllPages } from "@/services/api";
 import {
   AlertDialog,
   AlertDialogAction,
@@ -70,6 +72,8 @@ export default function AdminDashboard() {
 
   // Dialog states
   const [cloneDialogOpen, setCloneDialogOpen] = useState(false);
+  const [cloneReviewPeriod, setCloneReviewPeriod] = useState("");
+  const [cloneInvitations, setCloneInvitations] = useState(false);
   const [deleteDialogOpen, setDeleteDialogOpen] = useState(false);
   const [statusDialogOpen, setStatusDialogOpen] = useState(false);
   const [selectedForm, setSelectedForm] = useState<{
@@ -217,6 +221,8 @@ export default function AdminDashboard() {
 
   const handleCloneClick = (formId: string, formTitle: string) => {
     setSelectedForm({ id: formId, title: formTitle });
+    setCloneReviewPeriod("");
+    setCloneInvitations(false);
     setCloneDialogOpen(true);
   };
 
@@ -228,6 +234,11 @@ export default function AdminDashboard() {
         `/api/admin/forms/${selectedForm.id}/clone?token=${token}`,
         {
           method: "POST",
+          headers: { "Content-Type": "application/json" },
+          body: JSON.stringify({
+            review_period: cloneReviewPeriod.trim() || undefined,
+            copy_invitations: cloneInvitations,
+          }),
         },
       );
 
@@ -363,44 +374,15 @@ export default function AdminDashboard() {
 
   const exportFormAsJSON = async (formId: string, formTitle: string) => {
     try {
-      // Fetch full form data
-      const formRes = await fetch(`/api/forms/${formId}`);
-      if (!formRes.ok) throw new Error("Failed to load form");
-      const formData = await formRes.json();
-
-      // Transform to import format
-      const exportData = {
-        id: formData.id,
-        title: formData.title,
-        description: formData.description,
-        welcome_message: formData.welcome_message,
-        closing_message: formData.closing_message,
-        status: formData.status || "draft",
-        settings: formData.settings || {},
-        sections: formData.sections.map((section: any) => ({
-          id: section.id,
-          title: section.title,
-          description: section.description,
-          position: section.position,
-          questions: section.questions.map((q: any) => ({
-            id: q.id,
-            title: q.title,
-            question_type: q.type,
-            is_required: q.features?.required || false,
-            allow_comment: q.features?.allowComment || false,
-            help_text: q.description || "",
-            position: q.position,
-            placeholder: q.features?.placeholder,
-            charLimit: q.features?.charLimit,
-            rows: q.features?.rows,
-          })),
-        })),
-      };
+      // The server renders the definition in the import format, so it imports back unchanged
+      const res = await fetch(
+        `/api/admin/forms/${formId}/definition?token=${token}&format=json`,
+      );
+      if (!res.ok) throw new Error("Failed to load form definition");
+      const definition = await res.text();
 
-      // Download as JSON
-      const dataStr = JSON.stringify(exportData, null, 2);
       const dataUri =
-        "data:application/json;charset=utf-8," + encodeURIComponent(dataStr);
+        "data:application/json;charset=utf-8," + encodeURIComponent(definition);
       const exportFileDefaultName = `${formId}-${new Date().toISOString().split("T")[0]}.json`;
 
       const linkElement = document.createElement("a");
@@ -430,12 +412,12 @@ export default function AdminDashboard() {
       if (!formRes.ok) throw new Error("Failed to load form");
       const formData = await formRes.json();
 
-      // Fetch responses
-      const responsesRes = await fetch(
+      // Fetch responses, following pagination cursors
+      const responsesData = await fetchAllPages<any>(
         `/api/admin/responses?token=${token}&form_id=${formId}`,
-      );
-      if (!responsesRes.ok) throw new Error("Failed to load responses");
-      const responsesData = await responsesRes.json();
+      ).catch(() => {
+        throw new Error("Failed to load responses");
+      });
 
       // Extract sections and questions
       const sections: any[] = [];
@@ -682,21 +664,29 @@ export default function AdminDashboard() {
     setUploadError("");
     setUploadSuccess("");
 
-    if (!file.name.endsWith(".json")) {
-      setUploadError("Please upload a JSON file");
+    // The server reads YAML and Markdown definitions as well as JSON
+    const extension = file.name.split(".").pop()?.toLowerCase() ?? "";
+    const contentTypes: Record<string, string> = {
+      json: "application/json",
+      yaml: "application/yaml",
+      yml: "application/yaml",
+      md: "text/markdown",
+    };
+    const contentType = contentTypes[extension];
+    if (!contentType) {
+      setUploadError("Please upload a JSON, YAML or Markdown file");
       return;
     }
 
     try {
       const text = await file.text();
-      const config = JSON.parse(text);
 
       const response = await fetch(`/api/admin/import-form?token=${token}`, {
         method: "POST",
         headers: {
-          "Content-Type": "application/json",
+          "Content-Type": contentType,
         },
-        body: JSON.stringify(config),
+        body: text,
       });
 
       let result;
@@ -710,27 +700,38 @@ export default function AdminDashboard() {
       }
 
       if (!response.ok) {
-        throw new Error(result.error || "Failed to import form");
+        // Definition errors come with a pointer to each offending value
+        const details = (result.errors ?? [])
+          .map(
+            (e: { pointer: string; message: string }) =>
+              `${e.pointer || "/"}: ${e.message}`,
+          )
+          .join("\n");
+        throw new Error(
+          [result.error || "Failed to import form", details]
+            .filter(Boolean)
+            .join("\n"),
+        );
       }
 
       if (result.id_conflict) {
         setUploadSuccess(
-          `Form "${config.title}" imported with new ID due to conflict!`,
+          `Form "${result.title}" imported with new ID due to conflict!`,
         );
 
         // Show a toast with conflict information
         toast({
           title: "Form imported with new ID",
-          description: `"${config.title}" had an ID conflict, so it was imported with a new ID: ${result.form_id}`,
+          description: `"${result.title}" had an ID conflict, so it was imported with a new ID: ${result.form_id}`,
           variant: "success",
         });
       } else {
-        setUploadSuccess(`Form "${config.title}" imported successfully!`);
+        setUploadSuccess(`Form "${result.title}" imported successfully!`);
 
         // Show a toast for successful import with original ID
         toast({
           title: "Form imported successfully",
-          description: `"${config.title}" was imported with its original ID: ${result.form_id}`,
+          description: `"${result.title}" was imported with its original ID: ${result.form_id}`,
           variant: "success",
         });
       }
@@ -766,7 +767,7 @@ export default function AdminDashboard() {
             <input
               ref={fileInputRef}
               type="file"
-              accept=".json"
+              accept=".json,.yaml,.yml,.md"
               onChange={handleFileUpload}
               className="hidden"
               id="form-upload"
@@ -807,7 +808,7 @@ export default function AdminDashboard() {
         </div>
 
         {uploadError && (
-          <div className="mb-4 p-4 bg-red-100 border border-red-400 text-red-700 rounded">
+          <div className="mb-4 p-4 bg-red-100 border border-red-400 text-red-700 rounded whitespace-pre-line">
             {uploadError}
           </div>
         )}
@@ -1299,6 +1300,31 @@ export default function AdminDashboard() {
               create a duplicate of the form with all its questions.
             </AlertDialogDescription>
           </AlertDialogHeader>
+          <div className="space-y-4">
+            <div className="space-y-2">
+              <Label htmlFor="clone-review-period">
+                Review period (optional)
+              </Label>
+              <Input
+                id="clone-review-period"
+                placeholder="e.g. 2025"
+                value={cloneReviewPeriod}
+                onChange={(e) => setCloneReviewPeriod(e.target.value)}
+              />
+            </div>
+            <div className="flex items-center gap-2">
+              <Checkbox
+                id="clone-invitations"
+                checked={cloneInvitations}
+                onCheckedChange={(checked) =>
+                  setCloneInvitations(checked === true)
+                }
+              />
+              <Label htmlFor="clone-invitations">
+                Copy the invitation list with new links
+              </Label>
+            </div>
+          </div>
           <AlertDialogFooter>
             <AlertDialogCancel>Cancel</AlertDialogCancel>
             <AlertDialogAction onClick={cloneForm}>Clone</AlertDialogAction>
@@ -1,5 +1,5 @@
 import { useState, useEffect } from "react";
-import { useNavigate, useParams } from "react-router-dom";
+import { useNavigate, useParams, useSearchParams } from "react-router-dom";
 import {
   Card,
   CardContent,
@@ -33,6 +33,9 @@ const likertOptions = [
 export default function FormPage() {
   const navigate = useNavigate();
   const { formId } = useParams<{ formId: string }>();
+  // Single-use token from an invitation or reminder email link (`?invite=...`)
+  const [searchParams] = useSearchParams();
+  const inviteToken = searchParams.get("invite");
   const STORAGE_KEY = `likert-form-progress-${formId}`;
   const [formData, setFormData] = useState<FormData | null>(null);
   const [loading, setLoading] = useState(true);
@@ -299,6 +302,28 @@ export default function FormPage() {
       } else {
         initializeEmptyForm(transformedData);
       }
+
+      // Invitees answer under the name, email and role on their invitation
+      if (inviteToken) {
+        const inviteResponse = await fetch(
+          `/api/forms/${formId}/invitations/${encodeURIComponent(inviteToken)}`,
+        );
+        if (inviteResponse.ok) {
+          const invitation = await inviteResponse.json();
+          setName(invitation.name);
+          setEmail(invitation.email);
+          if (invitation.role) setRole(invitation.role);
+          if (invitation.has_submitted) {
+            setError(
+              "This invitation has already been used to submit a response for this form.",
+            );
+          }
+        } else {
+          setError(
+            "This invitation link is not valid for this form. Please use the link from your invitation email.",
+          );
+        }
+      }
     } catch (err) {
       setError("Failed to load form. Please refresh the page.");
     } finally {
@@ -738,6 +763,7 @@ export default function FormPage() {
           respondent_email: email,
           role: role,
           answers: v2Answers,
+          invitation_token: inviteToken ?? undefined,
         }),
       });
 
@@ -10,6 +10,7 @@ import {
 import { Button } from "@/components/ui/button";
 import { ChevronLeft, FileSpreadsheet } from "lucide-react";
 import * as XLSX from "xlsx";
+import { fetchAllPages, HttpError } from "@/services/api";
 
 interface Response {
   id: string;
@@ -121,18 +122,19 @@ export default function FormResults() {
       setSections(allSections.sort((a, b) => a.position - b.position));
       setQuestions(allQuestions.sort((a, b) => a.position - b.position));
 
-      // Fetch responses
-      const responsesRes = await fetch(
-        `/api/admin/responses?token=${token}&form_id=${formId}`,
-      );
-      if (!responsesRes.ok) {
-        if (responsesRes.status === 401) {
+      // Fetch responses, following pagination cursors
+      let responsesData: any[];
+      try {
+        responsesData = await fetchAllPages<any>(
+          `/api/admin/responses?token=${token}&form_id=${formId}`,
+        );
+      } catch (err) {
+        if (err instanceof HttpError && err.status === 401) {
           navigate("/admin");
           return;
         }
         throw new Error("Failed to load responses");
       }
-      const responsesData = await responsesRes.json();
 
       // Transform responses to have answers as an object keyed by question_id
       // and determine if response is completed based on whether all questions are answered
@@ -170,13 +172,15 @@ export default function FormResults() {
       const uniqueRoles: string[] = Array.from(new Set<string>(rolesList));
       setAvailableRoles(uniqueRoles.sort());
 
-      // Fetch respondents list (with PII)
-      const respondentsRes = await fetch(
-        `/api/admin/forms/${formId}/respondents?token=${token}`,
-      );
-      if (respondentsRes.ok) {
-        const respondentsData = await respondentsRes.json();
-        setRespondents(respondentsData.respondents || []);
+      // Fetch respondents list (with PII); optional, so failures are ignored
+      try {
+        setRespondents(
+          await fetchAllPages<any>(
+            `/api/admin/forms/${formId}/respondents?token=${token}`,
+          ),
+        );
+      } catch {
+        // Results still render without the respondent list
       }
     } catch (err) {
       setError(err instanceof Error ? err.message : "Failed to load data");
@@ -97,7 +97,10 @@ export interface FormWithSections {
 export interface FormResponse {
   respondent_name?: string;
   respondent_email?: string;
+  role?: string;
   answers: Answer[];
+  // Required when the form has an invitation list; read from the `?invite=` link
+  invitation_token?: string;
 }
 
 export interface Answer {
@@ -105,6 +108,95 @@ export interface Answer {
   value: any;
 }
 
+// Envelope returned by paginated admin listings
+export interface Page<T> {
+  items: T[];
+  next_cursor: string | null;
+  total: number;
+}
+
+// Options for copying a form; every field is optional
+export interface CloneFormOptions {
+  form_id?: string;
+  title?: string;
+  review_period?: string;
+  copy_responses?: boolean;
+  copy_invitations?: boolean;
+}
+
+// A template from the library, without its definition
+export interface FormTemplate {
+  id: string;
+  name: string;
+  description: string | null;
+  category: string | null;
+  built_in: boolean;
+  source_form_id: string | null;
+  created_at: string | null;
+  sections: number;
+  questions: number;
+}
+
+// A question shared across forms; copies carry its id as the `bankId` feature
+export interface BankQuestion {
+  id: string;
+  question_type: string;
+  title: string;
+  features: Record<string, any>;
+  tags: string[];
+  created_at: string;
+  updated_at: string;
+}
+
+export interface BankQuestionInput {
+  id?: string;
+  question_type: string;
+  title: string;
+  features?: Record<string, any>;
+  tags?: string[];
+}
+
+// One side of a statistical comparison: a likert question's ratings on a form
+export interface SampleSelector {
+  form_id: string;
+  question_id?: string;
+  bank_question_id?: string;
+  role?: string;
+}
+
+export class HttpError extends Error {
+  constructor(public status: number) {
+    super(`HTTP ${status}`);
+  }
+}
+
+// Follow next_cursor until every item of an admin listing has been fetched
+export async function fetchAllPages<T>(
+  url: string,
+  options: RequestInit = {},
+): Promise<T[]> {
+  const items: T[] = [];
+  let cursor: string | null = null;
+
+  do {
+    const separator = url.includes("?") ? "&" : "?";
+    const cursorParam = cursor ? `&cursor=${encodeURIComponent(cursor)}` : "";
+    const response = await fetch(
+      `${url}${separator}limit=500${cursorParam}`,
+      options,
+    );
+    if (!response.ok) {
+      throw new HttpError(response.status);
+    }
+
+    const page: Page<T> = await response.json();
+    items.push(...page.items);
+    cursor = page.next_cursor;
+  } while (cursor);
+
+  return items;
+}
+
 // Authentication types
 export interface LoginRequest {
   username: string;
@@ -166,7 +258,7 @@ export class AdminApiClient {
     const path = formId
       ? `/api/admin/responses?form_id=${formId}`
       : `/api/admin/responses`;
-    return this.request(path);
+    return this.request<Page<any>>(path);
   }
 
   async importForm(formData: any) {
@@ -189,11 +281,126 @@ export class AdminApiClient {
     });
   }
 
-  async cloneForm(formId: string) {
+  async cloneForm(formId: string, options: CloneFormOptions = {}) {
     return this.request(`/api/admin/forms/${formId}/clone`, {
       method: "POST",
+      body: JSON.stringify(options),
+    });
+  }
+
+  async listTemplates(category?: string) {
+    const query = category ? `?category=${encodeURIComponent(category)}` : "";
+    return this.request<{ templates: FormTemplate[] }>(
+      `/api/admin/templates${query}`,
+    );
+  }
+
+  async getTemplate(templateId: string) {
+    return this.request<FormTemplate & { definition: any }>(
+      `/api/admin/templates/${templateId}`,
+    );
+  }
+
+  async saveFormAsTemplate(
+    formId: string,
+    template: { name?: string; description?: string; category?: string } = {},
+  ) {
+    return this.request<FormTemplate>(`/api/admin/forms/${formId}/template`, {
+      method: "POST",
+      body: JSON.stringify(template),
+    });
+  }
+
+  async instantiateTemplate(
+    templateId: string,
+    form: { form_id?: string; title?: string } = {},
+  ) {
+    return this.request<{ form_id: string; title: string }>(
+      `/api/admin/templates/${templateId}/instantiate`,
+      {
+        method: "POST",
+        body: JSON.stringify(form),
+      },
+    );
+  }
+
+  async deleteTemplate(templateId: string) {
+    return this.request(`/api/admin/templates/${templateId}`, {
+      method: "DELETE",
+    });
+  }
+
+  async listBankQuestions(filter: { tag?: string; q?: string } = {}) {
+    const params = new URLSearchParams();
+    if (filter.tag) params.set("tag", filter.tag);
+    if (filter.q) params.set("q", filter.q);
+    const query = params.toString() ? `?${params}` : "";
+    return this.request<{
+      questions: BankQuestion[];
+      tags: { tag: string; count: number }[];
+    }>(`/api/admin/question-bank${query}`);
+  }
+
+  async createBankQuestion(question: BankQuestionInput) {
+    return this.request<BankQuestion>("/api/admin/question-bank", {
+      method: "POST",
+      body: JSON.stringify(question),
+    });
+  }
+
+  async updateBankQuestion(questionId: string, question: BankQuestionInput) {
+    return this.request<BankQuestion>(
+      `/api/admin/question-bank/${questionId}`,
+      {
+        method: "PUT",
+        body: JSON.stringify(question),
+      },
+    );
+  }
+
+  async deleteBankQuestion(questionId: string) {
+    return this.request(`/api/admin/question-bank/${questionId}`, {
+      method: "DELETE",
+    });
+  }
+
+  async getTrends(selection: { formIds: string[] } | { series: string }) {
+    const query =
+      "series" in selection
+        ? `series=${encodeURIComponent(selection.series)}`
+        : `form_ids=${selection.formIds.map(encodeURIComponent).join(",")}`;
+    return this.request<any>(`/api/admin/trends?${query}`);
+  }
+
+  async analyzeRoles(formId: string, questionId?: string) {
+    const query = questionId
+      ? `?question_id=${encodeURIComponent(questionId)}`
+      : "";
+    return this.request<any>(
+      `/api/admin/forms/${formId}/analysis/roles${query}`,
+    );
+  }
+
+  async compareSamples(a: SampleSelector, b: SampleSelector) {
+    return this.request<any>("/api/admin/analysis/compare", {
+      method: "POST",
+      body: JSON.stringify({ a, b }),
     });
   }
+
+  async addBankQuestions(
+    formId: string,
+    sectionId: string,
+    questionIds: string[],
+  ) {
+    return this.request(
+      `/api/admin/forms/${formId}/sections/${sectionId}/questions/from-bank`,
+      {
+        method: "POST",
+        body: JSON.stringify({ question_ids: questionIds }),
+      },
+    );
+  }
 }
 
 // API Client class
@@ -306,7 +513,17 @@ class ApiClient {
   }
 
   async getFormResponses(formId: string): Promise<any[]> {
-    return this.request<any[]>(`/api/admin/forms/${formId}/responses`);
+    const items: any[] = [];
+    let cursor: string | null = null;
+    do {
+      const cursorParam = cursor ? `&cursor=${encodeURIComponent(cursor)}` : "";
+      const page: Page<any> = await this.request<Page<any>>(
+        `/api/admin/forms/${formId}/responses?limit=500${cursorParam}`,
+      );
+      items.push(...page.items);
+      cursor = page.next_cursor;
+    } while (cursor);
+    return items;
   }
 
   async getFormStats(formId: string): Promise<any> {
@@ -340,12 +557,17 @@ export const api = {
   getForms: () => apiClient.listForms(),
   getForm: (id: string) => apiClient.getForm(id),
   createForm: (form: any) => apiClient.createForm(form),
-  submitFormResponse: (formId: string, responses: any) =>
+  submitFormResponse: (
+    formId: string,
+    responses: any,
+    invitationToken?: string,
+  ) =>
     apiClient.submitForm(formId, {
       answers: Object.entries(responses).map(([key, value]) => ({
         question_id: key,
         value,
       })),
+      invitation_token: invitationToken,
     }),
 };
 